@@ -0,0 +1,24 @@
+//! Throughput of the matching loop on the synthetic workloads of
+//! `bench_harness`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use scrolling_window_pattern_matcher::bench_harness::Workload;
+
+const ITEM_COUNT: usize = 10_000;
+
+fn matching(c: &mut Criterion) {
+    let mut group = c.benchmark_group("matching");
+    group.throughput(Throughput::Elements(ITEM_COUNT as u64));
+    for workload in Workload::ALL {
+        let items = workload.items(ITEM_COUNT);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(workload.name()),
+            &items,
+            |b, items| b.iter(|| workload.replay(items)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, matching);
+criterion_main!(benches);
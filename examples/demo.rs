@@ -4,6 +4,7 @@
 
 use scrolling_window_pattern_matcher::{ElementSettings, ExtractorAction, Matcher, PatternElement};
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 struct DemoContext {
     name: String,
@@ -117,8 +118,10 @@ fn optional_elements() {
     // Pattern: 1, optionally 2, then 3
     matcher.add_pattern(PatternElement::exact(1));
 
-    let mut settings = ElementSettings::default();
-    settings.optional = true;
+    let settings = ElementSettings {
+        optional: true,
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(2, settings));
 
     matcher.add_pattern(PatternElement::exact(3));
@@ -155,8 +158,10 @@ fn data_extractors() {
         Ok(ExtractorAction::Extract(state.current_item * 2))
     });
 
-    let mut settings = ElementSettings::default();
-    settings.extractor_id = Some(1);
+    let settings = ElementSettings {
+        extractor_id: Some(1),
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(10, settings));
 
     println!("   Testing extractor that doubles the value:");
@@ -3,12 +3,11 @@
 //! This example demonstrates stateful data extraction using the unified Matcher API
 //! with context management and extractor functions for accumulating extracted data.
 
-use scrolling_window_pattern_matcher::{
-    ElementSettings, ExtractorAction, ExtractorError, Matcher, PatternElement,
-};
+use scrolling_window_pattern_matcher::{ElementSettings, ExtractorAction, Matcher, PatternElement};
 use std::collections::HashMap;
 
 /// Example context that accumulates extracted data
+#[allow(dead_code)]
 #[derive(Default, Debug, Clone)]
 struct ExtractionContext {
     numbers: Vec<i32>,
@@ -20,9 +19,6 @@ struct ExtractionContext {
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== 🚀 UNIFIED: Stateful Extraction with Context Management ===\n");
 
-    // Create our extraction context
-    let context = ExtractionContext::default();
-
     // Demo 1: Number extraction from character sequences
     demo_number_extraction()?;
 
@@ -68,8 +64,10 @@ fn demo_number_extraction() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
-    let mut number_settings = ElementSettings::default();
-    number_settings.extractor_id = Some(1);
+    let number_settings = ElementSettings {
+        extractor_id: Some(1),
+        ..Default::default()
+    };
 
     // Pattern to match digits
     matcher.add_pattern(PatternElement::predicate_with_settings(
@@ -114,8 +112,10 @@ fn demo_word_extraction_with_context() -> Result<(), Box<dyn std::error::Error>>
         }
     });
 
-    let mut word_settings = ElementSettings::default();
-    word_settings.extractor_id = Some(2);
+    let word_settings = ElementSettings {
+        extractor_id: Some(2),
+        ..Default::default()
+    };
 
     matcher.add_pattern(PatternElement::predicate_with_settings(
         |c: &char| c.is_ascii_alphabetic(),
@@ -172,22 +172,28 @@ fn demo_complex_extraction() -> Result<(), Box<dyn std::error::Error>> {
     });
 
     // Configure patterns with different extractors
-    let mut small_settings = ElementSettings::default();
-    small_settings.extractor_id = Some(10);
+    let small_settings = ElementSettings {
+        extractor_id: Some(10),
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::predicate_with_settings(
-        |&x| x >= 1 && x <= 10,
+        |&x| (1..=10).contains(&x),
         small_settings,
     ));
 
-    let mut large_settings = ElementSettings::default();
-    large_settings.extractor_id = Some(20);
+    let large_settings = ElementSettings {
+        extractor_id: Some(20),
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::predicate_with_settings(
         |&x| x > 100,
         large_settings,
     ));
 
-    let mut reset_settings = ElementSettings::default();
-    reset_settings.extractor_id = Some(30);
+    let reset_settings = ElementSettings {
+        extractor_id: Some(30),
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(999, reset_settings));
 
     let test_data = vec![5, 150, 3, 250, 999, 8, 200];
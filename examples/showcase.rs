@@ -13,6 +13,7 @@ use scrolling_window_pattern_matcher::{
 };
 use std::collections::HashMap;
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 struct ShowcaseContext {
     name: String,
@@ -149,8 +150,10 @@ fn extractor_demonstrations() {
         ))
     });
 
-    let mut settings = ElementSettings::default();
-    settings.extractor_id = Some(1);
+    let settings = ElementSettings {
+        extractor_id: Some(1),
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(5, settings));
 
     if let Some(result) = matcher.process_item(5).unwrap() {
@@ -169,8 +172,10 @@ fn extractor_demonstrations() {
         }
     });
 
-    let mut settings2 = ElementSettings::default();
-    settings2.extractor_id = Some(2);
+    let settings2 = ElementSettings {
+        extractor_id: Some(2),
+        ..Default::default()
+    };
     matcher2.add_pattern(PatternElement::exact_with_settings(15, settings2));
 
     if let Some(result) = matcher2.process_item(15).unwrap() {
@@ -189,8 +194,10 @@ fn extractor_demonstrations() {
         }
     });
 
-    let mut settings3 = ElementSettings::default();
-    settings3.extractor_id = Some(3);
+    let settings3 = ElementSettings {
+        extractor_id: Some(3),
+        ..Default::default()
+    };
     matcher3.add_pattern(PatternElement::exact_with_settings(99, settings3));
     matcher3.add_pattern(PatternElement::exact(1));
 
@@ -210,8 +217,10 @@ fn optional_elements_showcase() {
 
     matcher.add_pattern(PatternElement::exact(1));
 
-    let mut settings = ElementSettings::default();
-    settings.optional = true;
+    let settings = ElementSettings {
+        optional: true,
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(2, settings));
 
     matcher.add_pattern(PatternElement::exact(3));
@@ -241,12 +250,16 @@ fn optional_elements_showcase() {
 
     matcher2.add_pattern(PatternElement::exact(10));
 
-    let mut opt1 = ElementSettings::default();
-    opt1.optional = true;
+    let opt1 = ElementSettings {
+        optional: true,
+        ..Default::default()
+    };
     matcher2.add_pattern(PatternElement::exact_with_settings(20, opt1));
 
-    let mut opt2 = ElementSettings::default();
-    opt2.optional = true;
+    let opt2 = ElementSettings {
+        optional: true,
+        ..Default::default()
+    };
     matcher2.add_pattern(PatternElement::exact_with_settings(30, opt2));
 
     matcher2.add_pattern(PatternElement::exact(40));
@@ -294,8 +307,10 @@ fn context_processing() {
         Ok(ExtractorAction::Extract(state.current_item * multiplier))
     });
 
-    let mut settings = ElementSettings::default();
-    settings.extractor_id = Some(10);
+    let settings = ElementSettings {
+        extractor_id: Some(10),
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(7, settings));
 
     println!("1. Position-aware Extractor:");
@@ -316,8 +331,12 @@ fn real_world_scenarios() {
     let mut http_matcher = Matcher::<i32, ShowcaseContext>::new(50);
 
     // Pattern: Client error (4xx) followed by server error (5xx)
-    http_matcher.add_pattern(PatternElement::predicate(|&code| code >= 400 && code < 500));
-    http_matcher.add_pattern(PatternElement::predicate(|&code| code >= 500 && code < 600));
+    http_matcher.add_pattern(PatternElement::predicate(|&code| {
+        (400..500).contains(&code)
+    }));
+    http_matcher.add_pattern(PatternElement::predicate(|&code| {
+        (500..600).contains(&code)
+    }));
 
     let status_codes = vec![200, 404, 500, 403, 502, 200, 401, 503];
     println!("   Status codes: {:?}", status_codes);
@@ -342,8 +361,10 @@ fn real_world_scenarios() {
         Ok(ExtractorAction::Extract(state.current_item))
     });
 
-    let mut port_settings = ElementSettings::default();
-    port_settings.extractor_id = Some(20);
+    let port_settings = ElementSettings {
+        extractor_id: Some(20),
+        ..Default::default()
+    };
 
     // Detect high ports being accessed sequentially
     port_matcher.add_pattern(PatternElement::predicate_with_settings(
@@ -397,8 +418,10 @@ fn error_handling_examples() {
         }
     });
 
-    let mut error_settings = ElementSettings::default();
-    error_settings.extractor_id = Some(100);
+    let error_settings = ElementSettings {
+        extractor_id: Some(100),
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(0, error_settings));
 
     println!("   Testing division by zero extractor:");
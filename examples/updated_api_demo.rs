@@ -3,9 +3,7 @@
 //! This example demonstrates the current unified API functionality showcasing
 //! all features of the simplified, single-matcher architecture.
 
-use scrolling_window_pattern_matcher::{
-    ElementSettings, ExtractorAction, ExtractorError, Matcher, PatternElement,
-};
+use scrolling_window_pattern_matcher::{ElementSettings, ExtractorAction, Matcher, PatternElement};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== Unified API Demo ===\n");
@@ -94,8 +92,10 @@ fn demo_advanced_patterns() -> Result<(), Box<dyn std::error::Error>> {
     let mut opt_matcher = Matcher::<i32, ()>::new(20);
     opt_matcher.add_pattern(PatternElement::exact(1));
 
-    let mut opt_settings = ElementSettings::default();
-    opt_settings.optional = true;
+    let opt_settings = ElementSettings {
+        optional: true,
+        ..Default::default()
+    };
     opt_matcher.add_pattern(PatternElement::exact_with_settings(2, opt_settings));
 
     opt_matcher.add_pattern(PatternElement::exact(3));
@@ -142,8 +142,10 @@ fn demo_extractor_processing() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
-    let mut settings = ElementSettings::default();
-    settings.extractor_id = Some(1);
+    let settings = ElementSettings {
+        extractor_id: Some(1),
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::predicate_with_settings(
         |x| *x > 0,
         settings,
@@ -168,8 +170,10 @@ fn demo_extractor_processing() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
-    let mut restart_settings = ElementSettings::default();
-    restart_settings.extractor_id = Some(10);
+    let restart_settings = ElementSettings {
+        extractor_id: Some(10),
+        ..Default::default()
+    };
     restart_matcher.add_pattern(PatternElement::exact_with_settings(99, restart_settings));
     restart_matcher.add_pattern(PatternElement::exact(5));
 
@@ -195,7 +199,7 @@ fn demo_data_processing() -> Result<(), Box<dyn std::error::Error>> {
     // Pattern: detect anomalies (values outside normal range)
     sensor_matcher.register_extractor(100, |state| {
         let value = state.current_item;
-        if value < 10 || value > 90 {
+        if !(10..=90).contains(&value) {
             println!(
                 "   🚨 ANOMALY detected: {} at position {}",
                 value, state.position
@@ -206,10 +210,12 @@ fn demo_data_processing() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
-    let mut anomaly_settings = ElementSettings::default();
-    anomaly_settings.extractor_id = Some(100);
+    let anomaly_settings = ElementSettings {
+        extractor_id: Some(100),
+        ..Default::default()
+    };
     sensor_matcher.add_pattern(PatternElement::predicate_with_settings(
-        |&x| x < 10 || x > 90,
+        |&x| !(10..=90).contains(&x),
         anomaly_settings,
     ));
 
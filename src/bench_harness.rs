@@ -0,0 +1,166 @@
+//! Synthetic workloads for measuring matching throughput.
+//!
+//! Each [`Workload`] generates a reproducible stream of items and a matcher
+//! tuned to stress one part of the matching loop. The crate's criterion
+//! benchmarks replay them, and [`measure`] times one from any program, so
+//! numbers can be reproduced on other hardware without the benchmark suite.
+//!
+//! ```rust
+//! use scrolling_window_pattern_matcher::bench_harness::{measure, Workload};
+//!
+//! let measurement = measure(Workload::ExactHeavy, 10_000);
+//! assert_eq!(measurement.items, 10_000);
+//! println!("{:.0} items/s", measurement.items_per_second());
+//! ```
+
+use std::time::{Duration, Instant};
+
+use crate::{Matcher, MatcherGroup, PatternElement};
+
+/// Number of matchers in the many-pattern workload.
+const MANY_PATTERN_COUNT: usize = 100;
+
+/// A synthetic workload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Workload {
+    /// A long run of exact elements over a small alphabet.
+    ExactHeavy,
+    /// Predicate elements doing arithmetic on every item.
+    PredicateHeavy,
+    /// Many two-element patterns in one matcher group.
+    ManyPatterns,
+}
+
+impl Workload {
+    /// Every workload.
+    pub const ALL: [Workload; 3] = [
+        Workload::ExactHeavy,
+        Workload::PredicateHeavy,
+        Workload::ManyPatterns,
+    ];
+
+    /// Get the name of the workload.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Workload::ExactHeavy => "exact_heavy",
+            Workload::PredicateHeavy => "predicate_heavy",
+            Workload::ManyPatterns => "many_patterns",
+        }
+    }
+
+    /// Generate `count` items for the workload. The same count always
+    /// generates the same items.
+    pub fn items(&self, count: usize) -> Vec<i32> {
+        let modulus = match self {
+            Workload::ExactHeavy => 4,
+            Workload::PredicateHeavy => 1000,
+            Workload::ManyPatterns => MANY_PATTERN_COUNT as u32 * 2,
+        };
+        let mut seed = 0x2545_f491_u32;
+        (0..count)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                (seed % modulus) as i32
+            })
+            .collect()
+    }
+
+    /// Process the items with a fresh matcher for the workload, returning the
+    /// number of matches found.
+    pub fn replay(&self, items: &[i32]) -> usize {
+        match self {
+            Workload::ExactHeavy => {
+                let mut matcher = Matcher::<i32, ()>::new(0);
+                for value in [0, 1, 2, 3, 0, 1] {
+                    matcher.add_pattern(PatternElement::exact(value));
+                }
+                count_matches(&mut matcher, items)
+            }
+            Workload::PredicateHeavy => {
+                let mut matcher = Matcher::<i32, ()>::new(0);
+                matcher.add_pattern(PatternElement::predicate(|item: &i32| item % 7 == 0));
+                matcher.add_pattern(PatternElement::predicate(|item: &i32| {
+                    (item * item) % 10 < 5
+                }));
+                matcher.add_pattern(PatternElement::predicate(|item: &i32| *item > 500));
+                count_matches(&mut matcher, items)
+            }
+            Workload::ManyPatterns => {
+                let mut group = MatcherGroup::new();
+                for start in 0..MANY_PATTERN_COUNT as i32 {
+                    let mut matcher = Matcher::<i32, ()>::new(0);
+                    matcher.add_pattern(PatternElement::exact(start * 2));
+                    matcher.add_pattern(PatternElement::range(0, 99));
+                    group.add(matcher);
+                }
+                items
+                    .iter()
+                    .map(|item| group.process_item(*item).map_or(0, |found| found.len()))
+                    .sum()
+            }
+        }
+    }
+}
+
+/// Process items one at a time, counting the matches.
+fn count_matches(matcher: &mut Matcher<i32, ()>, items: &[i32]) -> usize {
+    items
+        .iter()
+        .map(|item| {
+            matcher
+                .process_item_all(*item)
+                .map_or(0, |found| found.len())
+        })
+        .sum()
+}
+
+/// The outcome of timing a workload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    /// The workload that was timed.
+    pub workload: Workload,
+    /// Number of items processed.
+    pub items: usize,
+    /// Number of matches found.
+    pub matches: usize,
+    /// Time taken to process the items.
+    pub elapsed: Duration,
+}
+
+impl Measurement {
+    /// Get the number of items processed per second.
+    pub fn items_per_second(&self) -> f64 {
+        self.items as f64 / self.elapsed.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+}
+
+/// Time one replay of `count` items of a workload.
+pub fn measure(workload: Workload, count: usize) -> Measurement {
+    let items = workload.items(count);
+    let started = Instant::now();
+    let matches = workload.replay(&items);
+    Measurement {
+        workload,
+        items: count,
+        matches,
+        elapsed: started.elapsed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workloads_are_reproducible() {
+        for workload in Workload::ALL {
+            let items = workload.items(2000);
+            assert_eq!(items, workload.items(2000));
+            let matches = workload.replay(&items);
+            assert!(matches > 0, "{} found no matches", workload.name());
+            assert_eq!(measure(workload, 2000).matches, matches);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! Elements for DNA and RNA base streams.
+//!
+//! Genomic motifs are written with the IUPAC nucleotide codes, where letters
+//! other than `A`, `C`, `G` and `T` stand for sets of bases: `R` is a purine
+//! (`A` or `G`), `Y` a pyrimidine (`C` or `T`), `N` any base, and so on.
+//! [`IupacCode`] matches a base against such a set, and [`motif`] turns a
+//! whole motif into pattern elements for a matcher over `u8` bases.
+//!
+//! Bases are ASCII letters in either case, and `U` is read as `T`, so the
+//! same motif scans DNA and RNA.
+//!
+//! ```rust
+//! use scrolling_window_pattern_matcher::{bio, Matcher, ResetPolicy};
+//!
+//! // The TATA box consensus
+//! let mut matcher = Matcher::<u8, ()>::with_patterns(bio::motif("TATAWAW").unwrap(), 10);
+//! matcher.set_reset_policy(ResetPolicy::FallbackByPrefix);
+//! let results = matcher.process_items(b"GCTATAAATGC".to_vec()).unwrap();
+//! assert_eq!(results, vec![b'T']);
+//! ```
+
+use std::fmt;
+
+use crate::{ElementSettings, Matchable, MatcherError, PatternElement};
+
+/// Bit of each base in an [`IupacCode`] set.
+const A: u8 = 0b0001;
+const C: u8 = 0b0010;
+const G: u8 = 0b0100;
+const T: u8 = 0b1000;
+
+/// An IUPAC nucleotide code, the set of bases it stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IupacCode(u8);
+
+impl IupacCode {
+    /// Look up the code written as the given letter, in either case.
+    pub fn from_letter(letter: u8) -> Option<Self> {
+        let bases = match letter.to_ascii_uppercase() {
+            b'A' => A,
+            b'C' => C,
+            b'G' => G,
+            b'T' | b'U' => T,
+            b'R' => A | G,
+            b'Y' => C | T,
+            b'S' => C | G,
+            b'W' => A | T,
+            b'K' => G | T,
+            b'M' => A | C,
+            b'B' => C | G | T,
+            b'D' => A | G | T,
+            b'H' => A | C | T,
+            b'V' => A | C | G,
+            b'N' => A | C | G | T,
+            _ => return None,
+        };
+        Some(Self(bases))
+    }
+
+    /// The letter the code is written as, in upper case.
+    pub fn letter(self) -> u8 {
+        b"-ACMGRSVTWYHKDBN"[usize::from(self.0)]
+    }
+
+    /// Check if a base, given as a letter in either case, is in the set.
+    ///
+    /// Only `A`, `C`, `G`, `T` and `U` are bases: ambiguity codes in the
+    /// stream itself never match.
+    pub fn matches_base(self, base: u8) -> bool {
+        match base.to_ascii_uppercase() {
+            b'A' => self.0 & A != 0,
+            b'C' => self.0 & C != 0,
+            b'G' => self.0 & G != 0,
+            b'T' | b'U' => self.0 & T != 0,
+            _ => false,
+        }
+    }
+
+    /// The code for the complementary bases.
+    pub fn complement(self) -> Self {
+        let mut bases = 0;
+        for (base, paired) in [(A, T), (C, G), (G, C), (T, A)] {
+            if self.0 & base != 0 {
+                bases |= paired;
+            }
+        }
+        Self(bases)
+    }
+}
+
+impl fmt::Display for IupacCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", char::from(self.letter()))
+    }
+}
+
+impl Matchable<u8> for IupacCode {
+    fn matches(&self, item: &u8) -> Result<bool, MatcherError> {
+        Ok(self.matches_base(*item))
+    }
+
+    fn describe(&self) -> String {
+        format!("Iupac({})", self)
+    }
+}
+
+/// Parse a single IUPAC letter, reporting invalid ones as a pattern error.
+fn parse_code(letter: u8) -> Result<IupacCode, MatcherError> {
+    IupacCode::from_letter(letter).ok_or_else(|| {
+        MatcherError::InvalidPattern(format!(
+            "{:?} is not an IUPAC nucleotide code",
+            char::from(letter)
+        ))
+    })
+}
+
+impl<Context> PatternElement<u8, Context>
+where
+    Context: Clone + fmt::Debug,
+{
+    /// Create a new pattern element matching the bases of an IUPAC code.
+    pub fn iupac(code: u8) -> Result<Self, MatcherError> {
+        Ok(Self::custom(parse_code(code)?))
+    }
+
+    /// Create a new IUPAC pattern element with settings.
+    pub fn iupac_with_settings(
+        code: u8,
+        settings: ElementSettings<Context>,
+    ) -> Result<Self, MatcherError> {
+        Ok(Self::custom_with_settings(parse_code(code)?, settings))
+    }
+}
+
+/// One element per letter of an IUPAC motif, such as `"TATAWAW"`.
+pub fn motif<Context>(motif: &str) -> Result<Vec<PatternElement<u8, Context>>, MatcherError>
+where
+    Context: Clone + fmt::Debug,
+{
+    motif.bytes().map(PatternElement::iupac).collect()
+}
+
+/// The elements of a motif as it reads on the opposite strand.
+///
+/// Scanning one strand with both [`motif`] and this finds the motif on
+/// either strand.
+pub fn reverse_complement<Context>(
+    motif: &str,
+) -> Result<Vec<PatternElement<u8, Context>>, MatcherError>
+where
+    Context: Clone + fmt::Debug,
+{
+    motif
+        .bytes()
+        .rev()
+        .map(|letter| Ok(PatternElement::custom(parse_code(letter)?.complement())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Matcher, ResetPolicy};
+
+    #[test]
+    fn test_iupac_codes() {
+        let purine = IupacCode::from_letter(b'r').unwrap();
+        assert!(purine.matches_base(b'A') && purine.matches_base(b'g'));
+        assert!(!purine.matches_base(b'C') && !purine.matches_base(b'N'));
+        assert!(IupacCode::from_letter(b'T').unwrap().matches_base(b'U'));
+        assert_eq!(IupacCode::from_letter(b'X'), None);
+
+        for letter in *b"ACGTRYSWKMBDHVN" {
+            let code = IupacCode::from_letter(letter).unwrap();
+            assert_eq!(code.letter(), letter);
+        }
+        assert_eq!(
+            IupacCode::from_letter(b'R')
+                .unwrap()
+                .complement()
+                .to_string(),
+            "Y"
+        );
+        assert_eq!(
+            IupacCode::from_letter(b'B')
+                .unwrap()
+                .complement()
+                .to_string(),
+            "V"
+        );
+    }
+
+    #[test]
+    fn test_motif_scan() {
+        // EcoRII site CCWGG
+        let mut matcher = Matcher::<u8, ()>::with_patterns(motif("CCWGG").unwrap(), 10);
+        matcher.set_reset_policy(ResetPolicy::FallbackByPrefix);
+        let sequence = b"ACCAGGTCCTGGACCCGG";
+        let ends: Vec<usize> = sequence
+            .iter()
+            .enumerate()
+            .filter(|(_, base)| matcher.process_item(**base).unwrap().is_some())
+            .map(|(position, _)| position)
+            .collect();
+        assert_eq!(ends, vec![5, 11]);
+
+        assert!(matches!(
+            motif::<()>("TAXA"),
+            Err(MatcherError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_reverse_complement() {
+        let mut matcher =
+            Matcher::<u8, ()>::with_patterns(reverse_complement("GAATTR").unwrap(), 10);
+        matcher.set_reset_policy(ResetPolicy::FallbackByPrefix);
+        // YAATTC on this strand is GAATTR on the other
+        let results = matcher.process_items(b"GGTAATTCA".to_vec()).unwrap();
+        assert_eq!(results, vec![b'C']);
+    }
+}
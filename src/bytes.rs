@@ -0,0 +1,158 @@
+//! Fast path for matching exact byte sequences in byte streams.
+//!
+//! Running a `Matcher<u8, _>` feeds every byte through the generic pattern
+//! engine. When the pattern is a plain run of exact bytes, [`ByteMatcher`]
+//! finds it far faster by scanning for the first byte with `memchr` and
+//! comparing the rest of the run as a slice.
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::{MatcherError, PatternElement};
+
+/// Finds occurrences of an exact byte sequence in a chunked byte stream.
+///
+/// Occurrences do not overlap, and one split across chunk boundaries is
+/// found once the chunk completing it is fed in.
+#[derive(Debug, Clone)]
+pub struct ByteMatcher {
+    needle: Vec<u8>,
+    /// Unconsumed bytes that may begin an occurrence completed by the next chunk.
+    tail: Vec<u8>,
+    /// Stream offset of the first byte of `tail`.
+    offset: usize,
+}
+
+impl ByteMatcher {
+    /// Create a matcher for the given byte sequence.
+    pub fn new(needle: impl Into<Vec<u8>>) -> Result<Self, MatcherError> {
+        let needle = needle.into();
+        if needle.is_empty() {
+            return Err(MatcherError::NoPatterns);
+        }
+        Ok(Self {
+            needle,
+            tail: Vec::new(),
+            offset: 0,
+        })
+    }
+
+    /// Create a matcher from pattern elements, if they are all exact bytes
+    /// without settings.
+    pub fn from_patterns<Context>(patterns: &[PatternElement<u8, Context>]) -> Option<Self>
+    where
+        Context: Clone + fmt::Debug,
+    {
+        let needle = patterns
+            .iter()
+            .map(|pattern| match pattern {
+                PatternElement::Exact {
+                    value,
+                    settings: None,
+                } => Some(*value),
+                _ => None,
+            })
+            .collect::<Option<Vec<u8>>>()?;
+        Self::new(needle).ok()
+    }
+
+    /// Get the byte sequence this matcher looks for.
+    pub fn needle(&self) -> &[u8] {
+        &self.needle
+    }
+
+    /// Find all occurrences in a complete buffer, as byte ranges.
+    pub fn find_all(&self, haystack: &[u8]) -> Vec<Range<usize>> {
+        let (found, _) = self.scan(haystack);
+        found
+    }
+
+    /// Feed the next chunk of the stream, returning the stream byte ranges of
+    /// the occurrences it completes.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Range<usize>> {
+        let mut buffer = std::mem::take(&mut self.tail);
+        buffer.extend_from_slice(chunk);
+
+        let (found, resume) = self.scan(&buffer);
+        let offset = self.offset;
+
+        // Keep only the bytes that could still start an occurrence
+        let keep_from = resume.max(buffer.len().saturating_sub(self.needle.len() - 1));
+        self.tail = buffer.split_off(keep_from);
+        self.offset += keep_from;
+
+        found
+            .into_iter()
+            .map(|range| range.start + offset..range.end + offset)
+            .collect()
+    }
+
+    /// Forget any partial occurrence and restart stream offsets at zero.
+    pub fn reset(&mut self) {
+        self.tail.clear();
+        self.offset = 0;
+    }
+
+    /// Scan a buffer, returning the occurrences found and the position just
+    /// after the last one.
+    fn scan(&self, haystack: &[u8]) -> (Vec<Range<usize>>, usize) {
+        let first = self.needle[0];
+        let mut found = Vec::new();
+        let mut position = 0;
+        while let Some(hit) = memchr::memchr(first, &haystack[position..]) {
+            let start = position + hit;
+            let end = start + self.needle.len();
+            if end > haystack.len() {
+                break;
+            }
+            if haystack[start..end] == self.needle[..] {
+                found.push(start..end);
+                position = end;
+            } else {
+                position = start + 1;
+            }
+        }
+        let resume = found.last().map_or(0, |range| range.end);
+        (found, resume)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_all() {
+        let matcher = ByteMatcher::new(*b"aab").unwrap();
+        assert_eq!(
+            matcher.find_all(b"aaab xaab aabaab"),
+            vec![1..4, 6..9, 10..13, 13..16]
+        );
+        assert!(matches!(
+            ByteMatcher::new(Vec::new()),
+            Err(MatcherError::NoPatterns)
+        ));
+    }
+
+    #[test]
+    fn test_feed_across_chunks() {
+        let mut matcher = ByteMatcher::new(*b"GET ").unwrap();
+        assert!(matcher.feed(b"xxGE").is_empty());
+        assert_eq!(matcher.feed(b"T /a GET"), vec![2..6]);
+        assert_eq!(matcher.feed(b" /b"), vec![9..13]);
+
+        matcher.reset();
+        assert_eq!(matcher.feed(b"GET "), vec![0..4]);
+    }
+
+    #[test]
+    fn test_from_patterns() {
+        let patterns: Vec<PatternElement<u8, ()>> =
+            vec![PatternElement::exact(b'o'), PatternElement::exact(b'k')];
+        let matcher = ByteMatcher::from_patterns(&patterns).unwrap();
+        assert_eq!(matcher.needle(), b"ok");
+
+        let patterns: Vec<PatternElement<u8, ()>> = vec![PatternElement::range(b'a', b'z')];
+        assert!(ByteMatcher::from_patterns(&patterns).is_none());
+    }
+}
@@ -0,0 +1,211 @@
+//! Batch matching over columnar data.
+//!
+//! Columnar pipelines hand over whole columns rather than single items. These
+//! adapters run a matcher down a column, either a plain slice of values with
+//! an Arrow validity bitmap or an Arrow array, and report the row of the
+//! value that completed each match. Null rows are skipped, so a match may
+//! span them.
+
+use std::fmt;
+
+use arrow_array::{Array, ArrowPrimitiveType, PrimitiveArray, RecordBatch};
+use arrow_schema::DataType;
+
+use crate::{Matcher, MatcherError};
+
+/// Errors produced while matching a column of a record batch.
+#[derive(Debug)]
+pub enum ColumnError {
+    /// The batch has no column with the given name.
+    MissingColumn(String),
+    /// The column does not hold values of the matcher's item type.
+    TypeMismatch {
+        /// Name of the column.
+        column: String,
+        /// Type of the values the column holds.
+        data_type: DataType,
+    },
+    /// The matcher rejected an item.
+    Matcher(MatcherError),
+}
+
+impl fmt::Display for ColumnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnError::MissingColumn(column) => write!(f, "No column named {:?}", column),
+            ColumnError::TypeMismatch { column, data_type } => {
+                write!(f, "Column {:?} holds {} values", column, data_type)
+            }
+            ColumnError::Matcher(err) => write!(f, "Matcher error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ColumnError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ColumnError::Matcher(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<MatcherError> for ColumnError {
+    fn from(err: MatcherError) -> Self {
+        ColumnError::Matcher(err)
+    }
+}
+
+impl<T, Context> Matcher<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Process a column of values, returning the row of the value that
+    /// completed each match.
+    ///
+    /// `validity` is an Arrow validity bitmap: bit `i`, counting from the
+    /// least significant bit of the first byte, is set if row `i` is not null.
+    /// Rows without a bit in the bitmap are null. Without a bitmap every row
+    /// is valid.
+    pub fn process_column(
+        &mut self,
+        values: &[T],
+        validity: Option<&[u8]>,
+    ) -> Result<Vec<usize>, MatcherError> {
+        let is_valid = |row: usize| {
+            validity.map_or(true, |bits| {
+                bits.get(row / 8)
+                    .is_some_and(|byte| byte >> (row % 8) & 1 == 1)
+            })
+        };
+        self.process_rows(
+            values
+                .iter()
+                .enumerate()
+                .filter(|&(row, _)| is_valid(row))
+                .map(|(row, value)| (row, value.clone())),
+        )
+    }
+
+    /// Process the values of an Arrow array, returning the row of the value
+    /// that completed each match.
+    pub fn process_array<A>(
+        &mut self,
+        array: &PrimitiveArray<A>,
+    ) -> Result<Vec<usize>, MatcherError>
+    where
+        A: ArrowPrimitiveType<Native = T>,
+    {
+        self.process_rows(
+            array
+                .iter()
+                .enumerate()
+                .filter_map(|(row, value)| Some((row, value?))),
+        )
+    }
+
+    /// Process the named column of a record batch, returning the row of the
+    /// value that completed each match.
+    ///
+    /// The column must be a primitive array of type `A`.
+    pub fn process_batch<A>(
+        &mut self,
+        batch: &RecordBatch,
+        column: &str,
+    ) -> Result<Vec<usize>, ColumnError>
+    where
+        A: ArrowPrimitiveType<Native = T>,
+    {
+        let array = batch
+            .column_by_name(column)
+            .ok_or_else(|| ColumnError::MissingColumn(column.to_string()))?;
+        let array = array
+            .as_any()
+            .downcast_ref::<PrimitiveArray<A>>()
+            .ok_or_else(|| ColumnError::TypeMismatch {
+                column: column.to_string(),
+                data_type: array.data_type().clone(),
+            })?;
+        Ok(self.process_array(array)?)
+    }
+
+    /// Process rows of a column in order, mapping each completion back to
+    /// the row of the item that completed it.
+    fn process_rows(
+        &mut self,
+        rows: impl Iterator<Item = (usize, T)>,
+    ) -> Result<Vec<usize>, MatcherError> {
+        let first_index = self.offset();
+        let mut processed = Vec::new();
+        let mut found = Vec::new();
+        for (row, value) in rows {
+            processed.push(row);
+            let timestamp = self.epoch.elapsed();
+            for capture in self.process_captures(value, timestamp)? {
+                found.push(processed[capture.index - first_index]);
+            }
+        }
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PatternElement;
+    use arrow_array::types::Int32Type;
+    use arrow_array::Int32Array;
+    use arrow_schema::{Field, Schema};
+    use std::sync::Arc;
+
+    fn spike_matcher() -> Matcher<i32, ()> {
+        let mut matcher = Matcher::new(10);
+        matcher.add_pattern(PatternElement::range(0, 9));
+        matcher.add_pattern(PatternElement::range(100, 999));
+        matcher
+    }
+
+    #[test]
+    fn test_process_column_skips_nulls() {
+        let values = [1, 500, 2, 50, 300, 4];
+        // Row 3 is null, so rows 2 and 4 are adjacent
+        let validity = [0b0011_0111];
+
+        let mut matcher = spike_matcher();
+        assert_eq!(
+            matcher.process_column(&values, Some(&validity)).unwrap(),
+            vec![1, 4]
+        );
+        matcher.reset();
+        assert_eq!(matcher.process_column(&values, None).unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn test_process_batch() {
+        let schema = Schema::new(vec![
+            Field::new("latency", DataType::Int32, true),
+            Field::new("host", DataType::Utf8, false),
+        ]);
+        let latency = Int32Array::from(vec![Some(5), None, Some(250), Some(7), Some(8)]);
+        let host = arrow_array::StringArray::from(vec!["a", "b", "c", "d", "e"]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(latency), Arc::new(host)])
+            .unwrap();
+
+        let mut matcher = spike_matcher();
+        assert_eq!(
+            matcher
+                .process_batch::<Int32Type>(&batch, "latency")
+                .unwrap(),
+            vec![2]
+        );
+        assert!(matches!(
+            matcher.process_batch::<Int32Type>(&batch, "host"),
+            Err(ColumnError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            matcher.process_batch::<Int32Type>(&batch, "missing"),
+            Err(ColumnError::MissingColumn(_))
+        ));
+    }
+}
@@ -0,0 +1,266 @@
+//! Matching over the rows of CSV data.
+//!
+//! [`CsvMatcher`] reads records with the `csv` crate, maps each one to an
+//! item, either with a closure or by deserializing it with serde, and feeds
+//! the items to a [`Matcher`], yielding each match with the number of the row
+//! that completed it. This covers the common job of finding sequences of
+//! events in data exported from another system.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read};
+
+use ::csv::{Reader, StringRecord};
+use serde::de::DeserializeOwned;
+
+use crate::{Capture, Matcher, ReadError};
+
+/// Type alias for closures that turn a record into an item, given the header
+/// record if the data has one.
+type RecordMapper<T> = Box<dyn FnMut(&StringRecord, Option<&StringRecord>) -> io::Result<T>>;
+
+/// A match found in the rows of CSV data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvMatch<T> {
+    /// The value produced by the match.
+    pub value: T,
+    /// One-based number of the row that completed the match, not counting
+    /// the header row.
+    pub row: usize,
+}
+
+/// Drives a matcher with items mapped from the records of a CSV reader.
+///
+/// Like `LineMatcher`, the matcher is finished when the reader is exhausted
+/// so that a held completion is also reported.
+pub struct CsvMatcher<R, T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    reader: Reader<R>,
+    matcher: Matcher<T, Context>,
+    mapper: RecordMapper<T>,
+    record: StringRecord,
+    headers: Option<StringRecord>,
+    /// Item index of the first row, used to turn indices into row numbers.
+    first_index: usize,
+    rows_read: usize,
+    /// Matches completed together with the one last returned.
+    ready: VecDeque<CsvMatch<T>>,
+    done: bool,
+}
+
+impl<R, T, Context> CsvMatcher<R, T, Context>
+where
+    R: Read,
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Match over the records of a CSV reader, mapping each record to an item.
+    pub fn new<F>(reader: Reader<R>, matcher: Matcher<T, Context>, mut mapper: F) -> Self
+    where
+        F: FnMut(&StringRecord) -> T + 'static,
+    {
+        Self::with_mapper(reader, matcher, move |record, _| Ok(mapper(record)))
+    }
+
+    fn with_mapper<F>(reader: Reader<R>, matcher: Matcher<T, Context>, mapper: F) -> Self
+    where
+        F: FnMut(&StringRecord, Option<&StringRecord>) -> io::Result<T> + 'static,
+    {
+        Self {
+            reader,
+            first_index: matcher.total_processed(),
+            matcher,
+            mapper: Box::new(mapper),
+            record: StringRecord::new(),
+            headers: None,
+            rows_read: 0,
+            ready: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Get a reference to the underlying matcher.
+    pub fn matcher(&self) -> &Matcher<T, Context> {
+        &self.matcher
+    }
+
+    /// Get a mutable reference to the underlying matcher.
+    pub fn matcher_mut(&mut self) -> &mut Matcher<T, Context> {
+        &mut self.matcher
+    }
+
+    /// Get the number of rows read so far, not counting the header row.
+    pub fn rows_read(&self) -> usize {
+        self.rows_read
+    }
+
+    /// Take back the reader and the matcher.
+    pub fn into_inner(self) -> (Reader<R>, Matcher<T, Context>) {
+        (self.reader, self.matcher)
+    }
+
+    /// Read and process the next row, returning `None` at the end of the data.
+    fn process_row(&mut self) -> Result<Option<Vec<Capture<T>>>, ReadError> {
+        if self.headers.is_none() && self.reader.has_headers() {
+            self.headers = Some(self.reader.headers().map_err(io::Error::from)?.clone());
+        }
+        if !self
+            .reader
+            .read_record(&mut self.record)
+            .map_err(io::Error::from)?
+        {
+            return Ok(None);
+        }
+        self.rows_read += 1;
+
+        let item = (self.mapper)(&self.record, self.headers.as_ref())?;
+        let completed = self
+            .matcher
+            .process_captures(item, self.matcher.epoch.elapsed())?;
+        Ok(Some(completed))
+    }
+
+    /// Attach the row number of the completing item to a capture.
+    fn located(&self, capture: Capture<T>) -> CsvMatch<T> {
+        CsvMatch {
+            row: capture.index.saturating_sub(self.first_index) + 1,
+            value: capture.item,
+        }
+    }
+}
+
+impl<R, T, Context> CsvMatcher<R, T, Context>
+where
+    R: Read,
+    T: Clone + PartialEq + fmt::Debug + DeserializeOwned,
+    Context: Clone + fmt::Debug,
+{
+    /// Match over the records of a CSV reader, deserializing each record into
+    /// an item with serde. Fields are matched to struct fields by header name
+    /// when the reader has headers.
+    pub fn deserialize(reader: Reader<R>, matcher: Matcher<T, Context>) -> Self {
+        Self::with_mapper(reader, matcher, |record, headers| {
+            record.deserialize(headers).map_err(io::Error::from)
+        })
+    }
+}
+
+impl<R, T, Context> Iterator for CsvMatcher<R, T, Context>
+where
+    R: Read,
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    type Item = Result<CsvMatch<T>, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(found) = self.ready.pop_front() {
+                return Some(Ok(found));
+            }
+            if self.done {
+                return None;
+            }
+            match self.process_row() {
+                Ok(Some(captures)) => {
+                    for capture in captures {
+                        let found = self.located(capture);
+                        self.ready.push_back(found);
+                    }
+                }
+                Ok(None) => {
+                    self.done = true;
+                    let held = self.matcher.pending.clone();
+                    let report = self.matcher.finish();
+                    return report
+                        .completed
+                        .zip(held)
+                        .map(|(value, held)| Capture {
+                            item: value,
+                            ..held
+                        })
+                        .map(|capture| Ok(self.located(capture)));
+                }
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
+
+impl<R, T, Context> fmt::Debug for CsvMatcher<R, T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CsvMatcher")
+            .field("matcher", &self.matcher)
+            .field("rows_read", &self.rows_read)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PatternElement;
+
+    const EXPORT: &str = "user,action\nann,login\nbob,logout\nann,login\nann,sudo\n";
+
+    #[test]
+    fn test_csv_matcher_maps_records() {
+        let mut matcher = Matcher::<String, ()>::new(5);
+        matcher.add_pattern(PatternElement::exact("login".to_string()));
+        matcher.add_pattern(PatternElement::exact("logout".to_string()));
+
+        let reader = Reader::from_reader(EXPORT.as_bytes());
+        let mut rows = CsvMatcher::new(reader, matcher, |record: &StringRecord| {
+            record[1].to_string()
+        });
+        assert_eq!(
+            rows.next().unwrap().unwrap(),
+            CsvMatch {
+                value: "logout".to_string(),
+                row: 2,
+            }
+        );
+        assert!(rows.next().is_none());
+        assert_eq!(rows.rows_read(), 4);
+    }
+
+    #[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+    struct Event {
+        user: String,
+        action: String,
+    }
+
+    #[test]
+    fn test_csv_matcher_deserializes_records() {
+        let mut matcher = Matcher::<Event, ()>::new(5);
+        matcher.add_pattern(PatternElement::predicate(|event: &Event| {
+            event.action == "login"
+        }));
+        matcher.add_pattern(PatternElement::predicate(|event: &Event| {
+            event.action == "sudo"
+        }));
+
+        let reader = Reader::from_reader(EXPORT.as_bytes());
+        let found: Vec<CsvMatch<Event>> = CsvMatcher::deserialize(reader, matcher)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].value.user, "ann");
+        assert_eq!(found[0].row, 4);
+
+        let reader = Reader::from_reader("user,action\nann\n".as_bytes());
+        let mut rows = CsvMatcher::deserialize(reader, Matcher::<Event, ()>::new(5));
+        assert!(matches!(rows.next(), Some(Err(ReadError::Io(_)))));
+    }
+}
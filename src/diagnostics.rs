@@ -0,0 +1,398 @@
+//! Checking a matcher's configuration before processing.
+//!
+//! Mistakes in a pattern, such as an element referring to an extractor that
+//! was never registered or a range whose bounds are swapped, otherwise only
+//! show up as an error on some later item, or as a pattern that silently never
+//! matches. [`Matcher::validate`] finds them up front and says how to fix
+//! each one.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Bound;
+
+use crate::{ErrorCode, ExtractorId, Matcher, PatternElement, RangeOrder};
+
+/// How serious a problem found by [`Matcher::validate`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The matcher works, but probably not as intended.
+    Warning,
+    /// The matcher fails or can never match.
+    Error,
+}
+
+/// A problem with a matcher's configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// No pattern elements are configured.
+    NoPatterns,
+    /// An element refers to an extractor that is not registered.
+    MissingExtractor(ExtractorId),
+    /// An element's minimum repeat count is above its maximum.
+    RepeatBounds {
+        /// Configured minimum repeat count.
+        min: usize,
+        /// Configured maximum repeat count.
+        max: usize,
+    },
+    /// A range element's minimum is above its maximum, so no item is in it.
+    EmptyRange,
+    /// Every element is optional, so the pattern can match without consuming
+    /// anything.
+    AllOptional,
+    /// The pattern needs more items than the window holds, so it can never
+    /// be satisfied within the window.
+    LongerThanWindow {
+        /// Fewest items the pattern matches.
+        length: usize,
+        /// Size of the matcher's window.
+        window_size: usize,
+    },
+    /// The pattern may match more items than the window holds, so its longer
+    /// matches cannot be satisfied within the window.
+    MayExceedWindow {
+        /// Most items the pattern matches, `usize::MAX` if unbounded.
+        length: usize,
+        /// Size of the matcher's window.
+        window_size: usize,
+    },
+}
+
+/// A problem found by [`Matcher::validate`], with the element it concerns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Index of the pattern element with the problem, if it concerns one.
+    pub element: Option<usize>,
+    /// The problem.
+    pub issue: Issue,
+}
+
+impl Diagnostic {
+    /// Get how serious the problem is.
+    pub fn severity(&self) -> Severity {
+        match self.issue {
+            Issue::AllOptional | Issue::MayExceedWindow { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    /// Get the error code of the error the problem leads to, or would lead to
+    /// if it were an error.
+    pub fn code(&self) -> ErrorCode {
+        match self.issue {
+            Issue::NoPatterns => ErrorCode::NoPatterns,
+            Issue::MissingExtractor(_) => ErrorCode::MissingExtractor,
+            _ => ErrorCode::InvalidPattern,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(element) = self.element {
+            write!(f, "element {}: ", element)?;
+        }
+        match &self.issue {
+            Issue::NoPatterns => write!(f, "no pattern elements; add some with add_pattern"),
+            Issue::MissingExtractor(id) => write!(
+                f,
+                "extractor {} is not registered; register it with register_extractor",
+                id
+            ),
+            Issue::RepeatBounds { min, max } => write!(
+                f,
+                "min_repeat {} is above max_repeat {}, so the element can never be satisfied",
+                min, max
+            ),
+            Issue::EmptyRange => write!(
+                f,
+                "the range minimum is above its maximum, so no item is in it; swap the bounds"
+            ),
+            Issue::AllOptional => write!(
+                f,
+                "every element is optional, so the pattern can match without consuming anything; \
+                 make at least one element required"
+            ),
+            Issue::LongerThanWindow {
+                length,
+                window_size,
+            } => write!(
+                f,
+                "the pattern needs at least {} items but the window holds {}; enlarge the window",
+                length, window_size
+            ),
+            Issue::MayExceedWindow {
+                length: usize::MAX,
+                window_size,
+            } => write!(
+                f,
+                "the pattern can match any number of items but the window holds {}; \
+                 bound the repeat counts",
+                window_size
+            ),
+            Issue::MayExceedWindow {
+                length,
+                window_size,
+            } => write!(
+                f,
+                "the pattern can match up to {} items but the window holds {}; \
+                 enlarge the window or lower the repeat counts",
+                length, window_size
+            ),
+        }
+    }
+}
+
+impl<T, Context> Matcher<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Check the configuration for problems, returning one diagnostic per
+    /// problem found, errors first.
+    ///
+    /// An empty result means no problem was found. Only top-level elements
+    /// are checked, not the elements nested inside them.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        if self.patterns.is_empty() {
+            diagnostics.push(Diagnostic {
+                element: None,
+                issue: Issue::NoPatterns,
+            });
+        }
+
+        for (element, pattern) in self.patterns.iter().enumerate() {
+            let settings = pattern.settings();
+            let mut report = |issue| {
+                diagnostics.push(Diagnostic {
+                    element: Some(element),
+                    issue,
+                })
+            };
+            if let Some(id) = settings.extractor_id {
+                if !self.extractors.contains_key(&id) {
+                    report(Issue::MissingExtractor(id));
+                }
+            }
+            if settings.min_repeat > settings.max_repeat {
+                report(Issue::RepeatBounds {
+                    min: settings.min_repeat,
+                    max: settings.max_repeat,
+                });
+            }
+            if is_empty_range(pattern) {
+                report(Issue::EmptyRange);
+            }
+        }
+
+        let consuming: Vec<_> = self
+            .patterns
+            .iter()
+            .filter(|pattern| !pattern.is_assertion())
+            .collect();
+        if !consuming.is_empty()
+            && consuming
+                .iter()
+                .all(|pattern| pattern.settings().repeat_bounds().0 == 0)
+        {
+            diagnostics.push(Diagnostic {
+                element: None,
+                issue: Issue::AllOptional,
+            });
+        }
+        let (min, max) = self.length_bounds();
+        let window_size = self.window_size;
+        if min > window_size {
+            diagnostics.push(Diagnostic {
+                element: None,
+                issue: Issue::LongerThanWindow {
+                    length: min,
+                    window_size,
+                },
+            });
+        } else if max > window_size {
+            diagnostics.push(Diagnostic {
+                element: None,
+                issue: Issue::MayExceedWindow {
+                    length: max,
+                    window_size,
+                },
+            });
+        }
+
+        diagnostics.sort_by_key(|diagnostic| std::cmp::Reverse(diagnostic.severity()));
+        diagnostics
+    }
+
+    /// Get the fewest and most items a match of the pattern consumes, the
+    /// most being `usize::MAX` if unbounded.
+    ///
+    /// Assertions consume nothing, and optional elements count as zero
+    /// towards the fewest.
+    pub fn length_bounds(&self) -> (usize, usize) {
+        self.patterns
+            .iter()
+            .filter(|pattern| !pattern.is_assertion())
+            .fold((0, 0), |(min, max), pattern| {
+                let (element_min, element_max) = pattern.settings().repeat_bounds();
+                (
+                    min.saturating_add(element_min),
+                    max.saturating_add(element_max),
+                )
+            })
+    }
+}
+
+/// Check whether a range element has a minimum above its maximum.
+fn is_empty_range<T, Context>(pattern: &PatternElement<T, Context>) -> bool
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    match pattern {
+        PatternElement::Range {
+            start,
+            end,
+            order,
+            negated: false,
+            ..
+        } => is_empty_between(order, start.as_ref(), end.as_ref()),
+        PatternElement::Ranges {
+            intervals, order, ..
+        } => intervals
+            .iter()
+            .any(|(min, max)| is_empty_between(order, Bound::Included(min), Bound::Included(max))),
+        _ => false,
+    }
+}
+
+/// Check whether no item lies between two bounds.
+///
+/// A range unbounded at either end is never empty, and one whose bounds are
+/// equal is empty unless it includes both.
+fn is_empty_between<T>(order: &RangeOrder<T>, start: Bound<&T>, end: Bound<&T>) -> bool {
+    let (start_value, end_value) = match (start, end) {
+        (
+            Bound::Included(start) | Bound::Excluded(start),
+            Bound::Included(end) | Bound::Excluded(end),
+        ) => (start, end),
+        _ => return false,
+    };
+    match order.compare(start_value, end_value) {
+        Some(Ordering::Less) => false,
+        Some(Ordering::Equal) => {
+            !(matches!(start, Bound::Included(_)) && matches!(end, Bound::Included(_)))
+        }
+        // NaN bounds only contain items under an ordering that accepts NaN
+        _ => !order.contains(start_value, start, end),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ElementSettings, ExtractorAction};
+
+    #[test]
+    fn test_validate_reports_problems() {
+        let mut matcher = Matcher::<i32, ()>::new(3);
+        assert_eq!(matcher.validate()[0].issue, Issue::NoPatterns);
+
+        let settings = ElementSettings {
+            extractor_id: Some(7),
+            min_repeat: 3,
+            max_repeat: 2,
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(1, settings));
+        matcher.add_pattern(PatternElement::range(9, 5));
+        matcher.add_pattern(PatternElement::outside(9, 5));
+
+        let diagnostics = matcher.validate();
+        let issues: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.element, diagnostic.issue.clone()))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                (Some(0), Issue::MissingExtractor(7)),
+                (Some(0), Issue::RepeatBounds { min: 3, max: 2 }),
+                (Some(1), Issue::EmptyRange),
+                (
+                    None,
+                    Issue::LongerThanWindow {
+                        length: 5,
+                        window_size: 3
+                    }
+                ),
+            ]
+        );
+        assert_eq!(diagnostics[0].code(), ErrorCode::MissingExtractor);
+        assert_eq!(diagnostics[3].severity(), Severity::Error);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "element 0: extractor 7 is not registered; register it with register_extractor"
+        );
+
+        matcher.register_extractor(7, |state| Ok(ExtractorAction::Extract(state.current_item)));
+        matcher.set_window_size(10);
+        assert_eq!(matcher.validate().len(), 2);
+    }
+
+    #[test]
+    fn test_length_bounds() {
+        let mut matcher = Matcher::<i32, ()>::new(4);
+        assert_eq!(matcher.length_bounds(), (0, 0));
+
+        let mut settings = ElementSettings {
+            min_repeat: 2,
+            max_repeat: 3,
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact_with_settings(2, settings.clone()));
+        matcher.add_pattern(PatternElement::lookahead(PatternElement::exact(3)));
+        assert_eq!(matcher.length_bounds(), (3, 4));
+        assert!(matcher.validate().is_empty());
+
+        settings.max_repeat = usize::MAX;
+        matcher.add_pattern(PatternElement::exact_with_settings(3, settings));
+        assert_eq!(matcher.length_bounds(), (5, usize::MAX));
+        matcher.set_window_size(5);
+        let diagnostics = matcher.validate();
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic {
+                element: None,
+                issue: Issue::MayExceedWindow {
+                    length: usize::MAX,
+                    window_size: 5
+                }
+            }]
+        );
+        assert_eq!(diagnostics[0].severity(), Severity::Warning);
+    }
+
+    #[test]
+    fn test_validate_all_optional() {
+        let settings = ElementSettings {
+            optional: true,
+            ..Default::default()
+        };
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.add_pattern(PatternElement::exact_with_settings(1, settings.clone()));
+        matcher.add_pattern(PatternElement::exact_with_settings(2, settings));
+        assert_eq!(
+            matcher.validate(),
+            vec![Diagnostic {
+                element: None,
+                issue: Issue::AllOptional
+            }]
+        );
+
+        matcher.add_pattern(PatternElement::exact(3));
+        assert!(matcher.validate().is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+//! Graphviz DOT rendering of a pattern's state machine.
+//!
+//! State `i` waits for pattern element `i`, and the final state accepts. Each
+//! element becomes an edge labelled with the element and its quantifier.
+//! Elements that may match zero items get a dashed skip edge, elements that
+//! may repeat get a loop, and assertions, which consume nothing, are dashed.
+
+use std::fmt::{self, Write};
+
+use crate::{PatternElement, RepeatMode};
+
+/// Render the state machine for a sequence of pattern elements.
+pub(crate) fn render<T, Context>(patterns: &[PatternElement<T, Context>]) -> String
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    let mut dot = String::from("digraph pattern {\n");
+    dot.push_str("    rankdir=LR;\n");
+    dot.push_str("    node [shape=circle];\n");
+    let _ = writeln!(dot, "    {} [shape=doublecircle];", patterns.len());
+
+    for (state, pattern) in patterns.iter().enumerate() {
+        let next = state + 1;
+        let settings = pattern.settings();
+        let (min, max) = settings.repeat_bounds();
+        let label = escape(&format!(
+            "{}{}",
+            pattern,
+            quantifier(min, max, settings.repeat_mode)
+        ));
+
+        if pattern.is_assertion() {
+            let _ = writeln!(
+                dot,
+                "    {} -> {} [label=\"{}\", style=dashed];",
+                state, next, label
+            );
+            continue;
+        }
+
+        let _ = writeln!(dot, "    {} -> {} [label=\"{}\"];", state, next, label);
+        if max > 1 {
+            let _ = writeln!(dot, "    {} -> {} [label=\"{}\"];", next, next, label);
+        }
+        if min == 0 {
+            let _ = writeln!(
+                dot,
+                "    {} -> {} [label=\"ε\", style=dashed];",
+                state, next
+            );
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// Describe repeat bounds with regex-style quantifier syntax.
+fn quantifier(min: usize, max: usize, mode: RepeatMode) -> String {
+    let bounds = match (min, max) {
+        (1, 1) => return String::new(),
+        (0, 1) => "?".to_string(),
+        (0, usize::MAX) => "*".to_string(),
+        (1, usize::MAX) => "+".to_string(),
+        (min, usize::MAX) => format!("{{{},}}", min),
+        (min, max) => format!("{{{},{}}}", min, max),
+    };
+    match mode {
+        RepeatMode::Greedy => bounds,
+        RepeatMode::Lazy => bounds + "?",
+    }
+}
+
+/// Escape a label for use inside a double-quoted DOT string.
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ElementSettings;
+
+    #[test]
+    fn test_render_sequence() {
+        let optional = ElementSettings {
+            optional: true,
+            ..Default::default()
+        };
+        let repeated = ElementSettings {
+            max_repeat: usize::MAX,
+            ..Default::default()
+        };
+        let patterns: Vec<PatternElement<&str, ()>> = vec![
+            PatternElement::exact("a"),
+            PatternElement::exact_with_settings("b", optional),
+            PatternElement::exact_with_settings("c", repeated),
+            PatternElement::lookahead(PatternElement::exact("d")),
+        ];
+
+        let dot = render(&patterns);
+        assert!(dot.starts_with("digraph pattern {\n"));
+        assert!(dot.contains("    4 [shape=doublecircle];\n"));
+        assert!(dot.contains("    0 -> 1 [label=\"Exact(\\\"a\\\")\"];\n"));
+        assert!(dot.contains("    1 -> 2 [label=\"Exact(\\\"b\\\")?\"];\n"));
+        assert!(dot.contains("    1 -> 2 [label=\"ε\", style=dashed];\n"));
+        assert!(dot.contains("    3 -> 3 [label=\"Exact(\\\"c\\\")+\"];\n"));
+        assert!(dot.contains("    3 -> 4 [label=\"Lookahead(Exact(\\\"d\\\"))\", style=dashed];\n"));
+    }
+
+    #[test]
+    fn test_quantifier() {
+        assert_eq!(quantifier(1, 1, RepeatMode::Greedy), "");
+        assert_eq!(quantifier(0, usize::MAX, RepeatMode::Lazy), "*?");
+        assert_eq!(quantifier(2, 5, RepeatMode::Greedy), "{2,5}");
+        assert_eq!(quantifier(3, usize::MAX, RepeatMode::Greedy), "{3,}");
+    }
+}
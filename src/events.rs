@@ -0,0 +1,255 @@
+//! Bounded event queue used to deliver match events to subscribers.
+//!
+//! A queue is shared between the matcher, which pushes events as matches
+//! complete, and any number of consumers, which may live on other threads.
+//! When the queue is full the configured [`OverflowPolicy`] decides what
+//! happens, so slow consumers degrade predictably instead of consuming
+//! unbounded memory.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// What to do when an event is pushed into a full queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Discard the oldest queued event to make room for the new one.
+    #[default]
+    DropOldest,
+    /// Discard the new event and keep the queued ones.
+    DropNewest,
+    /// Block the producer until a consumer makes room.
+    ///
+    /// The consumer must run on a different thread than the matcher. Once
+    /// every other handle to the queue is dropped, no consumer is left to make
+    /// room, so the new event is discarded instead.
+    Block,
+}
+
+/// A completed match delivered through an [`EventQueue`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchEvent<T> {
+    /// The value produced by the match.
+    pub value: T,
+    /// Zero-based stream index of the item that completed the match.
+    pub index: usize,
+    /// Timestamp of the item that completed the match.
+    pub timestamp: Duration,
+}
+
+struct QueueState<E> {
+    events: VecDeque<E>,
+    dropped: u64,
+    /// Number of live handles to the queue.
+    handles: usize,
+}
+
+struct Shared<E> {
+    state: Mutex<QueueState<E>>,
+    not_full: Condvar,
+    capacity: usize,
+    policy: OverflowPolicy,
+}
+
+/// A bounded, thread-safe queue of events.
+///
+/// Cloning the queue produces another handle to the same underlying buffer.
+pub struct EventQueue<E> {
+    shared: Arc<Shared<E>>,
+}
+
+impl<E> Clone for EventQueue<E> {
+    fn clone(&self) -> Self {
+        self.lock().handles += 1;
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<E> Drop for EventQueue<E> {
+    fn drop(&mut self) {
+        self.lock().handles -= 1;
+        // A blocked producer may now be the last handle
+        self.shared.not_full.notify_all();
+    }
+}
+
+impl<E> EventQueue<E> {
+    /// Create a queue holding at most `capacity` events (minimum 1).
+    pub fn bounded(capacity: usize, policy: OverflowPolicy) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(QueueState {
+                    events: VecDeque::with_capacity(capacity),
+                    dropped: 0,
+                    handles: 1,
+                }),
+                not_full: Condvar::new(),
+                capacity,
+                policy,
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState<E>> {
+        self.shared
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Push an event, applying the overflow policy if the queue is full.
+    pub fn push(&self, event: E) {
+        let mut state = self.lock();
+        if state.events.len() >= self.shared.capacity {
+            match self.shared.policy {
+                OverflowPolicy::DropOldest => {
+                    state.events.pop_front();
+                    state.dropped += 1;
+                }
+                OverflowPolicy::DropNewest => {
+                    state.dropped += 1;
+                    return;
+                }
+                OverflowPolicy::Block => {
+                    while state.events.len() >= self.shared.capacity {
+                        if state.handles == 1 {
+                            state.dropped += 1;
+                            return;
+                        }
+                        state = self
+                            .shared
+                            .not_full
+                            .wait(state)
+                            .unwrap_or_else(PoisonError::into_inner);
+                    }
+                }
+            }
+        }
+        state.events.push_back(event);
+    }
+
+    /// Remove and return the oldest queued event.
+    pub fn pop(&self) -> Option<E> {
+        let event = self.lock().events.pop_front();
+        if event.is_some() {
+            self.shared.not_full.notify_one();
+        }
+        event
+    }
+
+    /// Remove and return all queued events.
+    pub fn drain(&self) -> Vec<E> {
+        let events: Vec<E> = self.lock().events.drain(..).collect();
+        self.shared.not_full.notify_all();
+        events
+    }
+
+    /// Get the number of queued events.
+    pub fn len(&self) -> usize {
+        self.lock().events.len()
+    }
+
+    /// Check if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.lock().events.is_empty()
+    }
+
+    /// Get the maximum number of queued events.
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    /// Get the overflow policy.
+    pub fn policy(&self) -> OverflowPolicy {
+        self.shared.policy
+    }
+
+    /// Get the number of events discarded because the queue was full.
+    pub fn dropped_count(&self) -> u64 {
+        self.lock().dropped
+    }
+}
+
+impl<E> std::fmt::Debug for EventQueue<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.lock();
+        f.debug_struct("EventQueue")
+            .field("len", &state.events.len())
+            .field("capacity", &self.shared.capacity)
+            .field("policy", &self.shared.policy)
+            .field("dropped", &state.dropped)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_drop_oldest() {
+        let queue = EventQueue::bounded(2, OverflowPolicy::DropOldest);
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+
+        assert_eq!(queue.drain(), vec![2, 3]);
+        assert_eq!(queue.dropped_count(), 1);
+    }
+
+    #[test]
+    fn test_drop_newest() {
+        let queue = EventQueue::bounded(2, OverflowPolicy::DropNewest);
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+
+        assert_eq!(queue.drain(), vec![1, 2]);
+        assert_eq!(queue.dropped_count(), 1);
+    }
+
+    #[test]
+    fn test_block_waits_for_consumer() {
+        let queue = EventQueue::bounded(1, OverflowPolicy::Block);
+        queue.push(1);
+
+        let producer = {
+            let queue = queue.clone();
+            thread::spawn(move || queue.push(2))
+        };
+
+        // Consume until the blocked producer has delivered its event
+        let mut received = Vec::new();
+        while received.len() < 2 {
+            match queue.pop() {
+                Some(event) => received.push(event),
+                None => thread::yield_now(),
+            }
+        }
+        producer.join().unwrap();
+
+        assert_eq!(received, vec![1, 2]);
+        assert_eq!(queue.dropped_count(), 0);
+    }
+
+    #[test]
+    fn test_block_drops_once_consumers_are_gone() {
+        let queue = EventQueue::bounded(1, OverflowPolicy::Block);
+        queue.push(1);
+
+        let producer = {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                queue.push(2);
+                (queue.drain(), queue.dropped_count())
+            })
+        };
+        drop(queue);
+
+        assert_eq!(producer.join().unwrap(), (vec![1], 1));
+    }
+}
@@ -0,0 +1,190 @@
+//! Patterns fixed at compile time.
+//!
+//! A `Matcher` stores its elements in a vector and reaches predicates through
+//! shared function pointers, which lets patterns change at runtime. When the
+//! pattern never changes, a [`StaticMatcher`] takes it as a tuple of typed
+//! elements instead, so every element test is statically dispatched and can
+//! be inlined. Closures are elements too, without being boxed.
+//!
+//! A static matcher follows the default `ResetPolicy::ResetToStart`: the
+//! item that breaks an attempt is discarded, and a completed match reports
+//! the item that completed it.
+//!
+//! ```rust
+//! use scrolling_window_pattern_matcher::fixed::{Exact, InRange, StaticMatcher};
+//!
+//! let mut matcher = StaticMatcher::new((Exact(1), InRange(5, 9), |item: &i32| item % 2 == 0));
+//! assert_eq!(matcher.process_items(vec![1, 7, 4, 1, 3, 1, 5, 8]), vec![4, 8]);
+//! ```
+
+use std::marker::PhantomData;
+
+/// A pattern element tested with static dispatch.
+pub trait StaticElement<T> {
+    /// Check if the element matches the item.
+    fn matches(&self, item: &T) -> bool;
+}
+
+/// Matches a specific value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Exact<T>(pub T);
+
+impl<T: PartialEq> StaticElement<T> for Exact<T> {
+    fn matches(&self, item: &T) -> bool {
+        *item == self.0
+    }
+}
+
+/// Matches values within the inclusive range from the first value to the
+/// second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InRange<T>(pub T, pub T);
+
+impl<T: PartialOrd> StaticElement<T> for InRange<T> {
+    fn matches(&self, item: &T) -> bool {
+        *item >= self.0 && *item <= self.1
+    }
+}
+
+impl<T, F> StaticElement<T> for F
+where
+    F: Fn(&T) -> bool,
+{
+    fn matches(&self, item: &T) -> bool {
+        self(item)
+    }
+}
+
+/// A fixed sequence of elements, implemented for tuples of up to eight.
+pub trait ElementChain<T> {
+    /// Number of elements in the sequence.
+    const LEN: usize;
+
+    /// Check if the element at `position` matches the item.
+    fn matches_at(&self, position: usize, item: &T) -> bool;
+}
+
+macro_rules! impl_element_chain {
+    ($len:expr; $($element:ident $index:tt),+) => {
+        impl<T, $($element: StaticElement<T>),+> ElementChain<T> for ($($element,)+) {
+            const LEN: usize = $len;
+
+            fn matches_at(&self, position: usize, item: &T) -> bool {
+                match position {
+                    $($index => self.$index.matches(item),)+
+                    _ => false,
+                }
+            }
+        }
+    };
+}
+
+impl_element_chain!(1; A 0);
+impl_element_chain!(2; A 0, B 1);
+impl_element_chain!(3; A 0, B 1, C 2);
+impl_element_chain!(4; A 0, B 1, C 2, D 3);
+impl_element_chain!(5; A 0, B 1, C 2, D 3, E 4);
+impl_element_chain!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+impl_element_chain!(7; A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_element_chain!(8; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+/// Matches a pattern fixed at compile time against a stream of items.
+#[derive(Debug, Clone)]
+pub struct StaticMatcher<T, P> {
+    pattern: P,
+    current_position: usize,
+    total_processed: usize,
+    _item: PhantomData<fn(&T)>,
+}
+
+impl<T, P> StaticMatcher<T, P>
+where
+    P: ElementChain<T>,
+{
+    /// Create a matcher for a tuple of elements.
+    pub fn new(pattern: P) -> Self {
+        Self {
+            pattern,
+            current_position: 0,
+            total_processed: 0,
+            _item: PhantomData,
+        }
+    }
+
+    /// Process a single item, returning it if it completes a match.
+    pub fn process_item(&mut self, item: T) -> Option<T> {
+        self.total_processed += 1;
+        if !self.pattern.matches_at(self.current_position, &item) {
+            self.current_position = 0;
+            return None;
+        }
+        self.current_position += 1;
+        if self.current_position < P::LEN {
+            return None;
+        }
+        self.current_position = 0;
+        Some(item)
+    }
+
+    /// Process multiple items and return the item that completed each match.
+    pub fn process_items<I>(&mut self, items: I) -> Vec<T>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        items
+            .into_iter()
+            .filter_map(|item| self.process_item(item))
+            .collect()
+    }
+
+    /// Get the index of the next element waiting for an item.
+    pub fn current_position(&self) -> usize {
+        self.current_position
+    }
+
+    /// Get the total number of items processed.
+    pub fn total_processed(&self) -> usize {
+        self.total_processed
+    }
+
+    /// Get the number of elements in the pattern.
+    pub fn pattern_count(&self) -> usize {
+        P::LEN
+    }
+
+    /// Abandon any match in progress and start the stream over.
+    pub fn reset(&mut self) {
+        self.current_position = 0;
+        self.total_processed = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Matcher, PatternElement};
+
+    #[test]
+    fn test_static_matcher_agrees_with_matcher() {
+        let mut seed = 7u32;
+        let items: Vec<i32> = (0..500)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (seed >> 16) as i32 % 12
+            })
+            .collect();
+
+        let mut dynamic = Matcher::<i32, ()>::new(0);
+        dynamic.add_pattern(PatternElement::exact(3));
+        dynamic.add_pattern(PatternElement::range(4, 9));
+        dynamic.add_pattern(PatternElement::predicate(|item: &i32| item % 2 == 1));
+        let expected = dynamic.process_items(items.clone()).unwrap();
+
+        let mut fixed = StaticMatcher::new((Exact(3), InRange(4, 9), |item: &i32| item % 2 == 1));
+        assert_eq!(fixed.pattern_count(), 3);
+        let found = fixed.process_items(items);
+        assert!(!found.is_empty());
+        assert_eq!(found, expected);
+        assert_eq!(fixed.total_processed(), 500);
+    }
+}
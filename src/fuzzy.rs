@@ -0,0 +1,140 @@
+//! Approximate string matching by edit distance.
+//!
+//! Tokens from OCR output or noisy logs are often a character or two away
+//! from the expected spelling. [`FuzzyString`] matches items within a maximum
+//! optimal string alignment distance, a Damerau-Levenshtein variant that
+//! counts insertions, deletions, substitutions, and adjacent transpositions.
+
+use std::fmt;
+
+use crate::{ElementSettings, Matchable, MatcherError, PatternElement};
+
+/// Matches string items within an edit distance of an expected value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyString {
+    expected: Vec<char>,
+    max_distance: usize,
+}
+
+impl FuzzyString {
+    /// Create a matcher for strings at most `max_distance` edits from `expected`.
+    pub fn new(expected: &str, max_distance: usize) -> Self {
+        Self {
+            expected: expected.chars().collect(),
+            max_distance,
+        }
+    }
+
+    /// Check if a string is within the maximum distance.
+    pub fn is_match(&self, value: &str) -> bool {
+        let value: Vec<char> = value.chars().collect();
+        // The length difference alone is a lower bound on the distance
+        if value.len().abs_diff(self.expected.len()) > self.max_distance {
+            return false;
+        }
+        distance(&self.expected, &value) <= self.max_distance
+    }
+}
+
+impl<T> Matchable<T> for FuzzyString
+where
+    T: AsRef<str>,
+{
+    fn matches(&self, item: &T) -> Result<bool, MatcherError> {
+        Ok(self.is_match(item.as_ref()))
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "Fuzzy({:?}, {})",
+            self.expected.iter().collect::<String>(),
+            self.max_distance
+        )
+    }
+}
+
+/// Optimal string alignment distance between two character sequences.
+fn distance(a: &[char], b: &[char]) -> usize {
+    // Three rolling rows: two back, previous, and current
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current: Vec<usize> = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(before[j - 2] + 1);
+            }
+            current[j] = best;
+        }
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+impl<T, Context> PatternElement<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug + AsRef<str>,
+    Context: Clone + fmt::Debug,
+{
+    /// Create a new pattern element matching strings within `max_distance`
+    /// edits of `value`.
+    pub fn fuzzy(value: &str, max_distance: usize) -> Self {
+        Self::custom(FuzzyString::new(value, max_distance))
+    }
+
+    /// Create a new fuzzy string pattern element with settings.
+    pub fn fuzzy_with_settings(
+        value: &str,
+        max_distance: usize,
+        settings: ElementSettings<Context>,
+    ) -> Self {
+        Self::custom_with_settings(FuzzyString::new(value, max_distance), settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Matcher;
+
+    fn chars(value: &str) -> Vec<char> {
+        value.chars().collect()
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance(&chars("timeout"), &chars("timeout")), 0);
+        assert_eq!(distance(&chars("timeout"), &chars("tinneout")), 2);
+        assert_eq!(distance(&chars("timeout"), &chars("tiemout")), 1);
+        assert_eq!(distance(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(distance(&chars(""), &chars("abc")), 3);
+    }
+
+    #[test]
+    fn test_fuzzy_elements() {
+        let mut matcher = Matcher::<String, ()>::new(10);
+        matcher.add_pattern(PatternElement::fuzzy("connection", 1));
+        matcher.add_pattern(PatternElement::fuzzy("timeout", 2));
+
+        let tokens = [
+            "conection",
+            "tirneout",
+            "connection",
+            "time",
+            "connectoin",
+            "timeuot",
+        ];
+        let results: Vec<String> = tokens
+            .iter()
+            .filter_map(|token| matcher.process_item(token.to_string()).unwrap())
+            .collect();
+        assert_eq!(results, vec!["tirneout".to_string(), "timeuot".to_string()]);
+    }
+}
@@ -0,0 +1,123 @@
+//! Glob pattern elements for streams of paths.
+//!
+//! File-event pipelines often need "any Rust source file" or "anything under
+//! `target/`" as a pattern step. [`GlobPattern`] compiles a shell-style glob
+//! with `globset` and matches items that can be viewed as paths.
+
+use std::fmt;
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobMatcher};
+
+use crate::{ElementSettings, Matchable, MatcherError, PatternElement};
+
+/// A compiled glob, matching items that can be viewed as paths.
+///
+/// `*` and `?` do not match the path separator, while `**` matches any number
+/// of directories, so `**/*.rs` matches both `lib.rs` and `src/lib.rs`.
+#[derive(Debug, Clone)]
+pub struct GlobPattern {
+    pattern: String,
+    matcher: GlobMatcher,
+}
+
+impl GlobPattern {
+    /// Compile a glob pattern.
+    pub fn new(pattern: &str) -> Result<Self, MatcherError> {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|err| MatcherError::InvalidPattern(err.to_string()))?;
+        Ok(Self {
+            pattern: pattern.to_string(),
+            matcher: glob.compile_matcher(),
+        })
+    }
+
+    /// Get the source of the glob.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Check if a path matches the glob.
+    pub fn is_match(&self, path: impl AsRef<Path>) -> bool {
+        self.matcher.is_match(path)
+    }
+}
+
+impl<T> Matchable<T> for GlobPattern
+where
+    T: AsRef<Path>,
+{
+    fn matches(&self, item: &T) -> Result<bool, MatcherError> {
+        Ok(self.is_match(item))
+    }
+
+    fn describe(&self) -> String {
+        format!("Glob({:?})", self.pattern)
+    }
+}
+
+impl<T, Context> PatternElement<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug + AsRef<Path>,
+    Context: Clone + fmt::Debug,
+{
+    /// Create a new pattern element matching paths against a glob.
+    pub fn glob(pattern: &str) -> Result<Self, MatcherError> {
+        Ok(Self::custom(GlobPattern::new(pattern)?))
+    }
+
+    /// Create a new glob pattern element with settings.
+    pub fn glob_with_settings(
+        pattern: &str,
+        settings: ElementSettings<Context>,
+    ) -> Result<Self, MatcherError> {
+        Ok(Self::custom_with_settings(
+            GlobPattern::new(pattern)?,
+            settings,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Matcher;
+
+    #[test]
+    fn test_glob_pattern() {
+        let glob = GlobPattern::new("**/*.rs").unwrap();
+        assert!(glob.is_match("lib.rs"));
+        assert!(glob.is_match("src/bin/main.rs"));
+        assert!(!glob.is_match("src/lib.rs.bak"));
+
+        let glob = GlobPattern::new("src/*.rs").unwrap();
+        assert!(glob.is_match("src/lib.rs"));
+        assert!(!glob.is_match("src/bin/main.rs"));
+
+        assert!(matches!(
+            GlobPattern::new("src/[.rs"),
+            Err(MatcherError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_glob_elements() {
+        // A source edit followed by a rebuild of the target directory
+        let mut matcher = Matcher::<String, ()>::new(10);
+        matcher.add_pattern(PatternElement::glob("**/*.rs").unwrap());
+        matcher.add_pattern(PatternElement::glob("target/**").unwrap());
+
+        let events = ["README.md", "src/io.rs", "target/debug/app", "src/lib.rs"];
+        let results: Vec<String> = events
+            .iter()
+            .filter_map(|event| matcher.process_item(event.to_string()).unwrap())
+            .collect();
+        assert_eq!(results, vec!["target/debug/app".to_string()]);
+        assert_eq!(
+            matcher.patterns()[0].to_string(),
+            "Custom(Glob(\"**/*.rs\"))".to_string()
+        );
+    }
+}
@@ -0,0 +1,746 @@
+//! Running several independent matchers over one stream.
+//!
+//! A [`MatcherGroup`] offers every item to each of its matchers and merges
+//! their matches, tagging each with the ID of the matcher that found it.
+//! Matchers are held as [`StreamMatcher`] trait objects, so a group can mix
+//! matchers with different context types, and each matcher's context is
+//! reachable through the group by the type it was created with.
+//!
+//! Groups of many matchers can build a prefilter that indexes the matchers by
+//! their first element, so an item is only offered to the matchers it could
+//! start and to those with a match in progress.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::Bound;
+use std::time::{Duration, Instant};
+
+use crate::{Capture, Matcher, MatcherError, PatternElement, RangeOrder, Selection};
+
+/// Identifier of a matcher within a `MatcherGroup`.
+pub type MatcherId = u32;
+
+/// The items that can start a match of a [`StreamMatcher`].
+#[derive(Debug, Clone)]
+pub enum FirstElement<T> {
+    /// Any item may start a match, or the matcher must see every item.
+    Any,
+    /// Only items equal to the value can start a match.
+    Exact(T),
+    /// Only items `order` places between `start` and `end` can start a match.
+    Range {
+        /// Lower bound of the range.
+        start: Bound<T>,
+        /// Upper bound of the range.
+        end: Bound<T>,
+        /// Ordering of items against the bounds.
+        order: RangeOrder<T>,
+    },
+}
+
+/// A matcher that can be driven through a trait object.
+pub trait StreamMatcher<T> {
+    /// Process an item observed at the given timestamp, returning the
+    /// captures of the matches it completes.
+    fn process_captures_at(
+        &mut self,
+        item: T,
+        timestamp: Duration,
+    ) -> Result<Vec<Capture<T>>, MatcherError>;
+
+    /// Get the name of the pattern, if it has one.
+    fn pattern_name(&self) -> Option<&str>;
+
+    /// Record that a named pattern completed at the given stream position.
+    fn record_completion(&mut self, pattern: &str, index: usize, timestamp: Duration);
+
+    /// Reset the matcher state.
+    fn reset(&mut self);
+
+    /// Get the matcher's context, if it has one.
+    fn context_any(&self) -> Option<&dyn Any> {
+        None
+    }
+
+    /// Get mutable access to the matcher's context, if it has one.
+    fn context_any_mut(&mut self) -> Option<&mut dyn Any> {
+        None
+    }
+
+    /// Get what happens to items between the items of a match, if the
+    /// matcher supports selection strategies.
+    fn selection(&self) -> Option<Selection> {
+        None
+    }
+
+    /// Choose what happens to items between the items of a match, returning
+    /// whether the matcher supports selection strategies.
+    fn set_selection(&mut self, _selection: Selection) -> bool {
+        false
+    }
+
+    /// Describe the items that can start a match.
+    ///
+    /// A group with a prefilter only offers an idle matcher the items its
+    /// first element accepts and passes the rest to `skip_items`, so anything
+    /// narrower than `FirstElement::Any` must make skipping an item equivalent
+    /// to processing it.
+    fn first_element(&self) -> FirstElement<T> {
+        FirstElement::Any
+    }
+
+    /// Check if the matcher has no match in progress.
+    fn is_idle(&self) -> bool {
+        false
+    }
+
+    /// Account for items a group did not offer because they could not start
+    /// a match, the last of them observed at the given timestamp.
+    fn skip_items(&mut self, _count: usize, _timestamp: Duration) {}
+}
+
+impl<T, Context> StreamMatcher<T> for Matcher<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug + 'static,
+{
+    fn process_captures_at(
+        &mut self,
+        item: T,
+        timestamp: Duration,
+    ) -> Result<Vec<Capture<T>>, MatcherError> {
+        self.process_captures(item, timestamp)
+    }
+
+    fn pattern_name(&self) -> Option<&str> {
+        self.name()
+    }
+
+    fn record_completion(&mut self, pattern: &str, index: usize, timestamp: Duration) {
+        Matcher::record_completion(self, pattern, index, timestamp);
+    }
+
+    fn reset(&mut self) {
+        Matcher::reset(self);
+    }
+
+    fn context_any(&self) -> Option<&dyn Any> {
+        self.context().map(|context| context as &dyn Any)
+    }
+
+    fn context_any_mut(&mut self) -> Option<&mut dyn Any> {
+        self.context_mut().map(|context| context as &mut dyn Any)
+    }
+
+    fn selection(&self) -> Option<Selection> {
+        Some(Matcher::selection(self))
+    }
+
+    fn set_selection(&mut self, selection: Selection) -> bool {
+        Matcher::set_selection(self, selection);
+        true
+    }
+
+    fn first_element(&self) -> FirstElement<T> {
+        // Items that cannot start a match still feed the window, sessions,
+        // observers, replay and rate elements
+        let skippable = self.window_size == 0
+            && self.session_gap.is_none()
+            && self.observers.is_empty()
+            && self.replay_skip == 0
+            && !self
+                .patterns
+                .iter()
+                .any(|pattern| matches!(pattern, PatternElement::Rate { .. }));
+        let Some(first) = self.patterns.first().filter(|_| skippable) else {
+            return FirstElement::Any;
+        };
+        if first.settings().repeat_bounds().0 == 0 {
+            return FirstElement::Any;
+        }
+        match first {
+            PatternElement::Exact { value, .. } => FirstElement::Exact(value.clone()),
+            PatternElement::Range {
+                start,
+                end,
+                order,
+                negated: false,
+                ..
+            } => FirstElement::Range {
+                start: start.clone(),
+                end: end.clone(),
+                order: order.clone(),
+            },
+            _ => FirstElement::Any,
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.current_position == 0
+            && self.repeat_count == 0
+            && self.captured.is_empty()
+            && self.pending.is_none()
+            && self.alternatives.is_empty()
+            && self.forks.is_empty()
+    }
+
+    fn skip_items(&mut self, count: usize, timestamp: Duration) {
+        self.total_processed += count;
+        self.stats.items_processed += count;
+        self.last_timestamp = timestamp;
+    }
+}
+
+/// A match found by one of the matchers in a group.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupMatch<T> {
+    /// ID of the matcher that found the match.
+    pub matcher: MatcherId,
+    /// Name of the matcher's pattern, if it has one.
+    pub pattern: Option<String>,
+    /// The value produced by the match.
+    pub value: T,
+    /// Zero-based stream index of the item that completed the match.
+    pub index: usize,
+}
+
+/// Finds the positions of the matchers an item could start a match in.
+type Prefilter<T> = Box<dyn Fn(&T) -> Vec<usize>>;
+
+/// A matcher in a group.
+struct Member<T> {
+    id: MatcherId,
+    matcher: Box<dyn StreamMatcher<T>>,
+    /// Number of the group's items the matcher has processed or skipped.
+    seen: usize,
+}
+
+/// Fans each item out to several matchers and merges their matches.
+///
+/// Completions of named patterns are passed on to the other matchers, so a
+/// pattern with `PatternSettings::after` can follow a pattern in another
+/// matcher of the group. Matchers are offered each item in the order they
+/// were added, so a match may begin on the very item that completed a
+/// pattern only if its matcher was added later.
+pub struct MatcherGroup<T> {
+    members: Vec<Member<T>>,
+    next_id: MatcherId,
+    epoch: Instant,
+    total_processed: usize,
+    last_timestamp: Duration,
+    prefilter: Option<Prefilter<T>>,
+    /// Positions of the matchers with a match in progress, while prefiltering.
+    active: Vec<usize>,
+}
+
+impl<T> MatcherGroup<T>
+where
+    T: Clone,
+{
+    /// Create an empty group.
+    pub fn new() -> Self {
+        Self {
+            members: Vec::new(),
+            next_id: 0,
+            epoch: Instant::now(),
+            total_processed: 0,
+            last_timestamp: Duration::ZERO,
+            prefilter: None,
+            active: Vec::new(),
+        }
+    }
+
+    /// Add a matcher to the group, returning its ID.
+    ///
+    /// Drops the prefilter, if the group has one.
+    pub fn add<M>(&mut self, matcher: M) -> MatcherId
+    where
+        M: StreamMatcher<T> + 'static,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.members.push(Member {
+            id,
+            matcher: Box::new(matcher),
+            seen: self.total_processed,
+        });
+        self.clear_prefilter();
+        id
+    }
+
+    /// Remove a matcher from the group, returning whether it was present.
+    ///
+    /// Drops the prefilter, if the group has one.
+    pub fn remove(&mut self, id: MatcherId) -> bool {
+        let before = self.members.len();
+        self.members.retain(|member| member.id != id);
+        self.clear_prefilter();
+        self.members.len() != before
+    }
+
+    /// Get the number of matchers in the group.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Check if the group has no matchers.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Get the IDs of the matchers in the group, in the order they were added.
+    pub fn ids(&self) -> Vec<MatcherId> {
+        self.members.iter().map(|member| member.id).collect()
+    }
+
+    /// Get the context of a matcher in the group, if it has a context of
+    /// type `C`.
+    pub fn context<C: 'static>(&self, id: MatcherId) -> Option<&C> {
+        self.members
+            .iter()
+            .find(|member| member.id == id)?
+            .matcher
+            .context_any()?
+            .downcast_ref()
+    }
+
+    /// Get mutable access to the context of a matcher in the group, if it has
+    /// a context of type `C`.
+    pub fn context_mut<C: 'static>(&mut self, id: MatcherId) -> Option<&mut C> {
+        self.members
+            .iter_mut()
+            .find(|member| member.id == id)?
+            .matcher
+            .context_any_mut()?
+            .downcast_mut()
+    }
+
+    /// Get what happens to items between the items of a match of one matcher
+    /// in the group.
+    ///
+    /// Each matcher has its own strategy, so strict framing patterns and
+    /// relaxed correlation patterns can share a stream.
+    pub fn selection(&self, id: MatcherId) -> Option<Selection> {
+        self.members
+            .iter()
+            .find(|member| member.id == id)?
+            .matcher
+            .selection()
+    }
+
+    /// Choose what happens to items between the items of a match of one
+    /// matcher in the group, returning whether the group has a matcher with
+    /// the ID that supports selection strategies.
+    pub fn set_selection(&mut self, id: MatcherId, selection: Selection) -> bool {
+        self.members
+            .iter_mut()
+            .find(|member| member.id == id)
+            .is_some_and(|member| member.matcher.set_selection(selection))
+    }
+
+    /// Drop the prefilter, so that every matcher is offered every item again.
+    pub fn clear_prefilter(&mut self) {
+        self.prefilter = None;
+        self.active.clear();
+    }
+
+    /// Check if the group has a prefilter.
+    pub fn has_prefilter(&self) -> bool {
+        self.prefilter.is_some()
+    }
+
+    /// Process a single item with every matcher.
+    pub fn process_item(&mut self, item: T) -> Result<Vec<GroupMatch<T>>, MatcherError> {
+        let timestamp = self.epoch.elapsed();
+        self.process_item_at(item, timestamp)
+    }
+
+    /// Process a single item observed at the given timestamp with every matcher.
+    ///
+    /// Every matcher sees the item even if another fails on it, so that they
+    /// stay aligned on the stream; the first error is then returned. With a
+    /// prefilter, matchers the item cannot start a match in skip it instead.
+    pub fn process_item_at(
+        &mut self,
+        item: T,
+        timestamp: Duration,
+    ) -> Result<Vec<GroupMatch<T>>, MatcherError> {
+        let index = self.total_processed;
+        self.total_processed += 1;
+        let mut found = Vec::new();
+        let mut first_error = None;
+
+        let positions: Vec<usize> = match &self.prefilter {
+            Some(prefilter) => {
+                let mut positions = prefilter(&item);
+                positions.extend(&self.active);
+                positions.sort_unstable();
+                positions.dedup();
+                positions
+            }
+            None => (0..self.members.len()).collect(),
+        };
+
+        for &position in &positions {
+            let member = &mut self.members[position];
+            let skipped = index - member.seen;
+            if skipped > 0 {
+                member.matcher.skip_items(skipped, self.last_timestamp);
+            }
+            member.seen = index + 1;
+
+            let id = member.id;
+            let matcher = &mut member.matcher;
+            match matcher.process_captures_at(item.clone(), timestamp) {
+                Ok(captures) => {
+                    let name = matcher.pattern_name().map(str::to_string);
+                    for capture in captures {
+                        if let Some(name) = &name {
+                            self.share_completion(id, name, &capture);
+                        }
+                        found.push(GroupMatch {
+                            matcher: id,
+                            pattern: name.clone(),
+                            value: capture.item,
+                            index: capture.index,
+                        });
+                    }
+                }
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+
+        if self.prefilter.is_some() {
+            self.active = positions
+                .into_iter()
+                .filter(|&position| !self.members[position].matcher.is_idle())
+                .collect();
+        }
+        self.last_timestamp = timestamp;
+
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(found),
+        }
+    }
+
+    /// Process a batch of items with every matcher.
+    pub fn process_items(&mut self, items: Vec<T>) -> Result<Vec<GroupMatch<T>>, MatcherError> {
+        let mut results = Vec::new();
+        for item in items {
+            results.extend(self.process_item(item)?);
+        }
+        Ok(results)
+    }
+
+    /// Get the total number of items processed.
+    pub fn total_processed(&self) -> usize {
+        self.total_processed
+    }
+
+    /// Reset every matcher in the group.
+    pub fn reset(&mut self) {
+        for member in &mut self.members {
+            member.matcher.reset();
+            member.seen = 0;
+        }
+        self.total_processed = 0;
+        self.last_timestamp = Duration::ZERO;
+        self.active.clear();
+    }
+
+    /// Tell the other matchers that a named pattern completed.
+    fn share_completion(&mut self, source: MatcherId, name: &str, capture: &Capture<T>) {
+        for member in &mut self.members {
+            if member.id != source {
+                member
+                    .matcher
+                    .record_completion(name, capture.index, capture.timestamp);
+            }
+        }
+    }
+}
+
+impl<T> MatcherGroup<T>
+where
+    T: Clone + Eq + Hash + 'static,
+{
+    /// Index the matchers by the items that can start their matches, so each
+    /// item is only offered to the matchers it could start a match in and to
+    /// those with a match in progress.
+    ///
+    /// Matchers whose pattern begins with an exact value are found with a
+    /// hash lookup and those beginning with a range by testing the range.
+    /// Any other matcher is offered every item. Adding or removing a matcher
+    /// drops the prefilter, so build it once the group is complete.
+    pub fn build_prefilter(&mut self) {
+        let mut exact: HashMap<T, Vec<usize>> = HashMap::new();
+        let mut ranges = Vec::new();
+        let mut always = Vec::new();
+        for (position, member) in self.members.iter().enumerate() {
+            match member.matcher.first_element() {
+                FirstElement::Exact(value) => exact.entry(value).or_default().push(position),
+                FirstElement::Range { start, end, order } => {
+                    ranges.push((start, end, order, position))
+                }
+                FirstElement::Any => always.push(position),
+            }
+        }
+        self.active = (0..self.members.len())
+            .filter(|&position| !self.members[position].matcher.is_idle())
+            .collect();
+        self.prefilter = Some(Box::new(move |item: &T| {
+            let mut positions = always.clone();
+            if let Some(starting) = exact.get(item) {
+                positions.extend(starting);
+            }
+            positions.extend(
+                ranges
+                    .iter()
+                    .filter(|(start, end, order, _)| {
+                        order.contains(item, start.as_ref(), end.as_ref())
+                    })
+                    .map(|(_, _, _, position)| *position),
+            );
+            positions
+        }));
+    }
+}
+
+impl<T> Default for MatcherGroup<T>
+where
+    T: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for MatcherGroup<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MatcherGroup")
+            .field(
+                "ids",
+                &self
+                    .members
+                    .iter()
+                    .map(|member| member.id)
+                    .collect::<Vec<_>>(),
+            )
+            .field("total_processed", &self.total_processed)
+            .field("prefiltered", &self.prefilter.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ElementSettings, ExtractorError, PatternElement, PatternSettings, StreamOffset};
+
+    #[test]
+    fn test_group_tags_matches() {
+        let mut high = Matcher::<i32, ()>::new(10);
+        high.add_pattern(PatternElement::range(90, 100));
+
+        // A matcher with a different context type
+        let mut pair = Matcher::<i32, String>::new(10);
+        pair.set_context("pairs".to_string());
+        pair.set_name("pairs");
+        pair.add_pattern(PatternElement::exact(1));
+        pair.add_pattern(PatternElement::exact(2));
+
+        let mut group = MatcherGroup::new();
+        let high_id = group.add(high);
+        let pair_id = group.add(pair);
+
+        let found = group.process_items(vec![1, 95, 1, 2]).unwrap();
+        assert_eq!(
+            found,
+            vec![
+                GroupMatch {
+                    matcher: high_id,
+                    pattern: None,
+                    value: 95,
+                    index: 1,
+                },
+                GroupMatch {
+                    matcher: pair_id,
+                    pattern: Some("pairs".to_string()),
+                    value: 2,
+                    index: 3,
+                },
+            ]
+        );
+
+        assert!(group.remove(high_id));
+        assert!(!group.remove(high_id));
+        assert_eq!(group.ids(), vec![pair_id]);
+    }
+
+    #[test]
+    fn test_group_pattern_contexts() {
+        let mut errors = Matcher::<i32, Vec<i32>>::new(10).with_context(Vec::new());
+        errors.add_pattern(PatternElement::range(500, 599));
+        let mut logins = Matcher::<i32, usize>::new(10).with_context(0);
+        logins.add_pattern(PatternElement::exact(1));
+
+        let mut group = MatcherGroup::new();
+        let errors_id = group.add(errors);
+        let logins_id = group.add(logins);
+
+        for found in group.process_items(vec![1, 503, 1, 200]).unwrap() {
+            if found.matcher == errors_id {
+                group
+                    .context_mut::<Vec<i32>>(errors_id)
+                    .unwrap()
+                    .push(found.value);
+            } else {
+                *group.context_mut::<usize>(logins_id).unwrap() += 1;
+            }
+        }
+        assert_eq!(group.context::<Vec<i32>>(errors_id), Some(&vec![503]));
+        assert_eq!(group.context::<usize>(logins_id), Some(&2));
+
+        // The context is only reachable by its own type
+        assert_eq!(group.context::<usize>(errors_id), None);
+    }
+
+    #[test]
+    fn test_group_shares_completions() {
+        let mut recon = Matcher::<&str, ()>::new(10);
+        recon.set_name("recon");
+        recon.add_pattern(PatternElement::exact("scan"));
+
+        let mut exploit = Matcher::<&str, ()>::new(10);
+        exploit
+            .set_pattern_settings(PatternSettings::default().after("recon", StreamOffset::Item(2)));
+        exploit.add_pattern(PatternElement::exact("exploit"));
+
+        let mut group = MatcherGroup::new();
+        group.add(recon);
+        let exploit_id = group.add(exploit);
+
+        let found = group
+            .process_items(vec!["exploit", "scan", "exploit", "x", "x", "exploit"])
+            .unwrap();
+        let alerts: Vec<usize> = found
+            .iter()
+            .filter(|found| found.matcher == exploit_id)
+            .map(|found| found.index)
+            .collect();
+        assert_eq!(alerts, vec![2]);
+    }
+
+    #[test]
+    fn test_group_selection_per_matcher() {
+        let pair = || {
+            let mut matcher = Matcher::<char, ()>::new(10);
+            matcher.add_pattern(PatternElement::exact('a'));
+            matcher.add_pattern(PatternElement::exact('b'));
+            matcher
+        };
+        let mut group = MatcherGroup::new();
+        let framing = group.add(pair());
+        let correlation = group.add(pair());
+        assert!(group.set_selection(correlation, Selection::SkipTillNextMatch));
+        assert!(!group.set_selection(99, Selection::SkipTillNextMatch));
+        assert_eq!(group.selection(framing), Some(Selection::Strict));
+        assert_eq!(
+            group.selection(correlation),
+            Some(Selection::SkipTillNextMatch)
+        );
+
+        let found: Vec<(MatcherId, usize)> = group
+            .process_items("abaxb".chars().collect())
+            .unwrap()
+            .into_iter()
+            .map(|found| (found.matcher, found.index))
+            .collect();
+        assert_eq!(
+            found,
+            vec![(framing, 1), (correlation, 1), (correlation, 4)]
+        );
+    }
+
+    #[test]
+    fn test_group_prefilter() {
+        fn build() -> MatcherGroup<i32> {
+            let mut group = MatcherGroup::new();
+            for start in 0..50 {
+                let mut pair = Matcher::<i32, ()>::new(0);
+                pair.add_pattern(PatternElement::exact(start));
+                pair.add_pattern(PatternElement::exact(start + 1));
+                group.add(pair);
+            }
+            let mut high = Matcher::<i32, ()>::new(0);
+            high.add_pattern(PatternElement::range(90, 100));
+            group.add(high);
+            // Matchers with a window see every item
+            let mut repeat = Matcher::<i32, ()>::new(5);
+            repeat.add_pattern(PatternElement::predicate(|item: &i32| *item > 0));
+            repeat.add_pattern(PatternElement::back_reference(1));
+            group.add(repeat);
+            group
+        }
+        let items = vec![7, 3, 4, 95, 4, 4, 60, 10, 11, 12];
+
+        let mut plain = build();
+        let expected = plain.process_items(items.clone()).unwrap();
+
+        let mut filtered = build();
+        filtered.build_prefilter();
+        assert!(filtered.has_prefilter());
+        let found = filtered.process_items(items).unwrap();
+        assert_eq!(found, expected);
+        assert_eq!(
+            found
+                .iter()
+                .map(|found| (found.value, found.index))
+                .collect::<Vec<_>>(),
+            vec![(4, 2), (95, 3), (4, 5), (11, 8), (12, 9)]
+        );
+
+        filtered.add(Matcher::<i32, ()>::new(0));
+        assert!(!filtered.has_prefilter());
+    }
+
+    #[test]
+    fn test_group_keeps_matchers_aligned_on_error() {
+        let mut failing = Matcher::<i32, ()>::new(10);
+        failing.register_extractor(1, |_| {
+            Err(ExtractorError::ProcessingFailed("boom".to_string()))
+        });
+        let settings = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+        failing.add_pattern(PatternElement::exact_with_settings(1, settings));
+
+        let mut other = Matcher::<i32, ()>::new(10);
+        other.add_pattern(PatternElement::exact(2));
+
+        let mut group = MatcherGroup::new();
+        group.add(failing);
+        let other_id = group.add(other);
+
+        assert!(matches!(
+            group.process_item(1),
+            Err(MatcherError::ExtractorFailed { .. })
+        ));
+        let found = group.process_item(2).unwrap();
+        assert_eq!(
+            found,
+            vec![GroupMatch {
+                matcher: other_id,
+                pattern: None,
+                value: 2,
+                index: 1,
+            }]
+        );
+    }
+}
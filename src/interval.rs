@@ -0,0 +1,133 @@
+//! Range elements from Rust range syntax.
+//!
+//! [`PatternElement::range`] takes an inclusive `(min, max)` pair, which cannot
+//! say "below 10" or "100 and up". [`PatternElement::from_range`] accepts any
+//! Rust range instead, such as `..10`, `100..`, or `1..5`, keeping each bound
+//! in the range element. [`Interval`] offers the same bounds as a standalone
+//! [`Matchable`].
+
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+
+use crate::{ElementSettings, Matchable, MatcherError, MaybeSync, PatternElement, RangeOrder};
+
+/// A range of values with each end included, excluded, or unbounded.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Interval<T> {
+    start: Bound<T>,
+    end: Bound<T>,
+}
+
+impl<T> Interval<T>
+where
+    T: Clone + PartialOrd,
+{
+    /// Create an interval from a Rust range.
+    pub fn new(range: impl RangeBounds<T>) -> Self {
+        Self {
+            start: range.start_bound().cloned(),
+            end: range.end_bound().cloned(),
+        }
+    }
+
+    /// Check if a value is in the interval.
+    pub fn contains(&self, value: &T) -> bool {
+        (self.start.as_ref(), self.end.as_ref()).contains(value)
+    }
+}
+
+impl<T> Matchable<T> for Interval<T>
+where
+    T: Clone + PartialOrd + fmt::Debug + MaybeSync,
+{
+    fn matches(&self, item: &T) -> Result<bool, MatcherError> {
+        Ok(self.contains(item))
+    }
+
+    fn describe(&self) -> String {
+        let start = match &self.start {
+            Bound::Included(start) => Some(format!(">= {:?}", start)),
+            Bound::Excluded(start) => Some(format!("> {:?}", start)),
+            Bound::Unbounded => None,
+        };
+        let end = match &self.end {
+            Bound::Included(end) => Some(format!("<= {:?}", end)),
+            Bound::Excluded(end) => Some(format!("< {:?}", end)),
+            Bound::Unbounded => None,
+        };
+        let bounds: Vec<String> = start.into_iter().chain(end).collect();
+        if bounds.is_empty() {
+            "Interval(..)".to_string()
+        } else {
+            format!("Interval({})", bounds.join(", "))
+        }
+    }
+}
+
+impl<T, Context> PatternElement<T, Context>
+where
+    T: Clone + PartialEq + PartialOrd + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Create a new range pattern element from Rust range syntax, such as
+    /// `1..=5`, `..10`, or `100..`.
+    ///
+    /// An inclusive range gives the same element as `PatternElement::range`.
+    pub fn from_range(range: impl RangeBounds<T>) -> Self {
+        Self::from_range_with_settings(range, ElementSettings::default())
+    }
+
+    /// Create a new range pattern element from Rust range syntax with
+    /// settings.
+    pub fn from_range_with_settings(
+        range: impl RangeBounds<T>,
+        settings: ElementSettings<Context>,
+    ) -> Self {
+        PatternElement::Range {
+            start: range.start_bound().cloned(),
+            end: range.end_bound().cloned(),
+            order: RangeOrder::natural(),
+            negated: false,
+            settings: Some(settings),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Matcher;
+
+    #[test]
+    fn test_from_range() {
+        assert_eq!(
+            PatternElement::<i32, ()>::from_range(1..=5),
+            PatternElement::range_with_settings(1, 5, ElementSettings::default())
+        );
+
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.add_pattern(PatternElement::from_range(..10));
+        matcher.add_pattern(PatternElement::from_range(100..));
+        matcher.add_pattern(PatternElement::from_range(1..5));
+        assert_eq!(
+            matcher.process_items(vec![9, 100, 5, 3, 500, 4]).unwrap(),
+            vec![4]
+        );
+        assert_eq!(matcher.patterns()[0].to_string(), "Range(..10)");
+        assert_eq!(matcher.patterns()[1].to_string(), "Range(100..)");
+        assert_eq!(matcher.patterns()[2].to_string(), "Range(1..5)");
+        assert_eq!(
+            PatternElement::<i32, ()>::from_range(1..5),
+            PatternElement::from_range(1..5)
+        );
+        assert_ne!(
+            PatternElement::<i32, ()>::from_range(1..5),
+            PatternElement::from_range(1..=5)
+        );
+
+        let everything = Interval::new((Bound::Excluded(0.5), Bound::Unbounded));
+        assert!(everything.contains(&0.6));
+        assert!(!everything.contains(&0.5));
+        assert_eq!(Interval::<f64>::new(..).describe(), "Interval(..)");
+    }
+}
@@ -0,0 +1,571 @@
+//! Adapters that drive a matcher from `std::io` sources.
+//!
+//! [`ReadMatcher`] pulls bytes from any [`Read`] implementation, decodes them
+//! into items, and feeds the items to a [`Matcher`], yielding each match with
+//! the byte range of the item that completed it. [`LineMatcher`] does the
+//! same for the lines of a [`BufRead`], reporting line numbers instead.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufRead, Read};
+use std::ops::Range;
+
+use crate::{Capture, Matcher, MatcherError};
+
+/// Size of each read from the underlying reader.
+const READ_CHUNK: usize = 8 * 1024;
+
+/// Type alias for decoders that turn the front of a byte buffer into an item.
+///
+/// A decoder returns the item and the number of bytes it used, or `None` when
+/// the buffer does not yet hold a complete item.
+pub type Decoder<T> = Box<dyn FnMut(&[u8]) -> io::Result<Option<(T, usize)>>>;
+
+/// Errors produced while matching over a reader.
+#[derive(Debug)]
+pub enum ReadError {
+    /// Reading or decoding failed.
+    Io(io::Error),
+    /// The matcher rejected an item.
+    Matcher(MatcherError),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Io(err) => write!(f, "I/O error: {}", err),
+            ReadError::Matcher(err) => write!(f, "Matcher error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadError::Io(err) => Some(err),
+            ReadError::Matcher(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for ReadError {
+    fn from(err: io::Error) -> Self {
+        ReadError::Io(err)
+    }
+}
+
+impl From<MatcherError> for ReadError {
+    fn from(err: MatcherError) -> Self {
+        ReadError::Matcher(err)
+    }
+}
+
+/// A match found in a byte stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadMatch<T> {
+    /// The value produced by the match.
+    pub value: T,
+    /// Zero-based index of the item that completed the match.
+    pub index: usize,
+    /// Byte range in the stream of the item that completed the match.
+    pub offset: Range<usize>,
+}
+
+/// Drives a matcher with items read from an [`io::Read`] source.
+///
+/// Iterating yields matches until the reader is exhausted, at which point
+/// the matcher is finished so that a held completion is also reported.
+pub struct ReadMatcher<R, T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    reader: R,
+    matcher: Matcher<T, Context>,
+    decoder: Decoder<T>,
+    buffer: Vec<u8>,
+    /// Position in `buffer` of the first byte not yet decoded.
+    start: usize,
+    /// Stream offset of the first byte of `buffer`.
+    offset: usize,
+    /// Byte ranges of items that a pending match may still report, with the
+    /// item index of the first one.
+    ranges: VecDeque<Range<usize>>,
+    first_range_index: usize,
+    /// Matches completed together with the one last returned.
+    ready: VecDeque<ReadMatch<T>>,
+    done: bool,
+}
+
+impl<R, Context> ReadMatcher<R, u8, Context>
+where
+    R: Read,
+    Context: Clone + fmt::Debug,
+{
+    /// Match over the raw bytes of a reader.
+    pub fn bytes(reader: R, matcher: Matcher<u8, Context>) -> Self {
+        Self::with_decoder(reader, matcher, |buffer: &[u8]| {
+            Ok(buffer.first().map(|byte| (*byte, 1)))
+        })
+    }
+}
+
+impl<R, T, Context> ReadMatcher<R, T, Context>
+where
+    R: Read,
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Match over items decoded from a reader.
+    ///
+    /// The decoder must use at least one byte for every item it returns.
+    pub fn with_decoder<F>(reader: R, matcher: Matcher<T, Context>, decoder: F) -> Self
+    where
+        F: FnMut(&[u8]) -> io::Result<Option<(T, usize)>> + 'static,
+    {
+        Self {
+            reader,
+            first_range_index: matcher.total_processed(),
+            matcher,
+            decoder: Box::new(decoder),
+            buffer: Vec::new(),
+            start: 0,
+            offset: 0,
+            ranges: VecDeque::new(),
+            ready: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Get a reference to the underlying matcher.
+    pub fn matcher(&self) -> &Matcher<T, Context> {
+        &self.matcher
+    }
+
+    /// Get a mutable reference to the underlying matcher.
+    pub fn matcher_mut(&mut self) -> &mut Matcher<T, Context> {
+        &mut self.matcher
+    }
+
+    /// Get the number of bytes decoded so far.
+    pub fn bytes_consumed(&self) -> usize {
+        self.offset + self.start
+    }
+
+    /// Take back the reader and the matcher.
+    pub fn into_inner(self) -> (R, Matcher<T, Context>) {
+        (self.reader, self.matcher)
+    }
+
+    /// Decode and process the next buffered item, if a complete one is buffered.
+    fn process_buffered(&mut self) -> Result<Option<Vec<Capture<T>>>, ReadError> {
+        let Some((item, used)) = (self.decoder)(&self.buffer[self.start..])? else {
+            return Ok(None);
+        };
+        if used == 0 || self.start + used > self.buffer.len() {
+            return Err(ReadError::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("decoder reported using {} bytes", used),
+            )));
+        }
+
+        let begin = self.bytes_consumed();
+        self.start += used;
+        self.ranges.push_back(begin..begin + used);
+
+        let completed = self
+            .matcher
+            .process_captures(item, self.matcher.epoch.elapsed())?;
+        Ok(Some(completed))
+    }
+
+    /// Attach the byte range of the completing item to a capture.
+    fn located(&self, capture: Capture<T>) -> ReadMatch<T> {
+        let offset = capture
+            .index
+            .checked_sub(self.first_range_index)
+            .and_then(|position| self.ranges.get(position))
+            .cloned()
+            .unwrap_or_default();
+        ReadMatch {
+            value: capture.item,
+            index: capture.index,
+            offset,
+        }
+    }
+
+    /// Drop the byte ranges of items that no match can report any more.
+    fn prune_ranges(&mut self) {
+        let live = self
+            .matcher
+            .earliest_capture_index()
+            .unwrap_or(self.matcher.total_processed());
+        while self.first_range_index < live && !self.ranges.is_empty() {
+            self.ranges.pop_front();
+            self.first_range_index += 1;
+        }
+    }
+
+    /// Read the next chunk, returning false at the end of the stream.
+    fn fill_buffer(&mut self) -> io::Result<bool> {
+        if self.start > 0 {
+            self.buffer.drain(..self.start);
+            self.offset += self.start;
+            self.start = 0;
+        }
+
+        let mut chunk = [0u8; READ_CHUNK];
+        let read = loop {
+            match self.reader.read(&mut chunk) {
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                result => break result?,
+            }
+        };
+        self.buffer.extend_from_slice(&chunk[..read]);
+        Ok(read > 0)
+    }
+
+    /// Finish the matcher at the end of the stream, reporting a held completion.
+    fn finish(&mut self) -> Result<Option<ReadMatch<T>>, ReadError> {
+        self.done = true;
+        let held = self.matcher.pending.clone();
+        let report = self.matcher.finish();
+
+        if self.start < self.buffer.len() {
+            return Err(ReadError::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "{} trailing bytes do not form a complete item",
+                    self.buffer.len() - self.start
+                ),
+            )));
+        }
+        Ok(report
+            .completed
+            .zip(held)
+            .map(|(value, held)| Capture {
+                item: value,
+                ..held
+            })
+            .map(|capture| self.located(capture)))
+    }
+}
+
+impl<R, T, Context> Iterator for ReadMatcher<R, T, Context>
+where
+    R: Read,
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    type Item = Result<ReadMatch<T>, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(found) = self.ready.pop_front() {
+                return Some(Ok(found));
+            }
+            if self.done {
+                return None;
+            }
+            match self.process_buffered() {
+                Ok(Some(captures)) => {
+                    for capture in captures {
+                        let found = self.located(capture);
+                        self.ready.push_back(found);
+                    }
+                    self.prune_ranges();
+                }
+                Ok(None) => match self.fill_buffer() {
+                    Ok(true) => {}
+                    Ok(false) => return self.finish().transpose(),
+                    Err(err) => {
+                        self.done = true;
+                        return Some(Err(ReadError::Io(err)));
+                    }
+                },
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
+
+impl<R, T, Context> fmt::Debug for ReadMatcher<R, T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadMatcher")
+            .field("matcher", &self.matcher)
+            .field("bytes_consumed", &(self.offset + self.start))
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+/// Type alias for closures that turn a line into an item.
+pub type LineMapper<T> = Box<dyn FnMut(&str) -> T>;
+
+/// A match found in the lines of a reader.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineMatch<T> {
+    /// The value produced by the match.
+    pub value: T,
+    /// One-based number of the line that completed the match.
+    pub line: usize,
+}
+
+/// Drives a matcher with items mapped from the lines of a [`BufRead`] source.
+///
+/// Line terminators (`\n` or `\r\n`) are stripped before a line is mapped.
+/// Like [`ReadMatcher`], the matcher is finished when the reader is exhausted
+/// so that a held completion is also reported.
+pub struct LineMatcher<R, T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    reader: R,
+    matcher: Matcher<T, Context>,
+    mapper: LineMapper<T>,
+    line: String,
+    /// Item index of the first line, used to turn indices into line numbers.
+    first_index: usize,
+    lines_read: usize,
+    /// Matches completed together with the one last returned.
+    ready: VecDeque<LineMatch<T>>,
+    done: bool,
+}
+
+impl<R, T, Context> LineMatcher<R, T, Context>
+where
+    R: BufRead,
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Match over the lines of a reader, mapping each line to an item.
+    pub fn new<F>(reader: R, matcher: Matcher<T, Context>, mapper: F) -> Self
+    where
+        F: FnMut(&str) -> T + 'static,
+    {
+        Self {
+            reader,
+            first_index: matcher.total_processed(),
+            matcher,
+            mapper: Box::new(mapper),
+            line: String::new(),
+            lines_read: 0,
+            ready: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Get a reference to the underlying matcher.
+    pub fn matcher(&self) -> &Matcher<T, Context> {
+        &self.matcher
+    }
+
+    /// Get a mutable reference to the underlying matcher.
+    pub fn matcher_mut(&mut self) -> &mut Matcher<T, Context> {
+        &mut self.matcher
+    }
+
+    /// Get the number of lines read so far.
+    pub fn lines_read(&self) -> usize {
+        self.lines_read
+    }
+
+    /// Take back the reader and the matcher.
+    pub fn into_inner(self) -> (R, Matcher<T, Context>) {
+        (self.reader, self.matcher)
+    }
+
+    /// Read and process the next line, returning `None` at the end of the stream.
+    fn process_line(&mut self) -> Result<Option<Vec<Capture<T>>>, ReadError> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line)? == 0 {
+            return Ok(None);
+        }
+        self.lines_read += 1;
+
+        let line = self.line.strip_suffix('\n').unwrap_or(&self.line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let item = (self.mapper)(line);
+        let completed = self
+            .matcher
+            .process_captures(item, self.matcher.epoch.elapsed())?;
+        Ok(Some(completed))
+    }
+
+    /// Attach the line number of the completing item to a capture.
+    fn located(&self, capture: Capture<T>) -> LineMatch<T> {
+        LineMatch {
+            line: capture.index.saturating_sub(self.first_index) + 1,
+            value: capture.item,
+        }
+    }
+}
+
+impl<R, T, Context> Iterator for LineMatcher<R, T, Context>
+where
+    R: BufRead,
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    type Item = Result<LineMatch<T>, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(found) = self.ready.pop_front() {
+                return Some(Ok(found));
+            }
+            if self.done {
+                return None;
+            }
+            match self.process_line() {
+                Ok(Some(captures)) => {
+                    for capture in captures {
+                        let found = self.located(capture);
+                        self.ready.push_back(found);
+                    }
+                }
+                Ok(None) => {
+                    self.done = true;
+                    let held = self.matcher.pending.clone();
+                    let report = self.matcher.finish();
+                    return report
+                        .completed
+                        .zip(held)
+                        .map(|(value, held)| Capture {
+                            item: value,
+                            ..held
+                        })
+                        .map(|capture| Ok(self.located(capture)));
+                }
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
+
+impl<R, T, Context> fmt::Debug for LineMatcher<R, T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LineMatcher")
+            .field("matcher", &self.matcher)
+            .field("lines_read", &self.lines_read)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PatternElement;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_bytes_with_offsets() {
+        let mut matcher = Matcher::<u8, ()>::new(10);
+        matcher.add_pattern(PatternElement::exact(b'o'));
+        matcher.add_pattern(PatternElement::exact(b'k'));
+
+        let found: Vec<ReadMatch<u8>> = ReadMatcher::bytes(Cursor::new("ok, not ok"), matcher)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let offsets: Vec<Range<usize>> = found.into_iter().map(|m| m.offset).collect();
+        assert_eq!(offsets, vec![1..2, 9..10]);
+    }
+
+    #[test]
+    fn test_decoded_records() {
+        // Big-endian u16 records
+        let data: Vec<u8> = [1u16, 500, 2, 700]
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect();
+        let mut matcher = Matcher::<u16, ()>::new(10);
+        matcher.add_pattern(PatternElement::range(400, 1000));
+
+        let decoder = |buffer: &[u8]| {
+            Ok(buffer
+                .get(..2)
+                .map(|bytes| (u16::from_be_bytes([bytes[0], bytes[1]]), 2)))
+        };
+        let found: Vec<ReadMatch<u16>> =
+            ReadMatcher::with_decoder(Cursor::new(data), matcher, decoder)
+                .collect::<Result<_, _>>()
+                .unwrap();
+        assert_eq!(
+            found,
+            vec![
+                ReadMatch {
+                    value: 500,
+                    index: 1,
+                    offset: 2..4,
+                },
+                ReadMatch {
+                    value: 700,
+                    index: 3,
+                    offset: 6..8,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truncated_record_is_an_error() {
+        let mut matcher = Matcher::<u16, ()>::new(10);
+        matcher.add_pattern(PatternElement::exact(1));
+        let decoder = |buffer: &[u8]| {
+            Ok(buffer
+                .get(..2)
+                .map(|bytes| (u16::from_be_bytes([bytes[0], bytes[1]]), 2)))
+        };
+
+        let mut reader = ReadMatcher::with_decoder(Cursor::new(vec![0, 1, 0]), matcher, decoder);
+        assert_eq!(reader.next().unwrap().unwrap().offset, 0..2);
+        assert!(matches!(reader.next(), Some(Err(ReadError::Io(_)))));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_lines_with_line_numbers() {
+        let mut matcher = Matcher::<String, ()>::new(10);
+        matcher.add_pattern(PatternElement::predicate(|line: &String| {
+            line.starts_with("ERROR")
+        }));
+        matcher.add_pattern(PatternElement::predicate(|line: &String| {
+            line.starts_with("FATAL")
+        }));
+
+        let log = "INFO start\r\nERROR disk\nFATAL abort\nINFO retry\nERROR net\nFATAL abort\n";
+        let found: Vec<LineMatch<String>> =
+            LineMatcher::new(Cursor::new(log), matcher, |line: &str| line.to_string())
+                .collect::<Result<_, _>>()
+                .unwrap();
+        assert_eq!(
+            found,
+            vec![
+                LineMatch {
+                    value: "FATAL abort".to_string(),
+                    line: 3,
+                },
+                LineMatch {
+                    value: "FATAL abort".to_string(),
+                    line: 6,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,456 @@
+//! Structural matching over JSON token streams.
+//!
+//! [`JsonTokens`] splits a JSON byte stream into tokens as it is read, so
+//! patterns can match the structure of huge JSON logs, such as a key followed
+//! by a particular value, without parsing whole documents. Any number of
+//! documents may follow one another, as in newline-delimited JSON.
+//!
+//! The tokenizer checks each token but not the grammar between them: commas
+//! and colons are skipped, and brackets are not checked for balance beyond
+//! telling keys from string values.
+//!
+//! ```rust
+//! use scrolling_window_pattern_matcher::{JsonToken, Matcher, PatternElement};
+//!
+//! let mut matcher = Matcher::<JsonToken, ()>::new(0);
+//! matcher.add_pattern(PatternElement::exact(JsonToken::Key("level".to_string())));
+//! matcher.add_pattern(PatternElement::exact(JsonToken::String("error".to_string())));
+//!
+//! let logs = "{\"level\": \"info\"}\n{\"level\": \"error\", \"code\": 7}\n";
+//! let mut errors = 0;
+//! matcher.process_json(logs.as_bytes(), |_| errors += 1).unwrap();
+//! assert_eq!(errors, 1);
+//! ```
+
+use std::fmt;
+use std::io::{self, Read};
+
+use crate::{MatchEvent, Matcher, ReadError};
+
+/// Size of each read from the underlying reader.
+const READ_CHUNK: usize = 8 * 1024;
+
+/// A token of a JSON document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonToken {
+    /// The `{` opening an object.
+    ObjectStart,
+    /// The `}` closing an object.
+    ObjectEnd,
+    /// The `[` opening an array.
+    ArrayStart,
+    /// The `]` closing an array.
+    ArrayEnd,
+    /// The key of an object member.
+    Key(String),
+    /// A string value.
+    String(String),
+    /// A number value.
+    Number(f64),
+    /// A boolean value.
+    Bool(bool),
+    /// A null value.
+    Null,
+}
+
+/// The kind of container a token is nested in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Object,
+    Array,
+}
+
+/// Reads JSON tokens from an [`io::Read`] source.
+#[derive(Debug)]
+pub struct JsonTokens<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    /// Position in `buffer` of the first byte not yet tokenized.
+    start: usize,
+    containers: Vec<Container>,
+    /// Whether the next string in the innermost object is a key.
+    expect_key: bool,
+    eof: bool,
+}
+
+impl<R: Read> JsonTokens<R> {
+    /// Tokenize the JSON read from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: Vec::new(),
+            start: 0,
+            containers: Vec::new(),
+            expect_key: false,
+            eof: false,
+        }
+    }
+
+    /// Take the next token from the buffer, if a complete one is buffered.
+    fn next_buffered(&mut self) -> io::Result<Option<JsonToken>> {
+        let rest = &self.buffer[self.start..];
+        let Some(skipped) = rest
+            .iter()
+            .position(|byte| !matches!(byte, b' ' | b'\t' | b'\n' | b'\r' | b',' | b':'))
+        else {
+            let skipped = rest.len();
+            self.note_separators(skipped);
+            self.start += skipped;
+            return Ok(None);
+        };
+        self.note_separators(skipped);
+        self.start += skipped;
+
+        let rest = &self.buffer[self.start..];
+        let (token, used) = match rest[0] {
+            b'{' => (JsonToken::ObjectStart, 1),
+            b'}' => (JsonToken::ObjectEnd, 1),
+            b'[' => (JsonToken::ArrayStart, 1),
+            b']' => (JsonToken::ArrayEnd, 1),
+            b'"' => match scan_string(rest)? {
+                Some((text, used)) => {
+                    if self.expect_key {
+                        (JsonToken::Key(text), used)
+                    } else {
+                        (JsonToken::String(text), used)
+                    }
+                }
+                None => return Ok(None),
+            },
+            b't' => match scan_literal(rest, b"true", self.eof)? {
+                Some(used) => (JsonToken::Bool(true), used),
+                None => return Ok(None),
+            },
+            b'f' => match scan_literal(rest, b"false", self.eof)? {
+                Some(used) => (JsonToken::Bool(false), used),
+                None => return Ok(None),
+            },
+            b'n' => match scan_literal(rest, b"null", self.eof)? {
+                Some(used) => (JsonToken::Null, used),
+                None => return Ok(None),
+            },
+            b'-' | b'0'..=b'9' => match scan_number(rest, self.eof)? {
+                Some((number, used)) => (JsonToken::Number(number), used),
+                None => return Ok(None),
+            },
+            other => {
+                return Err(invalid(format!(
+                    "unexpected byte {:?} at offset {}",
+                    other as char, self.start
+                )))
+            }
+        };
+        self.start += used;
+        self.enter(&token);
+        Ok(Some(token))
+    }
+
+    /// Track the commas among skipped separator bytes, which make the next
+    /// string in an object a key.
+    fn note_separators(&mut self, count: usize) {
+        let separators = &self.buffer[self.start..self.start + count];
+        if separators.contains(&b',') && self.containers.last() == Some(&Container::Object) {
+            self.expect_key = true;
+        }
+    }
+
+    /// Update the nesting after a token.
+    fn enter(&mut self, token: &JsonToken) {
+        match token {
+            JsonToken::ObjectStart => self.containers.push(Container::Object),
+            JsonToken::ArrayStart => self.containers.push(Container::Array),
+            JsonToken::ObjectEnd | JsonToken::ArrayEnd => {
+                self.containers.pop();
+            }
+            _ => {}
+        }
+        self.expect_key = matches!(token, JsonToken::ObjectStart);
+    }
+
+    /// Read the next chunk, returning false at the end of the stream.
+    fn fill_buffer(&mut self) -> io::Result<bool> {
+        if self.start > 0 {
+            self.buffer.drain(..self.start);
+            self.start = 0;
+        }
+
+        let mut chunk = [0u8; READ_CHUNK];
+        let read = loop {
+            match self.reader.read(&mut chunk) {
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                result => break result?,
+            }
+        };
+        self.buffer.extend_from_slice(&chunk[..read]);
+        Ok(read > 0)
+    }
+}
+
+impl<R: Read> Iterator for JsonTokens<R> {
+    type Item = io::Result<JsonToken>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_buffered() {
+                Ok(Some(token)) => return Some(Ok(token)),
+                Ok(None) if self.eof => {
+                    if self.start < self.buffer.len() {
+                        let err = io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "JSON stream ends inside a token",
+                        );
+                        self.buffer.clear();
+                        self.start = 0;
+                        return Some(Err(err));
+                    }
+                    return None;
+                }
+                Ok(None) => match self.fill_buffer() {
+                    Ok(more) => self.eof = !more,
+                    Err(err) => return Some(Err(err)),
+                },
+                Err(err) => {
+                    self.eof = true;
+                    self.buffer.clear();
+                    self.start = 0;
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
+
+impl<Context> Matcher<JsonToken, Context>
+where
+    Context: Clone + fmt::Debug,
+{
+    /// Process the tokens of the JSON read from `reader`, calling `on_match`
+    /// with each match as it completes.
+    pub fn process_json<R, F>(&mut self, reader: R, mut on_match: F) -> Result<(), ReadError>
+    where
+        R: Read,
+        F: FnMut(MatchEvent<JsonToken>),
+    {
+        for token in JsonTokens::new(reader) {
+            let token = token?;
+            let timestamp = self.epoch.elapsed();
+            for capture in self.process_captures(token, timestamp)? {
+                on_match(MatchEvent {
+                    value: capture.item,
+                    index: capture.index,
+                    timestamp: capture.timestamp,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Build an error for malformed JSON.
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Decode the string starting at the front of `bytes`, returning it and the
+/// number of bytes used, or `None` if its closing quote is not buffered yet.
+fn scan_string(bytes: &[u8]) -> io::Result<Option<(String, usize)>> {
+    let mut decoded = Vec::new();
+    let mut position = 1;
+    while let Some(&byte) = bytes.get(position) {
+        match byte {
+            b'"' => {
+                let text = String::from_utf8(decoded)
+                    .map_err(|_| invalid("string is not valid UTF-8".to_string()))?;
+                return Ok(Some((text, position + 1)));
+            }
+            b'\\' => {
+                let Some(&escaped) = bytes.get(position + 1) else {
+                    return Ok(None);
+                };
+                position += 2;
+                let character = match escaped {
+                    b'"' => '"',
+                    b'\\' => '\\',
+                    b'/' => '/',
+                    b'b' => '\u{8}',
+                    b'f' => '\u{c}',
+                    b'n' => '\n',
+                    b'r' => '\r',
+                    b't' => '\t',
+                    b'u' => {
+                        let Some((character, used)) = scan_unicode_escape(&bytes[position..])?
+                        else {
+                            return Ok(None);
+                        };
+                        position += used;
+                        character
+                    }
+                    other => {
+                        return Err(invalid(format!(
+                            "invalid escape \\{} in string",
+                            other as char
+                        )))
+                    }
+                };
+                let mut encoded = [0u8; 4];
+                decoded.extend_from_slice(character.encode_utf8(&mut encoded).as_bytes());
+            }
+            _ => {
+                decoded.push(byte);
+                position += 1;
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Decode the hex digits of a `\u` escape, and of the low surrogate
+/// following a high one, returning the character and the bytes used.
+fn scan_unicode_escape(bytes: &[u8]) -> io::Result<Option<(char, usize)>> {
+    let Some(high) = hex4(bytes)? else {
+        return Ok(None);
+    };
+    if !(0xD800..0xDC00).contains(&high) {
+        return char::from_u32(high)
+            .map(|character| Some((character, 4)))
+            .ok_or_else(|| invalid(format!("invalid escape \\u{:04x}", high)));
+    }
+    if bytes.len() < 6 {
+        return Ok(None);
+    }
+    if &bytes[4..6] != b"\\u" {
+        return Err(invalid(format!("unpaired surrogate \\u{:04x}", high)));
+    }
+    let Some(low) = hex4(&bytes[6..])? else {
+        return Ok(None);
+    };
+    let code = 0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+    char::from_u32(code)
+        .filter(|_| (0xDC00..0xE000).contains(&low))
+        .map(|character| Some((character, 10)))
+        .ok_or_else(|| invalid(format!("unpaired surrogate \\u{:04x}", high)))
+}
+
+/// Parse four hex digits, or `None` if fewer are buffered.
+fn hex4(bytes: &[u8]) -> io::Result<Option<u32>> {
+    let Some(digits) = bytes.get(..4) else {
+        return Ok(None);
+    };
+    std::str::from_utf8(digits)
+        .ok()
+        .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+        .map(Some)
+        .ok_or_else(|| invalid("invalid \\u escape".to_string()))
+}
+
+/// Match a literal at the front of `bytes`, returning its length, or `None`
+/// if only part of it is buffered.
+fn scan_literal(bytes: &[u8], literal: &[u8], eof: bool) -> io::Result<Option<usize>> {
+    if bytes.starts_with(literal) {
+        return Ok(Some(literal.len()));
+    }
+    if !eof && literal.starts_with(bytes) {
+        return Ok(None);
+    }
+    Err(invalid(format!(
+        "expected {}",
+        String::from_utf8_lossy(literal)
+    )))
+}
+
+/// Parse the number at the front of `bytes`, returning it and its length, or
+/// `None` if more of it may follow.
+fn scan_number(bytes: &[u8], eof: bool) -> io::Result<Option<(f64, usize)>> {
+    let length = bytes
+        .iter()
+        .position(|byte| !matches!(byte, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+        .unwrap_or(bytes.len());
+    if length == bytes.len() && !eof {
+        return Ok(None);
+    }
+    let text = std::str::from_utf8(&bytes[..length]).unwrap_or_default();
+    text.parse()
+        .map(|number| Some((number, length)))
+        .map_err(|_| invalid(format!("invalid number {}", text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PatternElement;
+
+    /// A reader that returns one byte per read, splitting every token.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn test_json_tokens() {
+        let json = br#"{"a": [1, -2.5e1, true, null], "b\u00e9\ud83d\ude00": {"c": "x\"y"}} 7"#;
+        let expected = vec![
+            JsonToken::ObjectStart,
+            JsonToken::Key("a".to_string()),
+            JsonToken::ArrayStart,
+            JsonToken::Number(1.0),
+            JsonToken::Number(-25.0),
+            JsonToken::Bool(true),
+            JsonToken::Null,
+            JsonToken::ArrayEnd,
+            JsonToken::Key("b\u{e9}\u{1f600}".to_string()),
+            JsonToken::ObjectStart,
+            JsonToken::Key("c".to_string()),
+            JsonToken::String("x\"y".to_string()),
+            JsonToken::ObjectEnd,
+            JsonToken::ObjectEnd,
+            JsonToken::Number(7.0),
+        ];
+        let tokens: Vec<JsonToken> = JsonTokens::new(&json[..])
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(tokens, expected);
+        let tokens: Vec<JsonToken> = JsonTokens::new(Trickle(json))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(tokens, expected);
+
+        let truncated: Vec<io::Result<JsonToken>> = JsonTokens::new(&br#"["ab"#[..]).collect();
+        assert_eq!(truncated.len(), 2);
+        assert_eq!(
+            truncated[1].as_ref().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn test_process_json() {
+        let mut matcher = Matcher::<JsonToken, ()>::new(0);
+        matcher.add_pattern(PatternElement::exact(JsonToken::Key("status".to_string())));
+        matcher.add_pattern(PatternElement::predicate(
+            |token: &JsonToken| matches!(token, JsonToken::Number(code) if *code >= 500.0),
+        ));
+
+        let logs = b"{\"status\": 200}\n{\"status\": 503, \"path\": \"/\"}\n{\"status\": 500}\n";
+        let mut found = Vec::new();
+        matcher
+            .process_json(Trickle(logs), |event| {
+                found.push((event.value, event.index))
+            })
+            .unwrap();
+        assert_eq!(
+            found,
+            vec![
+                (JsonToken::Number(503.0), 6),
+                (JsonToken::Number(500.0), 12)
+            ]
+        );
+    }
+}
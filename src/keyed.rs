@@ -0,0 +1,417 @@
+//! Matching independent sequences per key.
+//!
+//! Streams often interleave many logical sequences, such as the events of
+//! each user or connection. A [`KeyedMatcher`] extracts a key from every item
+//! and keeps a separate matcher per key, evicting keys that have been idle
+//! for longer than a time-to-live.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+use crate::{Capture, Matcher, MatcherError, StreamMatcher};
+
+/// Type alias for functions that extract the partition key from an item.
+pub type KeyFunction<T, K> = Box<dyn Fn(&T) -> K>;
+
+/// Type alias for functions that build the matcher for a new key.
+pub type MatcherFactory<T, Context> = Box<dyn Fn() -> Matcher<T, Context>>;
+
+/// A match found in the sequence of one key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyedMatch<K, T> {
+    /// The key whose sequence matched.
+    pub key: K,
+    /// The value produced by the match.
+    pub value: T,
+}
+
+/// The matcher of one key and when it last saw an item.
+struct KeyState<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    matcher: Matcher<T, Context>,
+    last_seen: Duration,
+    /// Index in the keyed stream of each item of the key that a match may
+    /// still report, starting with the key's item at `first_index`.
+    indices: VecDeque<usize>,
+    first_index: usize,
+}
+
+impl<T, Context> KeyState<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Move a capture from the key's own stream to the keyed stream.
+    fn located(&self, capture: Capture<T>) -> Capture<T> {
+        let index = capture
+            .index
+            .checked_sub(self.first_index)
+            .and_then(|position| self.indices.get(position))
+            .copied()
+            .unwrap_or(capture.index);
+        Capture { index, ..capture }
+    }
+
+    /// Drop the indices of items that no match can report any more.
+    fn prune_indices(&mut self) {
+        let live = self
+            .matcher
+            .earliest_capture_index()
+            .unwrap_or(self.matcher.total_processed());
+        while self.first_index < live && !self.indices.is_empty() {
+            self.indices.pop_front();
+            self.first_index += 1;
+        }
+    }
+}
+
+/// Keeps independent pattern state for each key of a partitioned stream.
+pub struct KeyedMatcher<K, T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    key_fn: KeyFunction<T, K>,
+    factory: MatcherFactory<T, Context>,
+    keys: HashMap<K, KeyState<T, Context>>,
+    ttl: Option<Duration>,
+    last_sweep: Duration,
+    epoch: Instant,
+    total_processed: usize,
+}
+
+impl<K, T, Context> KeyedMatcher<K, T, Context>
+where
+    K: Eq + Hash + Clone,
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Create a keyed matcher that partitions items with `key_fn` and builds
+    /// the matcher for each new key with `factory`.
+    pub fn new<F, M>(key_fn: F, factory: M) -> Self
+    where
+        F: Fn(&T) -> K + 'static,
+        M: Fn() -> Matcher<T, Context> + 'static,
+    {
+        Self {
+            key_fn: Box::new(key_fn),
+            factory: Box::new(factory),
+            keys: HashMap::new(),
+            ttl: None,
+            last_sweep: Duration::ZERO,
+            epoch: Instant::now(),
+            total_processed: 0,
+        }
+    }
+
+    /// Evict keys that have not seen an item for longer than `ttl`.
+    ///
+    /// Idle keys are swept at most once per `ttl`, so a key may linger for up
+    /// to twice the time-to-live before its state is dropped. The stream of
+    /// an evicted key is finished first, so a match it was holding back, such
+    /// as one waiting for optional elements, is still reported.
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.ttl = Some(ttl);
+    }
+
+    /// Get the time-to-live of idle keys.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Process a single item, returning every match it completes in the
+    /// sequence of its key.
+    pub fn process_item(&mut self, item: T) -> Result<Vec<KeyedMatch<K, T>>, MatcherError> {
+        let timestamp = self.epoch.elapsed();
+        self.process_item_at(item, timestamp)
+    }
+
+    /// Process a single item observed at the given timestamp.
+    ///
+    /// Matches completed by evicting idle keys come first.
+    pub fn process_item_at(
+        &mut self,
+        item: T,
+        timestamp: Duration,
+    ) -> Result<Vec<KeyedMatch<K, T>>, MatcherError> {
+        Ok(self
+            .process_keyed(item, timestamp)?
+            .into_iter()
+            .map(|(key, capture)| KeyedMatch {
+                key,
+                value: capture.item,
+            })
+            .collect())
+    }
+
+    /// Process an item, returning the captures of the matches it completes
+    /// with their keys and indices in the keyed stream.
+    fn process_keyed(
+        &mut self,
+        item: T,
+        timestamp: Duration,
+    ) -> Result<Vec<(K, Capture<T>)>, MatcherError> {
+        let mut found = Vec::new();
+        if let Some(ttl) = self.ttl {
+            if timestamp.saturating_sub(self.last_sweep) >= ttl {
+                found = self.evict(timestamp);
+                self.last_sweep = timestamp;
+            }
+        }
+
+        let index = self.total_processed;
+        self.total_processed += 1;
+        let key = (self.key_fn)(&item);
+        let factory = &self.factory;
+        let state = self.keys.entry(key.clone()).or_insert_with(|| KeyState {
+            matcher: factory(),
+            last_seen: timestamp,
+            indices: VecDeque::new(),
+            first_index: 0,
+        });
+        state.last_seen = timestamp;
+        state.indices.push_back(index);
+        let completed = state.matcher.process_captures(item, timestamp);
+        let located: Vec<_> = completed?
+            .into_iter()
+            .map(|capture| (key.clone(), state.located(capture)))
+            .collect();
+        state.prune_indices();
+        found.extend(located);
+        Ok(found)
+    }
+
+    /// Process a batch of items.
+    pub fn process_items(&mut self, items: Vec<T>) -> Result<Vec<KeyedMatch<K, T>>, MatcherError> {
+        let mut results = Vec::new();
+        for item in items {
+            results.extend(self.process_item(item)?);
+        }
+        Ok(results)
+    }
+
+    /// Drop the state of keys idle for longer than the time-to-live at
+    /// `now`, returning the matches their streams complete as they finish.
+    pub fn evict_idle(&mut self, now: Duration) -> Vec<KeyedMatch<K, T>> {
+        self.evict(now)
+            .into_iter()
+            .map(|(key, capture)| KeyedMatch {
+                key,
+                value: capture.item,
+            })
+            .collect()
+    }
+
+    /// Drop the state of idle keys, returning the captures of the matches
+    /// their streams complete as they finish.
+    fn evict(&mut self, now: Duration) -> Vec<(K, Capture<T>)> {
+        let Some(ttl) = self.ttl else {
+            return Vec::new();
+        };
+        let idle: Vec<K> = self
+            .keys
+            .iter()
+            .filter(|(_, state)| now.saturating_sub(state.last_seen) > ttl)
+            .map(|(key, _)| key.clone())
+            .collect();
+        let mut found = Vec::new();
+        for key in idle {
+            if let Some(mut state) = self.keys.remove(&key) {
+                // A completion at the end of the stream is the held one
+                let held = state.matcher.pending.clone();
+                let completed = state.matcher.finish().completed.zip(held);
+                if let Some((value, held)) = completed {
+                    let capture = Capture {
+                        item: value,
+                        ..held
+                    };
+                    found.push((key, state.located(capture)));
+                }
+            }
+        }
+        found
+    }
+
+    /// Get the number of keys with live state.
+    pub fn key_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Check if a key has live state.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.keys.contains_key(key)
+    }
+
+    /// Get the matcher of a key.
+    pub fn matcher(&self, key: &K) -> Option<&Matcher<T, Context>> {
+        self.keys.get(key).map(|state| &state.matcher)
+    }
+
+    /// Drop the state of a key, returning whether it had any.
+    pub fn remove_key(&mut self, key: &K) -> bool {
+        self.keys.remove(key).is_some()
+    }
+
+    /// Drop the state of every key.
+    pub fn reset(&mut self) {
+        self.keys.clear();
+        self.last_sweep = Duration::ZERO;
+        self.total_processed = 0;
+    }
+}
+
+impl<K, T, Context> StreamMatcher<T> for KeyedMatcher<K, T, Context>
+where
+    K: Eq + Hash + Clone,
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Captures are indexed by position in the keyed stream, across all keys.
+    fn process_captures_at(
+        &mut self,
+        item: T,
+        timestamp: Duration,
+    ) -> Result<Vec<Capture<T>>, MatcherError> {
+        Ok(self
+            .process_keyed(item, timestamp)?
+            .into_iter()
+            .map(|(_, capture)| capture)
+            .collect())
+    }
+
+    fn pattern_name(&self) -> Option<&str> {
+        None
+    }
+
+    fn record_completion(&mut self, _pattern: &str, _index: usize, _timestamp: Duration) {
+        // The matcher of each key indexes only the items of its own key, so
+        // positions in the shared stream do not carry over
+    }
+
+    fn reset(&mut self) {
+        KeyedMatcher::reset(self);
+    }
+}
+
+impl<K, T, Context> fmt::Debug for KeyedMatcher<K, T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyedMatcher")
+            .field("key_count", &self.keys.len())
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ElementSettings, MatchPolicy, PatternElement, PatternSettings};
+
+    fn login_sequence() -> Matcher<(u32, &'static str), ()> {
+        let mut matcher = Matcher::new(10);
+        matcher.add_pattern(PatternElement::predicate(|event: &(u32, &str)| {
+            event.1 == "fail"
+        }));
+        matcher.add_pattern(PatternElement::predicate(|event: &(u32, &str)| {
+            event.1 == "fail"
+        }));
+        matcher.add_pattern(PatternElement::predicate(|event: &(u32, &str)| {
+            event.1 == "success"
+        }));
+        matcher
+    }
+
+    #[test]
+    fn test_sequences_per_key() {
+        let mut matcher = KeyedMatcher::new(|event: &(u32, &str)| event.0, login_sequence);
+
+        // Interleaved, user 1 fails twice then succeeds; user 2 never does
+        let events = vec![
+            (1, "fail"),
+            (2, "fail"),
+            (1, "fail"),
+            (2, "success"),
+            (1, "success"),
+        ];
+        let found = matcher.process_items(events).unwrap();
+        assert_eq!(
+            found,
+            vec![KeyedMatch {
+                key: 1,
+                value: (1, "success"),
+            }]
+        );
+        assert_eq!(matcher.key_count(), 2);
+    }
+
+    #[test]
+    fn test_idle_keys_are_evicted() {
+        let mut matcher = KeyedMatcher::new(|event: &(u32, &str)| event.0, login_sequence);
+        matcher.set_ttl(Duration::from_secs(60));
+        let at = Duration::from_secs;
+
+        matcher.process_item_at((1, "fail"), at(0)).unwrap();
+        matcher.process_item_at((1, "fail"), at(10)).unwrap();
+        matcher.process_item_at((2, "fail"), at(50)).unwrap();
+
+        // User 1 went idle, so its partial match is forgotten
+        assert!(matcher
+            .process_item_at((2, "fail"), at(100))
+            .unwrap()
+            .is_empty());
+        assert!(!matcher.contains_key(&1));
+        assert!(matcher
+            .process_item_at((1, "success"), at(101))
+            .unwrap()
+            .is_empty());
+        assert_eq!(matcher.key_count(), 2);
+        assert!(matcher.evict_idle(at(500)).is_empty());
+        assert_eq!(matcher.key_count(), 0);
+    }
+
+    #[test]
+    fn test_evicted_keys_report_held_matches() {
+        let mut matcher = KeyedMatcher::new(
+            |event: &(u32, &str)| event.0,
+            || {
+                let mut matcher = login_sequence();
+                matcher.set_pattern_settings(
+                    PatternSettings::default().match_policy(MatchPolicy::LongestMatch),
+                );
+                let settings = ElementSettings {
+                    optional: true,
+                    ..Default::default()
+                };
+                matcher.add_pattern(PatternElement::predicate_with_settings(
+                    |event: &(u32, &str)| event.1 == "logout",
+                    settings,
+                ));
+                matcher
+            },
+        );
+        matcher.set_ttl(Duration::from_secs(60));
+        let at = Duration::from_secs;
+
+        // User 1's match waits for a logout that never comes
+        for event in [(1, "fail"), (1, "fail"), (1, "success")] {
+            assert!(matcher.process_item_at(event, at(0)).unwrap().is_empty());
+        }
+        assert_eq!(
+            matcher.process_item_at((2, "fail"), at(100)).unwrap(),
+            vec![KeyedMatch {
+                key: 1,
+                value: (1, "success"),
+            }]
+        );
+        assert!(!matcher.contains_key(&1));
+    }
+}
@@ -66,8 +66,99 @@
 //! assert_eq!(matcher.process_item(5).unwrap(), Some(10)); // 5 * 2 = 10
 //! ```
 
-use std::collections::HashMap;
+pub mod bench_harness;
+pub mod bio;
+#[cfg(feature = "memchr")]
+mod bytes;
+#[cfg(feature = "arrow")]
+mod columnar;
+#[cfg(feature = "csv")]
+mod csv;
+mod diagnostics;
+mod dot;
+mod events;
+pub mod fixed;
+mod fuzzy;
+#[cfg(feature = "glob")]
+mod glob;
+mod group;
+mod interval;
+mod io;
+#[cfg(feature = "json")]
+mod json;
+mod keyed;
+pub mod log;
+mod nested;
+mod observer;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod pattern;
+#[cfg(feature = "pcap")]
+mod pcap;
+pub mod presets;
+mod profile;
+#[cfg(feature = "python")]
+mod python;
+pub mod report;
+mod scored;
+mod sharded;
+#[cfg(feature = "simd")]
+mod simd;
+mod strings;
+#[cfg(feature = "tokio")]
+mod task;
+#[cfg(feature = "unicode")]
+mod text;
+mod trace;
+
+#[cfg(feature = "memchr")]
+pub use bytes::ByteMatcher;
+#[cfg(feature = "arrow")]
+pub use columnar::ColumnError;
+#[cfg(feature = "csv")]
+pub use csv::{CsvMatch, CsvMatcher};
+pub use diagnostics::{Diagnostic, Issue, Severity};
+pub use events::{EventQueue, MatchEvent, OverflowPolicy};
+pub use fuzzy::FuzzyString;
+#[cfg(feature = "glob")]
+pub use glob::GlobPattern;
+pub use group::{FirstElement, GroupMatch, MatcherGroup, MatcherId, StreamMatcher};
+pub use interval::Interval;
+pub use io::{Decoder, LineMapper, LineMatch, LineMatcher, ReadError, ReadMatch, ReadMatcher};
+#[cfg(feature = "json")]
+pub use json::{JsonToken, JsonTokens};
+pub use keyed::{KeyFunction, KeyedMatch, KeyedMatcher, MatcherFactory};
+pub use observer::MatcherObserver;
+#[cfg(feature = "rayon")]
+pub use parallel::{BatchOutcome, ParallelMatcherGroup};
+pub use pattern::Pattern;
+#[cfg(feature = "pcap")]
+pub use pcap::{PcapReader, PcapRecord, TcpFlags, TcpPacket};
+pub use profile::{ProfileEntry, ProfileReport};
+#[cfg(feature = "python")]
+pub use python::{register_predicate, scrolling_window_pattern_matcher, PyMatcher};
+pub use scored::{ScoredMatch, ScoredMatcher};
+pub use sharded::{ShardError, ShardMatch, ShardResult, ShardedMatcher};
+#[cfg(feature = "simd")]
+pub use simd::SimdRange;
+pub use strings::{Normalization, StringEquals};
+#[cfg(feature = "tokio")]
+pub use task::MatcherTask;
+#[cfg(feature = "unicode")]
+pub use text::{Segmentation, TextMatch};
+pub use trace::{TraceAction, TraceEvent};
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Bound;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use profile::Profiler;
+use trace::Tracer;
 
 pub type ExtractorId = u32;
 
@@ -84,22 +175,78 @@ pub enum MatchResult {
 
 /// Represents the current state during pattern matching.
 #[derive(Debug, Clone)]
-pub struct MatchState<T> {
+pub struct MatchState<'a, T> {
     /// The current item being matched.
     pub current_item: T,
-    /// The position in the current match sequence.
+    /// Index of the pattern element being evaluated.
     pub position: usize,
     /// The total number of items processed.
     pub total_processed: usize,
+    /// The items in the window before the current one, oldest first.
+    pub window: &'a [T],
+    /// Name of the pattern being evaluated, if it has one.
+    pub pattern_name: Option<&'a str>,
+    /// Timestamp of the current item.
+    pub timestamp: Duration,
+    /// Timestamp of the first item of the match attempt, which is the
+    /// current item's when it begins the attempt.
+    pub start_timestamp: Duration,
+}
+
+/// Stable, machine-readable category of an error.
+///
+/// Codes never change meaning between releases, so tooling can branch on them
+/// instead of matching on `Display` output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// No patterns have been configured.
+    NoPatterns,
+    /// Pattern configuration is invalid.
+    InvalidPattern,
+    /// Restored state does not fit the configured patterns.
+    InvalidState,
+    /// An extractor failed to process the current state.
+    ExtractorProcessingFailed,
+    /// An extractor is misconfigured.
+    ExtractorInvalidConfiguration,
+    /// A pattern element refers to an extractor that is not registered.
+    MissingExtractor,
+    /// An extractor failed with a user-defined error.
+    ExtractorCustom,
+}
+
+impl ErrorCode {
+    /// Get the stable string form of this code.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::NoPatterns => "no_patterns",
+            ErrorCode::InvalidPattern => "invalid_pattern",
+            ErrorCode::InvalidState => "invalid_state",
+            ErrorCode::ExtractorProcessingFailed => "extractor_processing_failed",
+            ErrorCode::ExtractorInvalidConfiguration => "extractor_invalid_configuration",
+            ErrorCode::MissingExtractor => "missing_extractor",
+            ErrorCode::ExtractorCustom => "extractor_custom",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// Error types for extractors.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum ExtractorError {
     /// Extractor failed to process the current state.
     ProcessingFailed(String),
     /// Invalid extractor configuration.
     InvalidConfiguration(String),
+    /// Extractor failed with a user-defined error.
+    Custom(Arc<dyn std::error::Error + Send + Sync>),
 }
 
 impl fmt::Display for ExtractorError {
@@ -109,11 +256,63 @@ impl fmt::Display for ExtractorError {
             ExtractorError::InvalidConfiguration(msg) => {
                 write!(f, "Invalid configuration: {}", msg)
             }
+            ExtractorError::Custom(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl PartialEq for ExtractorError {
+    /// Custom errors are equal only when they share the same underlying error.
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ExtractorError::ProcessingFailed(a), ExtractorError::ProcessingFailed(b)) => a == b,
+            (ExtractorError::InvalidConfiguration(a), ExtractorError::InvalidConfiguration(b)) => {
+                a == b
+            }
+            (ExtractorError::Custom(a), ExtractorError::Custom(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl std::error::Error for ExtractorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExtractorError::Custom(err) => Some(err.as_ref()),
+            _ => None,
         }
     }
 }
 
-impl std::error::Error for ExtractorError {}
+impl ExtractorError {
+    /// Wrap a user-defined error.
+    pub fn custom<E>(err: E) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        ExtractorError::Custom(Arc::new(err))
+    }
+
+    /// Get the user-defined error if it is of type `E`.
+    pub fn downcast_ref<E>(&self) -> Option<&E>
+    where
+        E: std::error::Error + 'static,
+    {
+        match self {
+            ExtractorError::Custom(err) => err.downcast_ref::<E>(),
+            _ => None,
+        }
+    }
+
+    /// Get the stable error code for this error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ExtractorError::ProcessingFailed(_) => ErrorCode::ExtractorProcessingFailed,
+            ExtractorError::InvalidConfiguration(_) => ErrorCode::ExtractorInvalidConfiguration,
+            ExtractorError::Custom(_) => ErrorCode::ExtractorCustom,
+        }
+    }
+}
 
 /// Action to take after an extractor runs.
 #[derive(Debug, Clone, PartialEq)]
@@ -126,18 +325,89 @@ pub enum ExtractorAction<T> {
     Restart,
 }
 
+/// Thread-safety bound on the closures and trait objects a matcher stores.
+///
+/// Without the `sync` feature every type satisfies it. With the feature only
+/// `Send + Sync` types do, which makes `Matcher` `Send` and `Sync` whenever
+/// its item and context types are.
+#[cfg(feature = "sync")]
+pub trait MaybeSync: Send + Sync {}
+
+#[cfg(feature = "sync")]
+impl<S: Send + Sync + ?Sized> MaybeSync for S {}
+
+/// Thread-safety bound on the closures and trait objects a matcher stores.
+///
+/// Without the `sync` feature every type satisfies it. With the feature only
+/// `Send + Sync` types do, which makes `Matcher` `Send` and `Sync` whenever
+/// its item and context types are.
+#[cfg(not(feature = "sync"))]
+pub trait MaybeSync {}
+
+#[cfg(not(feature = "sync"))]
+impl<S: ?Sized> MaybeSync for S {}
+
+/// Share a closure type behind an `Arc`, requiring `Send + Sync` with the
+/// `sync` feature.
+#[cfg(feature = "sync")]
+macro_rules! shared_fn {
+    ($($signature:tt)*) => { Arc<dyn $($signature)* + Send + Sync> };
+}
+
+/// Share a closure type behind an `Arc`, requiring `Send + Sync` with the
+/// `sync` feature.
+#[cfg(not(feature = "sync"))]
+macro_rules! shared_fn {
+    ($($signature:tt)*) => { Arc<dyn $($signature)*> };
+}
+
 /// Type alias for extractor functions.
-pub type Extractor<T> = Box<dyn Fn(&MatchState<T>) -> Result<ExtractorAction<T>, ExtractorError>>;
+pub type Extractor<T> =
+    shared_fn!(Fn(&MatchState<T>) -> Result<ExtractorAction<T>, ExtractorError>);
+
+/// Where in the stream and the pattern an error occurred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorLocation {
+    /// Name of the matcher's pattern, if it has one.
+    pub pattern: Option<String>,
+    /// Zero-based stream index of the item being processed.
+    pub index: usize,
+    /// Index of the pattern element that consumed the item.
+    pub element: usize,
+    /// ID of the extractor involved, if any.
+    pub extractor_id: Option<ExtractorId>,
+}
+
+impl fmt::Display for ErrorLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(pattern) = &self.pattern {
+            write!(f, "pattern {:?}, ", pattern)?;
+        }
+        write!(f, "item {}, element {}", self.index, self.element)?;
+        if let Some(id) = self.extractor_id {
+            write!(f, ", extractor {}", id)?;
+        }
+        Ok(())
+    }
+}
 
 /// Error types for the pattern matcher.
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum MatcherError {
     /// No patterns have been configured.
     NoPatterns,
     /// Pattern configuration is invalid.
     InvalidPattern(String),
+    /// Restored state does not fit the configured patterns.
+    InvalidState(String),
     /// Extractor execution failed.
-    ExtractorFailed(ExtractorError),
+    ExtractorFailed {
+        error: ExtractorError,
+        location: ErrorLocation,
+    },
+    /// A pattern element refers to an extractor ID that is not registered.
+    MissingExtractor { location: ErrorLocation },
 }
 
 impl fmt::Display for MatcherError {
@@ -145,15 +415,112 @@ impl fmt::Display for MatcherError {
         match self {
             MatcherError::NoPatterns => write!(f, "No patterns configured"),
             MatcherError::InvalidPattern(msg) => write!(f, "Invalid pattern: {}", msg),
-            MatcherError::ExtractorFailed(err) => write!(f, "Extractor failed: {}", err),
+            MatcherError::InvalidState(msg) => write!(f, "Invalid state: {}", msg),
+            MatcherError::ExtractorFailed { error, location } => {
+                write!(f, "Extractor failed at {}: {}", location, error)
+            }
+            MatcherError::MissingExtractor { location } => {
+                write!(f, "No extractor registered at {}", location)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MatcherError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MatcherError::ExtractorFailed { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl MatcherError {
+    /// Get the stable error code for this error.
+    ///
+    /// Extractor failures report the code of the underlying extractor error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            MatcherError::NoPatterns => ErrorCode::NoPatterns,
+            MatcherError::InvalidPattern(_) => ErrorCode::InvalidPattern,
+            MatcherError::InvalidState(_) => ErrorCode::InvalidState,
+            MatcherError::ExtractorFailed { error, .. } => error.code(),
+            MatcherError::MissingExtractor { .. } => ErrorCode::MissingExtractor,
+        }
+    }
+
+    /// Get the stream and pattern position where this error occurred, if known.
+    pub fn location(&self) -> Option<&ErrorLocation> {
+        match self {
+            MatcherError::ExtractorFailed { location, .. }
+            | MatcherError::MissingExtractor { location } => Some(location),
+            _ => None,
         }
     }
 }
 
-impl std::error::Error for MatcherError {}
+/// How a repeatable element chooses between matching again and moving on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RepeatMode {
+    /// Match as many items as possible, like regex `*`.
+    #[default]
+    Greedy,
+    /// Match as few items as possible, like regex `*?`: once the minimum is
+    /// reached, move on as soon as the following elements accept the item.
+    Lazy,
+}
+
+/// How a floating-point range element treats NaN items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum NanPolicy {
+    /// NaN is never in range.
+    #[default]
+    Reject,
+    /// NaN is always in range, e.g. to let missing readings through.
+    Accept,
+    /// Compare with IEEE 754 total ordering, in which positive NaN sorts
+    /// above infinity, negative NaN below negative infinity, and `-0.0`
+    /// below `0.0`.
+    TotalOrder,
+}
+
+/// The aggregate an aggregate element computes over its trailing items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Aggregate {
+    /// Sum of the values.
+    Sum,
+    /// Arithmetic mean of the values.
+    Average,
+    /// Smallest value.
+    Min,
+    /// Largest value.
+    Max,
+}
+
+impl Aggregate {
+    /// Compute the aggregate of a non-empty sequence of values.
+    pub fn compute(self, values: impl IntoIterator<Item = f64>) -> Option<f64> {
+        let mut values = values.into_iter();
+        let first = values.next()?;
+        let (mut total, mut count) = (first, 1usize);
+        let (mut min, mut max) = (first, first);
+        for value in values {
+            total += value;
+            count += 1;
+            min = min.min(value);
+            max = max.max(value);
+        }
+        Some(match self {
+            Aggregate::Sum => total,
+            Aggregate::Average => total / count as f64,
+            Aggregate::Min => min,
+            Aggregate::Max => max,
+        })
+    }
+}
 
 /// Configuration settings for pattern elements.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash)]
 pub struct ElementSettings<Context>
 where
     Context: Clone + fmt::Debug,
@@ -168,6 +535,21 @@ where
     pub context: Option<Context>,
     /// Associated extractor ID.
     pub extractor_id: Option<ExtractorId>,
+    /// Minimum number of consecutive items this element must match.
+    ///
+    /// Ignored when the element is optional, which allows zero matches.
+    pub min_repeat: usize,
+    /// Maximum number of consecutive items this element may match.
+    pub max_repeat: usize,
+    /// Whether repetitions consume as many or as few items as possible.
+    pub repeat_mode: RepeatMode,
+    /// Maximum distance from the item the previous element last matched to
+    /// the first item this element matches.
+    ///
+    /// An item offset counts stream positions, so `StreamOffset::Item(1)`
+    /// requires the very next item; a time offset compares timestamps. Has
+    /// no effect on the first element of a pattern.
+    pub max_gap: Option<StreamOffset>,
 }
 
 impl<Context> Clone for ElementSettings<Context>
@@ -181,6 +563,10 @@ where
             timeout_ms: self.timeout_ms,
             context: self.context.clone(),
             extractor_id: self.extractor_id,
+            min_repeat: self.min_repeat,
+            max_repeat: self.max_repeat,
+            repeat_mode: self.repeat_mode,
+            max_gap: self.max_gap,
         }
     }
 }
@@ -196,10 +582,445 @@ where
             timeout_ms: None,
             context: None,
             extractor_id: None,
+            min_repeat: 1,
+            max_repeat: 1,
+            repeat_mode: RepeatMode::default(),
+            max_gap: None,
+        }
+    }
+}
+
+impl<Context> ElementSettings<Context>
+where
+    Context: Clone + fmt::Debug,
+{
+    /// Get the inclusive bounds on the number of items this element matches.
+    pub fn repeat_bounds(&self) -> (usize, usize) {
+        let min = if self.optional { 0 } else { self.min_repeat };
+        (min, self.max_repeat)
+    }
+}
+
+/// A position in the input stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamOffset {
+    /// Zero-based index of an item in the stream.
+    Item(usize),
+    /// Timestamp of an item, as passed to `process_item_at`.
+    Time(Duration),
+}
+
+impl StreamOffset {
+    /// Compare the stream position of an item against this offset.
+    fn compare(&self, index: usize, timestamp: Duration) -> Ordering {
+        match self {
+            StreamOffset::Item(offset) => index.cmp(offset),
+            StreamOffset::Time(offset) => timestamp.cmp(offset),
         }
     }
 }
 
+/// A requirement that a match begin soon after another named pattern completes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Correlation {
+    /// Name of the pattern that must complete first.
+    pub pattern: String,
+    /// Maximum distance, in items or time, from that completion to the first
+    /// item of the match.
+    pub within: StreamOffset,
+}
+
+/// How the matcher recovers when an item fails to match a required element.
+///
+/// The policies trade throughput against the ability to find matches that
+/// overlap a failed attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ResetPolicy {
+    /// Start over from the first element; the failing item is discarded.
+    #[default]
+    ResetToStart,
+    /// Start over from the first element and test the failing item against it.
+    RetryCurrentItem,
+    /// Fall back to the longest suffix of the failed attempt that is still a
+    /// valid partial match, like the KMP string search algorithm. Extractors are
+    /// not re-run for replayed items.
+    FallbackByPrefix,
+    /// Start over and skip the rest of the window in which the failed attempt
+    /// began: no new match may begin until `window_size` items after its start.
+    DropWindow,
+}
+
+/// Which completion to report when a pattern can complete in several ways.
+///
+/// A pattern can complete as soon as all of its remaining elements are
+/// optional, and again each time one of those elements matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MatchPolicy {
+    /// Report the shortest completion and start over.
+    #[default]
+    FirstMatch,
+    /// Hold completions back until the match cannot be extended any further,
+    /// then report the longest one.
+    LongestMatch,
+    /// Report every completion as it happens.
+    AllMatches,
+}
+
+/// What happens to items that arrive between the items a match consumes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Selection {
+    /// Matches are contiguous: an item the pattern rejects ends the attempt
+    /// and the reset policy takes over.
+    #[default]
+    Strict,
+    /// Items the pattern rejects are skipped, so an attempt waits for the next
+    /// item that fits and only `within`, `max_gap` or a session gap end it.
+    SkipTillNextMatch,
+    /// As `SkipTillNextMatch`, and every item an attempt consumes also leaves
+    /// behind a copy of the attempt that skipped it, while every item that
+    /// can begin a match begins a new attempt. Matches are reported for every
+    /// combination of items that fits the pattern.
+    ///
+    /// The number of attempts grows quickly, so bound them with `within` or
+    /// `max_gap`. Beyond `MAX_PARALLEL_ATTEMPTS`, the oldest are dropped.
+    /// Attempts share the matcher's context.
+    SkipTillAnyMatch,
+}
+
+/// Most attempts kept alive at once under `Selection::SkipTillAnyMatch`.
+pub const MAX_PARALLEL_ATTEMPTS: usize = 1024;
+
+/// How the matcher explores the ways a pattern can consume an item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Engine {
+    /// Follow a single path, committing to the first way each item can be
+    /// consumed. An optional or repeated element that takes an item cannot
+    /// give it back to a later element.
+    #[default]
+    Greedy,
+    /// Follow every way the items of the current attempt can be consumed,
+    /// preferring the greedy path, so that the pattern completes whenever any
+    /// path does. Extractors and observers only see the preferred path.
+    Backtracking,
+}
+
+/// Configuration settings that apply to the pattern as a whole.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatternSettings {
+    /// Maximum time allowed between the first and the last matched item.
+    ///
+    /// Partial matches that exceed this duration are discarded.
+    pub within: Option<Duration>,
+    /// Earliest stream position at which a match may begin.
+    pub not_before: Option<StreamOffset>,
+    /// Latest stream position at which a match may begin.
+    pub not_after: Option<StreamOffset>,
+    /// How to recover from a mismatch, overriding the matcher default.
+    pub reset_policy: Option<ResetPolicy>,
+    /// Which completion to report when several are possible.
+    pub match_policy: MatchPolicy,
+    /// Another named pattern that must complete shortly before a match begins.
+    pub after: Option<Correlation>,
+    /// How to explore alternative ways of consuming items.
+    pub engine: Engine,
+    /// Report attempts that fail to complete within this limit of their first
+    /// item, instead of reporting completions.
+    pub absence: Option<StreamOffset>,
+    /// What happens to items that arrive between the items of a match.
+    pub selection: Selection,
+}
+
+impl PatternSettings {
+    /// Require all elements of the pattern to occur within the given duration.
+    pub fn within(mut self, duration: Duration) -> Self {
+        self.within = Some(duration);
+        self
+    }
+
+    /// Ignore items before the given offset when starting a new match.
+    pub fn not_before(mut self, offset: StreamOffset) -> Self {
+        self.not_before = Some(offset);
+        self
+    }
+
+    /// Ignore items after the given offset when starting a new match.
+    pub fn not_after(mut self, offset: StreamOffset) -> Self {
+        self.not_after = Some(offset);
+        self
+    }
+
+    /// Recover from mismatches with the given policy.
+    pub fn reset_policy(mut self, policy: ResetPolicy) -> Self {
+        self.reset_policy = Some(policy);
+        self
+    }
+
+    /// Choose which completion to report when several are possible.
+    pub fn match_policy(mut self, policy: MatchPolicy) -> Self {
+        self.match_policy = policy;
+        self
+    }
+
+    /// Explore alternative ways of consuming items with the given engine.
+    pub fn engine(mut self, engine: Engine) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    /// Choose what happens to items between the items of a match.
+    pub fn selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Detect the absence of the pattern rather than its presence.
+    ///
+    /// An attempt that has not completed by the time an item arrives more
+    /// than `limit` after its first item is abandoned and reported as a match
+    /// of that first item, so a pattern of a request followed by its response
+    /// reports requests left unanswered. Completed attempts are not reported.
+    /// `Matcher::check_absence` detects overdue attempts without waiting for
+    /// another item.
+    pub fn absence(mut self, limit: StreamOffset) -> Self {
+        self.absence = Some(limit);
+        self
+    }
+
+    /// Only begin a match within `within` of a completion of the named pattern.
+    ///
+    /// The matcher learns of completions of other patterns through
+    /// `Matcher::record_completion`.
+    pub fn after(mut self, pattern: impl Into<String>, within: StreamOffset) -> Self {
+        self.after = Some(Correlation {
+            pattern: pattern.into(),
+            within,
+        });
+        self
+    }
+
+    /// Check whether a match may begin at the given stream position.
+    pub fn can_start_at(&self, index: usize, timestamp: Duration) -> bool {
+        let after_start = self.not_before.map_or(true, |offset| {
+            offset.compare(index, timestamp) != Ordering::Less
+        });
+        let before_end = self.not_after.map_or(true, |offset| {
+            offset.compare(index, timestamp) != Ordering::Greater
+        });
+        after_start && before_end
+    }
+}
+
+/// Summary of a session of activity, delimited by inactivity gaps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionSummary {
+    /// Timestamp of the first item in the session.
+    pub start: Duration,
+    /// Timestamp of the last item in the session.
+    pub end: Duration,
+    /// Number of items processed during the session.
+    pub item_count: usize,
+    /// Number of matches completed during the session.
+    pub match_count: usize,
+}
+
+/// Counters describing the work a matcher has done.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatchStats {
+    /// Number of items processed.
+    pub items_processed: usize,
+    /// Number of matches completed.
+    pub matches_completed: usize,
+    /// Number of partial matches abandoned because an item did not match.
+    pub mismatch_resets: usize,
+    /// Number of times an extractor was run.
+    pub extractor_invocations: usize,
+    /// Number of items consumed by each pattern element, by index.
+    pub pattern_hits: Vec<usize>,
+}
+
+/// An item consumed by a pattern element during a match attempt.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Capture<T> {
+    /// The matched item.
+    pub item: T,
+    /// Zero-based stream index of the item.
+    pub index: usize,
+    /// Timestamp of the item.
+    pub timestamp: Duration,
+}
+
+/// A completed match with everything known about it.
+///
+/// With the `serde` feature, reports serialize directly to JSON and other
+/// formats for shipping to logging and alerting systems.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatchReport<T> {
+    /// Name of the matcher's pattern, if it has one.
+    pub pattern: Option<String>,
+    /// Stream indices of the items the match spans.
+    pub span: std::ops::Range<usize>,
+    /// Items consumed by the match, in stream order.
+    pub captures: Vec<Capture<T>>,
+    /// The value produced by the match, after extraction.
+    pub value: T,
+    /// Timestamp of the first item of the match.
+    pub start_timestamp: Duration,
+    /// Timestamp of the item that completed the match.
+    pub end_timestamp: Duration,
+}
+
+impl<T> MatchReport<T> {
+    /// Get the value produced by the match, as returned by `process_item`.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Take the value produced by the match.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// Iterate over the items consumed by the match.
+    pub fn items(&self) -> impl Iterator<Item = &T> {
+        self.captures.iter().map(|capture| &capture.item)
+    }
+}
+
+/// A match attempt that was still in progress when the stream ended.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialMatch<T> {
+    /// Index of the next pattern element that was waiting for an item.
+    pub position: usize,
+    /// Items matched so far, in stream order.
+    pub captured: Vec<Capture<T>>,
+}
+
+/// A pattern element a matcher is waiting for, as reported by
+/// `Matcher::expected_next`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExpectedElement {
+    /// Index of the pattern element.
+    pub position: usize,
+    /// Kind of the element, such as `"Exact"` or `"Range"`.
+    pub kind: &'static str,
+    /// The element as shown by its `Display` implementation, including the
+    /// value or range it matches.
+    pub description: String,
+    /// Number of items the element has already matched.
+    pub repeat: usize,
+    /// Whether the element may be passed over, because it is optional or has
+    /// already matched its minimum number of items.
+    pub optional: bool,
+}
+
+/// Outcome of ending the input stream with `Matcher::finish`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinishReport<T> {
+    /// Match completed at the end of the stream because all of its
+    /// remaining elements are optional.
+    pub completed: Option<T>,
+    /// Match attempt that could not complete before the stream ended.
+    pub partial: Option<PartialMatch<T>>,
+    /// Session closed by the end of the stream.
+    pub session: Option<SessionSummary>,
+}
+
+/// Snapshot of the in-flight progress of a matcher.
+///
+/// Patterns, extractors, and callbacks are not part of the snapshot: restore it
+/// into a matcher configured the same way as the one it was taken from. With the
+/// `serde` feature enabled the snapshot can be persisted and resumed after a
+/// restart.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatcherState<T> {
+    /// Index of the next pattern element to match.
+    pub position: usize,
+    /// Number of items the element at `position` has already matched.
+    pub repeat: usize,
+    /// Positions and repeat counts of the other live paths of a backtracking
+    /// attempt.
+    pub alternatives: Vec<(usize, usize)>,
+    /// Total number of items processed.
+    pub total_processed: usize,
+    /// Number of replayed items still to be skipped after `resume_from`.
+    pub replay_skip: usize,
+    /// Timestamp of the last processed item.
+    pub last_timestamp: Duration,
+    /// Items matched so far in the current attempt.
+    pub captured: Vec<Capture<T>>,
+    /// Completion held back while looking for a longer match.
+    pub pending: Option<Capture<T>>,
+    /// Earliest stream index at which a new match may begin.
+    pub next_start_index: usize,
+    /// The currently open session.
+    pub session: Option<SessionSummary>,
+    /// Most recently processed items, oldest first.
+    pub window: Vec<T>,
+    /// Timestamps of the most recent items, as many as rate elements need.
+    pub arrivals: Vec<Duration>,
+    /// Stream index and timestamp of the latest completion of each named
+    /// pattern, for correlation.
+    pub completions: HashMap<String, (usize, Duration)>,
+    /// Attempts in progress besides the live one, under
+    /// `Selection::SkipTillAnyMatch`.
+    pub forks: Vec<AttemptState<T>>,
+}
+
+/// Snapshot of a match attempt set aside under `Selection::SkipTillAnyMatch`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttemptState<T> {
+    /// Index of the next pattern element to match.
+    pub position: usize,
+    /// Number of items the element at `position` has already matched.
+    pub repeat: usize,
+    /// Positions and repeat counts of the other live paths of the attempt.
+    pub alternatives: Vec<(usize, usize)>,
+    /// Items matched so far by the attempt.
+    pub captured: Vec<Capture<T>>,
+    /// Completion held back while looking for a longer match.
+    pub pending: Option<Capture<T>>,
+}
+
+/// Type alias for callbacks fired when a pattern element matches an item,
+/// called with the item and its stream index.
+pub type ElementCallback<T> = shared_fn!(Fn(&T, usize));
+
+/// Type alias for completion extractors, which turn the captures of a whole
+/// match into the value it reports.
+pub type CompletionExtractor<T> = shared_fn!(Fn(&[Capture<T>]) -> T);
+
+/// Type alias for session summary callbacks.
+pub type SessionCallback = shared_fn!(Fn(&SessionSummary));
+
+/// Type alias for guard functions over the captures of a match attempt.
+pub type GuardFunction<T> = shared_fn!(Fn(&[Capture<T>], &T) -> bool);
+
+/// Type alias for functions over adjacent stream items.
+pub type PairFunction<T> = shared_fn!(Fn(&T, &T) -> bool);
+
+/// Type alias for predicate functions over a single item.
+pub type PredicateFunction<T> = shared_fn!(Fn(&T) -> bool);
+
+/// Type alias for functions testing the value of an aggregate.
+pub type AggregateFunction = shared_fn!(Fn(f64) -> bool);
+
+/// A user-defined kind of pattern element, used through `PatternElement::custom`.
+pub trait Matchable<T>: MaybeSync {
+    /// Check if this element matches the given item.
+    fn matches(&self, item: &T) -> Result<bool, MatcherError>;
+
+    /// Get a short description of this element for display.
+    fn describe(&self) -> String {
+        "<custom>".to_string()
+    }
+}
+
 /// A pattern element that can match against items of type T.
 pub enum PatternElement<T, Context>
 where
@@ -213,13 +1034,148 @@ where
     },
     /// Matches using a custom function.
     Predicate {
-        function: Box<dyn Fn(&T) -> bool>,
+        function: PredicateFunction<T>,
         settings: Option<ElementSettings<Context>>,
     },
-    /// Matches a range of values.
+    /// Matches using a custom function that also sees the items captured so
+    /// far in the current match attempt.
+    Guard {
+        function: GuardFunction<T>,
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Matches using a user-defined element kind.
+    Custom {
+        element: Arc<dyn Matchable<T>>,
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Matches a range of values, or when negated, values outside it.
+    ///
+    /// `order` performs the ordering comparison, so only range elements
+    /// require `T: PartialOrd`; build them with `PatternElement::range`, or
+    /// `PatternElement::from_range` for half-open and unbounded ranges.
     Range {
-        min: T,
-        max: T,
+        start: Bound<T>,
+        end: Bound<T>,
+        order: RangeOrder<T>,
+        negated: bool,
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Matches values within any of several inclusive ranges.
+    Ranges {
+        intervals: Vec<(T, T)>,
+        order: RangeOrder<T>,
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Matches any item in a set of values.
+    ///
+    /// `contains` performs the hash lookup, so only member elements require
+    /// `T: Eq + Hash`; build them with `PatternElement::member`.
+    Member {
+        values: HashSet<T>,
+        contains: fn(&HashSet<T>, &T) -> bool,
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Asserts that the next item matches (or, when negated, does not match)
+    /// the inner element without consuming it.
+    Lookahead {
+        inner: Box<PatternElement<T, Context>>,
+        negated: bool,
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Asserts that the item `offset` positions before the current one, which
+    /// must still be in the window, matches the inner element.
+    Lookbehind {
+        inner: Box<PatternElement<T, Context>>,
+        offset: usize,
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Matches an item equal to the item `offset` positions before it, which
+    /// must still be in the window.
+    BackReference {
+        offset: usize,
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Matches using a function of the item before this one in the stream
+    /// and this item, called as `function(previous, current)`.
+    ///
+    /// The previous item comes from the window, whether or not it matched
+    /// anything, so the element never matches with a window size of zero.
+    PairPredicate {
+        function: PairFunction<T>,
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Matches when the aggregate of the values of the last `length` items,
+    /// including this one, satisfies the function.
+    Aggregate {
+        length: usize,
+        aggregate: Aggregate,
+        value: fn(&T) -> f64,
+        function: AggregateFunction,
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Matches any item, then each repeat matches only an item equal to the
+    /// previous one, so the element consumes a run of equal items.
+    Run {
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Matches any item, then each repeat matches only an item strictly
+    /// greater (or, when decreasing, strictly less) than the previous one.
+    ///
+    /// `less` performs the ordering comparison, so only monotonic elements
+    /// require `T: PartialOrd`; build them with `PatternElement::increasing`.
+    Monotonic {
+        decreasing: bool,
+        less: fn(&T, &T) -> bool,
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Matches any item when at least `count` items, including this one,
+    /// arrived within the last `period` of item timestamps.
+    Rate {
+        count: usize,
+        period: Duration,
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Matches every item up to and including the first one the terminator
+    /// accepts, so the element captures a delimited stretch of the stream.
+    ///
+    /// The element is only satisfied once the terminator has matched, and
+    /// `max_repeat` bounds the items it consumes, terminator included.
+    Until {
+        terminator: Box<PatternElement<T, Context>>,
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Matches consecutive items for as long as the function accepts them,
+    /// called as `function(run, item)` with the items the element has
+    /// consumed so far in this attempt.
+    OneOrMore {
+        function: GuardFunction<T>,
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Matches a sequence of single-item elements as a unit, one item each,
+    /// so the whole sequence can be made optional.
+    ///
+    /// The element's repeat count is the number of items of the sequence
+    /// matched so far, and it is only satisfied once the sequence is complete
+    /// or, when optional, before it has begun.
+    Group {
+        elements: Vec<PatternElement<T, Context>>,
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Matches the items of a composed pattern as a unit.
+    ///
+    /// Built by [`Pattern::into_elements`]; the element's repeat count is the
+    /// number of items of the pattern matched so far.
+    Composite {
+        pattern: Box<Pattern<T, Context>>,
+        settings: Option<ElementSettings<Context>>,
+    },
+    /// Matches the items of a complete match of a nested matcher.
+    ///
+    /// The element's repeat count is the number of items the nested matcher
+    /// has taken in this attempt, and it is only satisfied by the item that
+    /// completes the nested match.
+    Nested {
+        matcher: Box<Matcher<T, Context>>,
         settings: Option<ElementSettings<Context>>,
     },
 }
@@ -235,16 +1191,130 @@ where
                 value: value.clone(),
                 settings: settings.clone(),
             },
-            PatternElement::Predicate { settings, .. } => {
-                // Note: Functions cannot be cloned, so we create a dummy predicate
-                PatternElement::Predicate {
-                    function: Box::new(|_| false),
-                    settings: settings.clone(),
-                }
-            }
-            PatternElement::Range { min, max, settings } => PatternElement::Range {
-                min: min.clone(),
-                max: max.clone(),
+            PatternElement::Predicate { function, settings } => PatternElement::Predicate {
+                function: Arc::clone(function),
+                settings: settings.clone(),
+            },
+            PatternElement::Guard { function, settings } => PatternElement::Guard {
+                function: Arc::clone(function),
+                settings: settings.clone(),
+            },
+            PatternElement::Custom { element, settings } => PatternElement::Custom {
+                element: Arc::clone(element),
+                settings: settings.clone(),
+            },
+            PatternElement::Range {
+                start,
+                end,
+                order,
+                negated,
+                settings,
+            } => PatternElement::Range {
+                start: start.clone(),
+                end: end.clone(),
+                order: order.clone(),
+                negated: *negated,
+                settings: settings.clone(),
+            },
+            PatternElement::Ranges {
+                intervals,
+                order,
+                settings,
+            } => PatternElement::Ranges {
+                intervals: intervals.clone(),
+                order: order.clone(),
+                settings: settings.clone(),
+            },
+            PatternElement::Member {
+                values,
+                contains,
+                settings,
+            } => PatternElement::Member {
+                values: values.clone(),
+                contains: *contains,
+                settings: settings.clone(),
+            },
+            PatternElement::Lookahead {
+                inner,
+                negated,
+                settings,
+            } => PatternElement::Lookahead {
+                inner: inner.clone(),
+                negated: *negated,
+                settings: settings.clone(),
+            },
+            PatternElement::Lookbehind {
+                inner,
+                offset,
+                settings,
+            } => PatternElement::Lookbehind {
+                inner: inner.clone(),
+                offset: *offset,
+                settings: settings.clone(),
+            },
+            PatternElement::BackReference { offset, settings } => PatternElement::BackReference {
+                offset: *offset,
+                settings: settings.clone(),
+            },
+            PatternElement::PairPredicate { function, settings } => PatternElement::PairPredicate {
+                function: Arc::clone(function),
+                settings: settings.clone(),
+            },
+            PatternElement::Aggregate {
+                length,
+                aggregate,
+                value,
+                function,
+                settings,
+            } => PatternElement::Aggregate {
+                length: *length,
+                aggregate: *aggregate,
+                value: *value,
+                function: Arc::clone(function),
+                settings: settings.clone(),
+            },
+            PatternElement::Run { settings } => PatternElement::Run {
+                settings: settings.clone(),
+            },
+            PatternElement::Monotonic {
+                decreasing,
+                less,
+                settings,
+            } => PatternElement::Monotonic {
+                decreasing: *decreasing,
+                less: *less,
+                settings: settings.clone(),
+            },
+            PatternElement::Rate {
+                count,
+                period,
+                settings,
+            } => PatternElement::Rate {
+                count: *count,
+                period: *period,
+                settings: settings.clone(),
+            },
+            PatternElement::Until {
+                terminator,
+                settings,
+            } => PatternElement::Until {
+                terminator: terminator.clone(),
+                settings: settings.clone(),
+            },
+            PatternElement::OneOrMore { function, settings } => PatternElement::OneOrMore {
+                function: Arc::clone(function),
+                settings: settings.clone(),
+            },
+            PatternElement::Group { elements, settings } => PatternElement::Group {
+                elements: elements.clone(),
+                settings: settings.clone(),
+            },
+            PatternElement::Composite { pattern, settings } => PatternElement::Composite {
+                pattern: pattern.clone(),
+                settings: settings.clone(),
+            },
+            PatternElement::Nested { matcher, settings } => PatternElement::Nested {
+                matcher: matcher.clone(),
                 settings: settings.clone(),
             },
         }
@@ -268,33 +1338,470 @@ where
                 .field("function", &"<function>")
                 .field("settings", settings)
                 .finish(),
-            PatternElement::Range { min, max, settings } => f
+            PatternElement::Guard { settings, .. } => f
+                .debug_struct("Guard")
+                .field("function", &"<function>")
+                .field("settings", settings)
+                .finish(),
+            PatternElement::Custom { element, settings } => f
+                .debug_struct("Custom")
+                .field("element", &element.describe())
+                .field("settings", settings)
+                .finish(),
+            PatternElement::Range {
+                start,
+                end,
+                order,
+                negated,
+                settings,
+            } => f
                 .debug_struct("Range")
-                .field("min", min)
-                .field("max", max)
+                .field("start", start)
+                .field("end", end)
+                .field("order", order)
+                .field("negated", negated)
                 .field("settings", settings)
                 .finish(),
-        }
+            PatternElement::Ranges {
+                intervals,
+                order,
+                settings,
+            } => f
+                .debug_struct("Ranges")
+                .field("intervals", intervals)
+                .field("order", order)
+                .field("settings", settings)
+                .finish(),
+            PatternElement::Member {
+                values, settings, ..
+            } => f
+                .debug_struct("Member")
+                .field("values", values)
+                .field("settings", settings)
+                .finish(),
+            PatternElement::Lookahead {
+                inner,
+                negated,
+                settings,
+            } => f
+                .debug_struct("Lookahead")
+                .field("inner", inner)
+                .field("negated", negated)
+                .field("settings", settings)
+                .finish(),
+            PatternElement::Lookbehind {
+                inner,
+                offset,
+                settings,
+            } => f
+                .debug_struct("Lookbehind")
+                .field("inner", inner)
+                .field("offset", offset)
+                .field("settings", settings)
+                .finish(),
+            PatternElement::BackReference { offset, settings } => f
+                .debug_struct("BackReference")
+                .field("offset", offset)
+                .field("settings", settings)
+                .finish(),
+            PatternElement::PairPredicate { settings, .. } => f
+                .debug_struct("PairPredicate")
+                .field("function", &"<function>")
+                .field("settings", settings)
+                .finish(),
+            PatternElement::Aggregate {
+                length,
+                aggregate,
+                settings,
+                ..
+            } => f
+                .debug_struct("Aggregate")
+                .field("length", length)
+                .field("aggregate", aggregate)
+                .field("function", &"<function>")
+                .field("settings", settings)
+                .finish(),
+            PatternElement::Run { settings } => {
+                f.debug_struct("Run").field("settings", settings).finish()
+            }
+            PatternElement::Monotonic {
+                decreasing,
+                settings,
+                ..
+            } => f
+                .debug_struct("Monotonic")
+                .field("decreasing", decreasing)
+                .field("settings", settings)
+                .finish(),
+            PatternElement::Rate {
+                count,
+                period,
+                settings,
+            } => f
+                .debug_struct("Rate")
+                .field("count", count)
+                .field("period", period)
+                .field("settings", settings)
+                .finish(),
+            PatternElement::Until {
+                terminator,
+                settings,
+            } => f
+                .debug_struct("Until")
+                .field("terminator", terminator)
+                .field("settings", settings)
+                .finish(),
+            PatternElement::OneOrMore { settings, .. } => f
+                .debug_struct("OneOrMore")
+                .field("function", &"<function>")
+                .field("settings", settings)
+                .finish(),
+            PatternElement::Group { elements, settings } => f
+                .debug_struct("Group")
+                .field("elements", elements)
+                .field("settings", settings)
+                .finish(),
+            PatternElement::Composite { pattern, settings } => f
+                .debug_struct("Composite")
+                .field("pattern", pattern)
+                .field("settings", settings)
+                .finish(),
+            PatternElement::Nested { matcher, settings } => f
+                .debug_struct("Nested")
+                .field("matcher", matcher)
+                .field("settings", settings)
+                .finish(),
+        }
     }
 }
 
+/// Elements display in the syntax [`Pattern`] parses, followed in brackets by
+/// any settings other than the defaults. Repeat bounds are written only when
+/// the element is part of a [`Pattern`], and the context is never written.
 impl<T, Context> fmt::Display for PatternElement<T, Context>
 where
     T: Clone + PartialEq + fmt::Debug,
     Context: Clone + fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, false)
+    }
+}
+
+impl<T, Context> PatternElement<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Write the element and its settings, leaving out the repeat bounds
+    /// unless `repeat` is set.
+    pub(crate) fn fmt_with(&self, f: &mut fmt::Formatter<'_>, repeat: bool) -> fmt::Result {
+        self.fmt_element(f)?;
+        // The bounds of groups and composites follow from their parts
+        let derived = matches!(
+            self,
+            PatternElement::Group { .. } | PatternElement::Composite { .. }
+        );
+        pattern::write_settings(f, &self.settings(), repeat && !derived)
+    }
+
+    fn fmt_element(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             PatternElement::Exact { value, .. } => write!(f, "Exact({:?})", value),
             PatternElement::Predicate { .. } => write!(f, "Predicate(<function>)"),
-            PatternElement::Range { min, max, .. } => write!(f, "Range({:?}..{:?})", min, max),
+            PatternElement::Guard { .. } => write!(f, "Guard(<function>)"),
+            PatternElement::Custom { element, .. } => write!(f, "Custom({})", element.describe()),
+            PatternElement::Range {
+                start,
+                end,
+                order,
+                negated,
+                ..
+            } => {
+                write!(f, "{}(", if *negated { "Outside" } else { "Range" })?;
+                match start {
+                    Bound::Included(start) => write!(f, "{:?}..", start)?,
+                    Bound::Excluded(start) => write!(f, "{:?}<..", start)?,
+                    Bound::Unbounded => write!(f, "..")?,
+                }
+                match end {
+                    Bound::Included(end) => write!(f, "={:?}", end)?,
+                    Bound::Excluded(end) => write!(f, "{:?}", end)?,
+                    Bound::Unbounded => {}
+                }
+                if order.kind() != OrderKind::Natural {
+                    write!(f, ", {:?}", order.kind())?;
+                }
+                write!(f, ")")
+            }
+            PatternElement::Ranges { intervals, .. } => write!(f, "Ranges({:?})", intervals),
+            PatternElement::Member { values, .. } => write!(f, "Member({} values)", values.len()),
+            PatternElement::Lookahead { inner, negated, .. } => {
+                if *negated {
+                    write!(f, "NegativeLookahead({})", inner)
+                } else {
+                    write!(f, "Lookahead({})", inner)
+                }
+            }
+            PatternElement::Lookbehind { inner, offset, .. } => {
+                write!(f, "Lookbehind({}, {})", inner, offset)
+            }
+            PatternElement::BackReference { offset, .. } => write!(f, "BackReference({})", offset),
+            PatternElement::PairPredicate { .. } => write!(f, "PairPredicate(<function>)"),
+            PatternElement::Aggregate {
+                length, aggregate, ..
+            } => write!(f, "Aggregate({:?} of {}, <function>)", aggregate, length),
+            PatternElement::Run { .. } => write!(f, "Run"),
+            PatternElement::Monotonic { decreasing, .. } => {
+                if *decreasing {
+                    write!(f, "Decreasing")
+                } else {
+                    write!(f, "Increasing")
+                }
+            }
+            PatternElement::Rate { count, period, .. } => {
+                write!(f, "Rate({} per {:?})", count, period)
+            }
+            PatternElement::Until { terminator, .. } => write!(f, "Until({})", terminator),
+            PatternElement::OneOrMore { .. } => write!(f, "OneOrMore(<function>)"),
+            PatternElement::Group { elements, settings } => {
+                let elements: Vec<String> = elements.iter().map(ToString::to_string).collect();
+                if settings.as_ref().is_some_and(|settings| settings.optional) {
+                    write!(f, "OptionalGroup({})", elements.join(", "))
+                } else {
+                    write!(f, "Group({})", elements.join(", "))
+                }
+            }
+            PatternElement::Composite { pattern, .. } => write!(f, "Composite({})", pattern),
+            PatternElement::Nested { matcher, .. } => {
+                let elements: Vec<String> =
+                    matcher.patterns().iter().map(ToString::to_string).collect();
+                write!(f, "Nested({})", elements.join(", "))
+            }
+        }
+    }
+}
+
+/// Elements compare by kind, data, and settings, so data-only patterns can be
+/// deduplicated and compared across configuration changes.
+///
+/// Elements built from closures are equal only to their clones, which share
+/// the closure. Range elements compare by their bounds and `RangeOrder`, so
+/// float ranges with different NaN policies differ. Member, monotonic, and
+/// aggregate elements compare by their data, not by the comparison or value
+/// function they were built with.
+impl<T, Context> PartialEq for PatternElement<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        let same_data = match (self, other) {
+            (PatternElement::Exact { value: a, .. }, PatternElement::Exact { value: b, .. }) => {
+                a == b
+            }
+            (
+                PatternElement::Predicate { function: a, .. },
+                PatternElement::Predicate { function: b, .. },
+            ) => Arc::ptr_eq(a, b),
+            (
+                PatternElement::Guard { function: a, .. },
+                PatternElement::Guard { function: b, .. },
+            )
+            | (
+                PatternElement::OneOrMore { function: a, .. },
+                PatternElement::OneOrMore { function: b, .. },
+            ) => Arc::ptr_eq(a, b),
+            (
+                PatternElement::Custom { element: a, .. },
+                PatternElement::Custom { element: b, .. },
+            ) => Arc::ptr_eq(a, b),
+            (
+                PatternElement::Range {
+                    start: a_start,
+                    end: a_end,
+                    order: a_order,
+                    negated: a_negated,
+                    ..
+                },
+                PatternElement::Range {
+                    start: b_start,
+                    end: b_end,
+                    order: b_order,
+                    negated: b_negated,
+                    ..
+                },
+            ) => {
+                a_start == b_start && a_end == b_end && a_order == b_order && a_negated == b_negated
+            }
+            (
+                PatternElement::Ranges {
+                    intervals: a,
+                    order: a_order,
+                    ..
+                },
+                PatternElement::Ranges {
+                    intervals: b,
+                    order: b_order,
+                    ..
+                },
+            ) => a == b && a_order == b_order,
+            (
+                PatternElement::Member {
+                    values: a,
+                    contains,
+                    ..
+                },
+                PatternElement::Member { values: b, .. },
+            ) => a.len() == b.len() && a.iter().all(|value| contains(b, value)),
+            (
+                PatternElement::Lookahead {
+                    inner: a,
+                    negated: a_negated,
+                    ..
+                },
+                PatternElement::Lookahead {
+                    inner: b,
+                    negated: b_negated,
+                    ..
+                },
+            ) => a == b && a_negated == b_negated,
+            (
+                PatternElement::Lookbehind {
+                    inner: a,
+                    offset: a_offset,
+                    ..
+                },
+                PatternElement::Lookbehind {
+                    inner: b,
+                    offset: b_offset,
+                    ..
+                },
+            ) => a == b && a_offset == b_offset,
+            (
+                PatternElement::BackReference { offset: a, .. },
+                PatternElement::BackReference { offset: b, .. },
+            ) => a == b,
+            (
+                PatternElement::PairPredicate { function: a, .. },
+                PatternElement::PairPredicate { function: b, .. },
+            ) => Arc::ptr_eq(a, b),
+            (
+                PatternElement::Aggregate {
+                    length: a_length,
+                    aggregate: a_aggregate,
+                    function: a,
+                    ..
+                },
+                PatternElement::Aggregate {
+                    length: b_length,
+                    aggregate: b_aggregate,
+                    function: b,
+                    ..
+                },
+            ) => a_length == b_length && a_aggregate == b_aggregate && Arc::ptr_eq(a, b),
+            (PatternElement::Run { .. }, PatternElement::Run { .. }) => true,
+            (
+                PatternElement::Monotonic { decreasing: a, .. },
+                PatternElement::Monotonic { decreasing: b, .. },
+            ) => a == b,
+            (
+                PatternElement::Rate {
+                    count: a_count,
+                    period: a_period,
+                    ..
+                },
+                PatternElement::Rate {
+                    count: b_count,
+                    period: b_period,
+                    ..
+                },
+            ) => a_count == b_count && a_period == b_period,
+            (
+                PatternElement::Until { terminator: a, .. },
+                PatternElement::Until { terminator: b, .. },
+            ) => a == b,
+            (
+                PatternElement::Group { elements: a, .. },
+                PatternElement::Group { elements: b, .. },
+            ) => a == b,
+            (
+                PatternElement::Composite { pattern: a, .. },
+                PatternElement::Composite { pattern: b, .. },
+            ) => a == b,
+            (
+                PatternElement::Nested { matcher: a, .. },
+                PatternElement::Nested { matcher: b, .. },
+            ) => a.patterns() == b.patterns() && a.pattern_settings() == b.pattern_settings(),
+            _ => false,
+        };
+        same_data && self.settings() == other.settings()
+    }
+}
+
+impl<T, Context> Eq for PatternElement<T, Context>
+where
+    T: Clone + Eq + fmt::Debug,
+    Context: Clone + fmt::Debug + Eq,
+{
+}
+
+/// Hashes agree with equality: closures hash by address, and member elements
+/// by their number of values, since a set has no order to hash in.
+impl<T, Context> Hash for PatternElement<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug + Hash,
+    Context: Clone + fmt::Debug + Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        fn address<F: ?Sized>(function: &Arc<F>) -> usize {
+            Arc::as_ptr(function) as *const () as usize
+        }
+
+        std::mem::discriminant(self).hash(state);
+        match self {
+            PatternElement::Exact { value, .. } => value.hash(state),
+            PatternElement::Predicate { function, .. } => address(function).hash(state),
+            PatternElement::Guard { function, .. } | PatternElement::OneOrMore { function, .. } => {
+                address(function).hash(state)
+            }
+            PatternElement::Custom { element, .. } => address(element).hash(state),
+            PatternElement::Range {
+                start,
+                end,
+                order,
+                negated,
+                ..
+            } => (start, end, order, negated).hash(state),
+            PatternElement::Ranges {
+                intervals, order, ..
+            } => (intervals, order).hash(state),
+            PatternElement::Member { values, .. } => values.len().hash(state),
+            PatternElement::Lookahead { inner, negated, .. } => (inner, negated).hash(state),
+            PatternElement::Lookbehind { inner, offset, .. } => (inner, offset).hash(state),
+            PatternElement::BackReference { offset, .. } => offset.hash(state),
+            PatternElement::PairPredicate { function, .. } => address(function).hash(state),
+            PatternElement::Aggregate {
+                length,
+                aggregate,
+                function,
+                ..
+            } => (length, aggregate, address(function)).hash(state),
+            PatternElement::Run { .. } => {}
+            PatternElement::Monotonic { decreasing, .. } => decreasing.hash(state),
+            PatternElement::Rate { count, period, .. } => (count, period).hash(state),
+            PatternElement::Until { terminator, .. } => terminator.hash(state),
+            PatternElement::Group { elements, .. } => elements.hash(state),
+            PatternElement::Composite { pattern, .. } => pattern.hash(state),
+            PatternElement::Nested { matcher, .. } => matcher.patterns().hash(state),
         }
+        self.settings().hash(state);
     }
 }
 
 impl<T, Context> PatternElement<T, Context>
 where
-    T: Clone + PartialEq + fmt::Debug + std::cmp::PartialOrd,
+    T: Clone + PartialEq + fmt::Debug,
     Context: Clone + fmt::Debug,
 {
     /// Get the settings for this pattern element.
@@ -302,19 +1809,143 @@ where
         match self {
             PatternElement::Exact { settings, .. } => settings.clone().unwrap_or_default(),
             PatternElement::Predicate { settings, .. } => settings.clone().unwrap_or_default(),
+            PatternElement::Guard { settings, .. } => settings.clone().unwrap_or_default(),
+            PatternElement::Custom { settings, .. } => settings.clone().unwrap_or_default(),
             PatternElement::Range { settings, .. } => settings.clone().unwrap_or_default(),
+            PatternElement::Ranges { settings, .. } => settings.clone().unwrap_or_default(),
+            PatternElement::Member { settings, .. } => settings.clone().unwrap_or_default(),
+            PatternElement::Lookahead { settings, .. } => settings.clone().unwrap_or_default(),
+            PatternElement::Lookbehind { settings, .. } => settings.clone().unwrap_or_default(),
+            PatternElement::BackReference { settings, .. } => settings.clone().unwrap_or_default(),
+            PatternElement::PairPredicate { settings, .. } => settings.clone().unwrap_or_default(),
+            PatternElement::Aggregate { settings, .. } => settings.clone().unwrap_or_default(),
+            PatternElement::Run { settings } => settings.clone().unwrap_or_default(),
+            PatternElement::Monotonic { settings, .. } => settings.clone().unwrap_or_default(),
+            PatternElement::Rate { settings, .. } => settings.clone().unwrap_or_default(),
+            PatternElement::Until { settings, .. } => settings.clone().unwrap_or_default(),
+            PatternElement::OneOrMore { settings, .. } => settings.clone().unwrap_or_default(),
+            PatternElement::Group { settings, .. } => settings.clone().unwrap_or_default(),
+            PatternElement::Composite { settings, .. } => settings.clone().unwrap_or_default(),
+            PatternElement::Nested { settings, .. } => settings.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Get the settings slot of this pattern element.
+    pub(crate) fn settings_mut(&mut self) -> &mut Option<ElementSettings<Context>> {
+        match self {
+            PatternElement::Exact { settings, .. }
+            | PatternElement::Predicate { settings, .. }
+            | PatternElement::Guard { settings, .. }
+            | PatternElement::Custom { settings, .. }
+            | PatternElement::Range { settings, .. }
+            | PatternElement::Ranges { settings, .. }
+            | PatternElement::Member { settings, .. }
+            | PatternElement::Lookahead { settings, .. }
+            | PatternElement::Lookbehind { settings, .. }
+            | PatternElement::BackReference { settings, .. }
+            | PatternElement::PairPredicate { settings, .. }
+            | PatternElement::Aggregate { settings, .. }
+            | PatternElement::Run { settings }
+            | PatternElement::Monotonic { settings, .. }
+            | PatternElement::Rate { settings, .. }
+            | PatternElement::Until { settings, .. }
+            | PatternElement::OneOrMore { settings, .. }
+            | PatternElement::Group { settings, .. }
+            | PatternElement::Composite { settings, .. }
+            | PatternElement::Nested { settings, .. } => settings,
         }
     }
 
     /// Check if this pattern element matches the given item.
+    ///
+    /// Elements that refer to earlier items or captures can only be evaluated
+    /// by a `Matcher` and return `MatcherError::InvalidPattern`.
     pub fn matches(&self, item: &T) -> Result<bool, MatcherError> {
         match self {
             PatternElement::Exact { value, .. } => Ok(item == value),
             PatternElement::Predicate { function, .. } => Ok(function(item)),
-            PatternElement::Range { min, max, .. } => Ok(item >= min && item <= max),
+            PatternElement::Custom { element, .. } => element.matches(item),
+            PatternElement::Range {
+                start,
+                end,
+                order,
+                negated,
+                ..
+            } => Ok(order.contains(item, start.as_ref(), end.as_ref()) != *negated),
+            PatternElement::Ranges {
+                intervals, order, ..
+            } => Ok(intervals.iter().any(|(min, max)| {
+                order.contains(item, Bound::Included(min), Bound::Included(max))
+            })),
+            PatternElement::Member {
+                values, contains, ..
+            } => Ok(contains(values, item)),
+            PatternElement::Lookahead { inner, negated, .. } => {
+                Ok(inner.matches(item)? != *negated)
+            }
+            PatternElement::Group { elements, .. } => match elements.first() {
+                Some(first) => first.matches(item),
+                None => Ok(false),
+            },
+            PatternElement::Guard { .. } | PatternElement::OneOrMore { .. } => Err(
+                MatcherError::InvalidPattern(format!("{} needs the matcher's captures", self)),
+            ),
+            PatternElement::Lookbehind { .. }
+            | PatternElement::BackReference { .. }
+            | PatternElement::PairPredicate { .. }
+            | PatternElement::Aggregate { .. } => Err(MatcherError::InvalidPattern(format!(
+                "{} needs the matcher's window",
+                self
+            ))),
+            PatternElement::Rate { .. } => Err(MatcherError::InvalidPattern(format!(
+                "{} needs the matcher's timestamps",
+                self
+            ))),
+            PatternElement::Run { .. }
+            | PatternElement::Monotonic { .. }
+            | PatternElement::Until { .. }
+            | PatternElement::Composite { .. }
+            | PatternElement::Nested { .. } => Err(MatcherError::InvalidPattern(format!(
+                "{} needs the matcher's captures",
+                self
+            ))),
+        }
+    }
+
+    /// Get the kind of this element, the name of its variant.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PatternElement::Exact { .. } => "Exact",
+            PatternElement::Predicate { .. } => "Predicate",
+            PatternElement::Guard { .. } => "Guard",
+            PatternElement::Custom { .. } => "Custom",
+            PatternElement::Range { .. } => "Range",
+            PatternElement::Ranges { .. } => "Ranges",
+            PatternElement::Member { .. } => "Member",
+            PatternElement::Lookahead { .. } => "Lookahead",
+            PatternElement::Lookbehind { .. } => "Lookbehind",
+            PatternElement::BackReference { .. } => "BackReference",
+            PatternElement::PairPredicate { .. } => "PairPredicate",
+            PatternElement::Aggregate { .. } => "Aggregate",
+            PatternElement::Run { .. } => "Run",
+            PatternElement::Monotonic { .. } => "Monotonic",
+            PatternElement::Rate { .. } => "Rate",
+            PatternElement::Until { .. } => "Until",
+            PatternElement::OneOrMore { .. } => "OneOrMore",
+            PatternElement::Group { .. } => "Group",
+            PatternElement::Composite { .. } => "Composite",
+            PatternElement::Nested { .. } => "Nested",
         }
     }
 
+    /// Check if this element is an assertion that does not consume items.
+    pub fn is_assertion(&self) -> bool {
+        matches!(
+            self,
+            PatternElement::Lookahead { .. } | PatternElement::Lookbehind { .. }
+        )
+    }
+
     /// Create a new exact match pattern element.
     pub fn exact(value: T) -> Self {
         PatternElement::Exact {
@@ -334,10 +1965,10 @@ where
     /// Create a new predicate pattern element.
     pub fn predicate<F>(function: F) -> Self
     where
-        F: Fn(&T) -> bool + 'static,
+        F: Fn(&T) -> bool + MaybeSync + 'static,
     {
         PatternElement::Predicate {
-            function: Box::new(function),
+            function: Arc::new(function),
             settings: None,
         }
     }
@@ -345,215 +1976,3009 @@ where
     /// Create a new predicate pattern element with settings.
     pub fn predicate_with_settings<F>(function: F, settings: ElementSettings<Context>) -> Self
     where
-        F: Fn(&T) -> bool + 'static,
+        F: Fn(&T) -> bool + MaybeSync + 'static,
     {
         PatternElement::Predicate {
-            function: Box::new(function),
+            function: Arc::new(function),
             settings: Some(settings),
         }
     }
 
-    /// Create a new range pattern element.
-    pub fn range(min: T, max: T) -> Self {
-        PatternElement::Range {
-            min,
-            max,
+    /// Create a new guard pattern element.
+    ///
+    /// The function receives the items captured so far in the current match
+    /// attempt, oldest first, and the item being offered.
+    pub fn guard<F>(function: F) -> Self
+    where
+        F: Fn(&[Capture<T>], &T) -> bool + MaybeSync + 'static,
+    {
+        PatternElement::Guard {
+            function: Arc::new(function),
             settings: None,
         }
     }
 
-    /// Create a new range pattern element with settings.
-    pub fn range_with_settings(min: T, max: T, settings: ElementSettings<Context>) -> Self {
-        PatternElement::Range {
-            min,
-            max,
+    /// Create a new pattern element matching one or more consecutive items,
+    /// as many as the function accepts.
+    ///
+    /// The function receives the run of items the element has consumed so far,
+    /// oldest first, and the item being offered, so the run can be
+    /// accumulated as it grows. Once the function rejects an item, the run
+    /// ends and the item is offered to the following element.
+    pub fn one_or_more<F>(function: F) -> Self
+    where
+        F: Fn(&[Capture<T>], &T) -> bool + MaybeSync + 'static,
+    {
+        let settings = ElementSettings {
+            max_repeat: usize::MAX,
+            ..Default::default()
+        };
+        PatternElement::OneOrMore {
+            function: Arc::new(function),
             settings: Some(settings),
         }
     }
-}
 
-/// The main pattern matcher that processes streaming data.
-pub struct Matcher<T, Context>
-where
-    T: Clone + PartialEq + fmt::Debug + std::cmp::PartialOrd,
-    Context: Clone + fmt::Debug,
-{
-    patterns: Vec<PatternElement<T, Context>>,
-    current_position: usize,
-    total_processed: usize,
-    window_size: usize,
-    extractors: HashMap<ExtractorId, Extractor<T>>,
-    context: Option<Context>,
-}
+    /// Create a new pattern element matching a sequence of elements as a
+    /// unit, one item each.
+    ///
+    /// Fails if an element can match other than exactly one item, or is an
+    /// assertion.
+    pub fn group(elements: Vec<PatternElement<T, Context>>) -> Result<Self, MatcherError> {
+        let settings = ElementSettings {
+            min_repeat: elements.len(),
+            max_repeat: elements.len(),
+            ..Default::default()
+        };
+        Self::group_with_settings(elements, settings)
+    }
 
-impl<T, Context> Matcher<T, Context>
-where
-    T: Clone + PartialEq + fmt::Debug + std::cmp::PartialOrd,
-    Context: Clone + fmt::Debug,
-{
-    /// Create a new matcher with the specified window size.
-    pub fn new(window_size: usize) -> Self {
-        Self {
-            patterns: Vec::new(),
-            current_position: 0,
-            total_processed: 0,
-            window_size,
-            extractors: HashMap::new(),
-            context: None,
-        }
+    /// Create a new pattern element matching a sequence of elements as a
+    /// unit that may be skipped entirely, as in an optional block of fields
+    /// between a header and a payload.
+    pub fn optional_group(elements: Vec<PatternElement<T, Context>>) -> Result<Self, MatcherError> {
+        let settings = ElementSettings {
+            optional: true,
+            min_repeat: elements.len(),
+            max_repeat: elements.len(),
+            ..Default::default()
+        };
+        Self::group_with_settings(elements, settings)
     }
 
-    /// Create a new matcher with patterns and window size.
-    pub fn with_patterns(patterns: Vec<PatternElement<T, Context>>, window_size: usize) -> Self {
-        Self {
-            patterns,
-            current_position: 0,
-            total_processed: 0,
-            window_size,
-            extractors: HashMap::new(),
-            context: None,
+    /// Create a new group pattern element with settings.
+    ///
+    /// The repeat bounds should both be the number of elements.
+    pub fn group_with_settings(
+        elements: Vec<PatternElement<T, Context>>,
+        settings: ElementSettings<Context>,
+    ) -> Result<Self, MatcherError> {
+        if let Some(element) = elements
+            .iter()
+            .find(|element| element.is_assertion() || element.settings().repeat_bounds() != (1, 1))
+        {
+            return Err(MatcherError::InvalidPattern(format!(
+                "{} does not match exactly one item, so it cannot be part of a group",
+                element
+            )));
         }
+        Ok(PatternElement::Group {
+            elements,
+            settings: Some(settings),
+        })
     }
 
-    /// Add a pattern element to the matcher.
-    pub fn add_pattern(&mut self, pattern: PatternElement<T, Context>) {
-        self.patterns.push(pattern);
+    /// Create a new guard pattern element with settings.
+    pub fn guard_with_settings<F>(function: F, settings: ElementSettings<Context>) -> Self
+    where
+        F: Fn(&[Capture<T>], &T) -> bool + MaybeSync + 'static,
+    {
+        PatternElement::Guard {
+            function: Arc::new(function),
+            settings: Some(settings),
+        }
     }
 
-    /// Register an extractor with the given ID.
-    pub fn register_extractor<F>(&mut self, id: ExtractorId, extractor: F)
+    /// Create a new delta pattern element, called as
+    /// `function(previous, current)` with the item matched just before this
+    /// one in the current attempt.
+    ///
+    /// The element never matches the first item of an attempt, since there
+    /// is no previously matched item to compare against.
+    pub fn delta<F>(function: F) -> Self
     where
-        F: Fn(&MatchState<T>) -> Result<ExtractorAction<T>, ExtractorError> + 'static,
+        F: Fn(&T, &T) -> bool + MaybeSync + 'static,
     {
-        self.extractors.insert(id, Box::new(extractor));
+        Self::guard(move |captured: &[Capture<T>], item: &T| {
+            captured
+                .last()
+                .is_some_and(|previous| function(&previous.item, item))
+        })
     }
 
-    /// Set the context for this matcher.
-    pub fn set_context(&mut self, context: Context) {
-        self.context = Some(context);
+    /// Create a new delta pattern element with settings.
+    pub fn delta_with_settings<F>(function: F, settings: ElementSettings<Context>) -> Self
+    where
+        F: Fn(&T, &T) -> bool + MaybeSync + 'static,
+    {
+        Self::guard_with_settings(
+            move |captured: &[Capture<T>], item: &T| {
+                captured
+                    .last()
+                    .is_some_and(|previous| function(&previous.item, item))
+            },
+            settings,
+        )
     }
 
-    /// Get the current context.
-    pub fn context(&self) -> Option<&Context> {
-        self.context.as_ref()
+    /// Create a new pattern element of a user-defined kind.
+    pub fn custom<M>(element: M) -> Self
+    where
+        M: Matchable<T> + 'static,
+    {
+        PatternElement::Custom {
+            element: Arc::new(element),
+            settings: None,
+        }
     }
 
-    /// Process a single item and return any extracted data.
-    pub fn process_item(&mut self, item: T) -> Result<Option<T>, MatcherError> {
-        if self.patterns.is_empty() {
-            return Err(MatcherError::NoPatterns);
+    /// Create a new pattern element of a user-defined kind with settings.
+    pub fn custom_with_settings<M>(element: M, settings: ElementSettings<Context>) -> Self
+    where
+        M: Matchable<T> + 'static,
+    {
+        PatternElement::Custom {
+            element: Arc::new(element),
+            settings: Some(settings),
         }
+    }
 
-        self.total_processed += 1;
-
-        let state = MatchState {
-            current_item: item.clone(),
-            position: self.current_position,
-            total_processed: self.total_processed,
-        };
-
-        let mut had_any_match = false;
-
-        loop {
-            // Check if we're at the end of patterns
-            if self.current_position >= self.patterns.len() {
-                self.current_position = 0;
-                // Only return the item if we had at least one actual match
-                return Ok(if had_any_match { Some(item) } else { None });
-            }
-
-            let pattern = &self.patterns[self.current_position];
-            let matches = pattern.matches(&item)?;
-
-            if matches {
-                had_any_match = true;
-
-                // Run any associated extractor before advancing position
-                let settings = pattern.settings();
-                if let Some(extractor_id) = settings.extractor_id {
-                    if let Some(extractor) = self.extractors.get(&extractor_id) {
-                        match extractor(&state).map_err(MatcherError::ExtractorFailed)? {
-                            ExtractorAction::Continue => {
-                                // Continue normal processing
-                            }
-                            ExtractorAction::Extract(data) => {
-                                self.current_position = 0;
-                                return Ok(Some(data));
-                            }
-                            ExtractorAction::Restart => {
-                                self.current_position = 0;
-                                return Ok(None);
-                            }
-                        }
-                    }
-                }
+    /// Create a new lookahead pattern element.
+    ///
+    /// The assertion is checked against the item that arrives after the
+    /// preceding element, which is then offered to the following elements.
+    /// A trailing lookahead completes the match when that item arrives.
+    pub fn lookahead(inner: PatternElement<T, Context>) -> Self {
+        PatternElement::Lookahead {
+            inner: Box::new(inner),
+            negated: false,
+            settings: None,
+        }
+    }
 
-                self.current_position += 1;
+    /// Create a new negative lookahead pattern element.
+    pub fn negative_lookahead(inner: PatternElement<T, Context>) -> Self {
+        PatternElement::Lookahead {
+            inner: Box::new(inner),
+            negated: true,
+            settings: None,
+        }
+    }
 
-                // Check if we've completed the pattern
-                if self.current_position >= self.patterns.len() {
-                    self.current_position = 0;
-                    return Ok(Some(item));
-                }
+    /// Create a new lookbehind pattern element.
+    ///
+    /// The assertion is checked against the item `offset` positions before the
+    /// item arriving at this element, without consuming either. It fails when
+    /// that item has already left the window.
+    pub fn lookbehind(inner: PatternElement<T, Context>, offset: usize) -> Self {
+        PatternElement::Lookbehind {
+            inner: Box::new(inner),
+            offset,
+            settings: None,
+        }
+    }
 
-                // Pattern element matched, exit loop
-                break;
-            } else {
-                // No match, check if element is optional
-                let settings = pattern.settings();
-                if settings.optional {
-                    self.current_position += 1;
-                    // Continue loop to check next pattern element against same item
-                } else {
-                    self.current_position = 0;
-                    break;
-                }
-            }
+    /// Create a new pattern element consuming up to `max_len` items followed
+    /// by one the terminator accepts, as in capturing everything between a
+    /// start and an end marker.
+    ///
+    /// The terminator is captured with the items before it. An attempt that
+    /// reaches `max_len` items without meeting the terminator fails.
+    pub fn until(terminator: PatternElement<T, Context>, max_len: usize) -> Self {
+        let settings = ElementSettings {
+            max_repeat: max_len.saturating_add(1),
+            ..Default::default()
+        };
+        PatternElement::Until {
+            terminator: Box::new(terminator),
+            settings: Some(settings),
         }
+    }
 
-        Ok(None)
+    /// Create a new back-reference pattern element.
+    pub fn back_reference(offset: usize) -> Self {
+        PatternElement::BackReference {
+            offset,
+            settings: None,
+        }
     }
 
-    /// Process multiple items and return all extracted data.
-    pub fn process_items(&mut self, items: Vec<T>) -> Result<Vec<T>, MatcherError> {
-        let mut results = Vec::new();
-        for item in items {
-            if let Some(extracted) = self.process_item(item)? {
-                results.push(extracted);
-            }
+    /// Create a new pair predicate pattern element, called as
+    /// `function(previous, current)` with the item just before this one in
+    /// the stream.
+    pub fn pair_predicate<F>(function: F) -> Self
+    where
+        F: Fn(&T, &T) -> bool + MaybeSync + 'static,
+    {
+        PatternElement::PairPredicate {
+            function: Arc::new(function),
+            settings: None,
         }
-        Ok(results)
     }
 
-    /// Reset the matcher state.
-    pub fn reset(&mut self) {
-        self.current_position = 0;
-        self.total_processed = 0;
+    /// Create a new pair predicate pattern element with settings.
+    pub fn pair_predicate_with_settings<F>(function: F, settings: ElementSettings<Context>) -> Self
+    where
+        F: Fn(&T, &T) -> bool + MaybeSync + 'static,
+    {
+        PatternElement::PairPredicate {
+            function: Arc::new(function),
+            settings: Some(settings),
+        }
     }
 
-    /// Get the current position in the pattern.
-    pub fn current_position(&self) -> usize {
-        self.current_position
+    /// Create a new aggregate pattern element over the values of the last
+    /// `length` items, using `value` to turn each item into a number.
+    ///
+    /// The element never matches until `length - 1` earlier items are in the
+    /// window, so the window size must be at least `length - 1`.
+    pub fn aggregate_by<F>(
+        length: usize,
+        aggregate: Aggregate,
+        value: fn(&T) -> f64,
+        function: F,
+    ) -> Self
+    where
+        F: Fn(f64) -> bool + MaybeSync + 'static,
+    {
+        PatternElement::Aggregate {
+            length,
+            aggregate,
+            value,
+            function: Arc::new(function),
+            settings: None,
+        }
     }
 
-    /// Get the total number of items processed.
-    pub fn total_processed(&self) -> usize {
-        self.total_processed
+    /// Create a new aggregate pattern element with a value function and settings.
+    pub fn aggregate_by_with_settings<F>(
+        length: usize,
+        aggregate: Aggregate,
+        value: fn(&T) -> f64,
+        function: F,
+        settings: ElementSettings<Context>,
+    ) -> Self
+    where
+        F: Fn(f64) -> bool + MaybeSync + 'static,
+    {
+        PatternElement::Aggregate {
+            length,
+            aggregate,
+            value,
+            function: Arc::new(function),
+            settings: Some(settings),
+        }
     }
 
-    /// Get the window size.
-    pub fn window_size(&self) -> usize {
-        self.window_size
+    /// Create a new pattern element matching `length` consecutive equal
+    /// items, whatever their value.
+    pub fn run(length: usize) -> Self {
+        Self::run_with_settings(length, ElementSettings::default())
     }
 
-    /// Set the window size.
-    pub fn set_window_size(&mut self, size: usize) {
-        self.window_size = size;
+    /// Create a new run pattern element with settings.
+    ///
+    /// The repeat bounds of `settings` are replaced by `length`.
+    pub fn run_with_settings(length: usize, mut settings: ElementSettings<Context>) -> Self {
+        settings.min_repeat = length;
+        settings.max_repeat = length;
+        PatternElement::Run {
+            settings: Some(settings),
+        }
     }
 
-    /// Get the number of patterns.
-    pub fn pattern_count(&self) -> usize {
-        self.patterns.len()
+    /// Create a new rate pattern element, matching any item that arrives
+    /// while at least `count` items, including itself, arrived within
+    /// `period`.
+    ///
+    /// Arrival times are the item timestamps, so bursts in replayed data are
+    /// detected with `Matcher::process_item_at`.
+    pub fn rate(count: usize, period: Duration) -> Self {
+        PatternElement::Rate {
+            count,
+            period,
+            settings: None,
+        }
+    }
+
+    /// Create a new rate pattern element with settings.
+    pub fn rate_with_settings(
+        count: usize,
+        period: Duration,
+        settings: ElementSettings<Context>,
+    ) -> Self {
+        PatternElement::Rate {
+            count,
+            period,
+            settings: Some(settings),
+        }
+    }
+
+    /// Create a new range pattern element with a caller-supplied ordering,
+    /// called as `compare(item, bound)`.
+    ///
+    /// Use this for item types without `PartialOrd`, or for an ordering other
+    /// than the natural one.
+    pub fn range_by<F>(min: T, max: T, compare: F) -> Self
+    where
+        F: Fn(&T, &T) -> Option<Ordering> + MaybeSync + 'static,
+    {
+        PatternElement::Range {
+            start: Bound::Included(min),
+            end: Bound::Included(max),
+            order: RangeOrder::custom(compare),
+            negated: false,
+            settings: None,
+        }
+    }
+}
+
+impl<T, Context> PatternElement<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug + PartialOrd,
+    Context: Clone + fmt::Debug,
+{
+    /// Create a new range pattern element.
+    pub fn range(min: T, max: T) -> Self {
+        PatternElement::Range {
+            start: Bound::Included(min),
+            end: Bound::Included(max),
+            order: RangeOrder::natural(),
+            negated: false,
+            settings: None,
+        }
+    }
+
+    /// Create a new range pattern element with settings.
+    pub fn range_with_settings(min: T, max: T, settings: ElementSettings<Context>) -> Self {
+        PatternElement::Range {
+            start: Bound::Included(min),
+            end: Bound::Included(max),
+            order: RangeOrder::natural(),
+            negated: false,
+            settings: Some(settings),
+        }
+    }
+
+    /// Create a new pattern element matching values strictly outside the
+    /// inclusive range `min..=max`.
+    pub fn outside(min: T, max: T) -> Self {
+        PatternElement::Range {
+            start: Bound::Included(min),
+            end: Bound::Included(max),
+            order: RangeOrder::natural(),
+            negated: true,
+            settings: None,
+        }
+    }
+
+    /// Create a new pattern element matching `length` strictly increasing
+    /// consecutive items.
+    pub fn increasing(length: usize) -> Self {
+        Self::monotonic(length, false, ElementSettings::default())
+    }
+
+    /// Create a new increasing pattern element with settings.
+    ///
+    /// The repeat bounds of `settings` are replaced by `length`.
+    pub fn increasing_with_settings(length: usize, settings: ElementSettings<Context>) -> Self {
+        Self::monotonic(length, false, settings)
+    }
+
+    /// Create a new pattern element matching `length` strictly decreasing
+    /// consecutive items.
+    pub fn decreasing(length: usize) -> Self {
+        Self::monotonic(length, true, ElementSettings::default())
+    }
+
+    /// Create a new decreasing pattern element with settings.
+    ///
+    /// The repeat bounds of `settings` are replaced by `length`.
+    pub fn decreasing_with_settings(length: usize, settings: ElementSettings<Context>) -> Self {
+        Self::monotonic(length, true, settings)
+    }
+
+    fn monotonic(length: usize, decreasing: bool, mut settings: ElementSettings<Context>) -> Self {
+        settings.min_repeat = length;
+        settings.max_repeat = length;
+        PatternElement::Monotonic {
+            decreasing,
+            less: |a, b| a < b,
+            settings: Some(settings),
+        }
+    }
+
+    /// Create a new outside-range pattern element with settings.
+    pub fn outside_with_settings(min: T, max: T, settings: ElementSettings<Context>) -> Self {
+        PatternElement::Range {
+            start: Bound::Included(min),
+            end: Bound::Included(max),
+            order: RangeOrder::natural(),
+            negated: true,
+            settings: Some(settings),
+        }
+    }
+
+    /// Create a new pattern element matching values within any of several
+    /// inclusive `(min, max)` ranges.
+    pub fn ranges(intervals: Vec<(T, T)>) -> Self {
+        PatternElement::Ranges {
+            intervals,
+            order: RangeOrder::natural(),
+            settings: None,
+        }
+    }
+
+    /// Create a new multi-range pattern element with settings.
+    pub fn ranges_with_settings(
+        intervals: Vec<(T, T)>,
+        settings: ElementSettings<Context>,
+    ) -> Self {
+        PatternElement::Ranges {
+            intervals,
+            order: RangeOrder::natural(),
+            settings: Some(settings),
+        }
+    }
+}
+
+impl<T, Context> PatternElement<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug + Eq + Hash,
+    Context: Clone + fmt::Debug,
+{
+    /// Create a new pattern element matching any of the given values.
+    ///
+    /// Each item is checked with a single hash lookup, so this scales to
+    /// large sets such as blocklists of addresses or IDs.
+    pub fn member(values: impl IntoIterator<Item = T>) -> Self {
+        PatternElement::Member {
+            values: values.into_iter().collect(),
+            contains: HashSet::contains,
+            settings: None,
+        }
+    }
+
+    /// Create a new member pattern element with settings.
+    pub fn member_with_settings(
+        values: impl IntoIterator<Item = T>,
+        settings: ElementSettings<Context>,
+    ) -> Self {
+        PatternElement::Member {
+            values: values.into_iter().collect(),
+            contains: HashSet::contains,
+            settings: Some(settings),
+        }
+    }
+}
+
+impl<T, Context> PatternElement<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug + Copy + Into<f64>,
+    Context: Clone + fmt::Debug,
+{
+    /// Create a new aggregate pattern element over the last `length` numeric
+    /// items, e.g. "the rolling average of the last 5 readings exceeds 100".
+    pub fn aggregate<F>(length: usize, aggregate: Aggregate, function: F) -> Self
+    where
+        F: Fn(f64) -> bool + MaybeSync + 'static,
+    {
+        Self::aggregate_by(length, aggregate, |item| (*item).into(), function)
+    }
+
+    /// Create a new numeric aggregate pattern element with settings.
+    pub fn aggregate_with_settings<F>(
+        length: usize,
+        aggregate: Aggregate,
+        function: F,
+        settings: ElementSettings<Context>,
+    ) -> Self
+    where
+        F: Fn(f64) -> bool + MaybeSync + 'static,
+    {
+        Self::aggregate_by_with_settings(
+            length,
+            aggregate,
+            |item| (*item).into(),
+            function,
+            settings,
+        )
+    }
+}
+
+/// Check whether an item at stream position `index` and `timestamp` is close
+/// enough to the latest capture for the element to start on it.
+fn within_gap<T, Context>(
+    pattern: &PatternElement<T, Context>,
+    index: usize,
+    timestamp: Duration,
+    captured: &[Capture<T>],
+) -> bool
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    let Some(previous) = captured.last() else {
+        return true;
+    };
+    let Some(max_gap) = pattern.settings().max_gap else {
+        return true;
+    };
+    match max_gap {
+        StreamOffset::Item(items) => index - previous.index <= items,
+        StreamOffset::Time(time) => timestamp.saturating_sub(previous.timestamp) <= time,
+    }
+}
+
+/// The kind of ordering a range element compares items with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OrderKind {
+    /// The `PartialOrd` ordering of the item type, under which items that do
+    /// not compare with the bounds, such as NaN, are out of range.
+    #[default]
+    Natural,
+    /// The `PartialOrd` ordering, with NaN always in range.
+    NanAccepting,
+    /// IEEE 754 total ordering of floats.
+    Total,
+    /// An ordering passed to `PatternElement::range_by`.
+    Custom,
+}
+
+/// Type alias for the orderings passed to `PatternElement::range_by`.
+pub type CompareFunction<T> = shared_fn!(Fn(&T, &T) -> Option<Ordering>);
+
+/// How a range element orders items against its bounds.
+///
+/// Orderings compare and hash by their kind, so they are data like the
+/// bounds, except that custom orderings are equal only to their clones.
+#[derive(Clone)]
+pub struct RangeOrder<T> {
+    kind: OrderKind,
+    compare: Comparison<T>,
+}
+
+/// The function behind a `RangeOrder`.
+#[derive(Clone)]
+enum Comparison<T> {
+    Builtin(fn(&T, &T) -> Option<Ordering>),
+    Custom(CompareFunction<T>),
+}
+
+impl<T> RangeOrder<T> {
+    /// Order items by `PartialOrd`.
+    pub fn natural() -> Self
+    where
+        T: PartialOrd,
+    {
+        Self {
+            kind: OrderKind::Natural,
+            compare: Comparison::Builtin(T::partial_cmp),
+        }
+    }
+
+    /// Order floats as the NaN policy says.
+    pub fn float(nan: NanPolicy) -> Self
+    where
+        T: Float,
+    {
+        let (kind, compare): (_, fn(&T, &T) -> Option<Ordering>) = match nan {
+            NanPolicy::Reject => (OrderKind::Natural, T::partial_cmp),
+            NanPolicy::Accept => return Self::nan_accepting(),
+            NanPolicy::TotalOrder => (OrderKind::Total, |a, b| Some(a.total_cmp(b))),
+        };
+        Self {
+            kind,
+            compare: Comparison::Builtin(compare),
+        }
+    }
+
+    /// Order items by `PartialOrd`, placing any item that does not compare
+    /// with itself, such as NaN, in every range.
+    pub(crate) fn nan_accepting() -> Self
+    where
+        T: PartialOrd,
+    {
+        Self {
+            kind: OrderKind::NanAccepting,
+            compare: Comparison::Builtin(T::partial_cmp),
+        }
+    }
+
+    /// Order items with a caller-supplied comparison.
+    pub fn custom<F>(compare: F) -> Self
+    where
+        F: Fn(&T, &T) -> Option<Ordering> + MaybeSync + 'static,
+    {
+        Self {
+            kind: OrderKind::Custom,
+            compare: Comparison::Custom(Arc::new(compare)),
+        }
+    }
+
+    /// Get the kind of ordering.
+    pub fn kind(&self) -> OrderKind {
+        self.kind
+    }
+
+    /// Compare an item with a bound.
+    pub fn compare(&self, item: &T, bound: &T) -> Option<Ordering> {
+        match &self.compare {
+            Comparison::Builtin(compare) => compare(item, bound),
+            Comparison::Custom(compare) => compare(item, bound),
+        }
+    }
+
+    /// Check whether `item` lies between `start` and `end`.
+    pub fn contains(&self, item: &T, start: Bound<&T>, end: Bound<&T>) -> bool {
+        // Only NaN fails to compare with itself
+        if self.kind == OrderKind::NanAccepting && self.compare(item, item).is_none() {
+            return true;
+        }
+        let above = match start {
+            Bound::Included(start) => self.compare(item, start).is_some_and(Ordering::is_ge),
+            Bound::Excluded(start) => self.compare(item, start).is_some_and(Ordering::is_gt),
+            Bound::Unbounded => true,
+        };
+        let below = match end {
+            Bound::Included(end) => self.compare(item, end).is_some_and(Ordering::is_le),
+            Bound::Excluded(end) => self.compare(item, end).is_some_and(Ordering::is_lt),
+            Bound::Unbounded => true,
+        };
+        above && below
+    }
+}
+
+impl<T> PartialEq for RangeOrder<T> {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.compare, &other.compare) {
+            (Comparison::Custom(a), Comparison::Custom(b)) => Arc::ptr_eq(a, b),
+            _ => self.kind == other.kind,
+        }
+    }
+}
+
+impl<T> Eq for RangeOrder<T> {}
+
+impl<T> Hash for RangeOrder<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.kind.hash(state);
+        if let Comparison::Custom(compare) = &self.compare {
+            (Arc::as_ptr(compare) as *const () as usize).hash(state);
+        }
+    }
+}
+
+impl<T> fmt::Debug for RangeOrder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.kind)
+    }
+}
+
+/// Floating-point item types usable with `PatternElement::float_range`.
+pub trait Float: Copy + PartialOrd + fmt::Debug {
+    /// Check if the value is NaN.
+    fn is_nan(self) -> bool;
+
+    /// Compare using IEEE 754 total ordering.
+    fn total_cmp(&self, other: &Self) -> Ordering;
+}
+
+macro_rules! impl_float {
+    ($($float:ty),*) => {
+        $(
+            impl Float for $float {
+                fn is_nan(self) -> bool {
+                    <$float>::is_nan(self)
+                }
+
+                fn total_cmp(&self, other: &Self) -> Ordering {
+                    <$float>::total_cmp(self, other)
+                }
+            }
+        )*
+    };
+}
+
+impl_float!(f32, f64);
+
+impl<T, Context> PatternElement<T, Context>
+where
+    T: Float,
+    Context: Clone + fmt::Debug,
+{
+    /// Create a new floating-point range pattern element with an explicit
+    /// NaN policy.
+    ///
+    /// Fails if a bound is NaN or `min` is greater than `max`, since such a
+    /// range would silently never match.
+    pub fn float_range(min: T, max: T, nan: NanPolicy) -> Result<Self, MatcherError> {
+        Self::float_range_inner(min, max, nan, None)
+    }
+
+    /// Create a new floating-point range pattern element with settings.
+    pub fn float_range_with_settings(
+        min: T,
+        max: T,
+        nan: NanPolicy,
+        settings: ElementSettings<Context>,
+    ) -> Result<Self, MatcherError> {
+        Self::float_range_inner(min, max, nan, Some(settings))
+    }
+
+    fn float_range_inner(
+        min: T,
+        max: T,
+        nan: NanPolicy,
+        settings: Option<ElementSettings<Context>>,
+    ) -> Result<Self, MatcherError> {
+        if min.is_nan() || max.is_nan() || min > max {
+            return Err(MatcherError::InvalidPattern(format!(
+                "invalid float range {:?}..={:?}",
+                min, max
+            )));
+        }
+        Ok(PatternElement::Range {
+            start: Bound::Included(min),
+            end: Bound::Included(max),
+            order: RangeOrder::float(nan),
+            negated: false,
+            settings,
+        })
+    }
+}
+
+/// Outcome of offering an item to the pattern.
+enum Advance {
+    /// The element at `element` consumed the item, which is its `repeat`th match.
+    Consumed { element: usize, repeat: usize },
+    /// A required element rejected the item.
+    Rejected,
+    /// The remaining elements were all skipped without consuming the item.
+    Exhausted,
+    /// Trailing assertions accepted the item, completing the match without
+    /// consuming it.
+    Completed,
+}
+
+/// A match attempt set aside while another is live in the matcher.
+#[derive(Clone)]
+struct Attempt<T, Context> {
+    position: usize,
+    repeat: usize,
+    captured: Vec<Capture<T>>,
+    pending: Option<Capture<T>>,
+    alternatives: Vec<(usize, usize)>,
+    context_checkpoint: Option<Option<Context>>,
+}
+
+impl<T, Context> Attempt<T, Context> {
+    /// Check if the attempt has not consumed anything yet.
+    fn is_idle(&self) -> bool {
+        self.position == 0 && self.repeat == 0 && self.captured.is_empty() && self.pending.is_none()
+    }
+}
+
+/// The main pattern matcher that processes streaming data.
+pub struct Matcher<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    patterns: Vec<PatternElement<T, Context>>,
+    settings: PatternSettings,
+    current_position: usize,
+    repeat_count: usize,
+    total_processed: usize,
+    window_size: usize,
+    extractors: HashMap<ExtractorId, Extractor<T>>,
+    context: Option<Context>,
+    epoch: Instant,
+    reset_policy: ResetPolicy,
+    last_timestamp: Duration,
+    captured: Vec<Capture<T>>,
+    pending: Option<Capture<T>>,
+    next_start_index: usize,
+    session_gap: Option<Duration>,
+    session: Option<SessionSummary>,
+    session_callback: Option<SessionCallback>,
+    subscribers: Vec<EventQueue<MatchEvent<T>>>,
+    window: VecDeque<T>,
+    /// Timestamps of the most recent items, as many as rate elements need.
+    arrivals: VecDeque<Duration>,
+    observers: Vec<Box<dyn MatcherObserver<T>>>,
+    stats: MatchStats,
+    name: Option<String>,
+    /// Stream index and timestamp of the latest completion of each named pattern.
+    completions: HashMap<String, (usize, Duration)>,
+    /// Positions and repeat counts of the other live paths of a backtracking attempt.
+    alternatives: Vec<(usize, usize)>,
+    /// Callbacks fired when the element at each position matches.
+    element_callbacks: Vec<Option<ElementCallback<T>>>,
+    completion_extractor: Option<CompletionExtractor<T>>,
+    transactional_context: bool,
+    /// The context as it was before the current attempt first changed it.
+    context_checkpoint: Option<Option<Context>>,
+    /// Number of replayed items still to be skipped after `resume_from`.
+    replay_skip: usize,
+    /// Extractor of each pattern element, resolved from `extractors` once
+    /// after the patterns or extractors change.
+    resolved_extractors: Option<Vec<Option<Extractor<T>>>>,
+    /// Evaluation counts and times, while profiling.
+    profiler: Option<Profiler>,
+    /// Recent decisions, while tracing.
+    tracer: Option<Tracer>,
+    /// Captures of each match completed while building match reports.
+    attempts: Option<Vec<Vec<Capture<T>>>>,
+    /// Attempts in progress besides the live one, under
+    /// `Selection::SkipTillAnyMatch`.
+    forks: Vec<Attempt<T, Context>>,
+}
+
+impl<T, Context> Matcher<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Create a new matcher with the specified window size.
+    pub fn new(window_size: usize) -> Self {
+        Self {
+            patterns: Vec::new(),
+            settings: PatternSettings::default(),
+            current_position: 0,
+            repeat_count: 0,
+            total_processed: 0,
+            window_size,
+            extractors: HashMap::new(),
+            context: None,
+            epoch: Instant::now(),
+            reset_policy: ResetPolicy::default(),
+            last_timestamp: Duration::ZERO,
+            captured: Vec::new(),
+            pending: None,
+            next_start_index: 0,
+            session_gap: None,
+            session: None,
+            session_callback: None,
+            subscribers: Vec::new(),
+            window: VecDeque::new(),
+            arrivals: VecDeque::new(),
+            observers: Vec::new(),
+            stats: MatchStats::default(),
+            name: None,
+            completions: HashMap::new(),
+            alternatives: Vec::new(),
+            element_callbacks: Vec::new(),
+            completion_extractor: None,
+            transactional_context: false,
+            context_checkpoint: None,
+            replay_skip: 0,
+            resolved_extractors: None,
+            profiler: None,
+            tracer: None,
+            attempts: None,
+            forks: Vec::new(),
+        }
+    }
+
+    /// Create a new matcher with patterns and window size.
+    pub fn with_patterns(patterns: Vec<PatternElement<T, Context>>, window_size: usize) -> Self {
+        Self {
+            patterns,
+            settings: PatternSettings::default(),
+            current_position: 0,
+            repeat_count: 0,
+            total_processed: 0,
+            window_size,
+            extractors: HashMap::new(),
+            context: None,
+            epoch: Instant::now(),
+            reset_policy: ResetPolicy::default(),
+            last_timestamp: Duration::ZERO,
+            captured: Vec::new(),
+            pending: None,
+            next_start_index: 0,
+            session_gap: None,
+            session: None,
+            session_callback: None,
+            subscribers: Vec::new(),
+            window: VecDeque::new(),
+            arrivals: VecDeque::new(),
+            observers: Vec::new(),
+            stats: MatchStats::default(),
+            name: None,
+            completions: HashMap::new(),
+            alternatives: Vec::new(),
+            element_callbacks: Vec::new(),
+            completion_extractor: None,
+            transactional_context: false,
+            context_checkpoint: None,
+            replay_skip: 0,
+            resolved_extractors: None,
+            profiler: None,
+            tracer: None,
+            attempts: None,
+            forks: Vec::new(),
+        }
+    }
+
+    /// Add a pattern element to the matcher.
+    pub fn add_pattern(&mut self, pattern: PatternElement<T, Context>) {
+        self.resolved_extractors = None;
+        self.patterns.push(pattern);
+    }
+
+    /// Remove the pattern element at `index` and return it.
+    ///
+    /// Any match attempt in progress is abandoned.
+    pub fn remove_pattern(
+        &mut self,
+        index: usize,
+    ) -> Result<PatternElement<T, Context>, MatcherError> {
+        self.check_pattern_index(index)?;
+        self.restart_attempts();
+        if index < self.stats.pattern_hits.len() {
+            self.stats.pattern_hits.remove(index);
+        }
+        if index < self.element_callbacks.len() {
+            self.element_callbacks.remove(index);
+        }
+        self.resolved_extractors = None;
+        Ok(self.patterns.remove(index))
+    }
+
+    /// Replace the pattern element at `index` and return the old one.
+    ///
+    /// Any match attempt in progress is abandoned.
+    pub fn replace_pattern(
+        &mut self,
+        index: usize,
+        pattern: PatternElement<T, Context>,
+    ) -> Result<PatternElement<T, Context>, MatcherError> {
+        self.check_pattern_index(index)?;
+        self.restart_attempts();
+        if let Some(hits) = self.stats.pattern_hits.get_mut(index) {
+            *hits = 0;
+        }
+        if let Some(callback) = self.element_callbacks.get_mut(index) {
+            *callback = None;
+        }
+        self.resolved_extractors = None;
+        Ok(std::mem::replace(&mut self.patterns[index], pattern))
+    }
+
+    /// Insert a pattern element at `index`, shifting later elements back.
+    ///
+    /// Any match attempt in progress is abandoned.
+    pub fn insert_pattern(
+        &mut self,
+        index: usize,
+        pattern: PatternElement<T, Context>,
+    ) -> Result<(), MatcherError> {
+        if index > self.patterns.len() {
+            return Err(MatcherError::InvalidPattern(format!(
+                "insertion index {} out of range for {} patterns",
+                index,
+                self.patterns.len()
+            )));
+        }
+        self.restart_attempts();
+        if index <= self.stats.pattern_hits.len() {
+            self.stats.pattern_hits.insert(index, 0);
+        }
+        if index <= self.element_callbacks.len() {
+            self.element_callbacks.insert(index, None);
+        }
+        self.resolved_extractors = None;
+        self.patterns.insert(index, pattern);
+        Ok(())
+    }
+
+    /// Swap the pattern elements at indices `a` and `b`.
+    ///
+    /// Any match attempt in progress is abandoned.
+    pub fn swap_patterns(&mut self, a: usize, b: usize) -> Result<(), MatcherError> {
+        self.check_pattern_index(a)?;
+        self.check_pattern_index(b)?;
+        self.restart_attempts();
+        if a.max(b) < self.stats.pattern_hits.len() {
+            self.stats.pattern_hits.swap(a, b);
+        }
+        if a.max(b) >= self.element_callbacks.len() {
+            self.element_callbacks.resize(a.max(b) + 1, None);
+        }
+        self.element_callbacks.swap(a, b);
+        self.resolved_extractors = None;
+        self.patterns.swap(a, b);
+        Ok(())
+    }
+
+    /// Remove all pattern elements, keeping extractors and context.
+    pub fn clear_patterns(&mut self) {
+        self.restart_attempts();
+        self.patterns.clear();
+        self.stats.pattern_hits.clear();
+        self.element_callbacks.clear();
+        self.resolved_extractors = None;
+    }
+
+    /// Check that `index` refers to a configured pattern element.
+    fn check_pattern_index(&self, index: usize) -> Result<(), MatcherError> {
+        if index < self.patterns.len() {
+            Ok(())
+        } else {
+            Err(MatcherError::InvalidPattern(format!(
+                "index {} out of range for {} patterns",
+                index,
+                self.patterns.len()
+            )))
+        }
+    }
+
+    /// Call `callback` with every item the element at `index` matches and the
+    /// item's stream index, replacing any callback already set for it.
+    ///
+    /// Unlike an extractor, the callback cannot affect matching, which makes
+    /// it a lightweight way to log or count element matches. The callback
+    /// follows the element when patterns are inserted, removed, or swapped,
+    /// and is dropped when the element is replaced.
+    pub fn on_element_match<F>(&mut self, index: usize, callback: F) -> Result<(), MatcherError>
+    where
+        F: Fn(&T, usize) + MaybeSync + 'static,
+    {
+        self.check_pattern_index(index)?;
+        if self.element_callbacks.len() <= index {
+            self.element_callbacks.resize(index + 1, None);
+        }
+        self.element_callbacks[index] = Some(Arc::new(callback));
+        Ok(())
+    }
+
+    /// Report the value computed by `extractor` from the captures of each
+    /// completed match, instead of the item that completed it.
+    ///
+    /// The extractor is called once per match with every captured item, in
+    /// stream order. Matches ended early by an element extractor returning
+    /// `ExtractorAction::Extract` report that value instead.
+    pub fn set_completion_extractor<F>(&mut self, extractor: F)
+    where
+        F: Fn(&[Capture<T>]) -> T + MaybeSync + 'static,
+    {
+        self.completion_extractor = Some(Arc::new(extractor));
+    }
+
+    /// Stop transforming completed matches with a completion extractor.
+    pub fn clear_completion_extractor(&mut self) {
+        self.completion_extractor = None;
+    }
+
+    /// Register an extractor with the given ID.
+    pub fn register_extractor<F>(&mut self, id: ExtractorId, extractor: F)
+    where
+        F: Fn(&MatchState<T>) -> Result<ExtractorAction<T>, ExtractorError> + MaybeSync + 'static,
+    {
+        self.resolved_extractors = None;
+        self.extractors.insert(id, Arc::new(extractor));
+    }
+
+    /// Remove the extractor with the given ID, returning it if it was registered.
+    pub fn unregister_extractor(&mut self, id: ExtractorId) -> Option<Extractor<T>> {
+        self.resolved_extractors = None;
+        self.extractors.remove(&id)
+    }
+
+    /// Resolve the extractor of every pattern element up front, checking that
+    /// each extractor ID refers to a registered extractor.
+    ///
+    /// Matching resolves extractors by itself the first time it runs after
+    /// the patterns or extractors change, reporting a missing extractor only
+    /// once an element that needs it consumes an item. Compiling reports it
+    /// before any item is processed, located at the next stream index.
+    pub fn compile(&mut self) -> Result<(), MatcherError> {
+        let resolved = self
+            .patterns
+            .iter()
+            .enumerate()
+            .map(|(element, pattern)| match pattern.settings().extractor_id {
+                Some(extractor_id) => self.extractors.get(&extractor_id).cloned().map(Some).ok_or(
+                    MatcherError::MissingExtractor {
+                        location: self.error_location(
+                            self.total_processed,
+                            element,
+                            Some(extractor_id),
+                        ),
+                    },
+                ),
+                None => Ok(None),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.resolved_extractors = Some(resolved);
+        Ok(())
+    }
+
+    /// Look up the extractor of every pattern element, if not done yet.
+    fn resolve_extractors(&mut self) {
+        if self.resolved_extractors.is_some() {
+            return;
+        }
+        let resolved = self
+            .patterns
+            .iter()
+            .map(|pattern| {
+                let extractor_id = pattern.settings().extractor_id?;
+                self.extractors.get(&extractor_id).cloned()
+            })
+            .collect();
+        self.resolved_extractors = Some(resolved);
+    }
+
+    /// Check if an extractor is registered with the given ID.
+    pub fn has_extractor(&self, id: ExtractorId) -> bool {
+        self.extractors.contains_key(&id)
+    }
+
+    /// Get the IDs of all registered extractors, in ascending order.
+    pub fn extractor_ids(&self) -> Vec<ExtractorId> {
+        let mut ids: Vec<ExtractorId> = self.extractors.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Name the pattern, so that other patterns can correlate with it.
+    ///
+    /// Completions of a named pattern are recorded with `record_completion`,
+    /// which makes them visible to its own `PatternSettings::after`.
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.name = Some(name.into());
+    }
+
+    /// Get the name of the pattern.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Record that the named pattern completed at the given stream position.
+    ///
+    /// Use this to tell a matcher about matches found by other matchers over
+    /// the same stream, for patterns with `PatternSettings::after`.
+    pub fn record_completion(&mut self, pattern: &str, index: usize, timestamp: Duration) {
+        self.completions
+            .insert(pattern.to_string(), (index, timestamp));
+    }
+
+    /// Get the stream index and timestamp of the latest recorded completion
+    /// of the named pattern.
+    pub fn last_completion(&self, pattern: &str) -> Option<(usize, Duration)> {
+        self.completions.get(pattern).copied()
+    }
+
+    /// Set the pattern-level settings.
+    pub fn set_pattern_settings(&mut self, settings: PatternSettings) {
+        self.settings = settings;
+    }
+
+    /// Get the pattern-level settings.
+    pub fn pattern_settings(&self) -> &PatternSettings {
+        &self.settings
+    }
+
+    /// Choose what happens to items between the items of a match.
+    ///
+    /// Attempts set aside under `Selection::SkipTillAnyMatch` are dropped
+    /// when switching to another strategy.
+    pub fn set_selection(&mut self, selection: Selection) {
+        if selection != Selection::SkipTillAnyMatch {
+            self.forks.clear();
+        }
+        self.settings.selection = selection;
+    }
+
+    /// Get what happens to items between the items of a match.
+    pub fn selection(&self) -> Selection {
+        self.settings.selection
+    }
+
+    /// Set the default reset policy for patterns that do not override it.
+    pub fn set_reset_policy(&mut self, policy: ResetPolicy) {
+        self.reset_policy = policy;
+    }
+
+    /// Get the reset policy in effect for the pattern.
+    pub fn reset_policy(&self) -> ResetPolicy {
+        self.settings.reset_policy.unwrap_or(self.reset_policy)
+    }
+
+    /// Close the current session after the given inactivity gap.
+    ///
+    /// Partial matches never span sessions: closing a session resets the pattern.
+    pub fn set_session_gap(&mut self, gap: Duration) {
+        self.session_gap = Some(gap);
+    }
+
+    /// Get the session inactivity gap.
+    pub fn session_gap(&self) -> Option<Duration> {
+        self.session_gap
+    }
+
+    /// Register a callback that receives a summary whenever a session closes.
+    pub fn on_session_end<F>(&mut self, callback: F)
+    where
+        F: Fn(&SessionSummary) + MaybeSync + 'static,
+    {
+        self.session_callback = Some(Arc::new(callback));
+    }
+
+    /// Get the summary of the currently open session.
+    pub fn current_session(&self) -> Option<&SessionSummary> {
+        self.session.as_ref()
+    }
+
+    /// Subscribe to completed matches through a bounded event queue.
+    ///
+    /// Every completed match is pushed into the returned queue in addition to
+    /// being returned from `process_item`.
+    pub fn subscribe(
+        &mut self,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> EventQueue<MatchEvent<T>> {
+        let queue = EventQueue::bounded(capacity, policy);
+        self.subscribers.push(queue.clone());
+        queue
+    }
+
+    /// Add an observer that is notified of matcher lifecycle events.
+    pub fn add_observer<O>(&mut self, observer: O)
+    where
+        O: MatcherObserver<T> + 'static,
+    {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Set the context for this matcher.
+    ///
+    /// With transactional context, a change made while a match attempt is in
+    /// progress is undone if the attempt fails.
+    pub fn set_context(&mut self, context: Context) {
+        self.stage_context();
+        self.context = Some(context);
+    }
+
+    /// Stage context changes made during a match attempt, committing them
+    /// only if the attempt completes.
+    ///
+    /// An attempt fails when an item mismatches, an extractor restarts it, it
+    /// runs out of time, or the stream finishes first. The context is then
+    /// rolled back to what it was when the attempt first changed it.
+    pub fn set_transactional_context(&mut self, enabled: bool) {
+        self.transactional_context = enabled;
+        if !enabled {
+            self.context_checkpoint = None;
+        }
+    }
+
+    /// Check whether context changes are staged per match attempt.
+    pub fn is_transactional_context(&self) -> bool {
+        self.transactional_context
+    }
+
+    /// Remember the context before the attempt in progress first changes it.
+    ///
+    /// An attempt is in progress from the first item it consumes, including
+    /// while its first element is still repeating.
+    fn stage_context(&mut self) {
+        if self.transactional_context && self.is_matching() && self.context_checkpoint.is_none() {
+            self.context_checkpoint = Some(self.context.clone());
+        }
+    }
+
+    /// Undo the context changes staged by a failed attempt.
+    fn roll_back_context(&mut self) {
+        // A held completion means the attempt did match
+        if self.pending.is_some() {
+            return;
+        }
+        if let Some(context) = self.context_checkpoint.take() {
+            self.context = context;
+        }
+    }
+
+    /// Set the context, returning the matcher for chaining after construction.
+    pub fn with_context(mut self, context: Context) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Get the current context.
+    pub fn context(&self) -> Option<&Context> {
+        self.context.as_ref()
+    }
+
+    /// Get mutable access to the current context.
+    pub fn context_mut(&mut self) -> Option<&mut Context> {
+        self.stage_context();
+        self.context.as_mut()
+    }
+
+    /// Remove and return the context, such as to recover the data it
+    /// accumulated once processing is done.
+    pub fn take_context(&mut self) -> Option<Context> {
+        self.stage_context();
+        self.context.take()
+    }
+
+    /// Replace the context with the result of applying `f` to it, if set.
+    pub fn map_context<F>(&mut self, f: F)
+    where
+        F: FnOnce(Context) -> Context,
+    {
+        self.stage_context();
+        self.context = self.context.take().map(f);
+    }
+
+    /// Process a single item and return any extracted data.
+    ///
+    /// The item is timestamped with the time elapsed since the matcher was created.
+    /// When several matches complete on the item, such as a held longest match
+    /// and a new one, the earliest is returned; use `process_item_all` to
+    /// receive all of them, or `process_item_reports` to receive everything
+    /// known about them.
+    pub fn process_item(&mut self, item: T) -> Result<Option<T>, MatcherError> {
+        let timestamp = self.epoch.elapsed();
+        self.process_item_at(item, timestamp)
+    }
+
+    /// Process a single item observed at the given timestamp.
+    ///
+    /// Timestamps are durations since an arbitrary epoch chosen by the caller and
+    /// are expected to be non-decreasing.
+    pub fn process_item_at(
+        &mut self,
+        item: T,
+        timestamp: Duration,
+    ) -> Result<Option<T>, MatcherError> {
+        let completed = self.process_captures(item, timestamp)?;
+        Ok(completed.into_iter().next().map(|capture| capture.item))
+    }
+
+    /// Process a single item by reference, cloning it only if the matcher
+    /// needs to keep it.
+    ///
+    /// The item is cloned when the pattern consumes it or the window must hold
+    /// it, so streams where most items cannot start a match, such as large
+    /// strings searched with a window size of zero, are matched without copies.
+    /// Results are the same as for `process_item`.
+    pub fn process_item_ref(&mut self, item: &T) -> Result<Option<T>, MatcherError> {
+        let timestamp = self.epoch.elapsed();
+        let completed = self.process_cow(Cow::Borrowed(item), timestamp)?;
+        Ok(completed.into_iter().next().map(|capture| capture.item))
+    }
+
+    /// Process a single item and return the data of every match it completes,
+    /// in the order the matches completed.
+    pub fn process_item_all(&mut self, item: T) -> Result<Vec<T>, MatcherError> {
+        let timestamp = self.epoch.elapsed();
+        self.process_item_all_at(item, timestamp)
+    }
+
+    /// Process a single item observed at the given timestamp and return the
+    /// data of every match it completes.
+    pub fn process_item_all_at(
+        &mut self,
+        item: T,
+        timestamp: Duration,
+    ) -> Result<Vec<T>, MatcherError> {
+        let completed = self.process_captures(item, timestamp)?;
+        Ok(completed.into_iter().map(|capture| capture.item).collect())
+    }
+
+    /// Process a single item and return a report of every match it
+    /// completes, in the order the matches completed.
+    pub fn process_item_reports(&mut self, item: T) -> Result<Vec<MatchReport<T>>, MatcherError> {
+        let timestamp = self.epoch.elapsed();
+        self.process_item_reports_at(item, timestamp)
+    }
+
+    /// Process a single item observed at the given timestamp and return a
+    /// report of every match it completes.
+    ///
+    /// Building reports copies the items of each completed match.
+    pub fn process_item_reports_at(
+        &mut self,
+        item: T,
+        timestamp: Duration,
+    ) -> Result<Vec<MatchReport<T>>, MatcherError> {
+        self.attempts = Some(Vec::new());
+        let completed = self.process_captures(item, timestamp);
+        let attempts = self.attempts.take().unwrap_or_default();
+        Ok(completed?
+            .into_iter()
+            .zip(attempts)
+            .map(|(completed, captures)| self.report(completed, captures))
+            .collect())
+    }
+
+    /// Build the report of the match `completed` that consumed `captures`.
+    fn report(&self, completed: Capture<T>, captures: Vec<Capture<T>>) -> MatchReport<T> {
+        MatchReport {
+            pattern: self.name.clone(),
+            span: captures
+                .first()
+                .map_or(completed.index, |first| first.index)
+                ..completed.index + 1,
+            start_timestamp: captures
+                .first()
+                .map_or(completed.timestamp, |first| first.timestamp),
+            end_timestamp: completed.timestamp,
+            value: completed.item,
+            captures,
+        }
+    }
+
+    /// Process a single item, returning the captures of the matches it completes.
+    fn process_captures(
+        &mut self,
+        item: T,
+        timestamp: Duration,
+    ) -> Result<Vec<Capture<T>>, MatcherError> {
+        self.process_cow(Cow::Owned(item), timestamp)
+    }
+
+    /// Process an owned or borrowed item, returning the captures of the
+    /// matches it completes.
+    fn process_cow(
+        &mut self,
+        item: Cow<'_, T>,
+        timestamp: Duration,
+    ) -> Result<Vec<Capture<T>>, MatcherError> {
+        if self.patterns.is_empty() {
+            return Err(MatcherError::NoPatterns);
+        }
+        if self.replay_skip > 0 {
+            self.replay_skip -= 1;
+            return Ok(Vec::new());
+        }
+        self.resolve_extractors();
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.fit(self.patterns.len());
+        }
+
+        self.total_processed += 1;
+        self.stats.items_processed += 1;
+        self.last_timestamp = timestamp;
+        self.record_arrival(timestamp);
+        let closed = self.track_session(timestamp);
+        let absent = self.detect_absence(self.total_processed - 1, timestamp);
+        // The attempt recorded for the absence is the last one recorded so far
+        let absent_attempt = absent.as_ref().and_then(|_| self.attempts.as_mut()?.pop());
+        let expired = self.expire_partial_match(timestamp);
+
+        let history = (self.window_size > 0).then(|| item.clone().into_owned());
+        let matched = match item {
+            _ if self.settings.selection == Selection::SkipTillAnyMatch => {
+                self.match_forks(item.into_owned(), timestamp)
+            }
+            Cow::Owned(item) => self.match_item(item, timestamp),
+            Cow::Borrowed(item) => self.match_borrowed(item, timestamp),
+        };
+        let matched = matched.map_err(|err| {
+            ::log::warn!(
+                "[{}] item {}: {}",
+                err.code(),
+                self.total_processed - 1,
+                err
+            );
+            err
+        });
+        if let Some(item) = history {
+            self.remember(item);
+        }
+        let matched = matched?;
+
+        let (closed, completed): (_, Vec<Capture<T>>) = if self.settings.absence.is_some() {
+            // Completions are the expected outcome, so only missing ones count
+            if let Some(attempts) = self.attempts.as_mut() {
+                *attempts = absent_attempt.into_iter().collect();
+            }
+            (None, absent.into_iter().collect())
+        } else {
+            (closed, expired.into_iter().chain(matched).collect())
+        };
+        if let Some(session) = self.session.as_mut() {
+            session.match_count += completed.len();
+        }
+
+        let result: Vec<Capture<T>> = closed.into_iter().chain(completed).collect();
+        for capture in &result {
+            self.publish(capture);
+        }
+        Ok(result)
+    }
+
+    /// Offer an item to every attempt in progress and to a new attempt,
+    /// setting aside a copy that skips the item of each attempt that consumes
+    /// it.
+    fn match_forks(
+        &mut self,
+        item: T,
+        timestamp: Duration,
+    ) -> Result<Vec<Capture<T>>, MatcherError> {
+        let mut attempts = vec![self.take_attempt()];
+        attempts.append(&mut self.forks);
+        attempts.retain(|attempt| !attempt.is_idle());
+        attempts.push(self.take_attempt());
+
+        let mut found = Vec::new();
+        let mut waiting = Vec::new();
+        for attempt in attempts {
+            let skipped = attempt.clone();
+            self.load_attempt(attempt);
+            found.extend(self.expire_partial_match(timestamp));
+            let before = (
+                self.current_position,
+                self.repeat_count,
+                self.captured.len(),
+            );
+            let completed = self.match_item(item.clone(), timestamp)?;
+            let consumed = !completed.is_empty()
+                || before
+                    != (
+                        self.current_position,
+                        self.repeat_count,
+                        self.captured.len(),
+                    );
+            found.extend(completed);
+
+            let attempt = self.take_attempt();
+            if consumed && !skipped.is_idle() {
+                waiting.push(skipped);
+            }
+            if !attempt.is_idle() {
+                waiting.push(attempt);
+            }
+        }
+
+        let excess = waiting.len().saturating_sub(MAX_PARALLEL_ATTEMPTS);
+        let mut waiting = waiting.into_iter().skip(excess);
+        if let Some(live) = waiting.next() {
+            self.load_attempt(live);
+        }
+        self.forks = waiting.collect();
+        Ok(found)
+    }
+
+    /// Move the live attempt out of the matcher, leaving it idle.
+    fn take_attempt(&mut self) -> Attempt<T, Context> {
+        let attempt = Attempt {
+            position: self.current_position,
+            repeat: self.repeat_count,
+            captured: std::mem::take(&mut self.captured),
+            pending: self.pending.take(),
+            alternatives: std::mem::take(&mut self.alternatives),
+            context_checkpoint: self.context_checkpoint.take(),
+        };
+        self.restart_match();
+        attempt
+    }
+
+    /// Make an attempt set aside with `take_attempt` the live one.
+    fn load_attempt(&mut self, attempt: Attempt<T, Context>) {
+        self.current_position = attempt.position;
+        self.repeat_count = attempt.repeat;
+        self.captured = attempt.captured;
+        self.pending = attempt.pending;
+        self.alternatives = attempt.alternatives;
+        self.context_checkpoint = attempt.context_checkpoint;
+    }
+
+    /// Run the pattern against a borrowed item, cloning it only if a match
+    /// attempt is in progress or the item can begin one.
+    fn match_borrowed(
+        &mut self,
+        item: &T,
+        timestamp: Duration,
+    ) -> Result<Vec<Capture<T>>, MatcherError> {
+        let index = self.total_processed - 1;
+        let idle = self.captured.is_empty() && self.pending.is_none() && self.current_position == 0;
+        if !idle || self.settings.engine != Engine::Greedy {
+            return self.match_item(item.clone(), timestamp);
+        }
+        if !self.can_start_at(index, timestamp) {
+            return Ok(Vec::new());
+        }
+        match self.advance(0, 0, item, index, timestamp, &[])? {
+            // Rejection from the first element rules out every path, so the
+            // mismatch recovery of `match_item` reduces to notifying observers
+            Advance::Rejected | Advance::Exhausted => {
+                for observer in &mut self.observers {
+                    observer.on_mismatch(0, item);
+                }
+                self.restart_match();
+                Ok(Vec::new())
+            }
+            advanced => self.apply_advance(item.clone(), index, timestamp, advanced),
+        }
+    }
+
+    /// Run the pattern against a single item, returning the completed matches.
+    fn match_item(
+        &mut self,
+        item: T,
+        timestamp: Duration,
+    ) -> Result<Vec<Capture<T>>, MatcherError> {
+        let index = self.total_processed - 1;
+        if self.captured.is_empty() && !self.can_start_at(index, timestamp) {
+            return Ok(Vec::new());
+        }
+
+        let advanced = match self.settings.engine {
+            Engine::Greedy => self.advance(
+                self.current_position,
+                self.repeat_count,
+                &item,
+                index,
+                timestamp,
+                &self.captured,
+            )?,
+            Engine::Backtracking => self.advance_all(&item, index, timestamp)?,
+        };
+        self.apply_advance(item, index, timestamp, advanced)
+    }
+
+    /// Act on how the pattern took the item at stream position `index`,
+    /// returning the completed matches.
+    fn apply_advance(
+        &mut self,
+        item: T,
+        index: usize,
+        timestamp: Duration,
+        advanced: Advance,
+    ) -> Result<Vec<Capture<T>>, MatcherError> {
+        let (element, repeat) = match advanced {
+            Advance::Consumed { element, repeat } => (element, repeat),
+            Advance::Rejected | Advance::Exhausted => {
+                if self.settings.selection != Selection::Strict
+                    && !self.captured.is_empty()
+                    && self.pending.is_none()
+                {
+                    // The item falls between the items of the match
+                    return Ok(Vec::new());
+                }
+                for observer in &mut self.observers {
+                    observer.on_mismatch(self.current_position, &item);
+                }
+                return self.recover_from_mismatch(item, index, timestamp);
+            }
+            Advance::Completed => {
+                // The item was only peeked at, so offer it to a new attempt
+                let Some(completed) = self.summarize(self.captured.len()) else {
+                    self.restart_match();
+                    return Ok(Vec::new());
+                };
+                self.restart_match();
+                let mut found = vec![completed];
+                found.extend(self.match_item(item, timestamp)?);
+                return Ok(found);
+            }
+        };
+
+        if self.stats.pattern_hits.len() < self.patterns.len() {
+            self.stats.pattern_hits.resize(self.patterns.len(), 0);
+        }
+        self.stats.pattern_hits[element] += 1;
+        if let Some(Some(callback)) = self.element_callbacks.get(element) {
+            callback(&item, index);
+        }
+
+        // Run any associated extractor before advancing position
+        let pattern = &self.patterns[element];
+        let item = match pattern.settings().extractor_id {
+            Some(extractor_id) => {
+                let extractor = match &self.resolved_extractors {
+                    Some(resolved) => resolved.get(element).and_then(Option::as_ref),
+                    None => self.extractors.get(&extractor_id),
+                };
+                let Some(extractor) = extractor else {
+                    return Err(MatcherError::MissingExtractor {
+                        location: self.error_location(index, element, Some(extractor_id)),
+                    });
+                };
+                self.stats.extractor_invocations += 1;
+                let state = MatchState {
+                    current_item: item,
+                    position: element,
+                    total_processed: self.total_processed,
+                    window: self.window.make_contiguous(),
+                    pattern_name: self.name.as_deref(),
+                    timestamp,
+                    start_timestamp: self
+                        .captured
+                        .first()
+                        .map_or(timestamp, |first| first.timestamp),
+                };
+                let started = self.profiler.is_some().then(Instant::now);
+                let outcome = extractor(&state);
+                if let (Some(profiler), Some(started)) = (self.profiler.as_mut(), started) {
+                    profiler.record_extractor(extractor_id, started.elapsed());
+                }
+                if let Some(tracer) = &self.tracer {
+                    tracer.record(TraceEvent::Extracted {
+                        index,
+                        element,
+                        extractor_id,
+                        action: match &outcome {
+                            Ok(ExtractorAction::Continue) => TraceAction::Continue,
+                            Ok(ExtractorAction::Extract(_)) => TraceAction::Extract,
+                            Ok(ExtractorAction::Restart) => TraceAction::Restart,
+                            Err(_) => TraceAction::Failed,
+                        },
+                    });
+                }
+                // The item is lent to the extractor rather than cloned for it
+                let item = state.current_item;
+                match outcome.map_err(|error| MatcherError::ExtractorFailed {
+                    error,
+                    location: self.error_location(index, element, Some(extractor_id)),
+                })? {
+                    ExtractorAction::Continue => item,
+                    ExtractorAction::Extract(data) => {
+                        if let Some(attempts) = self.attempts.as_mut() {
+                            let mut captures = self.captured.clone();
+                            captures.push(Capture {
+                                item,
+                                index,
+                                timestamp,
+                            });
+                            attempts.push(captures);
+                        }
+                        self.restart_match();
+                        return Ok(vec![Capture {
+                            item: data,
+                            index,
+                            timestamp,
+                        }]);
+                    }
+                    ExtractorAction::Restart => {
+                        self.notify_reset();
+                        self.restart_match();
+                        return Ok(Vec::new());
+                    }
+                }
+            }
+            None => item,
+        };
+
+        (self.current_position, self.repeat_count) = self.settle_position(element, repeat);
+
+        let capture = Capture {
+            item,
+            index,
+            timestamp,
+        };
+
+        // Check if we've completed the pattern
+        if self.current_position >= self.patterns.len() {
+            self.captured.push(capture);
+            let completed = self.summarize(self.captured.len());
+            self.restart_match();
+            return Ok(completed.into_iter().collect());
+        }
+
+        // Pattern element matched, remember it
+        for observer in &mut self.observers {
+            observer.on_partial(element, &capture);
+        }
+        self.captured.push(capture);
+        Ok(self.settle_completion().into_iter().collect())
+    }
+
+    /// Locate an error at the item at stream position `index`, consumed by
+    /// the element at `element`.
+    fn error_location(
+        &self,
+        index: usize,
+        element: usize,
+        extractor_id: Option<ExtractorId>,
+    ) -> ErrorLocation {
+        ErrorLocation {
+            pattern: self.name.clone(),
+            index,
+            element,
+            extractor_id,
+        }
+    }
+
+    /// Offer an item to the pattern, starting at `position` where the element
+    /// has already matched `repeat` items. Elements whose minimum is satisfied
+    /// are skipped when they do not accept the item.
+    fn advance(
+        &self,
+        mut position: usize,
+        mut repeat: usize,
+        item: &T,
+        index: usize,
+        timestamp: Duration,
+        captured: &[Capture<T>],
+    ) -> Result<Advance, MatcherError> {
+        let mut asserted = false;
+        loop {
+            let Some(pattern) = self.patterns.get(position) else {
+                return Ok(if asserted {
+                    Advance::Completed
+                } else {
+                    Advance::Exhausted
+                });
+            };
+            let settings = pattern.settings();
+            let (_, max) = settings.repeat_bounds();
+            let satisfied = self.is_satisfied(position, repeat, captured)?;
+
+            if pattern.is_assertion() {
+                if self.evaluate(position, item, index, timestamp, repeat, captured)? {
+                    asserted = true;
+                } else if !satisfied {
+                    return Ok(Advance::Rejected);
+                }
+                position += 1;
+                repeat = 0;
+                continue;
+            }
+
+            let move_on = settings.repeat_mode == RepeatMode::Lazy
+                && satisfied
+                && self.accepts_from(position + 1, item, index, timestamp, captured)?;
+            if !move_on
+                && repeat < max
+                && self.evaluate(position, item, index, timestamp, repeat, captured)?
+            {
+                return Ok(Advance::Consumed {
+                    element: position,
+                    repeat: repeat + 1,
+                });
+            }
+
+            if !satisfied {
+                return Ok(Advance::Rejected);
+            }
+            position += 1;
+            repeat = 0;
+        }
+    }
+
+    /// Offer an item to every live path of a backtracking attempt, keeping the
+    /// paths that consume it as alternatives and returning the preferred one.
+    ///
+    /// A path that completes the pattern is preferred over all others.
+    fn advance_all(
+        &mut self,
+        item: &T,
+        index: usize,
+        timestamp: Duration,
+    ) -> Result<Advance, MatcherError> {
+        let mut states = vec![(self.current_position, self.repeat_count)];
+        states.append(&mut self.alternatives);
+
+        let mut consumed = Vec::new();
+        for (position, repeat) in states {
+            for step in self.successors(position, repeat, item, index, timestamp)? {
+                if !consumed.contains(&step) {
+                    consumed.push(step);
+                }
+            }
+        }
+        if consumed.is_empty() {
+            // No path consumes the item, but trailing assertions may still complete
+            return self.advance(
+                self.current_position,
+                self.repeat_count,
+                item,
+                index,
+                timestamp,
+                &self.captured,
+            );
+        }
+
+        let preferred = consumed
+            .iter()
+            .position(|&(element, repeat)| {
+                self.settle_position(element, repeat).0 >= self.patterns.len()
+            })
+            .unwrap_or(0);
+        let (element, repeat) = consumed.remove(preferred);
+        for (other, count) in consumed {
+            let state = self.settle_position(other, count);
+            if !self.alternatives.contains(&state) {
+                self.alternatives.push(state);
+            }
+        }
+        Ok(Advance::Consumed { element, repeat })
+    }
+
+    /// List every way the elements from `position` onwards, the first of which
+    /// has already matched `repeat` items, can consume the item, as the
+    /// consuming element and its new repeat count, in order of preference.
+    fn successors(
+        &self,
+        position: usize,
+        repeat: usize,
+        item: &T,
+        index: usize,
+        timestamp: Duration,
+    ) -> Result<Vec<(usize, usize)>, MatcherError> {
+        let Some(pattern) = self.patterns.get(position) else {
+            return Ok(Vec::new());
+        };
+        let settings = pattern.settings();
+        let (_, max) = settings.repeat_bounds();
+        let satisfied = self.is_satisfied(position, repeat, &self.captured)?;
+
+        if pattern.is_assertion() {
+            if satisfied
+                || self.evaluate(position, item, index, timestamp, repeat, &self.captured)?
+            {
+                return self.successors(position + 1, 0, item, index, timestamp);
+            }
+            return Ok(Vec::new());
+        }
+
+        let mut consume = Vec::new();
+        if repeat < max
+            && self.evaluate(position, item, index, timestamp, repeat, &self.captured)?
+        {
+            consume.push((position, repeat + 1));
+        }
+        let mut skip = if satisfied {
+            self.successors(position + 1, 0, item, index, timestamp)?
+        } else {
+            Vec::new()
+        };
+        Ok(if settings.repeat_mode == RepeatMode::Lazy && satisfied {
+            skip.append(&mut consume);
+            skip
+        } else {
+            consume.append(&mut skip);
+            consume
+        })
+    }
+
+    /// Check whether the elements from `position` onwards would consume the
+    /// item or complete on it.
+    fn accepts_from(
+        &self,
+        position: usize,
+        item: &T,
+        index: usize,
+        timestamp: Duration,
+        captured: &[Capture<T>],
+    ) -> Result<bool, MatcherError> {
+        let mut asserted = false;
+        for (position, pattern) in self.patterns.iter().enumerate().skip(position) {
+            let matched = self.evaluate(position, item, index, timestamp, 0, captured)?;
+            if pattern.is_assertion() && matched {
+                asserted = true;
+                continue;
+            }
+            if matched {
+                return Ok(true);
+            }
+            if pattern.settings().repeat_bounds().0 > 0 {
+                return Ok(false);
+            }
+        }
+        Ok(asserted)
+    }
+
+    /// Check whether the element at `position` accepts the item, timing the
+    /// check while profiling and recording it while tracing.
+    fn evaluate(
+        &self,
+        position: usize,
+        item: &T,
+        index: usize,
+        timestamp: Duration,
+        repeat: usize,
+        captured: &[Capture<T>],
+    ) -> Result<bool, MatcherError> {
+        let pattern = &self.patterns[position];
+        let matched = if repeat == 0 && !within_gap(pattern, index, timestamp, captured) {
+            Ok(false)
+        } else if let Some(profiler) = &self.profiler {
+            let started = Instant::now();
+            let matched = self.element_matches(pattern, item, index, repeat, captured);
+            profiler.record_element(position, started.elapsed());
+            matched
+        } else {
+            self.element_matches(pattern, item, index, repeat, captured)
+        };
+        if let (Some(tracer), Ok(matched)) = (&self.tracer, &matched) {
+            tracer.record(TraceEvent::Evaluated {
+                index,
+                element: position,
+                repeat,
+                matched: *matched,
+            });
+        }
+        matched
+    }
+
+    /// Check whether an element accepts the item at stream position `index`,
+    /// resolving references to earlier items through the window and to the
+    /// current attempt through `captured`. `repeat` is the number of items the
+    /// element has already consumed in this attempt.
+    fn element_matches(
+        &self,
+        pattern: &PatternElement<T, Context>,
+        item: &T,
+        index: usize,
+        repeat: usize,
+        captured: &[Capture<T>],
+    ) -> Result<bool, MatcherError> {
+        match pattern {
+            PatternElement::Guard { function, .. } => Ok(function(captured, item)),
+            PatternElement::OneOrMore { function, .. } => {
+                let run = &captured[captured.len().saturating_sub(repeat)..];
+                Ok(function(run, item))
+            }
+            PatternElement::Group { elements, .. } => match elements.get(repeat) {
+                Some(element) => self.element_matches(element, item, index, 0, captured),
+                None => Ok(false),
+            },
+            PatternElement::Composite { pattern, .. } => {
+                self.composite_accepts(pattern, item, index, captured, repeat)
+            }
+            PatternElement::Nested { matcher, .. } => {
+                self.nested_accepts(matcher, item, captured, repeat)
+            }
+            PatternElement::Lookahead { inner, negated, .. } => {
+                Ok(self.element_matches(inner, item, index, repeat, captured)? != *negated)
+            }
+            PatternElement::Lookbehind { inner, offset, .. } => {
+                match self.item_before(item, index, *offset) {
+                    Some(earlier) => {
+                        self.element_matches(inner, earlier, index - offset, 0, captured)
+                    }
+                    None => Ok(false),
+                }
+            }
+            PatternElement::Run { .. } => Ok(repeat == 0
+                || captured
+                    .last()
+                    .is_some_and(|previous| previous.item == *item)),
+            PatternElement::Monotonic {
+                decreasing, less, ..
+            } => Ok(repeat == 0
+                || captured.last().is_some_and(|previous| {
+                    if *decreasing {
+                        less(item, &previous.item)
+                    } else {
+                        less(&previous.item, item)
+                    }
+                })),
+            PatternElement::BackReference { offset, .. } => Ok(self
+                .item_before(item, index, *offset)
+                .is_some_and(|earlier| earlier == item)),
+            PatternElement::PairPredicate { function, .. } => Ok(self
+                .item_before(item, index, 1)
+                .is_some_and(|previous| function(previous, item))),
+            PatternElement::Aggregate {
+                length,
+                aggregate,
+                value,
+                function,
+                ..
+            } => Ok(self
+                .trailing_aggregate(item, *length, *aggregate, *value)
+                .is_some_and(|total| function(total))),
+            PatternElement::Rate { count, period, .. } => {
+                Ok(self.arrival_rate_reached(*count, *period))
+            }
+            PatternElement::Until { terminator, .. } => {
+                if repeat > 0 && self.is_terminated(terminator, captured)? {
+                    return Ok(false);
+                }
+                // The last item the element may consume must end it
+                let (_, max) = pattern.settings().repeat_bounds();
+                if repeat + 1 >= max {
+                    return self.element_matches(terminator, item, index, 0, captured);
+                }
+                Ok(true)
+            }
+            _ => pattern.matches(item),
+        }
+    }
+
+    /// Check whether the latest capture was accepted by an until element's
+    /// terminator.
+    fn is_terminated(
+        &self,
+        terminator: &PatternElement<T, Context>,
+        captured: &[Capture<T>],
+    ) -> Result<bool, MatcherError> {
+        match captured.split_last() {
+            Some((last, earlier)) => {
+                self.element_matches(terminator, &last.item, last.index, 0, earlier)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Check whether the element at `position` has matched enough items after
+    /// consuming `repeat`, the last of which ends `captured`.
+    ///
+    /// An until element is satisfied only once its terminator has matched, and
+    /// a group, composite, or nested matcher that has begun only once it is
+    /// complete.
+    fn is_satisfied(
+        &self,
+        position: usize,
+        repeat: usize,
+        captured: &[Capture<T>],
+    ) -> Result<bool, MatcherError> {
+        let pattern = &self.patterns[position];
+        let (min, _) = pattern.settings().repeat_bounds();
+        match pattern {
+            PatternElement::Until { terminator, .. } if repeat > 0 => {
+                self.is_terminated(terminator, captured)
+            }
+            PatternElement::Group { elements, .. } if repeat > 0 => Ok(repeat >= elements.len()),
+            PatternElement::Composite { pattern, .. } if repeat > 0 => {
+                self.composite_complete(pattern, captured, repeat)
+            }
+            PatternElement::Nested { matcher, .. } if repeat > 0 => {
+                self.nested_complete(matcher, captured, repeat)
+            }
+            _ => Ok(repeat >= min),
+        }
+    }
+
+    /// Find the item `offset` positions before `item`, which is at stream
+    /// position `index`, if it is still in the window.
+    fn item_before<'a>(&'a self, item: &'a T, index: usize, offset: usize) -> Option<&'a T> {
+        if offset == 0 {
+            return Some(item);
+        }
+        let target = index.checked_sub(offset)?;
+        // The window ends just before the item currently being processed
+        let first = (self.total_processed - 1).checked_sub(self.window.len())?;
+        self.window.get(target.checked_sub(first)?)
+    }
+
+    /// Compute the aggregate of `item` and the `length - 1` items before it,
+    /// if they are all still in the window.
+    fn trailing_aggregate(
+        &self,
+        item: &T,
+        length: usize,
+        aggregate: Aggregate,
+        value: fn(&T) -> f64,
+    ) -> Option<f64> {
+        let earlier = length.checked_sub(1)?;
+        let first = self.window.len().checked_sub(earlier)?;
+        let values = self.window.range(first..).chain(Some(item)).map(value);
+        aggregate.compute(values)
+    }
+
+    /// Record the timestamp of a processed item, keeping as many as the rate
+    /// elements need.
+    fn record_arrival(&mut self, timestamp: Duration) {
+        let needed = self
+            .patterns
+            .iter()
+            .map(|pattern| match pattern {
+                PatternElement::Rate { count, .. } => *count,
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(0);
+        while self.arrivals.len() >= needed.max(1) {
+            self.arrivals.pop_front();
+        }
+        if needed > 0 {
+            self.arrivals.push_back(timestamp);
+        }
+    }
+
+    /// Check if the latest `count` items all arrived within `period` of the
+    /// current item.
+    fn arrival_rate_reached(&self, count: usize, period: Duration) -> bool {
+        if count == 0 {
+            return true;
+        }
+        self.arrivals
+            .len()
+            .checked_sub(count)
+            .and_then(|first| self.arrivals.get(first))
+            .is_some_and(|first| self.last_timestamp.saturating_sub(*first) <= period)
+    }
+
+    /// Record a processed item in the window, evicting the oldest if full.
+    fn remember(&mut self, item: T) {
+        while self.window.len() >= self.window_size {
+            self.window.pop_front();
+        }
+        self.window.push_back(item);
+    }
+
+    /// Move past an element once it has matched its maximum number of items.
+    fn settle_position(&self, element: usize, repeat: usize) -> (usize, usize) {
+        if repeat >= self.patterns[element].settings().repeat_bounds().1 {
+            (element + 1, 0)
+        } else {
+            (element, repeat)
+        }
+    }
+
+    /// Apply the match policy once the remaining elements can all match zero
+    /// more items.
+    fn settle_completion(&mut self) -> Option<Capture<T>> {
+        if !self.can_complete() {
+            return None;
+        }
+        let current = self.patterns[self.current_position].settings();
+
+        // A lazy element stops repeating as soon as the pattern can complete
+        let policy = if current.repeat_mode == RepeatMode::Lazy && self.repeat_count > 0 {
+            MatchPolicy::FirstMatch
+        } else {
+            self.settings.match_policy
+        };
+
+        match policy {
+            MatchPolicy::FirstMatch => {
+                let completed = self.summarize(self.captured.len());
+                self.restart_match();
+                completed
+            }
+            MatchPolicy::LongestMatch => {
+                self.pending = self.captured.last().cloned();
+                None
+            }
+            MatchPolicy::AllMatches => self.summarize(self.captured.len()),
+        }
+    }
+
+    /// Check whether the attempt in progress could complete here, as the
+    /// remaining elements can all match zero more items.
+    fn can_complete(&self) -> bool {
+        let mut remaining = self.patterns[self.current_position..].iter();
+        if remaining.next().is_none() {
+            return false;
+        }
+        // A terminator that cannot be evaluated leaves its element unsatisfied
+        let satisfied = self
+            .is_satisfied(self.current_position, self.repeat_count, &self.captured)
+            .unwrap_or(false);
+        satisfied && remaining.all(|pattern| pattern.settings().repeat_bounds().0 == 0)
+    }
+
+    /// Build the completion reported for a match made of the first `end`
+    /// captures of the current attempt, running the completion extractor if
+    /// one is set.
+    fn summarize(&mut self, end: usize) -> Option<Capture<T>> {
+        let captures = &self.captured[..end];
+        let last = captures.last()?;
+        let completed = match &self.completion_extractor {
+            Some(extractor) => Capture {
+                item: extractor(captures),
+                index: last.index,
+                timestamp: last.timestamp,
+            },
+            None => last.clone(),
+        };
+        if let Some(attempts) = self.attempts.as_mut() {
+            attempts.push(captures.to_vec());
+        }
+        Some(completed)
+    }
+
+    /// Take the completion held back by `MatchPolicy::LongestMatch`, if any.
+    fn take_held(&mut self) -> Option<Capture<T>> {
+        let held = self.pending.take()?;
+        // The held match is the prefix of the current attempt ending at its last item
+        match self
+            .captured
+            .iter()
+            .position(|capture| capture.index == held.index)
+        {
+            Some(last) => self.summarize(last + 1),
+            None => {
+                if let Some(attempts) = self.attempts.as_mut() {
+                    attempts.push(vec![held.clone()]);
+                }
+                Some(held)
+            }
+        }
+    }
+
+    /// Check whether a new match may begin at the given stream position.
+    fn can_start_at(&self, index: usize, timestamp: Duration) -> bool {
+        index >= self.next_start_index
+            && self.settings.can_start_at(index, timestamp)
+            && self.is_correlated(index, timestamp)
+    }
+
+    /// Check whether the pattern a match must follow completed recently enough.
+    fn is_correlated(&self, index: usize, timestamp: Duration) -> bool {
+        let Some(correlation) = &self.settings.after else {
+            return true;
+        };
+        self.completions
+            .get(&correlation.pattern)
+            .is_some_and(|(completed, at)| {
+                index >= *completed
+                    && match correlation.within {
+                        StreamOffset::Item(items) => index - completed <= items,
+                        StreamOffset::Time(time) => timestamp.saturating_sub(*at) <= time,
+                    }
+            })
+    }
+
+    /// Check whether a match that began at `start` has run out of time.
+    fn is_expired(&self, start: Duration, timestamp: Duration) -> bool {
+        self.settings
+            .within
+            .is_some_and(|within| timestamp.saturating_sub(start) > within)
+    }
+
+    /// Recover from a mismatch according to the reset policy in effect.
+    ///
+    /// A completion held back by `MatchPolicy::LongestMatch` is reported now.
+    /// An attempt that already matched was not a failure, so instead of the
+    /// reset policy taking over, the item is offered to a new attempt.
+    fn recover_from_mismatch(
+        &mut self,
+        item: T,
+        index: usize,
+        timestamp: Duration,
+    ) -> Result<Vec<Capture<T>>, MatcherError> {
+        if self.pending.is_some() || (!self.captured.is_empty() && self.can_complete()) {
+            let held = self.take_held();
+            self.restart_match();
+            let next = self.match_item(item, timestamp)?;
+            return Ok(held.into_iter().chain(next).collect());
+        }
+        if !self.captured.is_empty() {
+            self.stats.mismatch_resets += 1;
+        }
+        self.notify_reset();
+        let recovered = match self.reset_policy() {
+            ResetPolicy::ResetToStart => {
+                self.restart_match();
+                Vec::new()
+            }
+            ResetPolicy::RetryCurrentItem => {
+                let had_progress = !self.captured.is_empty();
+                self.restart_match();
+                if had_progress {
+                    self.match_item(item, timestamp)?
+                } else {
+                    Vec::new()
+                }
+            }
+            ResetPolicy::FallbackByPrefix => self
+                .fall_back_by_prefix(item, index, timestamp)?
+                .into_iter()
+                .collect(),
+            ResetPolicy::DropWindow => {
+                if let Some(first) = self.captured.first() {
+                    self.next_start_index = first.index + self.window_size;
+                }
+                self.restart_match();
+                Vec::new()
+            }
+        };
+        Ok(recovered)
+    }
+
+    /// Resume from the longest suffix of the failed attempt that still forms a
+    /// valid partial match.
+    fn fall_back_by_prefix(
+        &mut self,
+        item: T,
+        index: usize,
+        timestamp: Duration,
+    ) -> Result<Option<Capture<T>>, MatcherError> {
+        let mut candidates = std::mem::take(&mut self.captured);
+        candidates.push(Capture {
+            item,
+            index,
+            timestamp,
+        });
+        self.restart_match();
+
+        // The first candidate started the failed attempt, so skip it
+        for start in 1..candidates.len() {
+            let first = &candidates[start];
+            if !self.can_start_at(first.index, first.timestamp)
+                || self.is_expired(first.timestamp, timestamp)
+            {
+                continue;
+            }
+
+            if let Some((position, repeat)) = self.replay(&candidates[start..])? {
+                if position >= self.patterns.len() {
+                    self.captured = candidates.split_off(start);
+                    let completed = self.summarize(self.captured.len());
+                    self.restart_match();
+                    return Ok(completed);
+                }
+                self.current_position = position;
+                self.repeat_count = repeat;
+                self.captured = candidates.split_off(start);
+                return Ok(self.settle_completion());
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Feed items through the pattern from the first element without running
+    /// extractors, returning the resulting position and repeat count if they
+    /// all match.
+    fn replay(&self, captures: &[Capture<T>]) -> Result<Option<(usize, usize)>, MatcherError> {
+        let (mut position, mut repeat) = (0, 0);
+        for (count, capture) in captures.iter().enumerate() {
+            if position >= self.patterns.len() {
+                return Ok(None);
+            }
+            match self.advance(
+                position,
+                repeat,
+                &capture.item,
+                capture.index,
+                capture.timestamp,
+                &captures[..count],
+            )? {
+                Advance::Consumed {
+                    element,
+                    repeat: count,
+                } => {
+                    (position, repeat) = self.settle_position(element, count);
+                }
+                Advance::Rejected | Advance::Exhausted | Advance::Completed => return Ok(None),
+            }
+        }
+        Ok(Some((position, repeat)))
+    }
+
+    /// Deliver a completed match to every observer and subscriber.
+    fn publish(&mut self, completed: &Capture<T>) {
+        self.stats.matches_completed += 1;
+        if let Some(name) = &self.name {
+            self.completions
+                .insert(name.clone(), (completed.index, completed.timestamp));
+        }
+        for observer in &mut self.observers {
+            observer.on_match(completed);
+        }
+        for queue in &self.subscribers {
+            queue.push(MatchEvent {
+                value: completed.item.clone(),
+                index: completed.index,
+                timestamp: completed.timestamp,
+            });
+        }
+    }
+
+    /// Close the current session after an inactivity gap and record the new item.
+    ///
+    /// Returns the held completion of the closed session, if any.
+    fn track_session(&mut self, timestamp: Duration) -> Option<Capture<T>> {
+        let gap = self.session_gap?;
+
+        let mut completed = None;
+        if let Some(mut session) = self.session.take() {
+            if timestamp.saturating_sub(session.end) > gap {
+                completed = self.abandon_attempt();
+                // No attempt of the closed session may carry over to the next
+                self.restart_attempts();
+                if completed.is_some() {
+                    session.match_count += 1;
+                }
+                if let Some(callback) = &self.session_callback {
+                    callback(&session);
+                }
+            } else {
+                self.session = Some(session);
+            }
+        }
+
+        let session = self.session.get_or_insert(SessionSummary {
+            start: timestamp,
+            end: timestamp,
+            item_count: 0,
+            match_count: 0,
+        });
+        session.end = timestamp;
+        session.item_count += 1;
+        completed
+    }
+
+    /// Abandon the current attempt if it has run past the absence limit by
+    /// the item at stream position `index`, returning the item it began with.
+    fn detect_absence(&mut self, index: usize, timestamp: Duration) -> Option<Capture<T>> {
+        let limit = self.settings.absence?;
+        let first = self.captured.first()?;
+        let overdue = match limit {
+            StreamOffset::Item(items) => index - first.index > items,
+            StreamOffset::Time(time) => timestamp.saturating_sub(first.timestamp) > time,
+        };
+        if !overdue {
+            return None;
+        }
+        let first = first.clone();
+        if let Some(attempts) = self.attempts.as_mut() {
+            attempts.push(self.captured.clone());
+        }
+        self.abandon_attempt();
+        self.restart_attempts();
+        Some(first)
+    }
+
+    /// Discard the partial match if it can no longer complete in time.
+    ///
+    /// Returns the held completion of the discarded attempt, if any.
+    fn expire_partial_match(&mut self, timestamp: Duration) -> Option<Capture<T>> {
+        let first = self.captured.first()?;
+        if self.is_expired(first.timestamp, timestamp) {
+            self.abandon_attempt()
+        } else {
+            None
+        }
+    }
+
+    /// End the current match attempt, returning its held completion, if any.
+    fn abandon_attempt(&mut self) -> Option<Capture<T>> {
+        self.notify_reset();
+        let held = self.take_held();
+        self.restart_match();
+        held
+    }
+
+    /// Tell observers that the partial match in progress, if any, is abandoned,
+    /// and undo the context changes it staged.
+    fn notify_reset(&mut self) {
+        self.roll_back_context();
+        if self.captured.is_empty() {
+            return;
+        }
+        if let Some(tracer) = &self.tracer {
+            tracer.record(TraceEvent::Reset {
+                index: self.total_processed.saturating_sub(1),
+                element: self.current_position,
+                captured: self.captured.len(),
+            });
+        }
+        for observer in &mut self.observers {
+            observer.on_reset(self.current_position, &self.captured);
+        }
+    }
+
+    /// Get the stream index of the oldest item the current attempt may still
+    /// report.
+    fn earliest_capture_index(&self) -> Option<usize> {
+        let first = self.captured.first().map(|capture| capture.index);
+        let held = self.pending.as_ref().map(|capture| capture.index);
+        first.into_iter().chain(held).min()
+    }
+
+    /// Abandon every match attempt, including those set aside under
+    /// `Selection::SkipTillAnyMatch`, and start over from the first element.
+    fn restart_attempts(&mut self) {
+        self.restart_match();
+        self.forks.clear();
+    }
+
+    /// Abandon the current match attempt and start over from the first element.
+    fn restart_match(&mut self) {
+        self.current_position = 0;
+        self.repeat_count = 0;
+        self.captured.clear();
+        self.pending = None;
+        self.alternatives.clear();
+        self.context_checkpoint = None;
+    }
+
+    /// Signal the end of the input stream and report any unfinished work.
+    ///
+    /// A completion held back by `MatchPolicy::LongestMatch`, such as a match
+    /// whose trailing optional elements never arrived, is delivered to
+    /// subscribers like any other match. A partial match that still needs
+    /// required elements is reported with the items captured so far. The open
+    /// session, if any, is closed. The matcher is left ready for a new stream.
+    pub fn finish(&mut self) -> FinishReport<T> {
+        let mut report = FinishReport {
+            completed: None,
+            partial: None,
+            session: None,
+        };
+
+        if let Some(held) = self.take_held() {
+            if let Some(session) = self.session.as_mut() {
+                session.match_count += 1;
+            }
+            self.publish(&held);
+            report.completed = Some(held.item);
+        } else if !self.captured.is_empty() && self.settle_completion().is_none() {
+            self.roll_back_context();
+            report.partial = Some(PartialMatch {
+                position: self.current_position,
+                captured: std::mem::take(&mut self.captured),
+            });
+        }
+        self.restart_attempts();
+
+        if let Some(session) = self.session.take() {
+            if let Some(callback) = &self.session_callback {
+                callback(&session);
+            }
+            report.session = Some(session);
+        }
+
+        report
+    }
+
+    /// Take a snapshot of the in-flight matching progress.
+    ///
+    /// The context is not part of the snapshot, so this fails with
+    /// `MatcherError::InvalidState` while an attempt has context changes
+    /// staged by `stage_context`.
+    pub fn checkpoint(&self) -> Result<MatcherState<T>, MatcherError> {
+        let staged = self.context_checkpoint.is_some()
+            || self
+                .forks
+                .iter()
+                .any(|attempt| attempt.context_checkpoint.is_some());
+        if staged {
+            return Err(MatcherError::InvalidState(
+                "context changes of the attempt in progress are staged".to_string(),
+            ));
+        }
+        Ok(MatcherState {
+            position: self.current_position,
+            repeat: self.repeat_count,
+            alternatives: self.alternatives.clone(),
+            total_processed: self.total_processed,
+            replay_skip: self.replay_skip,
+            last_timestamp: self.last_timestamp,
+            captured: self.captured.clone(),
+            pending: self.pending.clone(),
+            next_start_index: self.next_start_index,
+            session: self.session.clone(),
+            window: self.window.iter().cloned().collect(),
+            arrivals: self.arrivals.iter().copied().collect(),
+            completions: self.completions.clone(),
+            forks: self
+                .forks
+                .iter()
+                .map(|attempt| AttemptState {
+                    position: attempt.position,
+                    repeat: attempt.repeat,
+                    alternatives: attempt.alternatives.clone(),
+                    captured: attempt.captured.clone(),
+                    pending: attempt.pending.clone(),
+                })
+                .collect(),
+        })
+    }
+
+    /// Check that an attempt of a snapshot fits the pattern.
+    fn validate_attempt(&self, position: usize, repeat: usize) -> Result<(), MatcherError> {
+        if position > self.patterns.len() {
+            return Err(MatcherError::InvalidState(format!(
+                "position {} exceeds pattern length {}",
+                position,
+                self.patterns.len()
+            )));
+        }
+        if let Some(pattern) = self.patterns.get(position) {
+            let (_, max) = pattern.settings().repeat_bounds();
+            if repeat > max {
+                return Err(MatcherError::InvalidState(format!(
+                    "repeat count {} exceeds the {} allowed at position {}",
+                    repeat, max, position
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Resume matching from a snapshot taken with `checkpoint`.
+    pub fn restore(&mut self, state: MatcherState<T>) -> Result<(), MatcherError> {
+        let forks = state
+            .forks
+            .iter()
+            .map(|attempt| (attempt.position, attempt.repeat, &attempt.alternatives));
+        let attempts =
+            std::iter::once((state.position, state.repeat, &state.alternatives)).chain(forks);
+        for (position, repeat, alternatives) in attempts {
+            self.validate_attempt(position, repeat)?;
+            for &(position, repeat) in alternatives {
+                self.validate_attempt(position, repeat)?;
+            }
+        }
+
+        self.current_position = state.position;
+        self.repeat_count = state.repeat;
+        self.total_processed = state.total_processed;
+        self.last_timestamp = state.last_timestamp;
+        self.captured = state.captured;
+        self.pending = state.pending;
+        self.next_start_index = state.next_start_index;
+        self.session = state.session;
+        self.window = state.window.into();
+        self.arrivals = state.arrivals.into();
+        self.completions = state.completions;
+        self.alternatives = state.alternatives;
+        self.forks = state
+            .forks
+            .into_iter()
+            .map(|attempt| Attempt {
+                position: attempt.position,
+                repeat: attempt.repeat,
+                captured: attempt.captured,
+                pending: attempt.pending,
+                alternatives: attempt.alternatives,
+                context_checkpoint: None,
+            })
+            .collect();
+        self.replay_skip = state.replay_skip;
+        Ok(())
+    }
+
+    /// Get the input offset of the next item.
+    ///
+    /// Items are numbered from zero, or from the offset passed to `seek`, and
+    /// each match result carries the offset of the item that completed it as
+    /// its index, so matches can be tied back to positions in the source.
+    pub fn offset(&self) -> usize {
+        self.total_processed - self.replay_skip
+    }
+
+    /// Number the next item `offset`, abandoning the match attempt in
+    /// progress and forgetting the items in the window.
+    ///
+    /// Use this to line stream indices up with the source, such as a Kafka
+    /// partition offset or a record number in a file, before processing
+    /// starts or after the source jumps.
+    pub fn seek(&mut self, offset: usize) {
+        self.restart_attempts();
+        self.total_processed = offset;
+        self.next_start_index = 0;
+        self.replay_skip = 0;
+        self.window.clear();
+        self.arrivals.clear();
+    }
+
+    /// Resume a source that replays items from `offset`, such as after a
+    /// crash, skipping the items this matcher has already processed.
+    ///
+    /// Restore a checkpoint first, then call this with the offset the source
+    /// restarts from: replayed items up to the checkpoint are ignored, so
+    /// every match is reported exactly once. An offset past the next one
+    /// means items were missed, so the matcher seeks to it instead.
+    pub fn resume_from(&mut self, offset: usize) {
+        if offset > self.total_processed {
+            self.seek(offset);
+        } else {
+            self.replay_skip = self.total_processed - offset;
+        }
+    }
+
+    /// Create an independent copy of the matcher for speculative matching.
+    ///
+    /// The fork starts from the same in-flight match state and context, so
+    /// hypothetical items can be fed to it without disturbing this matcher.
+    /// Unlike a clone, a fork has no side effects: it does not report closed
+    /// sessions or call element callbacks, and like a clone it has no
+    /// observers or subscribers.
+    pub fn fork(&self) -> Self {
+        let mut fork = self.clone();
+        fork.session_callback = None;
+        fork.element_callbacks.clear();
+        fork
+    }
+
+    /// Process multiple items and return all extracted data.
+    pub fn process_items(&mut self, items: Vec<T>) -> Result<Vec<T>, MatcherError> {
+        let mut results = Vec::new();
+        self.process_items_into(&mut results, items)?;
+        Ok(results)
+    }
+
+    /// Process multiple items, appending all extracted data to `output`.
+    ///
+    /// Clearing `output` between batches reuses its allocation, so batches
+    /// can be processed in a tight loop without allocating a results vector
+    /// for each. Matches completed before an error are still appended.
+    pub fn process_items_into<I>(
+        &mut self,
+        output: &mut Vec<T>,
+        items: I,
+    ) -> Result<(), MatcherError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        self.process_items_with(items, |event| output.push(event.value))
+    }
+
+    /// Process multiple items, calling `on_match` with each match as it
+    /// completes.
+    ///
+    /// Matches are handed over rather than collected, so forwarding them to a
+    /// sink allocates no result vector and copies no matched data.
+    pub fn process_items_with<I, F>(
+        &mut self,
+        items: I,
+        mut on_match: F,
+    ) -> Result<(), MatcherError>
+    where
+        I: IntoIterator<Item = T>,
+        F: FnMut(MatchEvent<T>),
+    {
+        for item in items {
+            let timestamp = self.epoch.elapsed();
+            for capture in self.process_captures(item, timestamp)? {
+                on_match(MatchEvent {
+                    value: capture.item,
+                    index: capture.index,
+                    timestamp: capture.timestamp,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Reset the matcher state.
+    pub fn reset(&mut self) {
+        self.restart_attempts();
+        self.next_start_index = 0;
+        self.total_processed = 0;
+        self.replay_skip = 0;
+        self.last_timestamp = Duration::ZERO;
+        self.session = None;
+        self.window.clear();
+        self.arrivals.clear();
+        self.completions.clear();
+    }
+
+    /// Get the current position in the pattern.
+    pub fn current_position(&self) -> usize {
+        self.current_position
+    }
+
+    /// Describe the elements that could match the next item, in pattern
+    /// order.
+    ///
+    /// The element at the current position comes first, unless it has
+    /// matched as many items as it may, followed by the elements after it for
+    /// as long as the ones before may be passed over. Only the live attempt
+    /// is described, not the parallel attempts of
+    /// `Selection::SkipTillAnyMatch`.
+    pub fn expected_next(&self) -> Vec<ExpectedElement> {
+        let mut expected = Vec::new();
+        let mut repeat = self.repeat_count;
+        for position in self.current_position..self.patterns.len() {
+            let pattern = &self.patterns[position];
+            let optional = self
+                .is_satisfied(position, repeat, &self.captured)
+                .unwrap_or(false);
+            if repeat < pattern.settings().repeat_bounds().1 {
+                expected.push(ExpectedElement {
+                    position,
+                    kind: pattern.kind(),
+                    description: pattern.to_string(),
+                    repeat,
+                    optional,
+                });
+            }
+            if !optional {
+                break;
+            }
+            repeat = 0;
+        }
+        expected
+    }
+
+    /// Get the total number of items processed.
+    pub fn total_processed(&self) -> usize {
+        self.total_processed
+    }
+
+    /// Get the statistics gathered since the matcher was created or the
+    /// statistics were last reset.
+    pub fn stats(&self) -> &MatchStats {
+        &self.stats
+    }
+
+    /// Reset the statistics to zero.
+    pub fn reset_stats(&mut self) {
+        self.stats = MatchStats::default();
+    }
+
+    /// Turn profiling on or off.
+    ///
+    /// While profiling, the matcher counts the evaluations of each element
+    /// and extractor and the time they take. Turning profiling off discards
+    /// the profile; turning it on when it is already on keeps it.
+    pub fn set_profiling(&mut self, enabled: bool) {
+        if !enabled {
+            self.profiler = None;
+        } else if self.profiler.is_none() {
+            self.profiler = Some(Profiler::default());
+        }
+    }
+
+    /// Check if profiling is on.
+    pub fn is_profiling(&self) -> bool {
+        self.profiler.is_some()
+    }
+
+    /// Get the profile gathered since profiling was turned on, if it is on.
+    pub fn profile_report(&self) -> Option<ProfileReport> {
+        self.profiler.as_ref().map(Profiler::report)
+    }
+
+    /// Turn tracing on, keeping the latest `capacity` events, or off with
+    /// `None`.
+    ///
+    /// While tracing, the matcher records every element evaluation, extractor
+    /// run, and abandoned partial match. Turning tracing off or changing the
+    /// capacity discards the trace.
+    pub fn set_tracing(&mut self, capacity: Option<usize>) {
+        match capacity {
+            None => self.tracer = None,
+            Some(capacity) => {
+                if self.tracer.as_ref().map(Tracer::capacity) != Some(capacity) {
+                    self.tracer = Some(Tracer::new(capacity));
+                }
+            }
+        }
+    }
+
+    /// Check if tracing is on.
+    pub fn is_tracing(&self) -> bool {
+        self.tracer.is_some()
+    }
+
+    /// Get the events recorded while tracing, oldest first.
+    pub fn trace(&self) -> Vec<TraceEvent> {
+        self.tracer.as_ref().map(Tracer::events).unwrap_or_default()
+    }
+
+    /// Discard the events recorded so far, leaving tracing on.
+    pub fn clear_trace(&self) {
+        if let Some(tracer) = &self.tracer {
+            tracer.clear();
+        }
+    }
+
+    /// Get the window size.
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    /// Set the window size.
+    pub fn set_window_size(&mut self, size: usize) {
+        self.window_size = size;
+        while self.window.len() > size {
+            self.window.pop_front();
+        }
+    }
+
+    /// Get the number of patterns.
+    pub fn pattern_count(&self) -> usize {
+        self.patterns.len()
     }
 
     /// Get a reference to the patterns.
@@ -561,427 +4986,2969 @@ where
         &self.patterns
     }
 
-    /// Check if the matcher is currently in a matching state.
-    pub fn is_matching(&self) -> bool {
-        self.current_position > 0
+    /// Render the pattern as a Graphviz DOT state machine.
+    pub fn to_dot(&self) -> String {
+        dot::render(&self.patterns)
+    }
+
+    /// Report the attempt in progress if, by `timestamp`, it has run past the
+    /// time limit set with `PatternSettings::absence`, returning the item it
+    /// began with.
+    ///
+    /// Call this from a timer to detect missing items while the stream is
+    /// quiet. Timestamps are on the same scale as those passed to
+    /// `process_item_at`.
+    pub fn check_absence(&mut self, timestamp: Duration) -> Option<T> {
+        let absent = self.detect_absence(self.total_processed.saturating_sub(1), timestamp)?;
+        if let Some(session) = self.session.as_mut() {
+            session.match_count += 1;
+        }
+        self.publish(&absent);
+        Some(absent.item)
+    }
+
+    /// Check if the matcher is currently in a matching state, with a match
+    /// attempt that has consumed items in progress.
+    pub fn is_matching(&self) -> bool {
+        self.current_position > 0
+            || self.repeat_count > 0
+            || !self.captured.is_empty()
+            || self.pending.is_some()
+            || !self.forks.is_empty()
+    }
+}
+
+/// Matching over borrowed items.
+///
+/// A matcher whose item type is a reference never clones the underlying
+/// values: patterns, captures, and results all hold references into the
+/// caller's data, so `T` itself does not need to implement `Clone`.
+impl<'a, T, Context> Matcher<&'a T, Context>
+where
+    T: PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Process a slice of items by reference, returning the stream index of
+    /// the item that completed each match.
+    ///
+    /// For a freshly created or reset matcher, stream indices are positions in
+    /// the slice.
+    pub fn process_slice(&mut self, items: &'a [T]) -> Result<Vec<usize>, MatcherError> {
+        let mut indices = Vec::new();
+        for item in items {
+            let timestamp = self.epoch.elapsed();
+            let completed = self.process_captures(item, timestamp)?;
+            indices.extend(completed.iter().map(|capture| capture.index));
+        }
+        Ok(indices)
+    }
+}
+
+impl<T, Context> fmt::Debug for Matcher<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Matcher")
+            .field("name", &self.name)
+            .field("pattern_count", &self.patterns.len())
+            .field("settings", &self.settings)
+            .field("current_position", &self.current_position)
+            .field("total_processed", &self.total_processed)
+            .field("window_size", &self.window_size)
+            .field("extractor_count", &self.extractors.len())
+            .field("has_context", &self.context.is_some())
+            .field("session_gap", &self.session_gap)
+            .field("subscriber_count", &self.subscribers.len())
+            .field("observer_count", &self.observers.len())
+            .finish()
+    }
+}
+
+/// Cloning a matcher copies its patterns, extractors, and in-flight match
+/// state, sharing the closures with the original. Observers and event
+/// subscribers stay with the original matcher.
+impl<T, Context> Clone for Matcher<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    fn clone(&self) -> Self {
+        Self {
+            patterns: self.patterns.clone(),
+            settings: self.settings.clone(),
+            current_position: self.current_position,
+            repeat_count: self.repeat_count,
+            total_processed: self.total_processed,
+            window_size: self.window_size,
+            extractors: self.extractors.clone(),
+            context: self.context.clone(),
+            epoch: self.epoch,
+            reset_policy: self.reset_policy,
+            last_timestamp: self.last_timestamp,
+            captured: self.captured.clone(),
+            pending: self.pending.clone(),
+            next_start_index: self.next_start_index,
+            session_gap: self.session_gap,
+            session: self.session.clone(),
+            session_callback: self.session_callback.clone(),
+            subscribers: Vec::new(),
+            window: self.window.clone(),
+            arrivals: self.arrivals.clone(),
+            observers: Vec::new(),
+            stats: self.stats.clone(),
+            name: self.name.clone(),
+            completions: self.completions.clone(),
+            alternatives: self.alternatives.clone(),
+            element_callbacks: self.element_callbacks.clone(),
+            completion_extractor: self.completion_extractor.clone(),
+            transactional_context: self.transactional_context,
+            context_checkpoint: self.context_checkpoint.clone(),
+            replay_skip: self.replay_skip,
+            resolved_extractors: self.resolved_extractors.clone(),
+            profiler: self.profiler.clone(),
+            tracer: self.tracer.clone(),
+            attempts: None,
+            forks: self.forks.clone(),
+        }
+    }
+}
+
+impl<T, Context> Default for Matcher<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    fn default() -> Self {
+        Self::new(10)
+    }
+}
+
+/// Collecting pattern elements builds a matcher with the default window size.
+impl<T, Context> FromIterator<PatternElement<T, Context>> for Matcher<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    fn from_iter<I: IntoIterator<Item = PatternElement<T, Context>>>(elements: I) -> Self {
+        let mut matcher = Self::default();
+        matcher.extend(elements);
+        matcher
+    }
+}
+
+/// Extending a matcher appends pattern elements, as by `add_pattern`.
+impl<T, Context> Extend<PatternElement<T, Context>> for Matcher<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    fn extend<I: IntoIterator<Item = PatternElement<T, Context>>>(&mut self, elements: I) {
+        for element in elements {
+            self.add_pattern(element);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestContext {
+        name: String,
+        value: i32,
+        captured_values: Vec<i32>,
+        counters: HashMap<String, usize>,
+    }
+
+    impl Default for TestContext {
+        fn default() -> Self {
+            Self {
+                name: "test".to_string(),
+                value: 0,
+                captured_values: Vec::new(),
+                counters: HashMap::new(),
+            }
+        }
+    }
+
+    // === Basic Pattern Matching Tests ===
+
+    #[test]
+    fn test_exact_match_simple() {
+        let mut matcher = Matcher::<i32, ()>::new(5);
+        matcher.add_pattern(PatternElement::exact(42));
+
+        assert_eq!(matcher.process_item(41).unwrap(), None);
+        assert_eq!(matcher.process_item(42).unwrap(), Some(42));
+    }
+
+    #[test]
+    fn test_exact_match_sequence() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact(2));
+        matcher.add_pattern(PatternElement::exact(3));
+
+        assert_eq!(matcher.process_item(1).unwrap(), None);
+        assert_eq!(matcher.process_item(2).unwrap(), None);
+        assert_eq!(matcher.process_item(3).unwrap(), Some(3));
+    }
+
+    #[test]
+    fn test_exact_match_with_settings() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+
+        let settings = ElementSettings {
+            optional: false,
+            max_retries: 2,
+            ..Default::default()
+        };
+
+        matcher.add_pattern(PatternElement::exact_with_settings(42, settings));
+
+        assert_eq!(matcher.process_item(42).unwrap(), Some(42));
+    }
+
+    #[test]
+    fn test_predicate_match() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+        matcher.add_pattern(PatternElement::predicate(|x| *x > 0));
+        matcher.add_pattern(PatternElement::predicate(|x| *x < 10));
+
+        assert_eq!(matcher.process_item(5).unwrap(), None);
+        assert_eq!(matcher.process_item(3).unwrap(), Some(3));
+    }
+
+    #[test]
+    fn test_predicate_with_settings() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+
+        let settings = ElementSettings {
+            timeout_ms: Some(1000),
+            ..Default::default()
+        };
+
+        matcher.add_pattern(PatternElement::predicate_with_settings(
+            |x| *x % 2 == 0,
+            settings,
+        ));
+
+        assert_eq!(matcher.process_item(4).unwrap(), Some(4));
+        assert_eq!(matcher.process_item(3).unwrap(), None);
+    }
+
+    #[test]
+    fn test_range_match() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+        matcher.add_pattern(PatternElement::range(1, 5));
+        matcher.add_pattern(PatternElement::range(6, 10));
+
+        assert_eq!(matcher.process_item(3).unwrap(), None);
+        assert_eq!(matcher.process_item(8).unwrap(), Some(8));
+    }
+
+    #[test]
+    fn test_range_with_settings() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+
+        let settings = ElementSettings {
+            optional: true,
+            ..Default::default()
+        };
+
+        matcher.add_pattern(PatternElement::range_with_settings(10, 20, settings));
+
+        assert_eq!(matcher.process_item(15).unwrap(), Some(15));
+        assert_eq!(matcher.process_item(25).unwrap(), None);
+    }
+
+    // === Extractor Tests ===
+
+    #[test]
+    fn test_extractor_extract() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+
+        // Register an extractor that doubles large values
+        matcher.register_extractor(1, |state| {
+            if state.current_item > 10 {
+                Ok(ExtractorAction::Extract(state.current_item * 2))
+            } else {
+                Ok(ExtractorAction::Continue)
+            }
+        });
+
+        let settings = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(15, settings));
+
+        assert_eq!(matcher.process_item(15).unwrap(), Some(30));
+    }
+
+    #[test]
+    fn test_extractor_continue() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+
+        matcher.register_extractor(1, |_state| Ok(ExtractorAction::Continue));
+
+        let settings = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(5, settings));
+        matcher.add_pattern(PatternElement::exact(10));
+
+        assert_eq!(matcher.process_item(5).unwrap(), None);
+        assert_eq!(matcher.process_item(10).unwrap(), Some(10));
+    }
+
+    #[test]
+    fn test_extractor_restart() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+
+        matcher.register_extractor(1, |_state| Ok(ExtractorAction::Restart));
+
+        let settings = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(5, settings));
+        matcher.add_pattern(PatternElement::exact(10));
+
+        assert_eq!(matcher.process_item(5).unwrap(), None);
+        assert_eq!(matcher.current_position(), 0); // Should be reset
+    }
+
+    #[test]
+    fn test_multiple_extractors() {
+        // Test extractor 1: Double the value
+        let mut matcher1 = Matcher::<i32, TestContext>::new(5);
+        matcher1.register_extractor(1, |state| {
+            Ok(ExtractorAction::Extract(state.current_item * 2))
+        });
+
+        let settings1 = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+        matcher1.add_pattern(PatternElement::exact_with_settings(10, settings1));
+
+        assert_eq!(matcher1.process_item(10).unwrap(), Some(20));
+
+        // Test extractor 2: Triple the value
+        let mut matcher2 = Matcher::<i32, TestContext>::new(5);
+        matcher2.register_extractor(2, |state| {
+            Ok(ExtractorAction::Extract(state.current_item * 3))
+        });
+
+        let settings2 = ElementSettings {
+            extractor_id: Some(2),
+            ..Default::default()
+        };
+        matcher2.add_pattern(PatternElement::exact_with_settings(5, settings2));
+
+        assert_eq!(matcher2.process_item(5).unwrap(), Some(15));
+    }
+
+    #[test]
+    fn test_extractor_sees_window() {
+        let mut matcher = Matcher::<i32, TestContext>::new(3);
+
+        // Report the sum of the items leading up to the match
+        matcher.register_extractor(1, |state| {
+            Ok(ExtractorAction::Extract(state.window.iter().sum()))
+        });
+
+        let settings = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(0, settings));
+
+        assert_eq!(matcher.process_item(0).unwrap(), Some(0));
+        assert_eq!(matcher.process_items(vec![1, 2, 3, 4, 0]).unwrap(), vec![9]);
+    }
+
+    #[test]
+    fn test_extractor_sees_pattern_identity() {
+        // One extractor shared by two patterns can tell them apart
+        let tag = |state: &MatchState<String>| {
+            let name = state.pattern_name.unwrap_or("unnamed");
+            Ok(ExtractorAction::Extract(format!(
+                "{}@{}",
+                name, state.position
+            )))
+        };
+        let settings = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+
+        let mut login = Matcher::<String, ()>::new(5);
+        login.set_name("login");
+        login.register_extractor(1, tag);
+        login.add_pattern(PatternElement::exact("user".to_string()));
+        login.add_pattern(PatternElement::exact_with_settings(
+            "pass".to_string(),
+            settings.clone(),
+        ));
+
+        let mut logout = Matcher::<String, ()>::new(5);
+        logout.register_extractor(1, tag);
+        logout.add_pattern(PatternElement::exact_with_settings(
+            "bye".to_string(),
+            settings,
+        ));
+
+        let items = ["user", "pass"].map(String::from).to_vec();
+        assert_eq!(login.process_items(items).unwrap(), vec!["login@1"]);
+        assert_eq!(
+            logout.process_item("bye".to_string()).unwrap(),
+            Some("unnamed@0".to_string())
+        );
+    }
+
+    // === Context Tests ===
+
+    #[test]
+    fn test_context_basic() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+        let context = TestContext {
+            name: "test".to_string(),
+            value: 42,
+            captured_values: vec![1, 2, 3],
+            counters: HashMap::new(),
+        };
+
+        matcher.set_context(context.clone());
+        assert_eq!(matcher.context(), Some(&context));
+    }
+
+    #[test]
+    fn test_context_lifecycle() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5).with_context(TestContext::default());
+        matcher.add_pattern(PatternElement::exact(1));
+
+        for item in [1, 2, 1] {
+            if matcher.process_item(item).unwrap().is_some() {
+                matcher.context_mut().unwrap().captured_values.push(item);
+            }
+        }
+        matcher.map_context(|mut context| {
+            context.value = context.captured_values.len() as i32;
+            context
+        });
+
+        let context = matcher.take_context().unwrap();
+        assert_eq!(context.captured_values, vec![1, 1]);
+        assert_eq!(context.value, 2);
+        assert_eq!(matcher.context(), None);
+    }
+
+    #[test]
+    fn test_context_with_extractor() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+
+        let context = TestContext::default();
+        matcher.set_context(context);
+
+        // Note: In this simplified design, extractors work with MatchState, not context
+        // This is different from the old StatefulMatcher design
+        matcher.register_extractor(1, |state| {
+            if state.position == 0 {
+                Ok(ExtractorAction::Extract(state.current_item + 100))
+            } else {
+                Ok(ExtractorAction::Continue)
+            }
+        });
+
+        let settings = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(42, settings));
+
+        assert_eq!(matcher.process_item(42).unwrap(), Some(142));
+    }
+
+    #[test]
+    fn test_transactional_context() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+        matcher.set_transactional_context(true);
+        matcher.set_context(TestContext::default());
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact(2));
+
+        let record = |matcher: &mut Matcher<i32, TestContext>, item| {
+            let found = matcher.process_item(item).unwrap();
+            let mut context = matcher.context().cloned().unwrap();
+            context.captured_values.push(item);
+            matcher.set_context(context);
+            found
+        };
+
+        // Changes made during a failed attempt are rolled back
+        record(&mut matcher, 1);
+        assert_eq!(matcher.process_item(3).unwrap(), None);
+        assert!(matcher.context().unwrap().captured_values.is_empty());
+
+        // Changes made during a completed attempt are kept
+        record(&mut matcher, 1);
+        assert_eq!(record(&mut matcher, 2), Some(2));
+        assert_eq!(matcher.context().unwrap().captured_values, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_transactional_context_within_first_element() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+        matcher.set_transactional_context(true);
+        matcher.set_context(TestContext::default());
+        matcher.add_pattern(PatternElement::exact_with_settings(
+            1,
+            ElementSettings {
+                min_repeat: 3,
+                max_repeat: 3,
+                ..Default::default()
+            },
+        ));
+
+        // The attempt is still at the first element when the context changes
+        assert_eq!(matcher.process_item(1).unwrap(), None);
+        matcher.context_mut().unwrap().captured_values.push(1);
+        assert_eq!(matcher.process_item(2).unwrap(), None);
+        assert!(matcher.context().unwrap().captured_values.is_empty());
+    }
+
+    // === State Management Tests ===
+
+    #[test]
+    fn test_reset() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact(2));
+
+        matcher.process_item(1).unwrap();
+        assert_eq!(matcher.current_position(), 1);
+        assert_eq!(matcher.total_processed(), 1);
+
+        matcher.reset();
+        assert_eq!(matcher.current_position(), 0);
+        assert_eq!(matcher.total_processed(), 0);
+    }
+
+    #[test]
+    fn test_state_inspection() {
+        let mut matcher = Matcher::<i32, TestContext>::new(10);
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact(2));
+
+        assert_eq!(matcher.window_size(), 10);
+        assert_eq!(matcher.pattern_count(), 2);
+        assert_eq!(matcher.current_position(), 0);
+        assert_eq!(matcher.total_processed(), 0);
+        assert!(!matcher.is_matching());
+
+        matcher.process_item(1).unwrap();
+        assert_eq!(matcher.current_position(), 1);
+        assert_eq!(matcher.total_processed(), 1);
+        assert!(matcher.is_matching());
+    }
+
+    #[test]
+    fn test_window_size_management() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+        assert_eq!(matcher.window_size(), 5);
+
+        matcher.set_window_size(20);
+        assert_eq!(matcher.window_size(), 20);
+    }
+
+    // === Multiple Item Processing Tests ===
+
+    #[test]
+    fn test_process_items() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact(2));
+
+        let items = vec![1, 2, 3, 1, 2, 4, 1, 2];
+        let results = matcher.process_items(items).unwrap();
+
+        // Should have found three complete patterns: [1,2] at positions 0-1, 3-4, and 6-7
+        assert_eq!(results, vec![2, 2, 2]);
+    }
+
+    #[test]
+    fn test_process_items_into() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact(2));
+
+        let mut results = Vec::with_capacity(4);
+        let capacity = results.capacity();
+        for batch in [vec![1, 2, 3, 1], vec![2, 4, 1, 3]] {
+            results.clear();
+            matcher.process_items_into(&mut results, batch).unwrap();
+            assert_eq!(results.len(), 1);
+        }
+        matcher.process_items_into(&mut results, [1, 2]).unwrap();
+        assert_eq!(results, vec![2, 2]);
+        assert_eq!(results.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_process_item_reports() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+        matcher.set_name("rise");
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact(2));
+        matcher.add_pattern(PatternElement::exact(3));
+
+        let mut reports = Vec::new();
+        for (second, item) in [7, 1, 2, 3].into_iter().enumerate() {
+            let timestamp = Duration::from_secs(second as u64);
+            reports.extend(matcher.process_item_reports_at(item, timestamp).unwrap());
+        }
+        assert_eq!(reports.len(), 1);
+        let report = &reports[0];
+        assert_eq!(report.pattern.as_deref(), Some("rise"));
+        assert_eq!(report.span, 1..4);
+        assert_eq!(report.items().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(*report.value(), 3);
+        assert_eq!(report.start_timestamp, Duration::from_secs(1));
+        assert_eq!(report.end_timestamp, Duration::from_secs(3));
+
+        // An extractor can end the match early with a value of its own
+        matcher.register_extractor(1, |state| {
+            Ok(ExtractorAction::Extract(state.current_item * 10))
+        });
+        let settings = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+        matcher
+            .replace_pattern(1, PatternElement::exact_with_settings(2, settings))
+            .unwrap();
+        assert!(matcher.process_item_reports(1).unwrap().is_empty());
+        let reports = matcher.process_item_reports(2).unwrap();
+        assert_eq!(reports[0].span, 4..6);
+        assert_eq!(reports[0].captures.len(), 2);
+        assert_eq!(reports[0].clone().into_value(), 20);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_match_report_json() {
+        let mut matcher = Matcher::<i32, ()>::new(5);
+        matcher.set_name("pair");
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact(2));
+        matcher
+            .process_item_reports_at(1, Duration::from_millis(5))
+            .unwrap();
+        let reports = matcher
+            .process_item_reports_at(2, Duration::from_millis(8))
+            .unwrap();
+
+        let json = serde_json::to_value(&reports[0]).unwrap();
+        assert_eq!(json["pattern"], "pair");
+        assert_eq!(json["span"], serde_json::json!({ "start": 0, "end": 2 }));
+        assert_eq!(json["value"], 2);
+        assert_eq!(json["captures"][0]["item"], 1);
+        let report: MatchReport<i32> = serde_json::from_value(json).unwrap();
+        assert_eq!(report, reports[0]);
+    }
+
+    #[test]
+    fn test_process_items_with() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact(2));
+
+        let mut indices = Vec::new();
+        matcher
+            .process_items_with([1, 2, 3, 1, 2, 4, 1, 2], |event| {
+                assert_eq!(event.value, 2);
+                indices.push(event.index);
+            })
+            .unwrap();
+        assert_eq!(indices, vec![1, 4, 7]);
+
+        matcher.clear_patterns();
+        assert!(matches!(
+            matcher.process_items_with([1], |_| {}),
+            Err(MatcherError::NoPatterns)
+        ));
+    }
+
+    #[test]
+    fn test_with_patterns_constructor() {
+        let patterns = vec![
+            PatternElement::exact(1),
+            PatternElement::exact(2),
+            PatternElement::exact(3),
+        ];
+
+        let mut matcher = Matcher::<i32, TestContext>::with_patterns(patterns, 10);
+
+        assert_eq!(matcher.pattern_count(), 3);
+        assert_eq!(matcher.window_size(), 10);
+
+        assert_eq!(matcher.process_item(1).unwrap(), None);
+        assert_eq!(matcher.process_item(2).unwrap(), None);
+        assert_eq!(matcher.process_item(3).unwrap(), Some(3));
+    }
+
+    // === Error Handling Tests ===
+
+    #[test]
+    fn test_no_patterns_error() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+
+        let result = matcher.process_item(42);
+        assert!(matches!(result, Err(MatcherError::NoPatterns)));
+    }
+
+    #[test]
+    fn test_extractor_error() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+
+        matcher.register_extractor(1, |_state| {
+            Err(ExtractorError::ProcessingFailed("Test error".to_string()))
+        });
+
+        let settings = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(42, settings));
+
+        let result = matcher.process_item(42);
+        assert!(matches!(result, Err(MatcherError::ExtractorFailed { .. })));
+
+        // The location names the pattern that failed
+        matcher.set_name("answer");
+        let err = matcher.process_item(42).unwrap_err();
+        assert_eq!(
+            err.location()
+                .and_then(|location| location.pattern.as_deref()),
+            Some("answer")
+        );
+        assert_eq!(
+            err.to_string(),
+            "Extractor failed at pattern \"answer\", item 1, element 0, extractor 1: \
+             Processing failed: Test error"
+        );
+    }
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(MatcherError::NoPatterns.code(), ErrorCode::NoPatterns);
+        assert_eq!(
+            MatcherError::InvalidState("bad".to_string()).code(),
+            ErrorCode::InvalidState
+        );
+
+        let err = MatcherError::ExtractorFailed {
+            error: ExtractorError::InvalidConfiguration("missing field".to_string()),
+            location: ErrorLocation {
+                pattern: None,
+                index: 0,
+                element: 0,
+                extractor_id: Some(1),
+            },
+        };
+        assert_eq!(err.code(), ErrorCode::ExtractorInvalidConfiguration);
+        assert_eq!(err.code().as_str(), "extractor_invalid_configuration");
+    }
+
+    #[test]
+    fn test_error_location() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.register_extractor(5, |_| {
+            Err(ExtractorError::ProcessingFailed("bad value".to_string()))
+        });
+        let settings = ElementSettings {
+            extractor_id: Some(5),
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact_with_settings(2, settings));
+
+        matcher.process_items(vec![0, 1]).unwrap();
+        let err = matcher.process_item(2).unwrap_err();
+        assert_eq!(
+            err.location(),
+            Some(&ErrorLocation {
+                pattern: None,
+                index: 2,
+                element: 1,
+                extractor_id: Some(5),
+            })
+        );
+        assert_eq!(
+            err.to_string(),
+            "Extractor failed at item 2, element 1, extractor 5: Processing failed: bad value"
+        );
+    }
+
+    #[test]
+    fn test_extractor_registry() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.register_extractor(2, |_| Ok(ExtractorAction::Continue));
+        matcher.register_extractor(1, |_| Ok(ExtractorAction::Continue));
+        assert_eq!(matcher.extractor_ids(), vec![1, 2]);
+        assert!(matcher.has_extractor(1));
+
+        assert!(matcher.unregister_extractor(1).is_some());
+        assert!(matcher.unregister_extractor(1).is_none());
+        assert!(!matcher.has_extractor(1));
+        assert_eq!(matcher.extractor_ids(), vec![2]);
+    }
+
+    #[test]
+    fn test_missing_extractor_is_an_error() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        let settings = ElementSettings {
+            extractor_id: Some(7),
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(1, settings));
+
+        let err = matcher.process_item(1).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::MissingExtractor);
+        assert_eq!(
+            err.location().and_then(|location| location.extractor_id),
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn test_compile_resolves_extractors() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        let settings = ElementSettings {
+            extractor_id: Some(7),
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact(0));
+        matcher.add_pattern(PatternElement::exact_with_settings(1, settings));
+
+        // A missing extractor is reported before any item is processed
+        let err = matcher.compile().unwrap_err();
+        assert_eq!(err.code(), ErrorCode::MissingExtractor);
+        assert_eq!(err.location().map(|location| location.element), Some(1));
+
+        matcher.register_extractor(7, |state| {
+            Ok(ExtractorAction::Extract(state.current_item + 10))
+        });
+        matcher.compile().unwrap();
+        assert_eq!(matcher.process_items(vec![0, 1]).unwrap(), vec![11]);
+
+        // Changing the extractors is picked up without compiling again
+        matcher.register_extractor(7, |state| {
+            Ok(ExtractorAction::Extract(state.current_item + 20))
+        });
+        assert_eq!(matcher.process_items(vec![0, 1]).unwrap(), vec![21]);
+    }
+
+    #[test]
+    fn test_custom_extractor_error() {
+        #[derive(Debug, PartialEq)]
+        struct LookupFailed(u32);
+
+        impl fmt::Display for LookupFailed {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "lookup failed for key {}", self.0)
+            }
+        }
+
+        impl std::error::Error for LookupFailed {}
+
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.register_extractor(1, |state| {
+            Err(ExtractorError::custom(LookupFailed(
+                state.current_item as u32,
+            )))
+        });
+        let settings = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(3, settings));
+
+        let err = matcher.process_item(3).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::ExtractorCustom);
+        let MatcherError::ExtractorFailed { error: inner, .. } = &err else {
+            panic!("expected an extractor failure, got {:?}", err);
+        };
+        assert_eq!(inner.downcast_ref::<LookupFailed>(), Some(&LookupFailed(3)));
+
+        // The domain error is reachable through the source chain
+        let source = std::error::Error::source(&err).and_then(|e| e.source());
+        assert_eq!(source.unwrap().to_string(), "lookup failed for key 3");
+    }
+
+    // === Complex Pattern Tests ===
+
+    #[test]
+    fn test_mixed_pattern_types() {
+        let mut matcher = Matcher::<i32, TestContext>::new(10);
+
+        // Pattern: exact(1), range(5-10), predicate(even)
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::range(5, 10));
+        matcher.add_pattern(PatternElement::predicate(|x| *x % 2 == 0));
+
+        assert_eq!(matcher.process_item(1).unwrap(), None); // Match first
+        assert_eq!(matcher.process_item(7).unwrap(), None); // Match second
+        assert_eq!(matcher.process_item(8).unwrap(), Some(8)); // Match third, complete pattern
+    }
+
+    #[test]
+    fn test_pattern_mismatch_reset() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact(2));
+        matcher.add_pattern(PatternElement::exact(3));
+
+        assert_eq!(matcher.process_item(1).unwrap(), None); // Position 1
+        assert_eq!(matcher.process_item(5).unwrap(), None); // Mismatch, reset to 0
+        assert_eq!(matcher.current_position(), 0);
+
+        assert_eq!(matcher.process_item(1).unwrap(), None); // Position 1 again
+        assert_eq!(matcher.process_item(2).unwrap(), None); // Position 2
+        assert_eq!(matcher.process_item(3).unwrap(), Some(3)); // Complete pattern
+    }
+
+    #[test]
+    fn test_optional_elements() {
+        let mut matcher = Matcher::<i32, TestContext>::new(5);
+
+        // First element is required
+        matcher.add_pattern(PatternElement::exact(1));
+
+        // Second element is optional
+        let settings = ElementSettings {
+            optional: true,
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(2, settings));
+
+        // Third element is required
+        matcher.add_pattern(PatternElement::exact(3));
+
+        // Test with optional element present
+        assert_eq!(matcher.process_item(1).unwrap(), None);
+        assert_eq!(matcher.process_item(2).unwrap(), None);
+        assert_eq!(matcher.process_item(3).unwrap(), Some(3));
+
+        matcher.reset();
+
+        // Test with optional element missing
+        assert_eq!(matcher.process_item(1).unwrap(), None);
+        assert_eq!(matcher.process_item(3).unwrap(), Some(3)); // Should skip optional 2
+    }
+
+    // === Match Policy Tests ===
+
+    fn optional_tail_matcher(policy: MatchPolicy) -> Matcher<i32, ()> {
+        let mut matcher = Matcher::<i32, ()>::new(5);
+        matcher.set_pattern_settings(PatternSettings::default().match_policy(policy));
+        matcher.add_pattern(PatternElement::exact(1));
+        let settings = ElementSettings {
+            optional: true,
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(2, settings.clone()));
+        matcher.add_pattern(PatternElement::exact_with_settings(3, settings));
+        matcher
+    }
+
+    #[test]
+    fn test_first_match_policy() {
+        let mut matcher = optional_tail_matcher(MatchPolicy::FirstMatch);
+        assert_eq!(matcher.process_items(vec![1, 2, 3]).unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn test_longest_match_policy() {
+        let mut matcher = optional_tail_matcher(MatchPolicy::LongestMatch);
+        assert_eq!(matcher.process_items(vec![1, 2, 3]).unwrap(), vec![3]);
+
+        // The held completion is reported once the match cannot be extended
+        assert_eq!(matcher.process_item(1).unwrap(), None);
+        assert_eq!(matcher.process_item(2).unwrap(), None);
+        assert_eq!(matcher.process_item(9).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_all_matches_policy() {
+        let mut matcher = optional_tail_matcher(MatchPolicy::AllMatches);
+        assert_eq!(matcher.process_items(vec![1, 2, 3]).unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_consecutive_occurrences_under_each_policy() {
+        // The item that ends one match begins the next
+        for policy in [
+            MatchPolicy::FirstMatch,
+            MatchPolicy::LongestMatch,
+            MatchPolicy::AllMatches,
+        ] {
+            let mut matcher = Matcher::<&str, ()>::new(5);
+            matcher.set_pattern_settings(PatternSettings::default().match_policy(policy));
+            matcher.add_pattern(PatternElement::exact("ERR"));
+            let settings = ElementSettings {
+                optional: true,
+                ..Default::default()
+            };
+            matcher.add_pattern(PatternElement::exact_with_settings("RETRY", settings));
+
+            let mut found = matcher.process_items(vec!["ERR"; 4]).unwrap();
+            found.extend(matcher.finish().completed);
+            assert_eq!(found, vec!["ERR"; 4], "{:?}", policy);
+
+            let mut found = matcher
+                .process_items(vec!["ERR", "RETRY", "ERR", "ERR", "RETRY"])
+                .unwrap();
+            found.extend(matcher.finish().completed);
+            let expected = match policy {
+                MatchPolicy::FirstMatch => vec!["ERR", "ERR", "ERR"],
+                MatchPolicy::LongestMatch => vec!["RETRY", "ERR", "RETRY"],
+                MatchPolicy::AllMatches => vec!["ERR", "RETRY", "ERR", "ERR", "RETRY"],
+            };
+            assert_eq!(found, expected, "{:?}", policy);
+        }
+    }
+
+    #[test]
+    fn test_completion_extractor() {
+        let sum = |captures: &[Capture<i32>]| captures.iter().map(|capture| capture.item).sum();
+
+        let mut matcher = optional_tail_matcher(MatchPolicy::AllMatches);
+        matcher.set_completion_extractor(sum);
+        assert_eq!(matcher.process_items(vec![1, 2, 3]).unwrap(), vec![1, 3, 6]);
+
+        // A held completion sums only the items it covers
+        let mut matcher = optional_tail_matcher(MatchPolicy::LongestMatch);
+        matcher.set_completion_extractor(sum);
+        assert_eq!(matcher.process_items(vec![1, 2, 9]).unwrap(), vec![3]);
+        assert_eq!(matcher.process_items(vec![1, 2, 3]).unwrap(), vec![6]);
+
+        matcher.clear_completion_extractor();
+        assert_eq!(matcher.process_items(vec![1, 2, 3]).unwrap(), vec![3]);
+    }
+
+    // === Repeat Tests ===
+
+    fn repeat_settings(min: usize, max: usize, mode: RepeatMode) -> ElementSettings<()> {
+        ElementSettings {
+            min_repeat: min,
+            max_repeat: max,
+            repeat_mode: mode,
+            ..Default::default()
+        }
+    }
+
+    fn any_then_terminator(mode: RepeatMode) -> Matcher<i32, ()> {
+        let mut matcher = Matcher::<i32, ()>::new(20);
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::predicate_with_settings(
+            |_| true,
+            repeat_settings(1, 10, mode),
+        ));
+        matcher.add_pattern(PatternElement::exact(999));
+        matcher
+    }
+
+    #[test]
+    fn test_greedy_repeat_swallows_terminator() {
+        let mut matcher = any_then_terminator(RepeatMode::Greedy);
+        assert_eq!(
+            matcher.process_items(vec![1, 5, 6, 999]).unwrap(),
+            Vec::<i32>::new()
+        );
+        assert_eq!(matcher.current_position(), 1);
+    }
+
+    #[test]
+    fn test_lazy_repeat_stops_at_terminator() {
+        let mut matcher = any_then_terminator(RepeatMode::Lazy);
+        assert_eq!(
+            matcher.process_items(vec![1, 5, 6, 999]).unwrap(),
+            vec![999]
+        );
+    }
+
+    #[test]
+    fn test_repeat_bounds() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.add_pattern(PatternElement::exact_with_settings(
+            7,
+            repeat_settings(2, 3, RepeatMode::Greedy),
+        ));
+        matcher.add_pattern(PatternElement::exact(0));
+
+        // Too few repetitions
+        assert_eq!(matcher.process_item(7).unwrap(), None);
+        assert_eq!(matcher.current_position(), 0);
+        assert!(matcher.is_matching());
+        assert_eq!(matcher.process_item(0).unwrap(), None);
+        assert!(!matcher.is_matching());
+        // Within bounds
+        assert_eq!(matcher.process_items(vec![7, 7, 7, 0]).unwrap(), vec![0]);
+        // Too many repetitions
+        assert_eq!(
+            matcher.process_items(vec![7, 7, 7, 7, 0]).unwrap(),
+            Vec::<i32>::new()
+        );
+    }
+
+    // === Pair Predicate Tests ===
+
+    #[test]
+    fn test_pair_predicate_sees_unmatched_items() {
+        // A drop of more than 50 between adjacent readings, after a marker
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.add_pattern(PatternElement::exact(0));
+        matcher.add_pattern(PatternElement::predicate_with_settings(
+            |_| true,
+            ElementSettings {
+                min_repeat: 0,
+                max_repeat: usize::MAX,
+                repeat_mode: RepeatMode::Lazy,
+                ..ElementSettings::default()
+            },
+        ));
+        matcher.add_pattern(PatternElement::pair_predicate(
+            |previous: &i32, current: &i32| previous - current > 50,
+        ));
+
+        let results = matcher.process_items(vec![0, 100, 90, 20, 10]).unwrap();
+        assert_eq!(results, vec![20]);
+    }
+
+    #[test]
+    fn test_pair_predicate_needs_window() {
+        let mut matcher = Matcher::<i32, ()>::new(0);
+        matcher.add_pattern(PatternElement::pair_predicate(|_: &i32, _: &i32| true));
+        assert!(matcher.process_items(vec![1, 2, 3]).unwrap().is_empty());
+    }
+
+    // === Aggregate Tests ===
+
+    #[test]
+    fn test_aggregate_element() {
+        // Rolling average of the last 3 readings above 100
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.add_pattern(PatternElement::aggregate(3, Aggregate::Average, |avg| {
+            avg > 100.0
+        }));
+
+        let results = matcher
+            .process_items(vec![150, 150, 90, 60, 200, 120, -100])
+            .unwrap();
+        assert_eq!(results, vec![90, 200, 120]);
+    }
+
+    #[test]
+    fn test_aggregate_by_and_compute() {
+        assert_eq!(Aggregate::Sum.compute([1.0, 2.0, 3.0]), Some(6.0));
+        assert_eq!(Aggregate::Min.compute([3.0, -1.0, 2.0]), Some(-1.0));
+        assert_eq!(Aggregate::Max.compute(Vec::new()), None);
+
+        // Peak of the last two string lengths is at least 5
+        let mut matcher = Matcher::<&str, ()>::new(1);
+        matcher.add_pattern(PatternElement::aggregate_by(
+            2,
+            Aggregate::Max,
+            |item: &&str| item.len() as f64,
+            |max| max >= 5.0,
+        ));
+        let results = matcher
+            .process_items(vec!["abcde", "a", "b", "cd"])
+            .unwrap();
+        assert_eq!(results, vec!["a"]);
+    }
+
+    // === Run Tests ===
+
+    #[test]
+    fn test_run_element() {
+        let mut matcher = Matcher::<char, ()>::new(10);
+        matcher.set_reset_policy(ResetPolicy::RetryCurrentItem);
+        matcher.add_pattern(PatternElement::run(3));
+
+        let results = matcher
+            .process_items("aabbbcddddd".chars().collect())
+            .unwrap();
+        assert_eq!(results, vec!['b', 'd']);
+    }
+
+    #[test]
+    fn test_run_after_other_elements() {
+        // A start marker followed by any value repeated twice
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.add_pattern(PatternElement::exact(0));
+        matcher.add_pattern(PatternElement::run(2));
+
+        let results = matcher.process_items(vec![0, 0, 1, 0, 4, 4]).unwrap();
+        assert_eq!(results, vec![4]);
+    }
+
+    #[test]
+    fn test_monotonic_elements() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.set_reset_policy(ResetPolicy::RetryCurrentItem);
+        matcher.add_pattern(PatternElement::increasing(3));
+
+        // Equal neighbours are not strictly increasing
+        let results = matcher.process_items(vec![1, 2, 2, 3, 4, 1]).unwrap();
+        assert_eq!(results, vec![4]);
+
+        let mut matcher = Matcher::<f64, ()>::new(10);
+        matcher.set_reset_policy(ResetPolicy::RetryCurrentItem);
+        matcher.add_pattern(PatternElement::decreasing(2));
+        matcher.add_pattern(PatternElement::increasing(2));
+
+        // A dip followed by a recovery
+        let results = matcher.process_items(vec![5.0, 3.0, 4.0, 6.0]).unwrap();
+        assert_eq!(results, vec![6.0]);
+        assert_eq!(matcher.patterns()[0].to_string(), "Decreasing");
+    }
+
+    // === Rate Tests ===
+
+    #[test]
+    fn test_rate_element() {
+        // Three or more requests within 100ms is a burst
+        let mut matcher = Matcher::<&str, ()>::new(10);
+        matcher.add_pattern(PatternElement::rate(3, Duration::from_millis(100)));
+
+        let arrivals = [0, 50, 300, 320, 340, 360, 600];
+        let bursts: Vec<u64> = arrivals
+            .iter()
+            .filter(|ms| {
+                matcher
+                    .process_item_at("request", Duration::from_millis(**ms))
+                    .unwrap()
+                    .is_some()
+            })
+            .copied()
+            .collect();
+        assert_eq!(bursts, vec![340, 360]);
+
+        assert!(matches!(
+            PatternElement::<&str, ()>::rate(3, Duration::from_secs(1)).matches(&"request"),
+            Err(MatcherError::InvalidPattern(_))
+        ));
+    }
+
+    // === Range Variant Tests ===
+
+    #[test]
+    fn test_outside_element() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.add_pattern(PatternElement::outside(10, 90));
+
+        let results = matcher.process_items(vec![5, 10, 50, 90, 91]).unwrap();
+        assert_eq!(results, vec![5, 91]);
+        assert_eq!(matcher.patterns()[0].to_string(), "Outside(10..=90)");
+    }
+
+    #[test]
+    fn test_ranges_element() {
+        // Privileged or ephemeral ports
+        let mut matcher = Matcher::<u16, ()>::new(10);
+        matcher.add_pattern(PatternElement::ranges(vec![(0, 1023), (49152, 65535)]));
+
+        let results = matcher
+            .process_items(vec![22, 8080, 1023, 1024, 50000])
+            .unwrap();
+        assert_eq!(results, vec![22, 1023, 50000]);
+        assert_eq!(
+            matcher.patterns()[0].to_string(),
+            "Ranges([(0, 1023), (49152, 65535)])"
+        );
+    }
+
+    // === Member Tests ===
+
+    #[test]
+    fn test_member_element() {
+        let blocked = (0..1000).map(|id| id * 7);
+        let mut matcher = Matcher::<u32, ()>::new(10);
+        matcher.add_pattern(PatternElement::member(blocked));
+
+        let results = matcher.process_items(vec![5, 14, 6993, 6994]).unwrap();
+        assert_eq!(results, vec![14, 6993]);
+        assert_eq!(matcher.patterns()[0].to_string(), "Member(1000 values)");
+
+        // Clones keep the set
+        let mut cloned = Matcher::<u32, ()>::new(10);
+        cloned.add_pattern(matcher.patterns()[0].clone());
+        assert_eq!(cloned.process_item(21).unwrap(), Some(21));
+    }
+
+    // === Float Range Tests ===
+
+    #[test]
+    fn test_float_range_nan_policies() {
+        let readings = [1.0, f64::NAN, 5.0, -0.0, f64::INFINITY];
+
+        let mut matcher = Matcher::<f64, ()>::new(10);
+        matcher.add_pattern(PatternElement::float_range(0.0, 10.0, NanPolicy::Reject).unwrap());
+        let results = matcher.process_items(readings.to_vec()).unwrap();
+        assert_eq!(results, vec![1.0, 5.0, -0.0]);
+
+        let mut matcher = Matcher::<f64, ()>::new(10);
+        matcher.add_pattern(PatternElement::float_range(0.0, 10.0, NanPolicy::Accept).unwrap());
+        let results = matcher.process_items(readings.to_vec()).unwrap();
+        assert_eq!(results.len(), 4);
+        assert!(results[1].is_nan());
+
+        // Total ordering puts -0.0 below 0.0 and NaN above infinity
+        let mut matcher = Matcher::<f64, ()>::new(10);
+        matcher.add_pattern(
+            PatternElement::float_range(0.0, f64::INFINITY, NanPolicy::TotalOrder).unwrap(),
+        );
+        let results = matcher.process_items(readings.to_vec()).unwrap();
+        assert_eq!(results, vec![1.0, 5.0, f64::INFINITY]);
+    }
+
+    #[test]
+    fn test_invalid_float_range() {
+        assert!(matches!(
+            PatternElement::<f32, ()>::float_range(f32::NAN, 1.0, NanPolicy::Reject),
+            Err(MatcherError::InvalidPattern(_))
+        ));
+        assert!(matches!(
+            PatternElement::<f32, ()>::float_range(2.0, 1.0, NanPolicy::TotalOrder),
+            Err(MatcherError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_range_by() {
+        // Case-insensitive alphabetical range
+        let mut matcher = Matcher::<String, ()>::new(10);
+        matcher.add_pattern(PatternElement::range_by(
+            "b".to_string(),
+            "d".to_string(),
+            |item: &String, bound: &String| item.to_lowercase().partial_cmp(bound),
+        ));
+        let words = vec![
+            "Apple".to_string(),
+            "Cherry".to_string(),
+            "elder".to_string(),
+        ];
+        let results = matcher.process_items(words).unwrap();
+        assert_eq!(results, vec!["Cherry".to_string()]);
+
+        // Custom orderings are equal only to their clones
+        let range = matcher.patterns()[0].clone();
+        assert_eq!(range, matcher.patterns()[0]);
+        assert_ne!(
+            range,
+            PatternElement::range_by("b".to_string(), "d".to_string(), |item, bound| {
+                item.to_lowercase().partial_cmp(bound)
+            })
+        );
+    }
+
+    // === Lookahead Tests ===
+
+    #[test]
+    fn test_lookahead_completes_without_consuming() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::lookahead(PatternElement::exact(1)));
+
+        // Each peeked 1 completes the previous match and starts a new one
+        assert_eq!(matcher.process_items(vec![1, 1, 1]).unwrap(), vec![1, 1]);
+        assert_eq!(matcher.current_position(), 1);
+    }
+
+    #[test]
+    fn test_negative_lookahead() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::negative_lookahead(PatternElement::exact(2)));
+        matcher.add_pattern(PatternElement::predicate(|_| true));
+
+        assert_eq!(matcher.process_items(vec![1, 2, 9, 1, 3]).unwrap(), vec![3]);
+        assert_eq!(
+            PatternElement::<i32, ()>::negative_lookahead(PatternElement::exact(2)).to_string(),
+            "NegativeLookahead(Exact(2))"
+        );
+    }
+
+    // === Lookbehind Tests ===
+
+    #[test]
+    fn test_lookbehind_checks_earlier_item() {
+        let mut matcher = Matcher::<i32, ()>::new(5);
+        matcher.add_pattern(PatternElement::lookbehind(PatternElement::exact(0), 2));
+        matcher.add_pattern(PatternElement::exact(7));
+
+        // Only a 7 two positions after a 0 matches
+        assert_eq!(
+            matcher.process_items(vec![7, 0, 1, 7, 0, 7]).unwrap(),
+            vec![7]
+        );
+    }
+
+    #[test]
+    fn test_back_reference_respects_window() {
+        let build = |window_size| {
+            let mut matcher = Matcher::<i32, ()>::new(window_size);
+            matcher.add_pattern(PatternElement::back_reference(3));
+            matcher
+        };
+
+        let items = vec![4, 5, 6, 4, 9];
+        assert_eq!(build(3).process_items(items.clone()).unwrap(), vec![4]);
+        // The referenced item has already left a smaller window
+        assert_eq!(build(2).process_items(items).unwrap(), Vec::<i32>::new());
+        assert!(matches!(
+            PatternElement::<i32, ()>::back_reference(1).matches(&1),
+            Err(MatcherError::InvalidPattern(_))
+        ));
+    }
+
+    // === Guard Tests ===
+
+    #[test]
+    fn test_guard_sees_captures() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.add_pattern(PatternElement::predicate(|_| true));
+        matcher.add_pattern(PatternElement::predicate(|_| true));
+        matcher.add_pattern(PatternElement::guard(|captures, item| {
+            *item > captures[0].item
+        }));
+
+        assert_eq!(
+            matcher.process_items(vec![5, 1, 3, 2, 0, 9]).unwrap(),
+            vec![9]
+        );
+    }
+
+    #[test]
+    fn test_guard_replayed_with_fallback() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.set_reset_policy(ResetPolicy::FallbackByPrefix);
+        matcher.add_pattern(PatternElement::predicate(|_| true));
+        matcher.add_pattern(PatternElement::guard(|captures, item| {
+            *item == captures[0].item + 1
+        }));
+        matcher.add_pattern(PatternElement::exact(0));
+
+        // After 1, 2 fails on 3, the attempt resumes from 2, 3
+        assert_eq!(matcher.process_items(vec![1, 2, 3, 0]).unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn test_delta_element() {
+        // Each price at least 10% above the last matched one
+        let mut matcher = Matcher::<f64, ()>::new(10);
+        matcher.set_reset_policy(ResetPolicy::RetryCurrentItem);
+        matcher.add_pattern(PatternElement::predicate(|_| true));
+        let rising = ElementSettings {
+            min_repeat: 2,
+            max_repeat: 2,
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::delta_with_settings(
+            |previous: &f64, current: &f64| *current >= previous * 1.1,
+            rising,
+        ));
+
+        let results = matcher
+            .process_items(vec![100.0, 105.0, 120.0, 140.0, 100.0])
+            .unwrap();
+        assert_eq!(results, vec![140.0]);
+
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.add_pattern(PatternElement::delta(|previous: &i32, current: &i32| {
+            current > previous
+        }));
+        assert_eq!(matcher.process_item(1).unwrap(), None);
+    }
+
+    // === Borrowed Item Tests ===
+
+    /// A payload type that counts how often it is cloned.
+    #[derive(Debug)]
+    struct Counted(String, Arc<std::sync::atomic::AtomicUsize>);
+
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            self.1.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Self(self.0.clone(), Arc::clone(&self.1))
+        }
+    }
+
+    impl PartialEq for Counted {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    #[test]
+    fn test_process_item_ref_defers_clone() {
+        let clones = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let line = |text: &str| Counted(text.to_string(), Arc::clone(&clones));
+
+        let mut matcher = Matcher::<Counted, ()>::new(0);
+        matcher.add_pattern(PatternElement::predicate(|line: &Counted| {
+            line.0.starts_with("ERROR")
+        }));
+        matcher.add_pattern(PatternElement::predicate(|line: &Counted| {
+            line.0.starts_with("WARN")
+        }));
+
+        let lines = [
+            line("INFO a"),
+            line("INFO b"),
+            line("ERROR c"),
+            line("WARN d"),
+        ];
+        let count = || clones.load(std::sync::atomic::Ordering::Relaxed);
+
+        // Items that cannot start a match are never cloned
+        let before = count();
+        assert_eq!(matcher.process_item_ref(&lines[0]).unwrap(), None);
+        assert_eq!(matcher.process_item_ref(&lines[1]).unwrap(), None);
+        assert_eq!(count(), before);
+
+        assert_eq!(matcher.process_item_ref(&lines[2]).unwrap(), None);
+        assert_eq!(
+            matcher.process_item_ref(&lines[3]).unwrap(),
+            Some(line("WARN d"))
+        );
+        assert!(count() > before);
+    }
+
+    #[test]
+    fn test_process_item_ref_evaluates_first_element_once() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let mut matcher = Matcher::<String, ()>::new(0);
+        matcher.add_pattern(PatternElement::predicate(move |line: &String| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            line.starts_with("ERROR")
+        }));
+        matcher.add_pattern(PatternElement::exact("WARN".to_string()));
+        let count = || calls.load(std::sync::atomic::Ordering::Relaxed);
+
+        assert_eq!(matcher.process_item_ref(&"INFO".to_string()).unwrap(), None);
+        assert_eq!(count(), 1);
+        assert_eq!(
+            matcher.process_item_ref(&"ERROR".to_string()).unwrap(),
+            None
+        );
+        assert_eq!(count(), 2);
+        assert_eq!(
+            matcher.process_item_ref(&"WARN".to_string()).unwrap(),
+            Some("WARN".to_string())
+        );
+        assert_eq!(count(), 2);
+    }
+
+    #[test]
+    fn test_extractor_does_not_clone_item() {
+        let clones = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut matcher = Matcher::<Counted, ()>::new(0);
+        matcher.register_extractor(1, |state| {
+            assert!(state.current_item.0.starts_with("GET"));
+            Ok(ExtractorAction::Continue)
+        });
+        let settings = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::predicate_with_settings(
+            |line: &Counted| line.0.starts_with("GET"),
+            settings,
+        ));
+        matcher.add_pattern(PatternElement::predicate(|_: &Counted| true));
+
+        let line = Counted("GET /".to_string(), Arc::clone(&clones));
+        assert_eq!(matcher.process_item(line).unwrap(), None);
+        assert_eq!(clones.load(std::sync::atomic::Ordering::Relaxed), 0);
+        assert_eq!(matcher.stats().extractor_invocations, 1);
+    }
+
+    /// A payload type that deliberately does not implement `Clone`.
+    #[derive(Debug, PartialEq, PartialOrd)]
+    struct LogLine(String);
+
+    #[test]
+    fn test_process_slice_by_reference() {
+        let lines = vec![
+            LogLine("INFO start".to_string()),
+            LogLine("ERROR disk".to_string()),
+            LogLine("WARN retry".to_string()),
+            LogLine("ERROR disk".to_string()),
+            LogLine("ERROR net".to_string()),
+        ];
+
+        let mut matcher = Matcher::<&LogLine, ()>::new(10);
+        matcher.add_pattern(PatternElement::predicate(|line: &&LogLine| {
+            line.0.starts_with("ERROR")
+        }));
+        matcher.add_pattern(PatternElement::predicate(|_| true));
+        matcher.add_pattern(PatternElement::back_reference(2));
+
+        assert_eq!(matcher.process_slice(&lines).unwrap(), vec![3]);
+
+        // Single borrowed items go through process_item unchanged
+        matcher.reset();
+        matcher.process_item(&lines[1]).unwrap();
+        matcher.process_item(&lines[2]).unwrap();
+        let completed = matcher.process_item(&lines[3]).unwrap();
+        assert!(std::ptr::eq(completed.unwrap(), &lines[3]));
+    }
+
+    #[test]
+    fn test_unordered_item_type() {
+        #[derive(Debug, Clone, PartialEq)]
+        enum Light {
+            Red,
+            Amber,
+            Green,
+        }
+
+        let mut matcher = Matcher::<Light, ()>::new(10);
+        matcher.add_pattern(PatternElement::exact(Light::Green));
+        matcher.add_pattern(PatternElement::predicate(|light| *light != Light::Green));
+        matcher.add_pattern(PatternElement::exact(Light::Red));
+
+        let lights = vec![Light::Green, Light::Amber, Light::Red];
+        assert_eq!(matcher.process_items(lights).unwrap(), vec![Light::Red]);
+    }
+
+    #[test]
+    fn test_custom_element() {
+        struct Within {
+            center: (f64, f64),
+            radius: f64,
+        }
+
+        impl Matchable<(f64, f64)> for Within {
+            fn matches(&self, item: &(f64, f64)) -> Result<bool, MatcherError> {
+                let (dx, dy) = (item.0 - self.center.0, item.1 - self.center.1);
+                Ok((dx * dx + dy * dy).sqrt() <= self.radius)
+            }
+
+            fn describe(&self) -> String {
+                format!("within {} of {:?}", self.radius, self.center)
+            }
+        }
+
+        let fence = || Within {
+            center: (0.0, 0.0),
+            radius: 1.0,
+        };
+        let inside = ElementSettings {
+            max_repeat: usize::MAX,
+            ..Default::default()
+        };
+        let mut matcher = Matcher::<(f64, f64), ()>::new(10);
+        matcher.add_pattern(PatternElement::custom_with_settings(fence(), inside));
+        matcher.add_pattern(PatternElement::negative_lookahead(PatternElement::custom(
+            fence(),
+        )));
+
+        // Report the last position inside the fence before leaving it
+        let track = vec![(2.0, 2.0), (0.5, 0.0), (0.2, 0.1), (3.0, 0.0)];
+        assert_eq!(matcher.process_items(track).unwrap(), vec![(0.2, 0.1)]);
+        assert_eq!(
+            matcher.patterns()[0].to_string(),
+            "Custom(within 1 of (0.0, 0.0))"
+        );
+    }
+
+    // === Observer Tests ===
+
+    #[derive(Clone, Default)]
+    struct RecordingObserver {
+        events: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl MatcherObserver<i32> for RecordingObserver {
+        fn on_match(&mut self, completed: &Capture<i32>) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("match {}", completed.item));
+        }
+
+        fn on_mismatch(&mut self, position: usize, item: &i32) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("mismatch {} at {}", item, position));
+        }
+
+        fn on_reset(&mut self, position: usize, captured: &[Capture<i32>]) {
+            self.events.lock().unwrap().push(format!(
+                "reset at {} after {}",
+                position,
+                captured.len()
+            ));
+        }
+
+        fn on_partial(&mut self, position: usize, capture: &Capture<i32>) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("partial {} at {}", capture.item, position));
+        }
+    }
+
+    #[test]
+    fn test_observer_lifecycle_events() {
+        let observer = RecordingObserver::default();
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.add_observer(observer.clone());
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact(2));
+
+        matcher.process_items(vec![1, 3, 1, 2]).unwrap();
+        assert_eq!(
+            *observer.events.lock().unwrap(),
+            vec![
+                "partial 1 at 0",
+                "mismatch 3 at 1",
+                "reset at 1 after 1",
+                "partial 1 at 0",
+                "match 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_element_callbacks() {
+        use std::sync::Mutex;
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::range(5, 9));
+        let sink = Arc::clone(&seen);
+        matcher
+            .on_element_match(1, move |item, index| {
+                sink.lock().unwrap().push((*item, index))
+            })
+            .unwrap();
+        assert!(matcher.on_element_match(2, |_, _| {}).is_err());
+
+        assert_eq!(
+            matcher.process_items(vec![1, 7, 6, 1, 5]).unwrap(),
+            vec![7, 5]
+        );
+        assert_eq!(*seen.lock().unwrap(), vec![(7, 1), (5, 4)]);
+
+        // The callback moves with its element
+        matcher.insert_pattern(0, PatternElement::exact(0)).unwrap();
+        matcher.process_items(vec![0, 1, 8]).unwrap();
+        assert_eq!(seen.lock().unwrap().last(), Some(&(8, 7)));
+    }
+
+    // === Pattern Management Tests ===
+
+    #[test]
+    fn test_remove_and_replace_pattern() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact(2));
+        matcher.add_pattern(PatternElement::exact(3));
+
+        // Reconfiguring abandons the attempt in progress
+        matcher.process_item(1).unwrap();
+        let removed = matcher.remove_pattern(1).unwrap();
+        assert_eq!(removed.to_string(), "Exact(2)");
+        assert_eq!(matcher.current_position(), 0);
+        assert_eq!(matcher.process_items(vec![1, 3]).unwrap(), vec![3]);
+
+        let old = matcher
+            .replace_pattern(1, PatternElement::exact(4))
+            .unwrap();
+        assert_eq!(old.to_string(), "Exact(3)");
+        assert_eq!(matcher.process_items(vec![1, 4]).unwrap(), vec![4]);
+
+        assert!(matches!(
+            matcher.remove_pattern(2),
+            Err(MatcherError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_clear_patterns_keeps_extractors() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.register_extractor(1, |state| {
+            Ok(ExtractorAction::Extract(state.current_item * 10))
+        });
+        matcher.add_pattern(PatternElement::exact(1));
+
+        matcher.clear_patterns();
+        assert_eq!(matcher.pattern_count(), 0);
+        assert!(matches!(
+            matcher.process_item(1),
+            Err(MatcherError::NoPatterns)
+        ));
+
+        let settings = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(2, settings));
+        assert_eq!(matcher.process_item(2).unwrap(), Some(20));
+    }
+
+    #[test]
+    fn test_insert_and_swap_patterns() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact(3));
+
+        matcher.insert_pattern(1, PatternElement::exact(2)).unwrap();
+        assert_eq!(matcher.process_items(vec![1, 2, 3]).unwrap(), vec![3]);
+
+        matcher.swap_patterns(0, 2).unwrap();
+        assert_eq!(matcher.process_items(vec![3, 2, 1]).unwrap(), vec![1]);
+
+        // Appending at the end is allowed, past it is not
+        matcher.insert_pattern(3, PatternElement::exact(4)).unwrap();
+        assert!(matches!(
+            matcher.insert_pattern(5, PatternElement::exact(5)),
+            Err(MatcherError::InvalidPattern(_))
+        ));
+        assert!(matches!(
+            matcher.swap_patterns(0, 4),
+            Err(MatcherError::InvalidPattern(_))
+        ));
     }
-}
 
-impl<T, Context> fmt::Debug for Matcher<T, Context>
-where
-    T: Clone + PartialEq + fmt::Debug + std::cmp::PartialOrd,
-    Context: Clone + fmt::Debug,
-{
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Matcher")
-            .field("pattern_count", &self.patterns.len())
-            .field("current_position", &self.current_position)
-            .field("total_processed", &self.total_processed)
-            .field("window_size", &self.window_size)
-            .field("extractor_count", &self.extractors.len())
-            .field("has_context", &self.context.is_some())
-            .finish()
+    // === Statistics Tests ===
+
+    #[test]
+    fn test_stats_counts_activity() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.register_extractor(1, |_| Ok(ExtractorAction::Continue));
+        let settings = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(1, settings));
+        matcher.add_pattern(PatternElement::exact(2));
+
+        matcher.process_items(vec![1, 3, 1, 2, 5]).unwrap();
+        let stats = matcher.stats();
+        assert_eq!(stats.items_processed, 5);
+        assert_eq!(stats.matches_completed, 1);
+        assert_eq!(stats.mismatch_resets, 1);
+        assert_eq!(stats.extractor_invocations, 2);
+        assert_eq!(stats.pattern_hits, vec![2, 1]);
+
+        matcher.reset_stats();
+        assert_eq!(*matcher.stats(), MatchStats::default());
     }
-}
 
-impl<T, Context> Default for Matcher<T, Context>
-where
-    T: Clone + PartialEq + fmt::Debug + std::cmp::PartialOrd,
-    Context: Clone + fmt::Debug,
-{
-    fn default() -> Self {
-        Self::new(10)
+    #[test]
+    fn test_profiling() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.register_extractor(1, |_| Ok(ExtractorAction::Continue));
+        let settings = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(1, settings));
+        matcher.add_pattern(PatternElement::predicate(|item: &i32| {
+            std::thread::sleep(Duration::from_millis(2));
+            *item == 2
+        }));
+        assert_eq!(matcher.profile_report(), None);
+
+        matcher.set_profiling(true);
+        matcher.process_items(vec![1, 3, 1, 2, 5]).unwrap();
+        let report = matcher.profile_report().unwrap();
+        let evaluations: Vec<u64> = report
+            .elements
+            .iter()
+            .map(|entry| entry.evaluations)
+            .collect();
+        assert_eq!(evaluations, vec![3, 2]);
+        assert!(report.elements[1].total_time >= Duration::from_millis(4));
+        assert_eq!(report.slowest_element(), Some(1));
+        assert_eq!(report.extractors[&1].evaluations, 2);
+
+        matcher.set_profiling(false);
+        assert!(!matcher.is_profiling());
+        assert_eq!(matcher.profile_report(), None);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::collections::HashMap;
+    // === Reset Policy Tests ===
 
-    #[derive(Debug, Clone, PartialEq)]
-    struct TestContext {
-        name: String,
-        value: i32,
-        captured_values: Vec<i32>,
-        counters: HashMap<String, usize>,
+    fn reset_policy_matcher(policy: ResetPolicy, pattern: &[i32]) -> Matcher<i32, ()> {
+        let mut matcher = Matcher::<i32, ()>::new(3);
+        matcher.set_reset_policy(policy);
+        for value in pattern {
+            matcher.add_pattern(PatternElement::exact(*value));
+        }
+        matcher
     }
 
-    impl Default for TestContext {
-        fn default() -> Self {
-            Self {
-                name: "test".to_string(),
-                value: 0,
-                captured_values: Vec::new(),
-                counters: HashMap::new(),
+    #[test]
+    fn test_reset_to_start_discards_failing_item() {
+        let mut matcher = reset_policy_matcher(ResetPolicy::ResetToStart, &[1, 2]);
+        assert_eq!(
+            matcher.process_items(vec![1, 1, 2]).unwrap(),
+            Vec::<i32>::new()
+        );
+    }
+
+    #[test]
+    fn test_retry_current_item() {
+        let mut matcher = reset_policy_matcher(ResetPolicy::RetryCurrentItem, &[1, 2]);
+        assert_eq!(matcher.process_items(vec![1, 1, 2]).unwrap(), vec![2]);
+    }
+
+    #[test]
+    fn test_fallback_by_prefix_finds_overlapping_match() {
+        // Retrying only the current item misses the overlap in 1 1 1 2
+        let mut retry = reset_policy_matcher(ResetPolicy::RetryCurrentItem, &[1, 1, 2]);
+        assert_eq!(
+            retry.process_items(vec![1, 1, 1, 2]).unwrap(),
+            Vec::<i32>::new()
+        );
+
+        let mut fallback = reset_policy_matcher(ResetPolicy::FallbackByPrefix, &[1, 1, 2]);
+        assert_eq!(fallback.process_items(vec![1, 1, 1, 2]).unwrap(), vec![2]);
+    }
+
+    #[test]
+    fn test_drop_window_skips_rest_of_window() {
+        let mut matcher = reset_policy_matcher(ResetPolicy::DropWindow, &[1, 2]);
+
+        // Attempt starts at index 0 and fails; indices 1 and 2 are skipped
+        assert_eq!(
+            matcher.process_items(vec![1, 3, 1, 2]).unwrap(),
+            Vec::<i32>::new()
+        );
+        assert_eq!(matcher.process_items(vec![1, 2]).unwrap(), vec![2]);
+    }
+
+    #[test]
+    fn test_pattern_reset_policy_overrides_matcher_default() {
+        let mut matcher = reset_policy_matcher(ResetPolicy::ResetToStart, &[1, 2]);
+        matcher.set_pattern_settings(
+            PatternSettings::default().reset_policy(ResetPolicy::RetryCurrentItem),
+        );
+        assert_eq!(matcher.reset_policy(), ResetPolicy::RetryCurrentItem);
+        assert_eq!(matcher.process_items(vec![1, 1, 2]).unwrap(), vec![2]);
+    }
+
+    // === Correlation Tests ===
+
+    #[test]
+    fn test_pattern_after_other_pattern() {
+        let mut recon = Matcher::<&str, ()>::new(10);
+        recon.set_name("recon");
+        recon.add_pattern(PatternElement::exact("scan"));
+
+        // An exploit only counts within 3 items of a completed scan
+        let mut exploit = Matcher::<&str, ()>::new(10);
+        exploit.set_name("exploit");
+        exploit
+            .set_pattern_settings(PatternSettings::default().after("recon", StreamOffset::Item(3)));
+        exploit.add_pattern(PatternElement::exact("exploit"));
+
+        let events = [
+            "exploit", "scan", "noise", "exploit", "noise", "noise", "noise", "exploit",
+        ];
+        let mut alerts = Vec::new();
+        for (index, event) in events.iter().enumerate() {
+            if recon.process_item(*event).unwrap().is_some() {
+                let (completed, at) = recon.last_completion("recon").unwrap();
+                exploit.record_completion("recon", completed, at);
+            }
+            if exploit.process_item(*event).unwrap().is_some() {
+                alerts.push(index);
             }
         }
+        assert_eq!(alerts, vec![3]);
+        assert_eq!(
+            exploit.last_completion("exploit").map(|(index, _)| index),
+            Some(3)
+        );
     }
 
-    // === Basic Pattern Matching Tests ===
+    #[test]
+    fn test_correlation_within_time() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.set_pattern_settings(
+            PatternSettings::default().after("login", StreamOffset::Time(Duration::from_secs(5))),
+        );
+        matcher.add_pattern(PatternElement::exact(1));
+
+        matcher.record_completion("login", 0, Duration::from_secs(10));
+        let at = |secs| Duration::from_secs(secs);
+        assert_eq!(matcher.process_item_at(1, at(11)).unwrap(), Some(1));
+        assert_eq!(matcher.process_item_at(1, at(16)).unwrap(), None);
+
+        matcher.reset();
+        assert_eq!(matcher.last_completion("login"), None);
+    }
+
+    // === Backtracking Tests ===
+
+    fn optional_then_required(engine: Engine) -> Matcher<i32, ()> {
+        let mut matcher = Matcher::new(10);
+        matcher.set_pattern_settings(PatternSettings::default().engine(engine));
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact_with_settings(
+            2,
+            ElementSettings {
+                optional: true,
+                ..Default::default()
+            },
+        ));
+        matcher.add_pattern(PatternElement::exact(2));
+        matcher
+    }
 
     #[test]
-    fn test_exact_match_simple() {
-        let mut matcher = Matcher::<i32, ()>::new(5);
-        matcher.add_pattern(PatternElement::exact(42));
+    fn test_backtracking_gives_optional_item_back() {
+        let mut greedy = optional_then_required(Engine::Greedy);
+        assert!(greedy.process_items(vec![1, 2]).unwrap().is_empty());
 
-        assert_eq!(matcher.process_item(41).unwrap(), None);
-        assert_eq!(matcher.process_item(42).unwrap(), Some(42));
+        let mut backtracking = optional_then_required(Engine::Backtracking);
+        assert_eq!(backtracking.process_items(vec![1, 2]).unwrap(), vec![2]);
+        assert_eq!(backtracking.process_items(vec![1, 2, 2]).unwrap(), vec![2]);
     }
 
     #[test]
-    fn test_exact_match_sequence() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
+    fn test_backtracking_shortens_greedy_repeat() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.set_pattern_settings(PatternSettings::default().engine(Engine::Backtracking));
+        matcher.add_pattern(PatternElement::exact_with_settings(
+            1,
+            ElementSettings {
+                max_repeat: 3,
+                ..Default::default()
+            },
+        ));
         matcher.add_pattern(PatternElement::exact(1));
         matcher.add_pattern(PatternElement::exact(2));
-        matcher.add_pattern(PatternElement::exact(3));
 
+        // The repeated element first takes both ones, leaving none for the second
         assert_eq!(matcher.process_item(1).unwrap(), None);
-        assert_eq!(matcher.process_item(2).unwrap(), None);
-        assert_eq!(matcher.process_item(3).unwrap(), Some(3));
+        assert_eq!(matcher.process_item(1).unwrap(), None);
+        assert_eq!(matcher.process_item(2).unwrap(), Some(2));
     }
 
+    // === Timing Tests ===
+
     #[test]
-    fn test_exact_match_with_settings() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
+    fn test_extractor_sees_timestamps() {
+        let mut matcher = Matcher::<i32, ()>::new(5);
+
+        // Report the latency between request and response in seconds
+        matcher.register_extractor(1, |state| {
+            let latency = state.timestamp - state.start_timestamp;
+            Ok(ExtractorAction::Extract(latency.as_secs() as i32))
+        });
+        let settings = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact_with_settings(2, settings));
 
-        let mut settings = ElementSettings::default();
-        settings.optional = false;
-        settings.max_retries = 2;
+        matcher.process_item_at(1, Duration::from_secs(10)).unwrap();
+        assert_eq!(
+            matcher.process_item_at(2, Duration::from_secs(17)).unwrap(),
+            Some(7)
+        );
+    }
 
-        matcher.add_pattern(PatternElement::exact_with_settings(42, settings));
+    #[test]
+    fn test_max_gap_between_elements() {
+        // Down, forward, punch, each within 200ms of the previous input
+        let quick = ElementSettings {
+            max_gap: Some(StreamOffset::Time(Duration::from_millis(200))),
+            ..Default::default()
+        };
+        let mut matcher = Matcher::<char, ()>::new(5);
+        matcher.set_reset_policy(ResetPolicy::RetryCurrentItem);
+        matcher.add_pattern(PatternElement::exact('D'));
+        matcher.add_pattern(PatternElement::exact_with_settings('F', quick.clone()));
+        matcher.add_pattern(PatternElement::exact_with_settings('P', quick));
+
+        let inputs = [
+            ('D', 0),
+            ('F', 150),
+            ('P', 400),
+            ('D', 500),
+            ('F', 650),
+            ('P', 800),
+        ];
+        let results: Vec<u64> = inputs
+            .iter()
+            .filter_map(|&(input, at)| {
+                matcher
+                    .process_item_at(input, Duration::from_millis(at))
+                    .unwrap()
+                    .map(|_| at)
+            })
+            .collect();
+        assert_eq!(results, vec![800]);
+    }
 
-        assert_eq!(matcher.process_item(42).unwrap(), Some(42));
+    #[test]
+    fn test_collect_patterns() {
+        let mut matcher: Matcher<i32, ()> = (1..=3).map(PatternElement::exact).collect();
+        assert_eq!(matcher.window_size(), 10);
+        assert_eq!(matcher.pattern_count(), 3);
+
+        matcher.extend([4, 5].map(PatternElement::exact));
+        assert_eq!(matcher.process_items(vec![1, 2, 3, 4, 5]).unwrap(), vec![5]);
     }
 
     #[test]
-    fn test_predicate_match() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
-        matcher.add_pattern(PatternElement::predicate(|x| *x > 0));
-        matcher.add_pattern(PatternElement::predicate(|x| *x < 10));
+    fn test_pattern_equality_and_hashing() {
+        use std::collections::HashSet;
 
-        assert_eq!(matcher.process_item(5).unwrap(), None);
-        assert_eq!(matcher.process_item(3).unwrap(), Some(3));
+        let optional = ElementSettings::<()> {
+            optional: true,
+            ..Default::default()
+        };
+        let rules = [
+            PatternElement::<i32, ()>::range(1, 5),
+            PatternElement::range(1, 5),
+            PatternElement::range_with_settings(1, 5, optional),
+            PatternElement::outside(1, 5),
+            PatternElement::group(vec![
+                PatternElement::exact(1),
+                PatternElement::member([2, 3]),
+            ])
+            .unwrap(),
+            PatternElement::group(vec![
+                PatternElement::exact(1),
+                PatternElement::member([3, 2]),
+            ])
+            .unwrap(),
+        ];
+        // Settings left unset compare equal to default settings
+        assert_eq!(
+            PatternElement::<i32, ()>::exact(1),
+            PatternElement::exact_with_settings(1, ElementSettings::default())
+        );
+        // A nested matcher's interior mutability does not take part in hashing
+        #[allow(clippy::mutable_key_type)]
+        let distinct: HashSet<_> = rules.iter().cloned().collect();
+        assert_eq!(distinct.len(), 4);
+
+        // Closures are only equal to themselves
+        let even = PatternElement::<i32, ()>::predicate(|item: &i32| item % 2 == 0);
+        assert_eq!(even, even.clone());
+        assert_ne!(even, PatternElement::predicate(|item: &i32| item % 2 == 0));
+
+        // Float ranges differ by how they treat NaN
+        let reject = PatternElement::<f64, ()>::float_range(0.0, 1.0, NanPolicy::Reject).unwrap();
+        let accept = PatternElement::<f64, ()>::float_range(0.0, 1.0, NanPolicy::Accept).unwrap();
+        assert_eq!(reject, reject.clone());
+        assert_ne!(reject, accept);
+        assert_eq!(reject, PatternElement::range(0.0, 1.0));
+
+        // Nested matchers also compare by their pattern settings
+        let nested = |selection| {
+            let mut sub = Matcher::<i32, ()>::new(5);
+            sub.set_pattern_settings(PatternSettings::default().selection(selection));
+            sub.add_pattern(PatternElement::exact(1));
+            PatternElement::matcher(sub)
+        };
+        assert_eq!(nested(Selection::Strict), nested(Selection::Strict));
+        assert_ne!(
+            nested(Selection::Strict),
+            nested(Selection::SkipTillNextMatch)
+        );
     }
 
     #[test]
-    fn test_predicate_with_settings() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
+    fn test_expected_next() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        let optional = ElementSettings {
+            optional: true,
+            ..Default::default()
+        };
+        let repeated = ElementSettings {
+            max_repeat: 2,
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(1, repeated));
+        matcher.add_pattern(PatternElement::range_with_settings(5, 9, optional));
+        matcher.add_pattern(PatternElement::exact(3));
 
-        let mut settings = ElementSettings::default();
-        settings.timeout_ms = Some(1000);
+        let expected = matcher.expected_next();
+        assert_eq!(expected.len(), 1);
+        assert_eq!(expected[0].kind, "Exact");
+        assert_eq!(expected[0].description, "Exact(1)");
+        assert!(!expected[0].optional);
 
-        matcher.add_pattern(PatternElement::predicate_with_settings(
-            |x| *x % 2 == 0,
-            settings,
-        ));
+        matcher.process_item(1).unwrap();
+        let waiting: Vec<_> = matcher
+            .expected_next()
+            .into_iter()
+            .map(|element| {
+                (
+                    element.position,
+                    element.kind,
+                    element.repeat,
+                    element.optional,
+                )
+            })
+            .collect();
+        assert_eq!(
+            waiting,
+            vec![
+                (0, "Exact", 1, true),
+                (1, "Range", 0, true),
+                (2, "Exact", 0, false)
+            ]
+        );
 
-        assert_eq!(matcher.process_item(4).unwrap(), Some(4));
-        assert_eq!(matcher.process_item(3).unwrap(), None);
+        // An element that has matched its maximum is no longer expected
+        matcher.process_item(1).unwrap();
+        assert_eq!(matcher.expected_next()[0].position, 1);
     }
 
     #[test]
-    fn test_range_match() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
-        matcher.add_pattern(PatternElement::range(1, 5));
-        matcher.add_pattern(PatternElement::range(6, 10));
+    fn test_optional_group() {
+        let build = |engine| {
+            let mut matcher = Matcher::<&str, ()>::new(10);
+            matcher.set_pattern_settings(PatternSettings::default().engine(engine));
+            matcher.add_pattern(PatternElement::exact("header"));
+            matcher.add_pattern(
+                PatternElement::optional_group(vec![
+                    PatternElement::exact("ext"),
+                    PatternElement::predicate(|field: &&str| field.starts_with("len=")),
+                    PatternElement::exact("ext-end"),
+                ])
+                .unwrap(),
+            );
+            matcher.add_pattern(PatternElement::exact("payload"));
+            matcher
+        };
 
-        assert_eq!(matcher.process_item(3).unwrap(), None);
-        assert_eq!(matcher.process_item(8).unwrap(), Some(8));
+        for engine in [Engine::Greedy, Engine::Backtracking] {
+            let mut matcher = build(engine);
+            // The block is either present in full or absent
+            for frame in [
+                vec!["header", "payload"],
+                vec!["header", "ext", "len=3", "ext-end", "payload"],
+            ] {
+                assert_eq!(matcher.process_items(frame).unwrap(), vec!["payload"]);
+            }
+            let partial = vec!["header", "ext", "len=3", "payload"];
+            assert!(matcher.process_items(partial).unwrap().is_empty());
+        }
+
+        let repeated =
+            PatternElement::<&str, ()>::group(vec![PatternElement::exact_with_settings(
+                "ext",
+                ElementSettings {
+                    max_repeat: 2,
+                    ..ElementSettings::default()
+                },
+            )]);
+        assert!(matches!(repeated, Err(MatcherError::InvalidPattern(_))));
     }
 
     #[test]
-    fn test_range_with_settings() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
+    fn test_one_or_more_sees_its_run() {
+        // Readings above 50 that stay within 10 of the run's mean, then a drop
+        let mut matcher = Matcher::<f64, ()>::new(10);
+        matcher.set_reset_policy(ResetPolicy::RetryCurrentItem);
+        matcher.add_pattern(PatternElement::one_or_more(
+            |run: &[Capture<f64>], reading: &f64| {
+                let mean = run.iter().map(|capture| capture.item).sum::<f64>() / run.len() as f64;
+                *reading > 50.0 && (run.is_empty() || (reading - mean).abs() <= 10.0)
+            },
+        ));
+        matcher.add_pattern(PatternElement::predicate(|reading: &f64| *reading < 20.0));
 
-        let mut settings = ElementSettings::default();
-        settings.optional = true;
+        let readings = [10.0, 60.0, 65.0, 58.0, 90.0, 5.0, 70.0, 15.0];
+        let mut spans = Vec::new();
+        for reading in readings {
+            for report in matcher.process_item_reports(reading).unwrap() {
+                spans.push(report.span);
+            }
+        }
+        // 90 breaks the first run before any drop and begins a run of its own
+        assert_eq!(spans, vec![4..6, 6..8]);
+    }
 
-        matcher.add_pattern(PatternElement::range_with_settings(10, 20, settings));
+    #[test]
+    fn test_until_captures_between_markers() {
+        let mut matcher = Matcher::<&str, ()>::new(10);
+        matcher.set_reset_policy(ResetPolicy::RetryCurrentItem);
+        matcher.add_pattern(PatternElement::exact("START"));
+        matcher.add_pattern(PatternElement::until(PatternElement::exact("END"), 3));
+
+        let items = [
+            "START", "a", "b", "END", "x", "START", "END", "START", "a", "b", "c", "d", "END",
+        ];
+        let mut spans = Vec::new();
+        for item in items {
+            for report in matcher.process_item_reports(item).unwrap() {
+                spans.push(report.span);
+            }
+        }
+        // The last stretch runs past three items before its terminator
+        assert_eq!(spans, vec![0..4, 5..7]);
 
-        assert_eq!(matcher.process_item(15).unwrap(), Some(15));
-        assert_eq!(matcher.process_item(25).unwrap(), None);
+        // A trailing until element still waits for its terminator
+        matcher.reset();
+        for item in ["START", "a", "b"] {
+            assert_eq!(matcher.process_item(item).unwrap(), None);
+        }
+        assert!(matcher.is_matching());
+        assert_eq!(
+            PatternElement::<&str, ()>::until(PatternElement::exact("END"), 3).to_string(),
+            "Until(Exact(\"END\"))"
+        );
     }
 
-    // === Extractor Tests ===
-
-    #[test]
-    fn test_extractor_extract() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
+    fn selecting(selection: Selection) -> Matcher<char, ()> {
+        let mut matcher = Matcher::new(10);
+        matcher.set_pattern_settings(PatternSettings::default().selection(selection));
+        matcher.add_pattern(PatternElement::exact('a'));
+        matcher.add_pattern(PatternElement::exact('b'));
+        matcher
+    }
 
-        // Register an extractor that doubles large values
-        matcher.register_extractor(1, |state| {
-            if state.current_item > 10 {
-                Ok(ExtractorAction::Extract(state.current_item * 2))
-            } else {
-                Ok(ExtractorAction::Continue)
+    fn spans(matcher: &mut Matcher<char, ()>, items: &str) -> Vec<std::ops::Range<usize>> {
+        let mut spans = Vec::new();
+        for item in items.chars() {
+            for report in matcher.process_item_reports(item).unwrap() {
+                spans.push(report.span);
             }
-        });
+        }
+        spans
+    }
 
-        let mut settings = ElementSettings::default();
-        settings.extractor_id = Some(1);
-        matcher.add_pattern(PatternElement::exact_with_settings(15, settings));
+    #[test]
+    fn test_skip_till_next_match() {
+        assert!(spans(&mut selecting(Selection::Strict), "axb").is_empty());
+        let mut matcher = selecting(Selection::SkipTillNextMatch);
+        // The second a is skipped by the attempt the first one began
+        assert_eq!(spans(&mut matcher, "axabxb"), vec![0..4]);
+
+        // An item gap bounds how many items may be skipped
+        let close = ElementSettings {
+            max_gap: Some(StreamOffset::Item(2)),
+            ..Default::default()
+        };
+        let mut matcher = Matcher::<char, ()>::new(10);
+        matcher.set_pattern_settings(
+            PatternSettings::default().selection(Selection::SkipTillNextMatch),
+        );
+        matcher.add_pattern(PatternElement::exact('a'));
+        matcher.add_pattern(PatternElement::exact_with_settings('b', close));
+        assert_eq!(spans(&mut matcher, "axb"), vec![0..3]);
+        assert!(spans(&mut matcher, "axxb").is_empty());
+    }
 
-        assert_eq!(matcher.process_item(15).unwrap(), Some(30));
+    #[test]
+    fn test_skip_till_any_match() {
+        let mut matcher = selecting(Selection::SkipTillAnyMatch);
+        assert_eq!(spans(&mut matcher, "aaxbb"), vec![0..4, 1..4, 0..5, 1..5]);
+        matcher.reset();
+        assert!(matcher.forks.is_empty());
     }
 
     #[test]
-    fn test_extractor_continue() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
+    fn test_absence_detection() {
+        let mut matcher = Matcher::<&str, ()>::new(5);
+        matcher.set_pattern_settings(
+            PatternSettings::default().absence(StreamOffset::Time(Duration::from_secs(5))),
+        );
+        matcher.add_pattern(PatternElement::exact("request"));
+        matcher.add_pattern(PatternElement::predicate_with_settings(
+            |_| true,
+            ElementSettings {
+                min_repeat: 0,
+                max_repeat: usize::MAX,
+                repeat_mode: RepeatMode::Lazy,
+                ..ElementSettings::default()
+            },
+        ));
+        matcher.add_pattern(PatternElement::exact("response"));
 
-        matcher.register_extractor(1, |_state| Ok(ExtractorAction::Continue));
+        let mut at = |item, seconds| {
+            matcher
+                .process_item_at(item, Duration::from_secs(seconds))
+                .unwrap()
+        };
+        // Answered in time, so nothing is reported
+        assert_eq!(at("request", 0), None);
+        assert_eq!(at("ping", 1), None);
+        assert_eq!(at("response", 2), None);
+        // Unanswered: reported once an item arrives too late
+        assert_eq!(at("request", 10), None);
+        assert_eq!(at("ping", 12), None);
+        assert_eq!(at("response", 16), Some("request"));
+
+        // A timer finds the missing response while the stream is quiet
+        assert_eq!(at("request", 20), None);
+        assert_eq!(matcher.check_absence(Duration::from_secs(24)), None);
+        assert_eq!(
+            matcher.check_absence(Duration::from_secs(26)),
+            Some("request")
+        );
+        assert!(!matcher.is_matching());
+        assert_eq!(matcher.stats().matches_completed, 2);
+
+        // Reports cover the abandoned attempt, not the one the item begins
+        matcher
+            .process_item_at("request", Duration::from_secs(30))
+            .unwrap();
+        matcher
+            .process_item_at("ping", Duration::from_secs(31))
+            .unwrap();
+        let reports = matcher
+            .process_item_reports_at("request", Duration::from_secs(36))
+            .unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].value, "request");
+        assert_eq!(reports[0].span, 7..8);
+        assert_eq!(reports[0].captures.len(), 2);
+        assert!(matcher.is_matching());
+    }
 
-        let mut settings = ElementSettings::default();
-        settings.extractor_id = Some(1);
-        matcher.add_pattern(PatternElement::exact_with_settings(5, settings));
-        matcher.add_pattern(PatternElement::exact(10));
+    #[test]
+    fn test_within_discards_stale_partial_match() {
+        let mut matcher = Matcher::<i32, ()>::new(5);
+        matcher.set_pattern_settings(PatternSettings::default().within(Duration::from_secs(30)));
+        matcher.add_pattern(PatternElement::range(400, 499));
+        matcher.add_pattern(PatternElement::range(500, 599));
 
-        assert_eq!(matcher.process_item(5).unwrap(), None);
-        assert_eq!(matcher.process_item(10).unwrap(), Some(10));
+        // 5xx arrives too late, so the partial match is discarded
+        assert_eq!(
+            matcher
+                .process_item_at(404, Duration::from_secs(0))
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            matcher
+                .process_item_at(503, Duration::from_secs(31))
+                .unwrap(),
+            None
+        );
+        assert!(!matcher.is_matching());
+
+        // Within the window the pattern completes
+        assert_eq!(
+            matcher
+                .process_item_at(404, Duration::from_secs(40))
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            matcher
+                .process_item_at(503, Duration::from_secs(70))
+                .unwrap(),
+            Some(503)
+        );
     }
 
     #[test]
-    fn test_extractor_restart() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
+    fn test_start_eligibility_window() {
+        let mut matcher = Matcher::<i32, ()>::new(5);
+        matcher.set_pattern_settings(
+            PatternSettings::default()
+                .not_before(StreamOffset::Item(2))
+                .not_after(StreamOffset::Time(Duration::from_secs(10))),
+        );
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact(2));
 
-        matcher.register_extractor(1, |_state| Ok(ExtractorAction::Restart));
+        // Startup phase is ignored
+        assert_eq!(
+            matcher.process_item_at(1, Duration::from_secs(0)).unwrap(),
+            None
+        );
+        assert_eq!(
+            matcher.process_item_at(2, Duration::from_secs(1)).unwrap(),
+            None
+        );
 
-        let mut settings = ElementSettings::default();
-        settings.extractor_id = Some(1);
-        matcher.add_pattern(PatternElement::exact_with_settings(5, settings));
-        matcher.add_pattern(PatternElement::exact(10));
+        // Matches may begin once the stream reaches item index 2
+        assert_eq!(
+            matcher.process_item_at(1, Duration::from_secs(2)).unwrap(),
+            None
+        );
+        assert_eq!(
+            matcher.process_item_at(2, Duration::from_secs(11)).unwrap(),
+            Some(2)
+        );
 
-        assert_eq!(matcher.process_item(5).unwrap(), None);
-        assert_eq!(matcher.current_position(), 0); // Should be reset
+        // No new match may begin after the activation window closes
+        assert_eq!(
+            matcher.process_item_at(1, Duration::from_secs(12)).unwrap(),
+            None
+        );
+        assert!(!matcher.is_matching());
     }
 
     #[test]
-    fn test_multiple_extractors() {
-        // Test extractor 1: Double the value
-        let mut matcher1 = Matcher::<i32, TestContext>::new(5);
-        matcher1.register_extractor(1, |state| {
-            Ok(ExtractorAction::Extract(state.current_item * 2))
-        });
+    fn test_session_gap_resets_partial_match() {
+        use std::sync::Mutex;
 
-        let mut settings1 = ElementSettings::default();
-        settings1.extractor_id = Some(1);
-        matcher1.add_pattern(PatternElement::exact_with_settings(10, settings1));
+        let summaries = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&summaries);
 
-        assert_eq!(matcher1.process_item(10).unwrap(), Some(20));
+        let mut matcher = Matcher::<&str, ()>::new(5);
+        matcher.set_session_gap(Duration::from_secs(60));
+        matcher.on_session_end(move |summary| sink.lock().unwrap().push(summary.clone()));
+        matcher.add_pattern(PatternElement::exact("view"));
+        matcher.add_pattern(PatternElement::exact("buy"));
 
-        // Test extractor 2: Triple the value
-        let mut matcher2 = Matcher::<i32, TestContext>::new(5);
-        matcher2.register_extractor(2, |state| {
-            Ok(ExtractorAction::Extract(state.current_item * 3))
-        });
+        let secs = Duration::from_secs;
+        assert_eq!(matcher.process_item_at("view", secs(0)).unwrap(), None);
+        assert_eq!(
+            matcher.process_item_at("buy", secs(30)).unwrap(),
+            Some("buy")
+        );
+        assert_eq!(matcher.process_item_at("view", secs(50)).unwrap(), None);
 
-        let mut settings2 = ElementSettings::default();
-        settings2.extractor_id = Some(2);
-        matcher2.add_pattern(PatternElement::exact_with_settings(5, settings2));
+        // The gap closes the session, so "buy" cannot complete the earlier "view"
+        assert_eq!(matcher.process_item_at("buy", secs(200)).unwrap(), None);
 
-        assert_eq!(matcher2.process_item(5).unwrap(), Some(15));
+        let summaries = summaries.lock().unwrap();
+        assert_eq!(
+            *summaries,
+            vec![SessionSummary {
+                start: secs(0),
+                end: secs(50),
+                item_count: 3,
+                match_count: 1,
+            }]
+        );
+        assert_eq!(matcher.current_session().unwrap().start, secs(200));
     }
 
-    // === Context Tests ===
-
     #[test]
-    fn test_context_basic() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
-        let context = TestContext {
-            name: "test".to_string(),
-            value: 42,
-            captured_values: vec![1, 2, 3],
-            counters: HashMap::new(),
-        };
+    fn test_reset_discards_open_session() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let closed = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&closed);
+        let mut matcher = Matcher::<&str, ()>::new(5);
+        matcher.set_session_gap(Duration::from_secs(60));
+        matcher.on_session_end(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        matcher.add_pattern(PatternElement::exact("view"));
 
-        matcher.set_context(context.clone());
-        assert_eq!(matcher.context(), Some(&context));
+        matcher
+            .process_item_at("view", Duration::from_secs(0))
+            .unwrap();
+        matcher.reset();
+        assert!(matcher.current_session().is_none());
+
+        // The first item after the reset opens a session rather than closing one
+        matcher
+            .process_item_at("view", Duration::from_secs(500))
+            .unwrap();
+        assert_eq!(closed.load(Ordering::SeqCst), 0);
+        assert_eq!(
+            matcher.current_session().unwrap().start,
+            Duration::from_secs(500)
+        );
     }
 
     #[test]
-    fn test_context_with_extractor() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
+    fn test_session_gap_ends_parallel_attempts() {
+        let secs = Duration::from_secs;
+        for selection in [
+            Selection::Strict,
+            Selection::SkipTillNextMatch,
+            Selection::SkipTillAnyMatch,
+        ] {
+            let mut matcher = Matcher::<i32, ()>::new(5);
+            matcher.set_pattern_settings(PatternSettings::default().selection(selection));
+            matcher.set_session_gap(secs(10));
+            matcher.add_pattern(PatternElement::exact(1));
+            matcher.add_pattern(PatternElement::exact(2));
+            matcher.add_pattern(PatternElement::exact(3));
+
+            assert_eq!(matcher.process_item_at(1, secs(0)).unwrap(), None);
+            assert_eq!(matcher.process_item_at(2, secs(1)).unwrap(), None);
+            assert_eq!(
+                matcher.process_item_at(3, secs(100)).unwrap(),
+                None,
+                "{:?}",
+                selection
+            );
+            assert!(matcher.forks.is_empty());
+        }
+    }
 
-        let context = TestContext::default();
-        matcher.set_context(context);
+    // === Simultaneous Completion Tests ===
 
-        // Note: In this simplified design, extractors work with MatchState, not context
-        // This is different from the old StatefulMatcher design
+    /// Holds back each item as a longest match, unless an extractor reports
+    /// large items straight away.
+    fn held_or_extracted() -> Matcher<i32, ()> {
+        let mut matcher = Matcher::new(10);
+        matcher.set_reset_policy(ResetPolicy::RetryCurrentItem);
+        matcher.set_pattern_settings(
+            PatternSettings::default().match_policy(MatchPolicy::LongestMatch),
+        );
+        matcher.add_pattern(PatternElement::predicate_with_settings(
+            |_: &i32| true,
+            ElementSettings {
+                extractor_id: Some(1),
+                ..Default::default()
+            },
+        ));
+        matcher.add_pattern(PatternElement::exact_with_settings(
+            0,
+            ElementSettings {
+                optional: true,
+                ..Default::default()
+            },
+        ));
         matcher.register_extractor(1, |state| {
-            if state.position == 0 {
-                Ok(ExtractorAction::Extract(state.current_item + 100))
+            Ok(if state.current_item > 100 {
+                ExtractorAction::Extract(state.current_item)
             } else {
-                Ok(ExtractorAction::Continue)
-            }
+                ExtractorAction::Continue
+            })
         });
+        matcher
+    }
 
-        let mut settings = ElementSettings::default();
-        settings.extractor_id = Some(1);
-        matcher.add_pattern(PatternElement::exact_with_settings(42, settings));
-
-        assert_eq!(matcher.process_item(42).unwrap(), Some(142));
+    #[test]
+    fn test_all_completions_on_one_item() {
+        let mut matcher = held_or_extracted();
+        assert!(matcher.process_item_all(5).unwrap().is_empty());
+        // 200 releases the held match of 5 and completes a match of its own
+        assert_eq!(matcher.process_item_all(200).unwrap(), vec![5, 200]);
+        assert_eq!(matcher.stats().matches_completed, 2);
+
+        let mut matcher = held_or_extracted();
+        assert_eq!(matcher.process_item(5).unwrap(), None);
+        assert_eq!(matcher.process_item(200).unwrap(), Some(5));
+        assert_eq!(matcher.process_items(vec![7, 300]).unwrap(), vec![7, 300]);
     }
 
-    // === State Management Tests ===
+    // === End of Stream Tests ===
 
     #[test]
-    fn test_reset() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
+    fn test_finish_completes_optional_tail() {
+        let mut matcher = Matcher::<i32, ()>::new(5);
+        matcher.set_pattern_settings(
+            PatternSettings::default().match_policy(MatchPolicy::LongestMatch),
+        );
         matcher.add_pattern(PatternElement::exact(1));
-        matcher.add_pattern(PatternElement::exact(2));
+        let settings = ElementSettings {
+            optional: true,
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(2, settings));
 
-        matcher.process_item(1).unwrap();
-        assert_eq!(matcher.current_position(), 1);
-        assert_eq!(matcher.total_processed(), 1);
+        assert_eq!(matcher.process_item(1).unwrap(), None);
 
-        matcher.reset();
-        assert_eq!(matcher.current_position(), 0);
-        assert_eq!(matcher.total_processed(), 0);
+        let report = matcher.finish();
+        assert_eq!(report.completed, Some(1));
+        assert_eq!(report.partial, None);
+        assert!(!matcher.is_matching());
     }
 
     #[test]
-    fn test_state_inspection() {
-        let mut matcher = Matcher::<i32, TestContext>::new(10);
+    fn test_finish_reports_partial_match() {
+        let mut matcher = Matcher::<i32, ()>::new(5);
+        matcher.set_session_gap(Duration::from_secs(60));
         matcher.add_pattern(PatternElement::exact(1));
         matcher.add_pattern(PatternElement::exact(2));
+        matcher.add_pattern(PatternElement::exact(3));
 
-        assert_eq!(matcher.window_size(), 10);
-        assert_eq!(matcher.pattern_count(), 2);
-        assert_eq!(matcher.current_position(), 0);
-        assert_eq!(matcher.total_processed(), 0);
-        assert!(!matcher.is_matching());
+        matcher.process_item_at(1, Duration::from_secs(5)).unwrap();
+        matcher.process_item_at(2, Duration::from_secs(6)).unwrap();
 
-        matcher.process_item(1).unwrap();
-        assert_eq!(matcher.current_position(), 1);
-        assert_eq!(matcher.total_processed(), 1);
-        assert!(matcher.is_matching());
+        let report = matcher.finish();
+        assert_eq!(report.completed, None);
+        assert_eq!(
+            report.partial,
+            Some(PartialMatch {
+                position: 2,
+                captured: vec![
+                    Capture {
+                        item: 1,
+                        index: 0,
+                        timestamp: Duration::from_secs(5),
+                    },
+                    Capture {
+                        item: 2,
+                        index: 1,
+                        timestamp: Duration::from_secs(6),
+                    },
+                ],
+            })
+        );
+        assert_eq!(report.session.unwrap().item_count, 2);
+        assert!(matcher.current_session().is_none());
     }
 
+    // === Checkpoint Tests ===
+
     #[test]
-    fn test_window_size_management() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
-        assert_eq!(matcher.window_size(), 5);
+    fn test_checkpoint_and_restore() {
+        let build = || {
+            let mut matcher = Matcher::<i32, ()>::new(5);
+            matcher.add_pattern(PatternElement::exact(1));
+            matcher.add_pattern(PatternElement::exact(2));
+            matcher.add_pattern(PatternElement::exact(3));
+            matcher
+        };
 
-        matcher.set_window_size(20);
-        assert_eq!(matcher.window_size(), 20);
+        let mut matcher = build();
+        matcher.process_item(1).unwrap();
+        matcher.process_item(2).unwrap();
+        let state = matcher.checkpoint().unwrap();
+        let captured: Vec<i32> = state.captured.iter().map(|c| c.item).collect();
+        assert_eq!(captured, vec![1, 2]);
+
+        // A freshly configured matcher resumes the in-flight partial match
+        let mut resumed = build();
+        resumed.restore(state).unwrap();
+        assert_eq!(resumed.total_processed(), 2);
+        assert_eq!(resumed.process_item(3).unwrap(), Some(3));
     }
 
-    // === Multiple Item Processing Tests ===
+    #[test]
+    fn test_seek_and_resume_from() {
+        let build = || {
+            let mut matcher = Matcher::<i32, ()>::new(5);
+            matcher.add_pattern(PatternElement::exact(1));
+            matcher.add_pattern(PatternElement::exact(2));
+            matcher
+        };
+        let source = [7, 1, 2, 1, 9, 1, 2];
+
+        // Indices follow the source offsets from the seek onwards
+        let mut matcher = build();
+        matcher.seek(100);
+        let mut found = Vec::new();
+        for &item in &source[..4] {
+            if matcher.process_item(item).unwrap().is_some() {
+                found.push(matcher.offset() - 1);
+            }
+        }
+        assert_eq!(found, vec![102]);
+        assert_eq!(matcher.offset(), 104);
+        let state = matcher.checkpoint().unwrap();
+
+        // After a crash the source replays from its last committed offset
+        let mut resumed = build();
+        resumed.restore(state).unwrap();
+        resumed.resume_from(102);
+        let mut indices = Vec::new();
+        for &item in &source[2..] {
+            resumed.process_item(item).unwrap();
+            indices.push(resumed.offset());
+        }
+        assert_eq!(indices, vec![103, 104, 105, 106, 107]);
+
+        // The replayed match at 102 is not reported again
+        let stats = resumed.stats();
+        assert_eq!(stats.matches_completed, 1);
+
+        // Items still to be skipped survive a checkpoint, but not a reset
+        resumed.resume_from(105);
+        let state = resumed.checkpoint().unwrap();
+        assert_eq!(state.replay_skip, 2);
+        let mut restored = build();
+        restored.restore(state).unwrap();
+        assert_eq!(restored.offset(), 105);
+        restored.reset();
+        assert_eq!(restored.process_item(1).unwrap(), None);
+        assert_eq!(restored.process_item(2).unwrap(), Some(2));
+    }
 
     #[test]
-    fn test_process_items() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
+    fn test_fork_speculates_without_side_effects() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let sessions = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&sessions);
+        let mut matcher = Matcher::<i32, ()>::new(5);
+        matcher.set_session_gap(Duration::from_secs(60));
+        matcher.on_session_end(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
         matcher.add_pattern(PatternElement::exact(1));
         matcher.add_pattern(PatternElement::exact(2));
+        matcher.add_pattern(PatternElement::exact(3));
+        let secs = Duration::from_secs;
+        matcher.process_item_at(1, secs(0)).unwrap();
+        matcher.process_item_at(2, secs(1)).unwrap();
 
-        let items = vec![1, 2, 3, 1, 2, 4, 1, 2];
-        let results = matcher.process_items(items).unwrap();
+        let mut fork = matcher.fork();
+        assert_eq!(fork.process_item_at(3, secs(2)).unwrap(), Some(3));
+        assert_eq!(fork.process_item_at(1, secs(500)).unwrap(), None);
+        assert_eq!(sessions.load(Ordering::SeqCst), 0);
 
-        // Should have found three complete patterns: [1,2] at positions 0-1, 3-4, and 6-7
-        assert_eq!(results, vec![2, 2, 2]);
+        // The live matcher is still waiting for the third element
+        assert!(matcher.is_matching());
+        assert_eq!(matcher.total_processed(), 2);
+        assert_eq!(matcher.process_item_at(3, secs(3)).unwrap(), Some(3));
     }
 
     #[test]
-    fn test_with_patterns_constructor() {
-        let patterns = vec![
-            PatternElement::exact(1),
-            PatternElement::exact(2),
-            PatternElement::exact(3),
-        ];
-
-        let mut matcher = Matcher::<i32, TestContext>::with_patterns(patterns, 10);
+    fn test_restore_rejects_incompatible_state() {
+        let mut matcher = Matcher::<i32, ()>::new(5);
+        matcher.add_pattern(PatternElement::exact(1));
 
-        assert_eq!(matcher.pattern_count(), 3);
-        assert_eq!(matcher.window_size(), 10);
+        let mut state = matcher.checkpoint().unwrap();
+        state.position = 4;
+        assert!(matches!(
+            matcher.restore(state),
+            Err(MatcherError::InvalidState(_))
+        ));
 
-        assert_eq!(matcher.process_item(1).unwrap(), None);
-        assert_eq!(matcher.process_item(2).unwrap(), None);
-        assert_eq!(matcher.process_item(3).unwrap(), Some(3));
+        // The first element matches a single item
+        let mut state = matcher.checkpoint().unwrap();
+        state.repeat = 2;
+        assert!(matches!(
+            matcher.restore(state),
+            Err(MatcherError::InvalidState(_))
+        ));
     }
 
-    // === Error Handling Tests ===
-
     #[test]
-    fn test_no_patterns_error() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
+    fn test_checkpoint_keeps_rate_arrivals() {
+        let build = || {
+            let mut matcher = Matcher::<&str, ()>::new(10);
+            matcher.add_pattern(PatternElement::rate(3, Duration::from_millis(100)));
+            matcher
+        };
+        let millis = Duration::from_millis;
 
-        let result = matcher.process_item(42);
-        assert!(matches!(result, Err(MatcherError::NoPatterns)));
+        let mut matcher = build();
+        matcher.process_item_at("request", millis(0)).unwrap();
+        matcher.process_item_at("request", millis(20)).unwrap();
+
+        // The burst spans the checkpoint
+        let mut resumed = build();
+        resumed.restore(matcher.checkpoint().unwrap()).unwrap();
+        assert_eq!(
+            resumed.process_item_at("request", millis(40)).unwrap(),
+            Some("request")
+        );
     }
 
     #[test]
-    fn test_extractor_error() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
-
-        matcher.register_extractor(1, |_state| {
-            Err(ExtractorError::ProcessingFailed("Test error".to_string()))
-        });
-
-        let mut settings = ElementSettings::default();
-        settings.extractor_id = Some(1);
-        matcher.add_pattern(PatternElement::exact_with_settings(42, settings));
+    fn test_checkpoint_keeps_correlated_completions() {
+        let build = || {
+            let mut matcher = Matcher::<i32, ()>::new(10);
+            matcher.set_pattern_settings(
+                PatternSettings::default()
+                    .after("login", StreamOffset::Time(Duration::from_secs(5))),
+            );
+            matcher.add_pattern(PatternElement::exact(1));
+            matcher
+        };
 
-        let result = matcher.process_item(42);
-        assert!(matches!(result, Err(MatcherError::ExtractorFailed(_))));
+        let mut matcher = build();
+        matcher.record_completion("login", 0, Duration::from_secs(10));
+        let mut resumed = build();
+        resumed.restore(matcher.checkpoint().unwrap()).unwrap();
+        assert_eq!(
+            resumed.last_completion("login"),
+            Some((0, Duration::from_secs(10)))
+        );
+        assert_eq!(
+            resumed.process_item_at(1, Duration::from_secs(11)).unwrap(),
+            Some(1)
+        );
     }
 
-    // === Complex Pattern Tests ===
-
     #[test]
-    fn test_mixed_pattern_types() {
-        let mut matcher = Matcher::<i32, TestContext>::new(10);
+    fn test_checkpoint_keeps_backtracking_paths() {
+        let build = || {
+            let mut matcher = optional_then_required(Engine::Backtracking);
+            matcher.add_pattern(PatternElement::exact(3));
+            matcher
+        };
 
-        // Pattern: exact(1), range(5-10), predicate(even)
-        matcher.add_pattern(PatternElement::exact(1));
-        matcher.add_pattern(PatternElement::range(5, 10));
-        matcher.add_pattern(PatternElement::predicate(|x| *x % 2 == 0));
+        let mut matcher = build();
+        matcher.process_item(1).unwrap();
+        matcher.process_item(2).unwrap();
+        let state = matcher.checkpoint().unwrap();
+        assert!(!state.alternatives.is_empty());
 
-        assert_eq!(matcher.process_item(1).unwrap(), None); // Match first
-        assert_eq!(matcher.process_item(7).unwrap(), None); // Match second
-        assert_eq!(matcher.process_item(8).unwrap(), Some(8)); // Match third, complete pattern
+        let mut resumed = build();
+        resumed.restore(state).unwrap();
+        assert_eq!(resumed.process_item(3).unwrap(), Some(3));
+        assert_eq!(matcher.process_item(3).unwrap(), Some(3));
     }
 
     #[test]
-    fn test_pattern_mismatch_reset() {
+    fn test_checkpoint_keeps_parallel_attempts() {
+        let mut uninterrupted = selecting(Selection::SkipTillAnyMatch);
+        let expected = spans(&mut uninterrupted, "aaxbb");
+
+        let mut matcher = selecting(Selection::SkipTillAnyMatch);
+        let mut found = spans(&mut matcher, "aa");
+        let state = matcher.checkpoint().unwrap();
+        assert_eq!(state.forks.len(), 1);
+
+        let mut resumed = selecting(Selection::SkipTillAnyMatch);
+        resumed.restore(state).unwrap();
+        found.extend(spans(&mut resumed, "xbb"));
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_checkpoint_refuses_staged_context() {
         let mut matcher = Matcher::<i32, TestContext>::new(5);
+        matcher.set_transactional_context(true);
+        matcher.set_context(TestContext::default());
         matcher.add_pattern(PatternElement::exact(1));
         matcher.add_pattern(PatternElement::exact(2));
-        matcher.add_pattern(PatternElement::exact(3));
-
-        assert_eq!(matcher.process_item(1).unwrap(), None); // Position 1
-        assert_eq!(matcher.process_item(5).unwrap(), None); // Mismatch, reset to 0
-        assert_eq!(matcher.current_position(), 0);
 
-        assert_eq!(matcher.process_item(1).unwrap(), None); // Position 1 again
-        assert_eq!(matcher.process_item(2).unwrap(), None); // Position 2
-        assert_eq!(matcher.process_item(3).unwrap(), Some(3)); // Complete pattern
+        matcher.process_item(1).unwrap();
+        matcher.context_mut().unwrap().captured_values.push(1);
+        assert!(matches!(
+            matcher.checkpoint(),
+            Err(MatcherError::InvalidState(_))
+        ));
+        assert_eq!(matcher.process_item(2).unwrap(), Some(2));
+        assert!(matcher.checkpoint().is_ok());
     }
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn test_optional_elements() {
-        let mut matcher = Matcher::<i32, TestContext>::new(5);
-
-        // First element is required
+    fn test_checkpoint_serde_round_trip() {
+        let mut matcher = Matcher::<i32, ()>::new(5);
         matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact(2));
+        matcher.process_item(1).unwrap();
 
-        // Second element is optional
-        let mut settings = ElementSettings::default();
-        settings.optional = true;
-        matcher.add_pattern(PatternElement::exact_with_settings(2, settings));
-
-        // Third element is required
-        matcher.add_pattern(PatternElement::exact(3));
-
-        // Test with optional element present
-        assert_eq!(matcher.process_item(1).unwrap(), None);
-        assert_eq!(matcher.process_item(2).unwrap(), None);
-        assert_eq!(matcher.process_item(3).unwrap(), Some(3));
-
-        matcher.reset();
-
-        // Test with optional element missing
-        assert_eq!(matcher.process_item(1).unwrap(), None);
-        assert_eq!(matcher.process_item(3).unwrap(), Some(3)); // Should skip optional 2
+        let json = serde_json::to_string(&matcher.checkpoint().unwrap()).unwrap();
+        let state: MatcherState<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(state, matcher.checkpoint().unwrap());
     }
 
     // === Edge Cases ===
@@ -1026,6 +7993,40 @@ mod tests {
         assert_eq!(patterns.len(), 2);
     }
 
+    // === Clone Tests ===
+
+    #[test]
+    fn test_cloned_pattern_keeps_closures() {
+        let pattern: PatternElement<i32, ()> = PatternElement::predicate(|item| *item > 3);
+        let cloned = pattern.clone();
+        assert!(cloned.matches(&5).unwrap());
+        assert!(!cloned.matches(&2).unwrap());
+    }
+
+    #[test]
+    fn test_cloned_matcher_continues_independently() {
+        let mut matcher = Matcher::<i32, ()>::new(5);
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::predicate_with_settings(
+            |item: &i32| *item % 2 == 0,
+            ElementSettings {
+                extractor_id: Some(1),
+                ..Default::default()
+            },
+        ));
+        matcher.register_extractor(1, |state| {
+            Ok(ExtractorAction::Extract(state.current_item * 10))
+        });
+        assert_eq!(matcher.process_item(1).unwrap(), None);
+
+        // The clone carries the partial match, but feeding it leaves the original alone
+        let mut cloned = matcher.clone();
+        assert_eq!(cloned.process_item(4).unwrap(), Some(40));
+        assert_eq!(cloned.total_processed(), 2);
+        assert_eq!(matcher.total_processed(), 1);
+        assert_eq!(matcher.process_item(6).unwrap(), Some(60));
+    }
+
     // === String Type Tests ===
 
     #[test]
@@ -1041,6 +8042,27 @@ mod tests {
         );
     }
 
+    // === Sync Tests ===
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn test_matcher_moves_between_threads() {
+        fn assert_send_sync<S: Send + Sync>(_: &S) {}
+
+        let mut matcher = Matcher::<i32, ()>::new(5);
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::predicate(|item: &i32| *item > 1));
+        matcher.register_extractor(1, |state| {
+            Ok(ExtractorAction::Extract(state.current_item * 10))
+        });
+        assert_send_sync(&matcher);
+
+        let found = std::thread::spawn(move || matcher.process_items(vec![1, 5]).unwrap())
+            .join()
+            .unwrap();
+        assert_eq!(found, vec![5]);
+    }
+
     // === Performance Test (Basic) ===
 
     #[test]
@@ -1054,7 +8076,7 @@ mod tests {
 
         let mut count = 0;
         for i in 0..1000 {
-            if let Some(_) = matcher.process_item(i % 10).unwrap() {
+            if matcher.process_item(i % 10).unwrap().is_some() {
                 count += 1;
             }
         }
@@ -0,0 +1,381 @@
+//! Ready-made patterns for application logs.
+//!
+//! [`LogLine::parse`] splits a raw log line into typed tokens: a leading
+//! timestamp, a severity level, an HTTP-style status code, and the message
+//! that remains. The presets then match common incident shapes over a stream
+//! of parsed lines, without a hand-written predicate for each.
+//!
+//! As with the numeric presets, the shapes are meant to be found anywhere in
+//! the log, so use them with `ResetPolicy::RetryCurrentItem` or
+//! `ResetPolicy::FallbackByPrefix`.
+//!
+//! ```rust
+//! use scrolling_window_pattern_matcher::log::{self, LogLine};
+//! use scrolling_window_pattern_matcher::{Matcher, ResetPolicy};
+//!
+//! let mut matcher = Matcher::<LogLine, ()>::with_patterns(log::error_burst(2), 10);
+//! matcher.set_reset_policy(ResetPolicy::RetryCurrentItem);
+//! let lines = [
+//!     "2024-05-01T10:00:00Z INFO started",
+//!     "2024-05-01T10:00:01Z ERROR disk full",
+//!     "2024-05-01T10:00:02Z ERROR disk full",
+//! ];
+//! let results = matcher
+//!     .process_items(lines.iter().map(|line| LogLine::parse(line)).collect())
+//!     .unwrap();
+//! assert_eq!(results.len(), 1);
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Capture, ElementSettings, PatternElement};
+
+/// Severity of a log line, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// Fine-grained tracing output.
+    Trace,
+    /// Debugging output.
+    Debug,
+    /// Routine information.
+    Info,
+    /// Something unexpected that did not stop the operation.
+    Warn,
+    /// An operation failed.
+    Error,
+    /// The process cannot continue.
+    Fatal,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Level::Trace => "TRACE",
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+            Level::Fatal => "FATAL",
+        })
+    }
+}
+
+/// Error returned when a word is not a recognized level name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLevelError(String);
+
+impl fmt::Display for ParseLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown log level {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseLevelError {}
+
+impl FromStr for Level {
+    type Err = ParseLevelError;
+
+    /// Parse a level name, ignoring case. Common aliases such as `WARNING`,
+    /// `ERR` and `CRITICAL` are accepted.
+    fn from_str(word: &str) -> Result<Self, Self::Err> {
+        match word.to_ascii_uppercase().as_str() {
+            "TRACE" => Ok(Level::Trace),
+            "DEBUG" => Ok(Level::Debug),
+            "INFO" | "NOTICE" => Ok(Level::Info),
+            "WARN" | "WARNING" => Ok(Level::Warn),
+            "ERROR" | "ERR" => Ok(Level::Error),
+            "FATAL" | "CRITICAL" | "CRIT" | "PANIC" => Ok(Level::Fatal),
+            _ => Err(ParseLevelError(word.to_string())),
+        }
+    }
+}
+
+/// A log line split into typed tokens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLine {
+    /// The leading timestamp, as written, without surrounding brackets.
+    pub timestamp: Option<String>,
+    /// The severity level, if the line names one before its message.
+    pub level: Option<Level>,
+    /// The first status code in the message, from 100 to 599.
+    pub status: Option<u16>,
+    /// The rest of the line after the timestamp and level.
+    pub message: String,
+}
+
+impl LogLine {
+    /// Split a raw log line into its tokens.
+    ///
+    /// The timestamp is a leading ISO 8601 date, optionally followed by a
+    /// time of day, or a leading bracketed word starting with a digit. The
+    /// level is the next word, with any brackets or trailing colon removed.
+    /// The status code is the first word of the message that is a three-digit
+    /// number from 100 to 599, either on its own or after an `=`. Any part
+    /// that is missing is `None`, and a line without any of them is all
+    /// message.
+    pub fn parse(line: &str) -> Self {
+        let mut rest = line.trim();
+
+        let mut timestamp = None;
+        if let Some((word, after)) = next_word(rest) {
+            let bare = strip_brackets(word);
+            if is_date(bare) {
+                let mut stamp = bare.to_string();
+                rest = after;
+                if let Some((time, after)) = next_word(rest) {
+                    let time = strip_brackets(time);
+                    if is_time(time) {
+                        stamp.push(' ');
+                        stamp.push_str(time);
+                        rest = after;
+                    }
+                }
+                timestamp = Some(stamp);
+            } else if word.starts_with('[')
+                && word.ends_with(']')
+                && bare.starts_with(|c: char| c.is_ascii_digit())
+            {
+                timestamp = Some(bare.to_string());
+                rest = after;
+            }
+        }
+
+        let mut level = None;
+        if let Some((word, after)) = next_word(rest) {
+            let bare = strip_brackets(word.trim_end_matches(':'));
+            if let Ok(parsed) = bare.parse() {
+                level = Some(parsed);
+                rest = after;
+            }
+        }
+
+        let message = rest.trim_start_matches([':', '-']).trim().to_string();
+        let status = message.split_whitespace().find_map(status_code);
+        Self {
+            timestamp,
+            level,
+            status,
+            message,
+        }
+    }
+
+    /// Whether the line is at least as severe as `level`.
+    pub fn is_at_least(&self, level: Level) -> bool {
+        self.level.is_some_and(|own| own >= level)
+    }
+
+    /// Whether the line reports a failure: a level of `Error` or above, or a
+    /// 5xx status code.
+    pub fn is_failure(&self) -> bool {
+        self.is_at_least(Level::Error) || self.status.is_some_and(|status| status >= 500)
+    }
+
+    /// Whether the line reports a 2xx status code.
+    pub fn is_success(&self) -> bool {
+        self.status
+            .is_some_and(|status| (200..300).contains(&status))
+    }
+}
+
+impl From<&str> for LogLine {
+    fn from(line: &str) -> Self {
+        Self::parse(line)
+    }
+}
+
+/// Split off the first whitespace-separated word.
+fn next_word(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start();
+    if text.is_empty() {
+        return None;
+    }
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    Some((&text[..end], &text[end..]))
+}
+
+/// Remove one pair of surrounding brackets, if present.
+fn strip_brackets(word: &str) -> &str {
+    word.strip_prefix('[')
+        .and_then(|word| word.strip_suffix(']'))
+        .unwrap_or(word)
+}
+
+/// Whether a word starts with an ISO 8601 date, `YYYY-MM-DD`.
+fn is_date(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    bytes.len() >= 10
+        && bytes[..4].iter().all(u8::is_ascii_digit)
+        && bytes[4] == b'-'
+        && bytes[5..7].iter().all(u8::is_ascii_digit)
+        && bytes[7] == b'-'
+        && bytes[8..10].iter().all(u8::is_ascii_digit)
+}
+
+/// Whether a word is a time of day such as `10:00:01.250Z`.
+fn is_time(word: &str) -> bool {
+    word.len() >= 5
+        && word.as_bytes()[..2].iter().all(u8::is_ascii_digit)
+        && word.as_bytes()[2] == b':'
+        && word
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, ':' | '.' | ',' | 'Z' | '+' | '-'))
+}
+
+/// Parse a word as a status code, on its own or after an `=`.
+fn status_code(word: &str) -> Option<u16> {
+    let word = word.rsplit('=').next()?;
+    let word = word.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+    if word.len() != 3 || !word.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    word.parse().ok().filter(|code| (100..600).contains(code))
+}
+
+/// `count` consecutive lines at level `Error` or above.
+pub fn error_burst<Context>(count: usize) -> Vec<PatternElement<LogLine, Context>>
+where
+    Context: Clone + fmt::Debug,
+{
+    let settings = ElementSettings {
+        min_repeat: count,
+        max_repeat: count,
+        ..Default::default()
+    };
+    vec![PatternElement::predicate_with_settings(
+        |line: &LogLine| line.is_at_least(Level::Error),
+        settings,
+    )]
+}
+
+/// One or more consecutive failures followed by a success.
+///
+/// A failure is a line at level `Error` or above or with a 5xx status code,
+/// and a success is a line with a 2xx status code. The match completes on
+/// the successful line.
+pub fn retry_then_success<Context>() -> Vec<PatternElement<LogLine, Context>>
+where
+    Context: Clone + fmt::Debug,
+{
+    let retries = ElementSettings {
+        min_repeat: 1,
+        max_repeat: usize::MAX,
+        ..Default::default()
+    };
+    vec![
+        PatternElement::predicate_with_settings(|line: &LogLine| line.is_failure(), retries),
+        PatternElement::predicate(|line: &LogLine| line.is_success()),
+    ]
+}
+
+/// `steps` consecutive rises in severity, ending at `Error` or above.
+///
+/// Lines without a level break the escalation. The match completes on the
+/// line that makes the last rise, after `steps + 1` lines.
+pub fn level_escalation<Context>(steps: usize) -> Vec<PatternElement<LogLine, Context>>
+where
+    Context: Clone + fmt::Debug,
+{
+    let rises = |captured: &[Capture<LogLine>], line: &LogLine| {
+        let before = captured.last().and_then(|capture| capture.item.level);
+        matches!((before, line.level), (Some(before), Some(level)) if level > before)
+    };
+    let mut patterns = vec![PatternElement::predicate(|line: &LogLine| {
+        line.level.is_some()
+    })];
+    if steps > 1 {
+        let settings = ElementSettings {
+            min_repeat: steps - 1,
+            max_repeat: steps - 1,
+            ..Default::default()
+        };
+        patterns.push(PatternElement::guard_with_settings(rises, settings));
+    }
+    if steps > 0 {
+        patterns.push(PatternElement::guard(
+            move |captured: &[Capture<LogLine>], line: &LogLine| {
+                rises(captured, line) && line.is_at_least(Level::Error)
+            },
+        ));
+    }
+    patterns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Matcher, ResetPolicy};
+
+    fn sliding(patterns: Vec<PatternElement<LogLine, ()>>) -> Matcher<LogLine, ()> {
+        let mut matcher = Matcher::with_patterns(patterns, 10);
+        matcher.set_reset_policy(ResetPolicy::RetryCurrentItem);
+        matcher
+    }
+
+    fn run(matcher: &mut Matcher<LogLine, ()>, lines: &[&str]) -> Vec<String> {
+        matcher
+            .process_items(lines.iter().map(|line| LogLine::parse(line)).collect())
+            .unwrap()
+            .into_iter()
+            .map(|line| line.message)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_tokens() {
+        let line = LogLine::parse("2024-05-01 10:00:01.250 [WARN] GET /api status=503 in 12ms");
+        assert_eq!(line.timestamp.as_deref(), Some("2024-05-01 10:00:01.250"));
+        assert_eq!(line.level, Some(Level::Warn));
+        assert_eq!(line.status, Some(503));
+        assert_eq!(line.message, "GET /api status=503 in 12ms");
+
+        let line = LogLine::parse("[1714557601] error: connection reset");
+        assert_eq!(line.timestamp.as_deref(), Some("1714557601"));
+        assert_eq!(line.level, Some(Level::Error));
+        assert_eq!(line.status, None);
+        assert_eq!(line.message, "connection reset");
+
+        let line = LogLine::parse("plain text with 1234 in it");
+        assert_eq!(line.timestamp, None);
+        assert_eq!(line.level, None);
+        assert_eq!(line.status, None);
+        assert_eq!(line.message, "plain text with 1234 in it");
+    }
+
+    #[test]
+    fn test_error_burst() {
+        let mut matcher = sliding(error_burst(3));
+        let found = run(
+            &mut matcher,
+            &[
+                "ERROR a", "ERROR b", "INFO c", "ERROR d", "FATAL e", "ERROR f",
+            ],
+        );
+        assert_eq!(found, vec!["f"]);
+    }
+
+    #[test]
+    fn test_retry_then_success() {
+        let mut matcher = sliding(retry_then_success());
+        let found = run(
+            &mut matcher,
+            &[
+                "INFO GET / 200",
+                "WARN GET / 503",
+                "WARN GET / 502",
+                "INFO GET / 200 recovered",
+            ],
+        );
+        assert_eq!(found, vec!["GET / 200 recovered"]);
+    }
+
+    #[test]
+    fn test_level_escalation() {
+        let mut matcher = sliding(level_escalation(2));
+        let found = run(
+            &mut matcher,
+            &["INFO a", "ERROR b", "INFO c", "WARN d", "ERROR e"],
+        );
+        assert_eq!(found, vec!["e"]);
+    }
+}
@@ -0,0 +1,154 @@
+//! Delegating a stretch of the stream to a nested matcher.
+//!
+//! A protocol often embeds a smaller one, such as a handshake inside a
+//! session. [`PatternElement::matcher`] lets the smaller one be written once as
+//! a matcher of its own and used as a single element of larger patterns: the
+//! element consumes items for as long as the nested matcher is working on a
+//! match, and is satisfied by the item that completes it.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::{Capture, ElementSettings, Matcher, MatcherError, PatternElement};
+
+/// Progress of a nested matcher over the items an element has consumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Progress {
+    /// A match is under way.
+    Matching,
+    /// The last item completed a match.
+    Completed,
+    /// The items cannot be part of a match ending at the last of them.
+    Failed,
+}
+
+impl<T, Context> PatternElement<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Create a new pattern element matching the items of a complete match of
+    /// `sub`.
+    ///
+    /// The nested matcher's patterns, settings, and extractors are used as
+    /// configured; its stream state is not, as every attempt starts it over.
+    pub fn matcher(sub: Matcher<T, Context>) -> Self {
+        let settings = ElementSettings {
+            max_repeat: usize::MAX,
+            ..Default::default()
+        };
+        Self::matcher_with_settings(sub, settings)
+    }
+
+    /// Create a new nested matcher pattern element with settings.
+    pub fn matcher_with_settings(
+        sub: Matcher<T, Context>,
+        settings: ElementSettings<Context>,
+    ) -> Self {
+        PatternElement::Nested {
+            matcher: Box::new(sub),
+            settings: Some(settings),
+        }
+    }
+}
+
+impl<T, Context> Matcher<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Check whether a nested matcher can take `item` after `run`, the items
+    /// the element has already consumed, which end `captured`.
+    pub(crate) fn nested_accepts(
+        &self,
+        sub: &Matcher<T, Context>,
+        item: &T,
+        captured: &[Capture<T>],
+        run: usize,
+    ) -> Result<bool, MatcherError> {
+        let items = captured[captured.len() - run..]
+            .iter()
+            .map(|capture| (&capture.item, capture.timestamp))
+            .chain([(item, self.last_timestamp)]);
+        Ok(nested_progress(sub, items)? != Progress::Failed)
+    }
+
+    /// Check whether the last of `run`, the items a nested matcher element
+    /// has consumed, which end `captured`, completed a match.
+    pub(crate) fn nested_complete(
+        &self,
+        sub: &Matcher<T, Context>,
+        captured: &[Capture<T>],
+        run: usize,
+    ) -> Result<bool, MatcherError> {
+        let items = captured[captured.len() - run..]
+            .iter()
+            .map(|capture| (&capture.item, capture.timestamp));
+        Ok(nested_progress(sub, items)? == Progress::Completed)
+    }
+}
+
+/// Replay items through a fresh copy of a nested matcher.
+///
+/// Every item must continue a single match attempt, and only the last may
+/// complete it.
+fn nested_progress<'a, T, Context>(
+    sub: &Matcher<T, Context>,
+    items: impl IntoIterator<Item = (&'a T, Duration)>,
+) -> Result<Progress, MatcherError>
+where
+    T: Clone + PartialEq + fmt::Debug + 'a,
+    Context: Clone + fmt::Debug,
+{
+    let mut sub = sub.clone();
+    sub.reset();
+    let mut progress = Progress::Matching;
+    for (item, timestamp) in items {
+        if progress == Progress::Completed {
+            return Ok(Progress::Failed);
+        }
+        progress = if !sub.process_captures(item.clone(), timestamp)?.is_empty() {
+            Progress::Completed
+        } else if sub.captured.is_empty() && sub.pending.is_none() {
+            return Ok(Progress::Failed);
+        } else {
+            Progress::Matching
+        };
+    }
+    Ok(progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_matcher() {
+        // A handshake of SYN, SYN-ACK, and ACK, reused inside a session
+        let mut handshake = Matcher::<&str, ()>::new(10);
+        handshake.add_pattern(PatternElement::exact("SYN"));
+        handshake.add_pattern(PatternElement::exact("SYN-ACK"));
+        handshake.add_pattern(PatternElement::exact("ACK"));
+
+        let mut session = Matcher::<&str, ()>::new(10);
+        session.add_pattern(PatternElement::exact("connect"));
+        session.add_pattern(PatternElement::matcher(handshake));
+        session.add_pattern(PatternElement::exact("data"));
+        assert_eq!(
+            session.patterns()[1].to_string(),
+            "Nested(Exact(\"SYN\"), Exact(\"SYN-ACK\"), Exact(\"ACK\"))"
+        );
+
+        let stream = vec![
+            "connect", "SYN", "SYN-ACK", "ACK", "data", // complete
+            "connect", "SYN", "ACK", "data", // broken handshake
+            "connect", "SYN", "SYN-ACK", "data", // unfinished handshake
+        ];
+        let spans: Vec<_> = stream
+            .into_iter()
+            .flat_map(|item| session.process_item_reports(item).unwrap())
+            .map(|report| report.span)
+            .collect();
+        assert_eq!(spans, vec![0..5]);
+    }
+}
@@ -0,0 +1,28 @@
+//! Observer hooks for the matcher lifecycle.
+//!
+//! Observers are notified as items move a match attempt forward, fail to
+//! match, abandon a partial match, or complete a match. They are intended for
+//! debugging and monitoring rule sets without changing the patterns or their
+//! extractors.
+
+use crate::{Capture, MaybeSync};
+
+/// Receives lifecycle events from a `Matcher`.
+///
+/// Every method has an empty default implementation, so an observer only
+/// implements the events it cares about. With the `sync` feature, observers
+/// must be `Send + Sync`.
+pub trait MatcherObserver<T>: MaybeSync {
+    /// Called when a match completes.
+    fn on_match(&mut self, _completed: &Capture<T>) {}
+
+    /// Called when the element at `position` rejects an item.
+    fn on_mismatch(&mut self, _position: usize, _item: &T) {}
+
+    /// Called when a partial match waiting at `position` is abandoned.
+    fn on_reset(&mut self, _position: usize, _captured: &[Capture<T>]) {}
+
+    /// Called when the element at `position` consumes an item without
+    /// completing the pattern.
+    fn on_partial(&mut self, _position: usize, _capture: &Capture<T>) {}
+}
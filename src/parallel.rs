@@ -0,0 +1,309 @@
+//! Parallel evaluation of many independent matchers.
+//!
+//! With hundreds of signature patterns, offering every item to each matcher
+//! in turn becomes the bottleneck. [`ParallelMatcherGroup`] hands a batch of
+//! items to all of its matchers at once on the rayon thread pool; each
+//! matcher still sees the items in order, so results are the same as running
+//! the matchers one after another.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+
+use crate::{GroupMatch, MatcherError, MatcherId, StreamMatcher};
+
+/// A matcher that can be moved to a worker thread.
+type SendMatcher<T> = Box<dyn StreamMatcher<T> + Send>;
+
+/// Matches and errors of a batch processed by a `ParallelMatcherGroup`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchOutcome<T> {
+    /// Matches of every matcher, ordered by stream index, then by matcher ID.
+    pub matches: Vec<GroupMatch<T>>,
+    /// The first error of each matcher that failed, ordered by matcher ID.
+    pub errors: Vec<(MatcherId, MatcherError)>,
+}
+
+/// Runs a batch of items through several matchers in parallel.
+///
+/// Unlike `MatcherGroup`, completions of named patterns are not shared
+/// between the matchers, since they run independently over each batch.
+/// Matchers must be `Send`, which `Matcher` is with the `sync` feature that
+/// the `rayon` feature enables.
+pub struct ParallelMatcherGroup<T> {
+    matchers: Vec<(MatcherId, SendMatcher<T>)>,
+    next_id: MatcherId,
+    epoch: Instant,
+    total_processed: usize,
+}
+
+impl<T> ParallelMatcherGroup<T>
+where
+    T: Clone + Send + Sync,
+{
+    /// Create an empty group.
+    pub fn new() -> Self {
+        Self {
+            matchers: Vec::new(),
+            next_id: 0,
+            epoch: Instant::now(),
+            total_processed: 0,
+        }
+    }
+
+    /// Add a matcher to the group, returning its ID.
+    pub fn add<M>(&mut self, matcher: M) -> MatcherId
+    where
+        M: StreamMatcher<T> + Send + 'static,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.matchers.push((id, Box::new(matcher)));
+        id
+    }
+
+    /// Remove a matcher from the group, returning whether it was present.
+    pub fn remove(&mut self, id: MatcherId) -> bool {
+        let before = self.matchers.len();
+        self.matchers.retain(|(existing, _)| *existing != id);
+        self.matchers.len() != before
+    }
+
+    /// Get the number of matchers in the group.
+    pub fn len(&self) -> usize {
+        self.matchers.len()
+    }
+
+    /// Check if the group has no matchers.
+    pub fn is_empty(&self) -> bool {
+        self.matchers.is_empty()
+    }
+
+    /// Process a batch of items with every matcher in parallel.
+    ///
+    /// Every matcher processes the whole batch even if another fails, so that
+    /// they stay aligned on the stream, and the matches of every matcher are
+    /// returned alongside the errors of those that failed.
+    ///
+    /// The items are all timestamped with the time elapsed since the group
+    /// was created, as they arrived together.
+    pub fn process_items(&mut self, items: &[T]) -> BatchOutcome<T> {
+        let timestamp = self.epoch.elapsed();
+        self.process_batch(items.len(), |position| (&items[position], timestamp))
+    }
+
+    /// Process a batch of items, each paired with the timestamp it was
+    /// observed at.
+    pub fn process_items_at(&mut self, items: &[(T, Duration)]) -> BatchOutcome<T> {
+        self.process_batch(items.len(), |position| {
+            let (item, timestamp) = &items[position];
+            (item, *timestamp)
+        })
+    }
+
+    /// Process the `len` items of a batch, looked up by their position in it
+    /// with `item_at`, with every matcher in parallel.
+    fn process_batch<'a, F>(&mut self, len: usize, item_at: F) -> BatchOutcome<T>
+    where
+        F: Fn(usize) -> (&'a T, Duration) + Sync,
+        T: 'a,
+    {
+        self.total_processed += len;
+        let outcomes: Vec<BatchOutcome<T>> = self
+            .matchers
+            .par_iter_mut()
+            .map(|(id, matcher)| {
+                let mut found = Vec::new();
+                let mut first_error = None;
+                for (item, timestamp) in (0..len).map(&item_at) {
+                    match matcher.process_captures_at(item.clone(), timestamp) {
+                        Ok(captures) => {
+                            let name = matcher.pattern_name();
+                            found.extend(captures.into_iter().map(|capture| GroupMatch {
+                                matcher: *id,
+                                pattern: name.map(str::to_string),
+                                value: capture.item,
+                                index: capture.index,
+                            }))
+                        }
+                        Err(err) => {
+                            first_error.get_or_insert((*id, err));
+                        }
+                    }
+                }
+                BatchOutcome {
+                    matches: found,
+                    errors: first_error.into_iter().collect(),
+                }
+            })
+            .collect();
+
+        let mut matches = Vec::new();
+        let mut errors = Vec::new();
+        for outcome in outcomes {
+            matches.extend(outcome.matches);
+            errors.extend(outcome.errors);
+        }
+        matches.sort_by_key(|found| (found.index, found.matcher));
+        BatchOutcome { matches, errors }
+    }
+
+    /// Get the total number of items processed.
+    pub fn total_processed(&self) -> usize {
+        self.total_processed
+    }
+
+    /// Reset every matcher in the group.
+    pub fn reset(&mut self) {
+        for (_, matcher) in &mut self.matchers {
+            matcher.reset();
+        }
+        self.total_processed = 0;
+    }
+}
+
+impl<T> Default for ParallelMatcherGroup<T>
+where
+    T: Clone + Send + Sync,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for ParallelMatcherGroup<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParallelMatcherGroup")
+            .field(
+                "ids",
+                &self.matchers.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            )
+            .field("total_processed", &self.total_processed)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Capture, Matcher, PatternElement, PatternSettings};
+
+    /// Matches every item divisible by a fixed value.
+    struct Multiple {
+        of: u64,
+        processed: usize,
+    }
+
+    impl StreamMatcher<u64> for Multiple {
+        fn process_captures_at(
+            &mut self,
+            item: u64,
+            timestamp: Duration,
+        ) -> Result<Vec<Capture<u64>>, MatcherError> {
+            let index = self.processed;
+            self.processed += 1;
+            Ok((item % self.of == 0)
+                .then_some(Capture {
+                    item,
+                    index,
+                    timestamp,
+                })
+                .into_iter()
+                .collect())
+        }
+
+        fn pattern_name(&self) -> Option<&str> {
+            None
+        }
+
+        fn record_completion(&mut self, _pattern: &str, _index: usize, _timestamp: Duration) {}
+
+        fn reset(&mut self) {
+            self.processed = 0;
+        }
+    }
+
+    #[test]
+    fn test_parallel_group_orders_matches() {
+        let mut group = ParallelMatcherGroup::new();
+        let ids: Vec<MatcherId> = (2..=100)
+            .map(|of| group.add(Multiple { of, processed: 0 }))
+            .collect();
+        assert_eq!(group.len(), 99);
+
+        let found = group.process_items(&[7, 12, 97]).matches;
+        let summary: Vec<(usize, u64)> = found
+            .iter()
+            .map(|found| (found.index, found.value))
+            .collect();
+        // 7 and 97 are prime, and 12 has five divisors in range
+        assert_eq!(
+            summary,
+            vec![(0, 7), (1, 12), (1, 12), (1, 12), (1, 12), (1, 12), (2, 97),]
+        );
+        assert_eq!(found[0].matcher, ids[5]);
+        assert_eq!(group.total_processed(), 3);
+    }
+
+    #[test]
+    fn test_parallel_group_runs_matchers() {
+        let mut group = ParallelMatcherGroup::new();
+        for step in 1..=3u64 {
+            let mut matcher = Matcher::<u64, ()>::new(5);
+            matcher.add_pattern(PatternElement::predicate(|item: &u64| *item > 0));
+            matcher.add_pattern(PatternElement::pair_predicate(move |previous, current| {
+                *current == *previous + step
+            }));
+            group.add(matcher);
+        }
+
+        let found = group.process_items(&[1, 3, 4, 5]).matches;
+        let summary: Vec<(usize, MatcherId)> = found
+            .iter()
+            .map(|found| (found.index, found.matcher))
+            .collect();
+        assert_eq!(summary, vec![(1, 1), (3, 0)]);
+    }
+
+    #[test]
+    fn test_parallel_group_uses_item_timestamps() {
+        let mut group = ParallelMatcherGroup::new();
+        let mut matcher = Matcher::<u64, ()>::new(5);
+        matcher.set_pattern_settings(PatternSettings::default().within(Duration::from_secs(10)));
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::exact(2));
+        group.add(matcher);
+
+        let at = Duration::from_secs;
+        // The second pair arrives too late for the first item of the pair
+        let found = group
+            .process_items_at(&[(1, at(0)), (2, at(20)), (1, at(30)), (2, at(35))])
+            .matches;
+        let indices: Vec<usize> = found.iter().map(|found| found.index).collect();
+        assert_eq!(indices, vec![3]);
+    }
+
+    #[test]
+    fn test_parallel_group_keeps_matches_of_other_matchers_on_error() {
+        let mut group = ParallelMatcherGroup::new();
+        group.add(Multiple {
+            of: 2,
+            processed: 0,
+        });
+        let failing = group.add(Matcher::<u64, ()>::new(5));
+        group.add(Multiple {
+            of: 3,
+            processed: 0,
+        });
+
+        let outcome = group.process_items(&[2, 3, 6]);
+        let summary: Vec<(usize, MatcherId)> = outcome
+            .matches
+            .iter()
+            .map(|found| (found.index, found.matcher))
+            .collect();
+        assert_eq!(summary, vec![(0, 0), (1, 2), (2, 0), (2, 2)]);
+        assert_eq!(outcome.errors, vec![(failing, MatcherError::NoPatterns)]);
+    }
+}
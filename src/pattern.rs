@@ -0,0 +1,1190 @@
+//! Patterns composed from smaller patterns.
+//!
+//! A [`Pattern`] is built from pattern elements with combinators, so complex
+//! rules are assembled from small reusable pieces rather than spelled out as
+//! one flat element list:
+//!
+//! ```rust
+//! use scrolling_window_pattern_matcher::{Matcher, Pattern, PatternElement};
+//!
+//! let digit = Pattern::from(PatternElement::<char, ()>::predicate(|c: &char| c.is_ascii_digit()));
+//! let sign = Pattern::from(PatternElement::exact('+')).or(PatternElement::exact('-'));
+//! let field = sign.optional().then(digit.repeat(1..=3)).then(PatternElement::exact(';'));
+//!
+//! let mut matcher = Matcher::with_patterns(field.into_elements().unwrap(), 10);
+//! let found = matcher.process_items("x-42;+7;".chars().collect()).unwrap();
+//! assert_eq!(found, vec![';', ';']);
+//! ```
+//!
+//! [`Pattern::into_elements`] keeps the plain elements of a sequence as
+//! separate elements of the matcher's pattern, and turns every other part into
+//! a composite element that consumes the items of that part as a unit.
+//!
+//! Patterns display in a compact syntax that parses back with `str::parse`,
+//! so data patterns can be logged, diffed, and stored in configuration:
+//! parts of a sequence are separated by spaces, alternatives by `|`, and
+//! counts follow a part as `?`, `{min,max}`, or `{min,}`. The settings of an
+//! element follow it in brackets, except for its context, which is not
+//! written.
+//!
+//! ```rust
+//! use scrolling_window_pattern_matcher::Pattern;
+//!
+//! let source = "((Exact(1) | Exact(2)) Range(5..=9){1,3} Exact(0)[repeat = 2.., lazy, max_gap = 1.5s])";
+//! let pattern: Pattern<i32, ()> = source.parse().unwrap();
+//! assert_eq!(pattern.to_string(), source);
+//! ```
+
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::{
+    Capture, ElementSettings, ExtractorId, Matcher, MatcherError, PatternElement, RangeOrder,
+    RepeatMode, StreamOffset,
+};
+
+/// A pattern built from elements with the `then`, `or`, `repeat`, and
+/// `optional` combinators.
+///
+/// Elements inside an alternation or repetition must each match exactly one
+/// item; [`Pattern::into_elements`] rejects any that do not. Patterns compare
+/// and hash structurally, like their elements.
+#[derive(PartialEq, Eq, Hash)]
+pub struct Pattern<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    node: Node<T, Context>,
+}
+
+/// A part of a composed pattern.
+#[derive(PartialEq, Eq, Hash)]
+enum Node<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    Element(PatternElement<T, Context>),
+    Sequence(Vec<Node<T, Context>>),
+    Alternation(Vec<Node<T, Context>>),
+    Repeat {
+        node: Box<Node<T, Context>>,
+        min: usize,
+        max: usize,
+    },
+}
+
+impl<T, Context> Clone for Node<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    fn clone(&self) -> Self {
+        match self {
+            Node::Element(element) => Node::Element(element.clone()),
+            Node::Sequence(nodes) => Node::Sequence(nodes.clone()),
+            Node::Alternation(nodes) => Node::Alternation(nodes.clone()),
+            Node::Repeat { node, min, max } => Node::Repeat {
+                node: node.clone(),
+                min: *min,
+                max: *max,
+            },
+        }
+    }
+}
+
+impl<T, Context> fmt::Display for Node<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Node::Element(element) => element.fmt_with(f, true),
+            Node::Sequence(nodes) => {
+                let nodes: Vec<String> = nodes.iter().map(ToString::to_string).collect();
+                write!(f, "({})", nodes.join(" "))
+            }
+            Node::Alternation(nodes) => {
+                let nodes: Vec<String> = nodes.iter().map(ToString::to_string).collect();
+                write!(f, "({})", nodes.join(" | "))
+            }
+            Node::Repeat { node, min, max } => match (*min, *max) {
+                (0, 1) => write!(f, "{}?", node),
+                (min, usize::MAX) => write!(f, "{}{{{},}}", node, min),
+                (min, max) => write!(f, "{}{{{},{}}}", node, min, max),
+            },
+        }
+    }
+}
+
+impl<T, Context> Node<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Fewest items the node matches.
+    fn min_len(&self) -> usize {
+        match self {
+            Node::Element(_) => 1,
+            Node::Sequence(nodes) => nodes.iter().map(Node::min_len).sum(),
+            Node::Alternation(nodes) => nodes.iter().map(Node::min_len).min().unwrap_or(0),
+            Node::Repeat { node, min, .. } => node.min_len().saturating_mul(*min),
+        }
+    }
+
+    /// Most items the node matches, `usize::MAX` if unbounded.
+    fn max_len(&self) -> usize {
+        match self {
+            Node::Element(_) => 1,
+            Node::Sequence(nodes) => nodes
+                .iter()
+                .fold(0, |total, node| total.saturating_add(node.max_len())),
+            Node::Alternation(nodes) => nodes.iter().map(Node::max_len).max().unwrap_or(0),
+            Node::Repeat { node, max, .. } => node.max_len().saturating_mul(*max),
+        }
+    }
+
+    /// Find an element that cannot be part of a composite element.
+    fn invalid_element(&self) -> Option<&PatternElement<T, Context>> {
+        match self {
+            Node::Element(element) => (element.is_assertion()
+                || element.settings().repeat_bounds() != (1, 1))
+                .then_some(element),
+            Node::Sequence(nodes) | Node::Alternation(nodes) => {
+                nodes.iter().find_map(Node::invalid_element)
+            }
+            Node::Repeat { node, .. } => node.invalid_element(),
+        }
+    }
+}
+
+impl<T, Context> Pattern<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Create a pattern matching a single element.
+    pub fn element(element: PatternElement<T, Context>) -> Self {
+        Self {
+            node: Node::Element(element),
+        }
+    }
+
+    /// Match this pattern followed by `next`.
+    pub fn then(self, next: impl Into<Self>) -> Self {
+        let mut nodes = match self.node {
+            Node::Sequence(nodes) => nodes,
+            node => vec![node],
+        };
+        match next.into().node {
+            Node::Sequence(next) => nodes.extend(next),
+            node => nodes.push(node),
+        }
+        Self {
+            node: Node::Sequence(nodes),
+        }
+    }
+
+    /// Match either this pattern or `other`.
+    pub fn or(self, other: impl Into<Self>) -> Self {
+        let mut nodes = match self.node {
+            Node::Alternation(nodes) => nodes,
+            node => vec![node],
+        };
+        match other.into().node {
+            Node::Alternation(other) => nodes.extend(other),
+            node => nodes.push(node),
+        }
+        Self {
+            node: Node::Alternation(nodes),
+        }
+    }
+
+    /// Match this pattern a number of times within `counts`, such as `2..=5`
+    /// or `1..` for one or more.
+    ///
+    /// An empty range matches nothing.
+    pub fn repeat(self, counts: impl RangeBounds<usize>) -> Self {
+        let min = match counts.start_bound() {
+            Bound::Included(&min) => min,
+            Bound::Excluded(&min) => min.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let max = match counts.end_bound() {
+            Bound::Included(&max) => Some(max),
+            Bound::Excluded(&max) => max.checked_sub(1),
+            Bound::Unbounded => Some(usize::MAX),
+        };
+        // An empty range such as `0..0` leaves no count that can match
+        let (min, max) = max.map_or((1, 0), |max| (min, max));
+        Self {
+            node: Node::Repeat {
+                node: Box::new(self.node),
+                min,
+                max,
+            },
+        }
+    }
+
+    /// Match this pattern or nothing.
+    pub fn optional(self) -> Self {
+        self.repeat(0..=1)
+    }
+
+    /// Fewest items the pattern matches.
+    pub fn min_len(&self) -> usize {
+        self.node.min_len()
+    }
+
+    /// Most items the pattern matches, `usize::MAX` if unbounded.
+    pub fn max_len(&self) -> usize {
+        self.node.max_len()
+    }
+
+    /// Turn the pattern into elements to add to a matcher.
+    ///
+    /// Each plain element of a sequence stays an element of its own, and any
+    /// other part becomes a composite element. Fails if an element inside a
+    /// composite can match other than exactly one item, or is an assertion.
+    pub fn into_elements(self) -> Result<Vec<PatternElement<T, Context>>, MatcherError> {
+        let nodes = match self.node {
+            Node::Sequence(nodes) => nodes,
+            node => vec![node],
+        };
+        nodes.into_iter().map(Self::into_element).collect()
+    }
+
+    /// Turn a top-level part of the pattern into an element.
+    fn into_element(node: Node<T, Context>) -> Result<PatternElement<T, Context>, MatcherError> {
+        if let Node::Element(element) = node {
+            return Ok(element);
+        }
+        if let Some(element) = node.invalid_element() {
+            return Err(MatcherError::InvalidPattern(format!(
+                "{} does not match exactly one item, so it cannot be part of a composite",
+                element
+            )));
+        }
+        let settings = ElementSettings {
+            min_repeat: node.min_len(),
+            max_repeat: node.max_len(),
+            ..Default::default()
+        };
+        Ok(PatternElement::Composite {
+            pattern: Box::new(Pattern { node }),
+            settings: Some(settings),
+        })
+    }
+}
+
+impl<T, Context> Clone for Pattern<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    fn clone(&self) -> Self {
+        Self {
+            node: self.node.clone(),
+        }
+    }
+}
+
+impl<T, Context> fmt::Debug for Pattern<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Pattern").field(&self.to_string()).finish()
+    }
+}
+
+impl<T, Context> fmt::Display for Pattern<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.node)
+    }
+}
+
+/// Collecting patterns or elements matches them one after another.
+impl<T, Context, P> FromIterator<P> for Pattern<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+    P: Into<Pattern<T, Context>>,
+{
+    fn from_iter<I: IntoIterator<Item = P>>(parts: I) -> Self {
+        let mut pattern = Self {
+            node: Node::Sequence(Vec::new()),
+        };
+        pattern.extend(parts);
+        pattern
+    }
+}
+
+/// Extending a pattern appends patterns or elements to match after it.
+impl<T, Context, P> Extend<P> for Pattern<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+    P: Into<Pattern<T, Context>>,
+{
+    fn extend<I: IntoIterator<Item = P>>(&mut self, parts: I) {
+        let node = std::mem::replace(&mut self.node, Node::Sequence(Vec::new()));
+        let mut nodes = match node {
+            Node::Sequence(nodes) => nodes,
+            node => vec![node],
+        };
+        for part in parts {
+            match part.into().node {
+                Node::Sequence(part) => nodes.extend(part),
+                node => nodes.push(node),
+            }
+        }
+        self.node = Node::Sequence(nodes);
+    }
+}
+
+impl<T, Context> From<PatternElement<T, Context>> for Pattern<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    fn from(element: PatternElement<T, Context>) -> Self {
+        Self::element(element)
+    }
+}
+
+impl<T, Context> Matcher<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Check whether a composite element accepts `item` at stream position
+    /// `index` after `run`, the items it has already consumed, which end
+    /// `captured`.
+    pub(crate) fn composite_accepts(
+        &self,
+        pattern: &Pattern<T, Context>,
+        item: &T,
+        index: usize,
+        captured: &[Capture<T>],
+        run: usize,
+    ) -> Result<bool, MatcherError> {
+        let base = captured.len() - run;
+        let accepts = |element: &PatternElement<T, Context>, at: usize| {
+            if at == run {
+                self.element_matches(element, item, index, 0, captured)
+            } else {
+                let capture = &captured[base + at];
+                self.element_matches(
+                    element,
+                    &capture.item,
+                    capture.index,
+                    0,
+                    &captured[..base + at],
+                )
+            }
+        };
+        let mut partial = false;
+        let ends = ends(&pattern.node, 0, run + 1, &accepts, &mut partial)?;
+        Ok(partial || ends.contains(&(run + 1)))
+    }
+
+    /// Check whether a composite element has matched a whole number of items
+    /// of its pattern with `run`, the items it has consumed, which end
+    /// `captured`.
+    pub(crate) fn composite_complete(
+        &self,
+        pattern: &Pattern<T, Context>,
+        captured: &[Capture<T>],
+        run: usize,
+    ) -> Result<bool, MatcherError> {
+        let base = captured.len() - run;
+        let accepts = |element: &PatternElement<T, Context>, at: usize| {
+            let capture = &captured[base + at];
+            self.element_matches(
+                element,
+                &capture.item,
+                capture.index,
+                0,
+                &captured[..base + at],
+            )
+        };
+        let mut partial = false;
+        Ok(ends(&pattern.node, 0, run, &accepts, &mut partial)?.contains(&run))
+    }
+}
+
+/// Find every position `node` can end at when matched against items `start`
+/// to `len` of a run, checking an element against the item at a position with
+/// `accepts`.
+///
+/// Sets `partial` if the node could go on past the last item.
+fn ends<T, Context, F>(
+    node: &Node<T, Context>,
+    start: usize,
+    len: usize,
+    accepts: &F,
+    partial: &mut bool,
+) -> Result<Vec<usize>, MatcherError>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+    F: Fn(&PatternElement<T, Context>, usize) -> Result<bool, MatcherError>,
+{
+    match node {
+        Node::Element(element) => {
+            if start == len {
+                *partial = true;
+                return Ok(Vec::new());
+            }
+            Ok(if accepts(element, start)? {
+                vec![start + 1]
+            } else {
+                Vec::new()
+            })
+        }
+        Node::Sequence(nodes) => {
+            let mut positions = vec![start];
+            for node in nodes {
+                positions = ends_from(node, &positions, len, accepts, partial)?;
+                if positions.is_empty() {
+                    break;
+                }
+            }
+            Ok(positions)
+        }
+        Node::Alternation(nodes) => {
+            let mut positions = Vec::new();
+            for node in nodes {
+                positions.extend(ends(node, start, len, accepts, partial)?);
+            }
+            positions.sort_unstable();
+            positions.dedup();
+            Ok(positions)
+        }
+        Node::Repeat { node, min, max } => {
+            let mut found = if *min == 0 { vec![start] } else { Vec::new() };
+            let mut frontier = vec![start];
+            let mut count = 0;
+            while count < *max && !frontier.is_empty() {
+                frontier = ends_from(node, &frontier, len, accepts, partial)?;
+                count += 1;
+                if count >= *min {
+                    // Repetitions that consume nothing cannot reach anything new
+                    frontier.retain(|position| !found.contains(position));
+                    found.extend(&frontier);
+                }
+            }
+            found.sort_unstable();
+            Ok(found)
+        }
+    }
+}
+
+/// Find every position `node` can end at when started at any of `starts`.
+fn ends_from<T, Context, F>(
+    node: &Node<T, Context>,
+    starts: &[usize],
+    len: usize,
+    accepts: &F,
+    partial: &mut bool,
+) -> Result<Vec<usize>, MatcherError>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+    F: Fn(&PatternElement<T, Context>, usize) -> Result<bool, MatcherError>,
+{
+    let mut positions = Vec::new();
+    for &start in starts {
+        positions.extend(ends(node, start, len, accepts, partial)?);
+    }
+    positions.sort_unstable();
+    positions.dedup();
+    Ok(positions)
+}
+
+/// Write the settings of an element that differ from the defaults in
+/// brackets, leaving out the repeat bounds unless `repeat` is set.
+pub(crate) fn write_settings<Context>(
+    f: &mut fmt::Formatter<'_>,
+    settings: &ElementSettings<Context>,
+    repeat: bool,
+) -> fmt::Result
+where
+    Context: Clone + fmt::Debug,
+{
+    let mut parts = Vec::new();
+    if repeat {
+        if settings.optional {
+            parts.push("optional".to_string());
+        }
+        match (settings.min_repeat, settings.max_repeat) {
+            (1, 1) => {}
+            (min, usize::MAX) => parts.push(format!("repeat = {}..", min)),
+            (min, max) => parts.push(format!("repeat = {}..={}", min, max)),
+        }
+        if settings.repeat_mode == RepeatMode::Lazy {
+            parts.push("lazy".to_string());
+        }
+    }
+    if let Some(id) = settings.extractor_id {
+        parts.push(format!("extractor_id = {}", id));
+    }
+    match settings.max_gap {
+        Some(StreamOffset::Item(items)) => parts.push(format!("max_gap = {}", items)),
+        Some(StreamOffset::Time(time)) => parts.push(format!("max_gap = {:?}", time)),
+        None => {}
+    }
+    if let Some(timeout) = settings.timeout_ms {
+        parts.push(format!("timeout_ms = {}", timeout));
+    }
+    if settings.max_retries != 0 {
+        parts.push(format!("max_retries = {}", settings.max_retries));
+    }
+    if parts.is_empty() {
+        Ok(())
+    } else {
+        write!(f, "[{}]", parts.join(", "))
+    }
+}
+
+/// Parse a duration in the form `Duration` is debug-formatted in, such as
+/// `1.5s` or `250µs`.
+fn parse_duration(text: &str) -> Option<Duration> {
+    let (number, nanos_per_unit) = [
+        ("ns", 1),
+        ("µs", 1_000),
+        ("ms", 1_000_000),
+        ("s", 1_000_000_000),
+    ]
+    .into_iter()
+    .find_map(|(unit, nanos)| Some((text.strip_suffix(unit)?, nanos)))?;
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut nanos = whole.parse::<u128>().ok()?.checked_mul(nanos_per_unit)?;
+    if !fraction.is_empty() {
+        nanos +=
+            fraction.parse::<u128>().ok()? * nanos_per_unit / 10u128.pow(fraction.len() as u32);
+    }
+    let secs = u64::try_from(nanos / 1_000_000_000).ok()?;
+    Some(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+}
+
+/// Parses the syntax patterns are displayed in.
+///
+/// Only data elements can be parsed: exact values, ranges, back references,
+/// assertions over them, groups, and composites. Elements built from closures
+/// are displayed as `<function>` and cannot be read back, and neither can
+/// ranges with a total or custom ordering. Parsed elements have no context.
+impl<T, Context> FromStr for Pattern<T, Context>
+where
+    T: Clone + PartialEq + PartialOrd + fmt::Debug + FromStr,
+    Context: Clone + fmt::Debug,
+{
+    type Err = MatcherError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            source,
+            position: 0,
+        };
+        let node = parser.expression()?;
+        parser.skip_whitespace();
+        if parser.position < source.len() {
+            return Err(parser.error("expected the end of the pattern"));
+        }
+        Ok(Self { node })
+    }
+}
+
+/// Recursive-descent parser for displayed patterns.
+struct Parser<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Parse alternatives separated by `|`.
+    fn expression<T, Context>(&mut self) -> Result<Node<T, Context>, MatcherError>
+    where
+        T: Clone + PartialEq + PartialOrd + fmt::Debug + FromStr,
+        Context: Clone + fmt::Debug,
+    {
+        let mut nodes = vec![self.sequence()?];
+        while self.eat("|") {
+            nodes.push(self.sequence()?);
+        }
+        Ok(if nodes.len() == 1 {
+            nodes.remove(0)
+        } else {
+            Node::Alternation(nodes)
+        })
+    }
+
+    /// Parse parts separated by whitespace.
+    fn sequence<T, Context>(&mut self) -> Result<Node<T, Context>, MatcherError>
+    where
+        T: Clone + PartialEq + PartialOrd + fmt::Debug + FromStr,
+        Context: Clone + fmt::Debug,
+    {
+        let mut nodes = vec![self.repetition()?];
+        loop {
+            self.skip_whitespace();
+            match self.rest().chars().next() {
+                None | Some('|') | Some(')') => break,
+                _ => nodes.push(self.repetition()?),
+            }
+        }
+        Ok(if nodes.len() == 1 {
+            nodes.remove(0)
+        } else {
+            Node::Sequence(nodes)
+        })
+    }
+
+    /// Parse a part followed by any number of `?` and `{min,max}` counts.
+    fn repetition<T, Context>(&mut self) -> Result<Node<T, Context>, MatcherError>
+    where
+        T: Clone + PartialEq + PartialOrd + fmt::Debug + FromStr,
+        Context: Clone + fmt::Debug,
+    {
+        let mut node = if self.eat("(") {
+            let node = self.expression()?;
+            self.expect(")")?;
+            node
+        } else {
+            Node::Element(self.element()?)
+        };
+        loop {
+            let (min, max) = if self.rest().starts_with('?') {
+                self.position += 1;
+                (0, 1)
+            } else if self.rest().starts_with('{') {
+                self.position += 1;
+                let min = self.count()?;
+                self.expect(",")?;
+                let max = if self.eat("}") {
+                    usize::MAX
+                } else {
+                    let max = self.count()?;
+                    self.expect("}")?;
+                    max
+                };
+                (min, max)
+            } else {
+                return Ok(node);
+            };
+            node = Node::Repeat {
+                node: Box::new(node),
+                min,
+                max,
+            };
+        }
+    }
+
+    /// Parse a single element.
+    fn element<T, Context>(&mut self) -> Result<PatternElement<T, Context>, MatcherError>
+    where
+        T: Clone + PartialEq + PartialOrd + fmt::Debug + FromStr,
+        Context: Clone + fmt::Debug,
+    {
+        self.skip_whitespace();
+        let start = self.position;
+        let name = self.name();
+        if !self.rest().starts_with('(') {
+            self.position = start;
+            return Err(self.error("expected an element"));
+        }
+        self.position += 1;
+        let mut element = match name {
+            "Exact" => PatternElement::exact(self.value(&[")"])?),
+            "Range" | "Outside" => {
+                let (start, end) = self.bounds()?;
+                let order = if self.eat(",") {
+                    self.order()?
+                } else {
+                    RangeOrder::natural()
+                };
+                PatternElement::Range {
+                    start,
+                    end,
+                    order,
+                    negated: name == "Outside",
+                    settings: None,
+                }
+            }
+            "Ranges" => {
+                self.expect("[")?;
+                let mut intervals = Vec::new();
+                while !self.eat("]") {
+                    if !intervals.is_empty() {
+                        self.expect(",")?;
+                    }
+                    self.expect("(")?;
+                    let min = self.value(&[","])?;
+                    self.expect(",")?;
+                    let max = self.value(&[")"])?;
+                    self.expect(")")?;
+                    intervals.push((min, max));
+                }
+                PatternElement::ranges(intervals)
+            }
+            "BackReference" => PatternElement::back_reference(self.count()?),
+            "Lookahead" => PatternElement::lookahead(self.element()?),
+            "NegativeLookahead" => PatternElement::negative_lookahead(self.element()?),
+            "Lookbehind" => {
+                let inner = self.element()?;
+                self.expect(",")?;
+                PatternElement::lookbehind(inner, self.count()?)
+            }
+            "Group" | "OptionalGroup" => {
+                let mut elements = vec![self.element()?];
+                while self.eat(",") {
+                    elements.push(self.element()?);
+                }
+                if name == "Group" {
+                    PatternElement::group(elements)?
+                } else {
+                    PatternElement::optional_group(elements)?
+                }
+            }
+            "Composite" => Pattern::into_element(self.expression()?)?,
+            _ => {
+                self.position = start;
+                return Err(self.error("expected a data element"));
+            }
+        };
+        self.expect(")")?;
+        if self.rest().starts_with('[') {
+            self.position += 1;
+            let mut settings = element.settings();
+            self.settings(&mut settings)?;
+            *element.settings_mut() = Some(settings);
+        }
+        Ok(element)
+    }
+
+    /// Parse the ordering of a range.
+    fn order<T: PartialOrd>(&mut self) -> Result<RangeOrder<T>, MatcherError> {
+        self.skip_whitespace();
+        let start = self.position;
+        let name = self.name();
+        match name {
+            "Natural" => Ok(RangeOrder::natural()),
+            "NanAccepting" => Ok(RangeOrder::nan_accepting()),
+            _ => {
+                self.position = start;
+                Err(self.error("expected a natural or NaN-accepting ordering"))
+            }
+        }
+    }
+
+    /// Parse settings up to the closing bracket into `settings`.
+    fn settings<Context>(
+        &mut self,
+        settings: &mut ElementSettings<Context>,
+    ) -> Result<(), MatcherError>
+    where
+        Context: Clone + fmt::Debug,
+    {
+        loop {
+            self.skip_whitespace();
+            let start = self.position;
+            match self.name() {
+                "optional" => settings.optional = true,
+                "lazy" => settings.repeat_mode = RepeatMode::Lazy,
+                "repeat" => {
+                    self.expect("=")?;
+                    settings.min_repeat = self.count()?;
+                    self.expect("..")?;
+                    settings.max_repeat = if self.eat("=") {
+                        self.count()?
+                    } else {
+                        usize::MAX
+                    };
+                }
+                "extractor_id" => {
+                    self.expect("=")?;
+                    settings.extractor_id = Some(self.value::<ExtractorId>(&[",", "]"])?);
+                }
+                "max_gap" => {
+                    self.expect("=")?;
+                    let gap: String = self.value(&[",", "]"])?;
+                    let gap = match gap.parse() {
+                        Ok(items) => StreamOffset::Item(items),
+                        Err(_) => StreamOffset::Time(
+                            parse_duration(&gap).ok_or_else(|| self.error("invalid gap"))?,
+                        ),
+                    };
+                    settings.max_gap = Some(gap);
+                }
+                "timeout_ms" => {
+                    self.expect("=")?;
+                    settings.timeout_ms = Some(self.value(&[",", "]"])?);
+                }
+                "max_retries" => {
+                    self.expect("=")?;
+                    settings.max_retries = self.count()?;
+                }
+                _ => {
+                    self.position = start;
+                    return Err(self.error("expected a setting"));
+                }
+            }
+            if self.eat("]") {
+                return Ok(());
+            }
+            self.expect(",")?;
+        }
+    }
+
+    /// Consume a name made of letters, digits, and underscores.
+    fn name(&mut self) -> &'a str {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(rest.len());
+        self.position += len;
+        &rest[..len]
+    }
+
+    /// Parse the bounds of a range, written as Rust range syntax, with
+    /// `start<..` for a start that is excluded.
+    fn bounds<T: FromStr>(&mut self) -> Result<(Bound<T>, Bound<T>), MatcherError> {
+        let start = if self.eat("..") {
+            Bound::Unbounded
+        } else {
+            let start = self.value(&["<..", ".."])?;
+            let excluded = self.eat("<");
+            self.expect("..")?;
+            if excluded {
+                Bound::Excluded(start)
+            } else {
+                Bound::Included(start)
+            }
+        };
+        let end = if self.eat("=") {
+            Bound::Included(self.value(&[",", ")"])?)
+        } else if self.rest().trim_start().starts_with([',', ')']) {
+            Bound::Unbounded
+        } else {
+            Bound::Excluded(self.value(&[",", ")"])?)
+        };
+        Ok((start, end))
+    }
+
+    /// Parse a value, either a quoted literal or the text up to the first of
+    /// `ends`.
+    fn value<T: FromStr>(&mut self, ends: &[&str]) -> Result<T, MatcherError> {
+        self.skip_whitespace();
+        let start = self.position;
+        let text = match self.rest().chars().next() {
+            Some(quote @ ('"' | '\'')) => self.quoted(quote)?,
+            _ => {
+                let len = ends
+                    .iter()
+                    .filter_map(|end| self.rest().find(end))
+                    .min()
+                    .unwrap_or(self.rest().len());
+                let text = self.rest()[..len].trim_end().to_string();
+                self.position += len;
+                text
+            }
+        };
+        text.parse().map_err(|_| {
+            self.position = start;
+            self.error("invalid value")
+        })
+    }
+
+    /// Parse a quoted literal, undoing the escapes of its `Debug` form.
+    fn quoted(&mut self, quote: char) -> Result<String, MatcherError> {
+        let mut chars = self.rest().char_indices().skip(1);
+        let mut text = String::new();
+        while let Some((offset, c)) = chars.next() {
+            if c == quote {
+                self.position += offset + 1;
+                return Ok(text);
+            }
+            if c != '\\' {
+                text.push(c);
+                continue;
+            }
+            let escaped = match chars.next().map(|(_, c)| c) {
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                Some('0') => '\0',
+                Some('u') => {
+                    let digits: String = chars
+                        .by_ref()
+                        .map(|(_, c)| c)
+                        .skip_while(|&c| c == '{')
+                        .take_while(|&c| c != '}')
+                        .collect();
+                    u32::from_str_radix(&digits, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| self.error("invalid unicode escape"))?
+                }
+                Some(c) => c,
+                None => break,
+            };
+            text.push(escaped);
+        }
+        Err(self.error("unterminated quoted value"))
+    }
+
+    /// Parse a count.
+    fn count(&mut self) -> Result<usize, MatcherError> {
+        self.skip_whitespace();
+        let len = self
+            .rest()
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.rest().len());
+        let count = self.rest()[..len]
+            .parse()
+            .map_err(|_| self.error("expected a count"))?;
+        self.position += len;
+        Ok(count)
+    }
+
+    /// Consume `token` if it comes next, after any whitespace.
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consume `token`, failing if something else comes next.
+    fn expect(&mut self, token: &str) -> Result<(), MatcherError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", token)))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.source[self.position..]
+    }
+
+    fn error(&self, message: &str) -> MatcherError {
+        MatcherError::InvalidPattern(format!("{} at column {}", message, self.position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Engine, MatchPolicy, NanPolicy, PatternSettings};
+
+    fn letter(c: char) -> Pattern<char, ()> {
+        Pattern::from(PatternElement::exact(c))
+    }
+
+    #[test]
+    fn test_combinators() {
+        // a (b c | d){2,3} e?
+        let pattern = letter('a')
+            .then(letter('b').then(letter('c')).or(letter('d')).repeat(2..=3))
+            .then(letter('e').optional());
+        assert_eq!(pattern.min_len(), 3);
+        assert_eq!(pattern.max_len(), 8);
+        assert_eq!(
+            pattern.to_string(),
+            "(Exact('a') ((Exact('b') Exact('c')) | Exact('d')){2,3} Exact('e')?)"
+        );
+
+        for engine in [Engine::Greedy, Engine::Backtracking] {
+            let mut matcher = Matcher::with_patterns(pattern.clone().into_elements().unwrap(), 10);
+            matcher.set_pattern_settings(
+                PatternSettings::default()
+                    .engine(engine)
+                    .match_policy(MatchPolicy::LongestMatch),
+            );
+            for (input, expected) in [
+                ("adbce", vec!['e']),
+                ("abcdx", vec!['d']),
+                ("adx", vec![]),
+                ("abcbcbcbcx", vec!['c']),
+            ] {
+                matcher.reset();
+                assert_eq!(
+                    matcher.process_items(input.chars().collect()).unwrap(),
+                    expected,
+                    "{} with {:?}",
+                    input,
+                    engine
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let pattern =
+            Pattern::<String, ()>::from(PatternElement::exact("say \"hi\"\n".to_string()))
+                .then(
+                    Pattern::from(PatternElement::range("a".to_string(), "f".to_string()))
+                        .or(PatternElement::outside("x".to_string(), "z".to_string()))
+                        .repeat(2..),
+                )
+                .then(
+                    PatternElement::optional_group(vec![PatternElement::exact("|".to_string())])
+                        .unwrap(),
+                )
+                .then(PatternElement::lookbehind(
+                    PatternElement::exact("(".to_string()),
+                    2,
+                ));
+        let source = pattern.to_string();
+        let parsed: Pattern<String, ()> = source.parse().unwrap();
+        assert_eq!(parsed.to_string(), source);
+
+        let elements = pattern.into_elements().unwrap();
+        let composite = elements[1].to_string();
+        assert_eq!(
+            composite,
+            "Composite((Range(\"a\"..=\"f\") | Outside(\"x\"..=\"z\")){2,})"
+        );
+        let parsed: Pattern<String, ()> = composite.parse().unwrap();
+        assert_eq!(parsed.to_string(), composite);
+
+        let numbers =
+            "(Ranges([(1.0, 3.0), (7.5, 9.0)]){0,4} BackReference(2) NegativeLookahead(Exact(-1.5)))";
+        let parsed: Pattern<f64, ()> = numbers.parse().unwrap();
+        assert_eq!(parsed.to_string(), numbers);
+
+        for source in [
+            "Range(1..5)",
+            "Range(1..=5)",
+            "Range(-3..)",
+            "Range(..10)",
+            "Range(..=10)",
+            "Range(..)",
+            "Outside(0<..=7)",
+        ] {
+            let parsed: Pattern<i32, ()> = source.parse().unwrap();
+            assert_eq!(parsed.to_string(), source);
+        }
+        assert_eq!(
+            "Range(1..5)".parse::<Pattern<i32, ()>>().unwrap(),
+            Pattern::from(PatternElement::from_range(1..5))
+        );
+    }
+
+    #[test]
+    fn test_parse_display_keeps_settings() {
+        let lazy = ElementSettings {
+            optional: true,
+            min_repeat: 2,
+            max_repeat: usize::MAX,
+            repeat_mode: RepeatMode::Lazy,
+            extractor_id: Some(7),
+            max_gap: Some(StreamOffset::Time(Duration::from_micros(1500))),
+            timeout_ms: Some(250),
+            max_retries: 3,
+            ..ElementSettings::default()
+        };
+        let gap = ElementSettings {
+            max_gap: Some(StreamOffset::Item(4)),
+            ..ElementSettings::default()
+        };
+        let original = Pattern::<f64, ()>::from(PatternElement::exact_with_settings(1.5, lazy))
+            .then(PatternElement::float_range(0.0, 1.0, NanPolicy::Accept).unwrap())
+            .then(PatternElement::from_range_with_settings(..2.5, gap.clone()))
+            .then(
+                Pattern::from(PatternElement::exact_with_settings(9.0, gap))
+                    .or(PatternElement::exact(8.0))
+                    .optional(),
+            );
+        let source = original.to_string();
+        assert_eq!(
+            source,
+            "(Exact(1.5)[optional, repeat = 2.., lazy, extractor_id = 7, max_gap = 1.5ms, \
+             timeout_ms = 250, max_retries = 3] Range(0.0..=1.0, NanAccepting) \
+             Range(..2.5)[max_gap = 4] (Exact(9.0)[max_gap = 4] | Exact(8.0))?)"
+        );
+        assert_eq!(source.parse::<Pattern<f64, ()>>().unwrap(), original);
+
+        let total =
+            PatternElement::<f64, ()>::float_range(0.0, 1.0, NanPolicy::TotalOrder).unwrap();
+        assert_eq!(total.to_string(), "Range(0.0..=1.0, Total)");
+        assert!(total.to_string().parse::<Pattern<f64, ()>>().is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for source in [
+            "",
+            "Exact(1",
+            "Exact(x)",
+            "Predicate(<function>)",
+            "Exact(1) Exact(2))",
+            "Exact(1){2}",
+            "(Exact(1) | Run{3,3})",
+        ] {
+            assert!(
+                matches!(
+                    source.parse::<Pattern<i32, ()>>(),
+                    Err(MatcherError::InvalidPattern(_))
+                ),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_collect_pattern() {
+        let mut word: Pattern<char, ()> = "ab".chars().map(PatternElement::exact).collect();
+        word.extend([letter('c').optional()]);
+        assert_eq!(word.to_string(), "(Exact('a') Exact('b') Exact('c')?)");
+        assert_eq!(
+            word,
+            letter('a').then(letter('b')).then(letter('c').optional())
+        );
+        assert_eq!(word.into_elements().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_into_elements() {
+        let elements = letter('a')
+            .then(letter('b').repeat(2..))
+            .then(letter('c').or(letter('d')))
+            .into_elements()
+            .unwrap();
+        assert_eq!(elements.len(), 3);
+        assert!(matches!(elements[0], PatternElement::Exact { .. }));
+        assert_eq!(elements[1].settings().repeat_bounds(), (2, usize::MAX));
+        assert_eq!(elements[2].settings().repeat_bounds(), (1, 1));
+        assert!(matches!(elements[2], PatternElement::Composite { .. }));
+
+        let repeated = Pattern::from(PatternElement::<char, ()>::run(3)).or(letter('x'));
+        assert!(matches!(
+            repeated.into_elements(),
+            Err(MatcherError::InvalidPattern(_))
+        ));
+    }
+}
@@ -0,0 +1,422 @@
+//! Matching over captured network packets.
+//!
+//! [`PcapReader`] reads the records of a classic libpcap capture file, and
+//! [`Matcher::process_pcap`] decodes each record into an item with a caller
+//! supplied decoder and matches it at the time it was captured. Time-based
+//! settings such as session gaps therefore follow capture time, not the time
+//! the file happens to be read.
+//!
+//! [`TcpPacket::decode`] is a ready-made decoder for TCP over Ethernet or raw
+//! IP, which is enough for intrusion-detection style patterns over handshake
+//! flags, such as a SYN answered by a SYN-ACK and then reset.
+//!
+//! The pcapng format is not supported.
+
+use std::fmt;
+use std::io::{self, Read};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::ops::BitOr;
+use std::time::Duration;
+
+use crate::{MatchEvent, Matcher, ReadError};
+
+/// Link type of Ethernet captures.
+const LINKTYPE_ETHERNET: u32 = 1;
+/// Link type of captures of bare IPv4 or IPv6 packets.
+const LINKTYPE_RAW: u32 = 101;
+/// Largest packet a record may claim to hold.
+const MAX_SNAPLEN: u32 = 256 * 1024;
+
+/// A packet record of a capture file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PcapRecord {
+    /// When the packet was captured, since the Unix epoch.
+    pub timestamp: Duration,
+    /// The captured bytes, starting at the link layer.
+    pub data: Vec<u8>,
+    /// Length of the packet on the wire, which may exceed the captured bytes.
+    pub original_len: u32,
+}
+
+/// Reads the packet records of a libpcap capture file.
+///
+/// Both byte orders and both microsecond and nanosecond timestamps are
+/// accepted.
+pub struct PcapReader<R> {
+    reader: R,
+    big_endian: bool,
+    nanoseconds: bool,
+    link_type: u32,
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Read the file header from `reader`.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0; 24];
+        reader.read_exact(&mut header)?;
+        let (big_endian, nanoseconds) = match header[..4] {
+            [0xd4, 0xc3, 0xb2, 0xa1] => (false, false),
+            [0xa1, 0xb2, 0xc3, 0xd4] => (true, false),
+            [0x4d, 0x3c, 0xb2, 0xa1] => (false, true),
+            [0xa1, 0xb2, 0x3c, 0x4d] => (true, true),
+            _ => return Err(invalid("not a pcap capture file".to_string())),
+        };
+        let mut pcap = Self {
+            reader,
+            big_endian,
+            nanoseconds,
+            link_type: 0,
+        };
+        pcap.link_type = pcap.u32_at(&header, 20);
+        Ok(pcap)
+    }
+
+    /// Link-layer header type of the captured packets, as registered with
+    /// tcpdump.org.
+    pub fn link_type(&self) -> u32 {
+        self.link_type
+    }
+
+    /// Read the next record, or `None` at the end of the file.
+    fn read_record(&mut self) -> io::Result<Option<PcapRecord>> {
+        let mut header = [0; 16];
+        let mut filled = 0;
+        while filled < header.len() {
+            match self.reader.read(&mut header[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(invalid("truncated record header".to_string())),
+                Ok(read) => filled += read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        let seconds = self.u32_at(&header, 0);
+        let fraction = self.u32_at(&header, 4);
+        let captured = self.u32_at(&header, 8);
+        if captured > MAX_SNAPLEN {
+            return Err(invalid(format!(
+                "record claims {} captured bytes",
+                captured
+            )));
+        }
+        let mut data = vec![0; captured as usize];
+        self.reader.read_exact(&mut data)?;
+
+        let nanos = if self.nanoseconds {
+            fraction
+        } else {
+            fraction.saturating_mul(1000)
+        };
+        Ok(Some(PcapRecord {
+            timestamp: Duration::new(u64::from(seconds), nanos.min(999_999_999)),
+            data,
+            original_len: self.u32_at(&header, 12),
+        }))
+    }
+
+    /// Read a field of a header in the file's byte order.
+    fn u32_at(&self, bytes: &[u8], at: usize) -> u32 {
+        let field = [bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]];
+        if self.big_endian {
+            u32::from_be_bytes(field)
+        } else {
+            u32::from_le_bytes(field)
+        }
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = io::Result<PcapRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// The control flags of a TCP segment.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TcpFlags(u8);
+
+impl TcpFlags {
+    /// No more data from the sender.
+    pub const FIN: TcpFlags = TcpFlags(0x01);
+    /// Synchronize sequence numbers.
+    pub const SYN: TcpFlags = TcpFlags(0x02);
+    /// Reset the connection.
+    pub const RST: TcpFlags = TcpFlags(0x04);
+    /// Push buffered data to the application.
+    pub const PSH: TcpFlags = TcpFlags(0x08);
+    /// The acknowledgment number is significant.
+    pub const ACK: TcpFlags = TcpFlags(0x10);
+    /// The urgent pointer is significant.
+    pub const URG: TcpFlags = TcpFlags(0x20);
+
+    /// Flags from the flag byte of a TCP header.
+    pub fn from_bits(bits: u8) -> Self {
+        TcpFlags(bits & 0x3f)
+    }
+
+    /// The flags as the flag byte of a TCP header.
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Whether every flag in `other` is set.
+    pub fn contains(self, other: TcpFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for TcpFlags {
+    type Output = TcpFlags;
+
+    fn bitor(self, other: TcpFlags) -> TcpFlags {
+        TcpFlags(self.0 | other.0)
+    }
+}
+
+impl fmt::Display for TcpFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = [
+            (TcpFlags::SYN, "SYN"),
+            (TcpFlags::FIN, "FIN"),
+            (TcpFlags::RST, "RST"),
+            (TcpFlags::PSH, "PSH"),
+            (TcpFlags::ACK, "ACK"),
+            (TcpFlags::URG, "URG"),
+        ];
+        let set: Vec<_> = names
+            .iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| *name)
+            .collect();
+        if set.is_empty() {
+            f.write_str("-")
+        } else {
+            f.write_str(&set.join("-"))
+        }
+    }
+}
+
+/// The addressing and flags of a captured TCP segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TcpPacket {
+    /// Address of the sender.
+    pub source: IpAddr,
+    /// Address of the receiver.
+    pub destination: IpAddr,
+    /// Port of the sender.
+    pub source_port: u16,
+    /// Port of the receiver.
+    pub destination_port: u16,
+    /// The segment's control flags.
+    pub flags: TcpFlags,
+}
+
+impl TcpPacket {
+    /// Decode the TCP segment in a record captured with the given link type.
+    ///
+    /// Ethernet, with or without a VLAN tag, and raw IP captures are
+    /// understood. Returns `None` for any other link type, for packets that
+    /// are not TCP, for IP fragments after the first, and for records too
+    /// short to hold the TCP flags.
+    pub fn decode(link_type: u32, record: &PcapRecord) -> Option<Self> {
+        let data = record.data.as_slice();
+        let packet = match link_type {
+            LINKTYPE_ETHERNET => {
+                let mut ether_type = u16::from_be_bytes([*data.get(12)?, *data.get(13)?]);
+                let mut start = 14;
+                if ether_type == 0x8100 {
+                    ether_type = u16::from_be_bytes([*data.get(16)?, *data.get(17)?]);
+                    start = 18;
+                }
+                if ether_type != 0x0800 && ether_type != 0x86dd {
+                    return None;
+                }
+                data.get(start..)?
+            }
+            LINKTYPE_RAW => data,
+            _ => return None,
+        };
+
+        let (source, destination, segment) = match packet.first()? >> 4 {
+            4 => {
+                let header_len = usize::from(packet[0] & 0x0f) * 4;
+                let fragment_offset = u16::from_be_bytes([*packet.get(6)?, *packet.get(7)?]);
+                if *packet.get(9)? != 6 || fragment_offset & 0x1fff != 0 {
+                    return None;
+                }
+                let source: [u8; 4] = packet.get(12..16)?.try_into().ok()?;
+                let destination: [u8; 4] = packet.get(16..20)?.try_into().ok()?;
+                (
+                    IpAddr::V4(Ipv4Addr::from(source)),
+                    IpAddr::V4(Ipv4Addr::from(destination)),
+                    packet.get(header_len.max(20)..)?,
+                )
+            }
+            6 => {
+                // Extension headers are not followed
+                if *packet.get(6)? != 6 {
+                    return None;
+                }
+                let source: [u8; 16] = packet.get(8..24)?.try_into().ok()?;
+                let destination: [u8; 16] = packet.get(24..40)?.try_into().ok()?;
+                (
+                    IpAddr::V6(Ipv6Addr::from(source)),
+                    IpAddr::V6(Ipv6Addr::from(destination)),
+                    packet.get(40..)?,
+                )
+            }
+            _ => return None,
+        };
+
+        Some(Self {
+            source,
+            destination,
+            source_port: u16::from_be_bytes([*segment.first()?, *segment.get(1)?]),
+            destination_port: u16::from_be_bytes([*segment.get(2)?, *segment.get(3)?]),
+            flags: TcpFlags::from_bits(*segment.get(13)?),
+        })
+    }
+}
+
+impl<T, Context> Matcher<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Process the packets of the capture file read from `reader`, calling
+    /// `on_match` with each match as it completes.
+    ///
+    /// `decode` turns a record, along with the capture's link type, into an
+    /// item. Records it returns `None` for are skipped. Items are processed
+    /// at their capture time, so match timestamps are times since the Unix
+    /// epoch.
+    pub fn process_pcap<R, D, F>(
+        &mut self,
+        reader: R,
+        mut decode: D,
+        mut on_match: F,
+    ) -> Result<(), ReadError>
+    where
+        R: Read,
+        D: FnMut(u32, &PcapRecord) -> Option<T>,
+        F: FnMut(MatchEvent<T>),
+    {
+        let mut records = PcapReader::new(reader)?;
+        let link_type = records.link_type();
+        for record in &mut records {
+            let record = record?;
+            let Some(item) = decode(link_type, &record) else {
+                continue;
+            };
+            for capture in self.process_captures(item, record.timestamp)? {
+                on_match(MatchEvent {
+                    value: capture.item,
+                    index: capture.index,
+                    timestamp: capture.timestamp,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Build an error for a malformed capture file.
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PatternElement;
+
+    /// An Ethernet frame holding an IPv4 TCP segment with the given flags.
+    fn frame(source_port: u16, destination_port: u16, flags: TcpFlags) -> Vec<u8> {
+        let mut frame = vec![0; 14];
+        frame[12..14].copy_from_slice(&0x0800u16.to_be_bytes());
+        let mut ip = vec![0; 20];
+        ip[0] = 0x45;
+        ip[9] = 6;
+        ip[12..16].copy_from_slice(&[10, 0, 0, 1]);
+        ip[16..20].copy_from_slice(&[10, 0, 0, 2]);
+        frame.extend(ip);
+        let mut tcp = vec![0; 20];
+        tcp[0..2].copy_from_slice(&source_port.to_be_bytes());
+        tcp[2..4].copy_from_slice(&destination_port.to_be_bytes());
+        tcp[12] = 5 << 4;
+        tcp[13] = flags.bits();
+        frame.extend(tcp);
+        frame
+    }
+
+    /// A little-endian microsecond capture file of Ethernet frames.
+    fn capture(frames: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let mut file = Vec::new();
+        file.extend(0xa1b2_c3d4u32.to_le_bytes());
+        file.extend(2u16.to_le_bytes());
+        file.extend(4u16.to_le_bytes());
+        file.extend([0; 8]);
+        file.extend(65535u32.to_le_bytes());
+        file.extend(LINKTYPE_ETHERNET.to_le_bytes());
+        for (millis, data) in frames {
+            file.extend((millis / 1000).to_le_bytes());
+            file.extend((millis % 1000 * 1000).to_le_bytes());
+            file.extend((data.len() as u32).to_le_bytes());
+            file.extend((data.len() as u32).to_le_bytes());
+            file.extend(data);
+        }
+        file
+    }
+
+    #[test]
+    fn test_decode_tcp_packet() {
+        let file = capture(&[(1500, frame(40000, 22, TcpFlags::SYN))]);
+        let mut reader = PcapReader::new(file.as_slice()).unwrap();
+        let record = reader.next().unwrap().unwrap();
+        assert!(reader.next().is_none());
+        assert_eq!(record.timestamp, Duration::from_millis(1500));
+
+        let packet = TcpPacket::decode(reader.link_type(), &record).unwrap();
+        assert_eq!(packet.source, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(packet.destination_port, 22);
+        assert_eq!(packet.flags, TcpFlags::SYN);
+        assert_eq!((TcpFlags::SYN | TcpFlags::ACK).to_string(), "SYN-ACK");
+        assert_eq!(TcpPacket::decode(LINKTYPE_RAW, &record), None);
+    }
+
+    #[test]
+    fn test_process_pcap() {
+        let has = |flags: TcpFlags| move |packet: &TcpPacket| packet.flags == flags;
+        let mut matcher = Matcher::<TcpPacket, ()>::new(0);
+        matcher.add_pattern(PatternElement::predicate(has(TcpFlags::SYN)));
+        matcher.add_pattern(PatternElement::predicate(
+            has(TcpFlags::SYN | TcpFlags::ACK),
+        ));
+        matcher.add_pattern(PatternElement::predicate(has(TcpFlags::RST)));
+
+        let file = capture(&[
+            (1000, frame(40000, 22, TcpFlags::SYN)),
+            (1001, frame(22, 40000, TcpFlags::SYN | TcpFlags::ACK)),
+            (1002, frame(40000, 22, TcpFlags::RST)),
+            (1003, frame(40000, 22, TcpFlags::ACK)),
+        ]);
+        let mut found = Vec::new();
+        matcher
+            .process_pcap(file.as_slice(), TcpPacket::decode, |event| {
+                found.push(event)
+            })
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].index, 2);
+        assert_eq!(found[0].timestamp, Duration::from_millis(1002));
+        assert_eq!(found[0].value.source_port, 40000);
+
+        assert!(matches!(
+            matcher.process_pcap(&b"not a capture file at all"[..], TcpPacket::decode, |_| {}),
+            Err(ReadError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,178 @@
+//! Ready-made patterns for numeric time series.
+//!
+//! Each preset returns the pattern elements for a common shape, ready for
+//! `Matcher::with_patterns` or to extend with further elements. Items are
+//! compared as `f64`, so any `Copy` type convertible into `f64` works.
+//!
+//! The shapes are meant to be found anywhere in the series, so use them with
+//! `ResetPolicy::RetryCurrentItem` or `ResetPolicy::FallbackByPrefix`: the
+//! default policy discards the item that breaks an attempt, although that
+//! item may well begin the next one.
+//!
+//! ```rust
+//! use scrolling_window_pattern_matcher::{presets, Matcher, ResetPolicy};
+//!
+//! let mut matcher = Matcher::<f64, ()>::with_patterns(presets::spike(10.0, 1.0), 10);
+//! matcher.set_reset_policy(ResetPolicy::RetryCurrentItem);
+//! let results = matcher.process_items(vec![5.0, 5.5, 20.0, 6.0]).unwrap();
+//! assert_eq!(results, vec![6.0]);
+//! ```
+
+use std::fmt;
+
+use crate::{Capture, ElementSettings, PatternElement};
+
+/// Get the value of the most recent capture.
+fn last<T: Copy + Into<f64>>(captured: &[Capture<T>]) -> Option<f64> {
+    captured.last().map(|capture| capture.item.into())
+}
+
+/// Get the value of the first capture.
+fn first<T: Copy + Into<f64>>(captured: &[Capture<T>]) -> Option<f64> {
+    captured.first().map(|capture| capture.item.into())
+}
+
+/// Settings for an element that must match exactly `count` items.
+fn repeated<Context>(count: usize) -> ElementSettings<Context>
+where
+    Context: Clone + fmt::Debug,
+{
+    ElementSettings {
+        min_repeat: count,
+        max_repeat: count,
+        ..Default::default()
+    }
+}
+
+/// A jump of more than `jump` above the previous value, followed by a
+/// return to within `tolerance` of the value before the jump.
+///
+/// The match completes on the returning item.
+pub fn spike<T, Context>(jump: f64, tolerance: f64) -> Vec<PatternElement<T, Context>>
+where
+    T: Clone + PartialEq + fmt::Debug + Copy + Into<f64>,
+    Context: Clone + fmt::Debug,
+{
+    vec![
+        PatternElement::predicate(|_| true),
+        PatternElement::guard(move |captured: &[Capture<T>], item: &T| {
+            last(captured).is_some_and(|before| (*item).into() - before > jump)
+        }),
+        PatternElement::guard(move |captured: &[Capture<T>], item: &T| {
+            first(captured).is_some_and(|before| ((*item).into() - before).abs() <= tolerance)
+        }),
+    ]
+}
+
+/// `length` consecutive values all within `tolerance` of the first.
+pub fn plateau<T, Context>(length: usize, tolerance: f64) -> Vec<PatternElement<T, Context>>
+where
+    T: Clone + PartialEq + fmt::Debug + Copy + Into<f64>,
+    Context: Clone + fmt::Debug,
+{
+    let mut patterns = vec![PatternElement::predicate(|_| true)];
+    if length > 1 {
+        patterns.push(PatternElement::guard_with_settings(
+            move |captured: &[Capture<T>], item: &T| {
+                first(captured).is_some_and(|start| ((*item).into() - start).abs() <= tolerance)
+            },
+            repeated(length - 1),
+        ));
+    }
+    patterns
+}
+
+/// `steps` consecutive rises of at least `min_step` each.
+///
+/// The match completes on the item that makes the last rise, after
+/// `steps + 1` items.
+pub fn staircase<T, Context>(steps: usize, min_step: f64) -> Vec<PatternElement<T, Context>>
+where
+    T: Clone + PartialEq + fmt::Debug + Copy + Into<f64>,
+    Context: Clone + fmt::Debug,
+{
+    vec![
+        PatternElement::predicate(|_| true),
+        PatternElement::guard_with_settings(
+            move |captured: &[Capture<T>], item: &T| {
+                last(captured).is_some_and(|before| (*item).into() - before >= min_step)
+            },
+            repeated(steps),
+        ),
+    ]
+}
+
+/// A rise from below `low` to at least `high`, ignoring any wavering in
+/// between.
+///
+/// The gap between the two thresholds is the hysteresis band: noise around a
+/// single threshold does not produce repeated crossings, because the value
+/// must fall below `low` again before the next crossing can start.
+pub fn threshold_crossing<T, Context>(low: f64, high: f64) -> Vec<PatternElement<T, Context>>
+where
+    T: Clone + PartialEq + fmt::Debug + Copy + Into<f64>,
+    Context: Clone + fmt::Debug,
+{
+    let band = ElementSettings {
+        min_repeat: 0,
+        max_repeat: usize::MAX,
+        ..Default::default()
+    };
+    vec![
+        PatternElement::predicate(move |item: &T| (*item).into() < low),
+        PatternElement::predicate_with_settings(move |item: &T| (*item).into() < high, band),
+        PatternElement::predicate(move |item: &T| (*item).into() >= high),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Matcher, ResetPolicy};
+
+    fn sliding<T>(patterns: Vec<PatternElement<T, ()>>) -> Matcher<T, ()>
+    where
+        T: Clone + PartialEq + fmt::Debug,
+    {
+        let mut matcher = Matcher::with_patterns(patterns, 10);
+        matcher.set_reset_policy(ResetPolicy::RetryCurrentItem);
+        matcher
+    }
+
+    #[test]
+    fn test_spike() {
+        let mut matcher = sliding::<i32>(spike(10.0, 2.0));
+        let results = matcher
+            .process_items(vec![50, 52, 70, 51, 60, 75, 40, 61])
+            .unwrap();
+        assert_eq!(results, vec![51]);
+    }
+
+    #[test]
+    fn test_plateau() {
+        let mut matcher = sliding::<f64>(plateau(3, 0.5));
+        let results = matcher
+            .process_items(vec![1.0, 5.0, 5.2, 4.6, 7.0, 7.0])
+            .unwrap();
+        assert_eq!(results, vec![4.6]);
+    }
+
+    #[test]
+    fn test_staircase() {
+        let mut matcher = sliding::<u32>(staircase(3, 5.0));
+        let results = matcher
+            .process_items(vec![10, 20, 22, 30, 40, 50, 51])
+            .unwrap();
+        assert_eq!(results, vec![50]);
+    }
+
+    #[test]
+    fn test_threshold_crossing_with_hysteresis() {
+        let mut matcher = sliding::<f32>(threshold_crossing(10.0, 20.0));
+        // Wavering around 20 only crosses again after dropping below 10
+        let results = matcher
+            .process_items(vec![5.0, 15.0, 12.0, 21.0, 19.0, 22.0, 8.0, 25.0])
+            .unwrap();
+        assert_eq!(results, vec![21.0, 25.0]);
+    }
+}
@@ -0,0 +1,117 @@
+//! Profiling where a matcher spends its time.
+//!
+//! With profiling turned on, a matcher counts every evaluation of each
+//! pattern element and every extractor run, and adds up the time each took,
+//! so the element or extractor slowing down a large rule set can be found.
+//! Lookahead and lookbehind elements include the time of their inner element.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::ExtractorId;
+
+/// Number of evaluations of an element or extractor and the time they took.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProfileEntry {
+    /// Number of times the element or extractor was evaluated.
+    pub evaluations: u64,
+    /// Cumulative time spent evaluating it.
+    pub total_time: Duration,
+}
+
+/// Evaluation counts and times of a profiled matcher.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProfileReport {
+    /// Profile of each pattern element, by index.
+    pub elements: Vec<ProfileEntry>,
+    /// Profile of each extractor that was run, by ID.
+    pub extractors: HashMap<ExtractorId, ProfileEntry>,
+}
+
+impl ProfileReport {
+    /// Get the index of the element that took the most time, if any element
+    /// was evaluated.
+    pub fn slowest_element(&self) -> Option<usize> {
+        self.elements
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.evaluations > 0)
+            .max_by_key(|(_, entry)| entry.total_time)
+            .map(|(position, _)| position)
+    }
+}
+
+/// Counters for one element, updated through a shared reference because
+/// elements are evaluated while the matcher is borrowed.
+#[derive(Debug, Default)]
+struct ElementCounter {
+    evaluations: AtomicU64,
+    nanos: AtomicU64,
+}
+
+/// Collects the profile of a matcher.
+#[derive(Debug, Default)]
+pub(crate) struct Profiler {
+    elements: Vec<ElementCounter>,
+    extractors: HashMap<ExtractorId, ProfileEntry>,
+}
+
+impl Profiler {
+    /// Make room for the counters of `count` elements.
+    pub(crate) fn fit(&mut self, count: usize) {
+        if self.elements.len() < count {
+            self.elements.resize_with(count, Default::default);
+        }
+    }
+
+    /// Record an evaluation of the element at `position`.
+    pub(crate) fn record_element(&self, position: usize, elapsed: Duration) {
+        if let Some(counter) = self.elements.get(position) {
+            counter.evaluations.fetch_add(1, Ordering::Relaxed);
+            counter
+                .nanos
+                .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Record a run of an extractor.
+    pub(crate) fn record_extractor(&mut self, id: ExtractorId, elapsed: Duration) {
+        let entry = self.extractors.entry(id).or_default();
+        entry.evaluations += 1;
+        entry.total_time += elapsed;
+    }
+
+    /// Take a snapshot of the counters.
+    pub(crate) fn report(&self) -> ProfileReport {
+        ProfileReport {
+            elements: self
+                .elements
+                .iter()
+                .map(|counter| ProfileEntry {
+                    evaluations: counter.evaluations.load(Ordering::Relaxed),
+                    total_time: Duration::from_nanos(counter.nanos.load(Ordering::Relaxed)),
+                })
+                .collect(),
+            extractors: self.extractors.clone(),
+        }
+    }
+}
+
+impl Clone for Profiler {
+    fn clone(&self) -> Self {
+        Self {
+            elements: self
+                .elements
+                .iter()
+                .map(|counter| ElementCounter {
+                    evaluations: AtomicU64::new(counter.evaluations.load(Ordering::Relaxed)),
+                    nanos: AtomicU64::new(counter.nanos.load(Ordering::Relaxed)),
+                })
+                .collect(),
+            extractors: self.extractors.clone(),
+        }
+    }
+}
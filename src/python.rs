@@ -0,0 +1,155 @@
+//! Python bindings.
+//!
+//! The `python` feature exports [`PyMatcher`] to Python as `Matcher`, so
+//! detection rules can be prototyped in notebooks against the same engine
+//! that runs in production. Items are floats. Patterns are built from exact
+//! values, inclusive ranges, and predicates looked up by name in a registry
+//! filled from Rust with [`register_predicate`], which keeps the predicate
+//! logic itself in the production code base.
+//!
+//! To build an extension module, call [`scrolling_window_pattern_matcher`]
+//! from the `#[pymodule]` of a `cdylib` crate built with maturin.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::{Matcher, MatcherError, PatternElement};
+
+/// A predicate that can be shared with Python matchers by name.
+type NamedPredicate = Arc<dyn Fn(&f64) -> bool + Send + Sync>;
+
+/// Predicates available to Python matchers, by name.
+fn predicates() -> &'static Mutex<HashMap<String, NamedPredicate>> {
+    static PREDICATES: OnceLock<Mutex<HashMap<String, NamedPredicate>>> = OnceLock::new();
+    PREDICATES.get_or_init(Default::default)
+}
+
+/// Make a predicate available to Python matchers under the given name,
+/// replacing any predicate already registered with it.
+pub fn register_predicate<F>(name: impl Into<String>, predicate: F)
+where
+    F: Fn(&f64) -> bool + Send + Sync + 'static,
+{
+    predicates()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(name.into(), Arc::new(predicate));
+}
+
+/// A matcher over a stream of floats, exposed to Python as `Matcher`.
+#[pyclass(name = "Matcher", unsendable)]
+pub struct PyMatcher {
+    inner: Matcher<f64, ()>,
+}
+
+#[pymethods]
+impl PyMatcher {
+    /// Create a matcher with the given window size.
+    #[new]
+    #[pyo3(signature = (window_size = 10))]
+    fn new(window_size: usize) -> Self {
+        Self {
+            inner: Matcher::new(window_size),
+        }
+    }
+
+    /// Append an element matching exactly `value`.
+    fn exact(&mut self, value: f64) {
+        self.inner.add_pattern(PatternElement::exact(value));
+    }
+
+    /// Append an element matching values from `min` to `max` inclusive.
+    fn range(&mut self, min: f64, max: f64) {
+        self.inner.add_pattern(PatternElement::range(min, max));
+    }
+
+    /// Append an element matching values accepted by a registered predicate.
+    fn predicate(&mut self, name: &str) -> PyResult<()> {
+        let predicate = predicates()
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(name)
+            .cloned()
+            .ok_or_else(|| PyKeyError::new_err(format!("no predicate named {:?}", name)))?;
+        self.inner
+            .add_pattern(PatternElement::predicate(move |item: &f64| predicate(item)));
+        Ok(())
+    }
+
+    /// Process one item, returning the value of the match it completes.
+    fn process(&mut self, item: f64) -> PyResult<Option<f64>> {
+        self.inner.process_item(item).map_err(to_py_err)
+    }
+
+    /// Process a sequence of items, returning the value of every match.
+    fn process_all(&mut self, items: Vec<f64>) -> PyResult<Vec<f64>> {
+        self.inner.process_items(items).map_err(to_py_err)
+    }
+
+    /// Abandon any match in progress and start the stream over.
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    /// Index of the next pattern element waiting for an item.
+    #[getter]
+    fn current_position(&self) -> usize {
+        self.inner.current_position()
+    }
+
+    /// Number of elements in the pattern.
+    fn __len__(&self) -> usize {
+        self.inner.pattern_count()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Matcher(window_size={}, patterns={})",
+            self.inner.window_size(),
+            self.inner.pattern_count()
+        )
+    }
+}
+
+/// Report a matcher error to Python as a `ValueError`.
+fn to_py_err(err: MatcherError) -> PyErr {
+    PyValueError::new_err(format!("[{}] {}", err.code(), err))
+}
+
+/// The `scrolling_window_pattern_matcher` Python module.
+#[pymodule]
+pub fn scrolling_window_pattern_matcher(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyMatcher>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_python_matcher() {
+        register_predicate("negative", |value| *value < 0.0);
+
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::new(py, "swpm").unwrap();
+            scrolling_window_pattern_matcher(&module).unwrap();
+            let matcher = module.getattr("Matcher").unwrap().call1((5,)).unwrap();
+
+            matcher.call_method1("range", (90.0, 100.0)).unwrap();
+            matcher.call_method1("predicate", ("negative",)).unwrap();
+            assert!(matcher.call_method1("predicate", ("missing",)).is_err());
+
+            let found: Vec<f64> = matcher
+                .call_method1("process_all", (vec![95.0, 1.0, 99.0, -3.0],))
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(found, vec![-3.0]);
+            assert_eq!(matcher.len().unwrap(), 2);
+        });
+    }
+}
@@ -0,0 +1,6 @@
+//! Exporting match reports.
+//!
+//! Writers that stream [`MatchReport`](crate::MatchReport)s to other tools,
+//! such as spreadsheets, as matches are found.
+
+pub mod csv;
@@ -0,0 +1,145 @@
+//! CSV export of match reports.
+//!
+//! [`CsvWriter`] writes one row per match to any `io::Write`, starting with a
+//! header row. The captured values of a match are flattened into a single
+//! field, separated by semicolons, and timestamps are written in seconds.
+//!
+//! ```rust
+//! use scrolling_window_pattern_matcher::report::csv::CsvWriter;
+//! use scrolling_window_pattern_matcher::{Matcher, PatternElement};
+//!
+//! let mut matcher = Matcher::<i32, ()>::new(5);
+//! matcher.add_pattern(PatternElement::exact(1));
+//! matcher.add_pattern(PatternElement::exact(2));
+//!
+//! let mut csv = CsvWriter::new(Vec::new());
+//! for item in [1, 2] {
+//!     for report in matcher.process_item_reports(item).unwrap() {
+//!         csv.write_report(&report).unwrap();
+//!     }
+//! }
+//! let output = String::from_utf8(csv.into_inner()).unwrap();
+//! assert!(output.lines().nth(1).unwrap().starts_with("0,2,,"));
+//! ```
+
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::MatchReport;
+
+/// Columns written for each match.
+const HEADER: &str = "start,end,pattern,start_seconds,end_seconds,value,captures";
+
+/// Separator between the captured values of a match.
+const CAPTURE_SEPARATOR: &str = ";";
+
+/// Writes match reports as CSV rows.
+#[derive(Debug)]
+pub struct CsvWriter<W: Write> {
+    writer: W,
+    header_written: bool,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// Create a writer that writes rows to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            header_written: false,
+        }
+    }
+
+    /// Write a row for a match, preceded by the header row if this is the
+    /// first row.
+    pub fn write_report<T: fmt::Display>(&mut self, report: &MatchReport<T>) -> io::Result<()> {
+        if !self.header_written {
+            writeln!(self.writer, "{}", HEADER)?;
+            self.header_written = true;
+        }
+        let captures = report
+            .items()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(CAPTURE_SEPARATOR);
+        writeln!(
+            self.writer,
+            "{},{},{},{},{},{},{}",
+            report.span.start,
+            report.span.end,
+            escape(report.pattern.as_deref().unwrap_or("")),
+            report.start_timestamp.as_secs_f64(),
+            report.end_timestamp.as_secs_f64(),
+            escape(&report.value.to_string()),
+            escape(&captures)
+        )
+    }
+
+    /// Write a row for each match.
+    pub fn write_reports<'a, T, I>(&mut self, reports: I) -> io::Result<()>
+    where
+        T: fmt::Display + 'a,
+        I: IntoIterator<Item = &'a MatchReport<T>>,
+    {
+        for report in reports {
+            self.write_report(report)?;
+        }
+        Ok(())
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Get the underlying writer back.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Quote a field if it contains a delimiter, quote, or line break.
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Capture, MatchReport};
+    use std::time::Duration;
+
+    fn capture(item: &str, index: usize) -> Capture<String> {
+        Capture {
+            item: item.to_string(),
+            index,
+            timestamp: Duration::from_millis(index as u64 * 500),
+        }
+    }
+
+    #[test]
+    fn test_csv_writer() {
+        let report = MatchReport {
+            pattern: Some("login, then \"sudo\"".to_string()),
+            span: 3..5,
+            captures: vec![capture("login", 3), capture("sudo", 4)],
+            value: "sudo".to_string(),
+            start_timestamp: Duration::from_millis(1500),
+            end_timestamp: Duration::from_secs(2),
+        };
+
+        let mut csv = CsvWriter::new(Vec::new());
+        csv.write_reports([&report, &report]).unwrap();
+        let output = String::from_utf8(csv.into_inner()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], HEADER);
+        assert_eq!(
+            lines[1],
+            "3,5,\"login, then \"\"sudo\"\"\",1.5,2,sudo,login;sudo"
+        );
+    }
+}
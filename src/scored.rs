@@ -0,0 +1,336 @@
+//! Fuzzy sequence detection by weighted scoring.
+//!
+//! In noisy data a strict pattern is brittle: one dropped or spurious item
+//! breaks the match. A [`ScoredMatcher`] gives each element a weight instead.
+//! An element that matches adds its weight to the score of an attempt, an
+//! element that is skipped subtracts it, and an item that matches nothing
+//! subtracts a noise penalty. A match is reported as soon as an attempt's
+//! score reaches the threshold, whether or not every element was seen.
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::{MatcherError, PatternElement};
+
+/// A match found by a [`ScoredMatcher`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredMatch {
+    /// Stream indices of the items the match spans.
+    pub span: Range<usize>,
+    /// Score of the match when it reached the threshold.
+    pub score: f64,
+    /// Indices of the elements that matched, in order.
+    pub matched: Vec<usize>,
+}
+
+/// The best attempt that has got through the first elements of the pattern.
+#[derive(Debug, Clone)]
+struct Attempt {
+    score: f64,
+    start: usize,
+    matched: Vec<usize>,
+}
+
+/// Matches a sequence of weighted elements approximately.
+///
+/// For each number of elements gone through, only the best scoring attempt
+/// is kept, so every item costs time linear in the number of elements. An
+/// attempt is dropped once its score falls to zero, as starting over is then
+/// at least as good, or once it spans more items than the window holds.
+#[derive(Debug, Clone)]
+pub struct ScoredMatcher<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    elements: Vec<(PatternElement<T, Context>, f64)>,
+    threshold: f64,
+    noise_penalty: f64,
+    window_size: usize,
+    /// Best attempt that has gone through each number of elements, the
+    /// first entry, for none, always being empty.
+    attempts: Vec<Option<Attempt>>,
+    index: usize,
+}
+
+impl<T, Context> ScoredMatcher<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Create a scored matcher reporting attempts that reach `threshold`
+    /// within `window_size` items.
+    pub fn new(window_size: usize, threshold: f64) -> Self {
+        Self {
+            elements: Vec::new(),
+            threshold,
+            noise_penalty: 1.0,
+            window_size,
+            attempts: vec![None],
+            index: 0,
+        }
+    }
+
+    /// Add an element worth `weight`, which must be finite and positive.
+    ///
+    /// The element is evaluated with `PatternElement::matches`, so elements
+    /// that refer to earlier items or captures fail when evaluated.
+    pub fn add_pattern(
+        &mut self,
+        element: PatternElement<T, Context>,
+        weight: f64,
+    ) -> Result<(), MatcherError> {
+        if !(weight.is_finite() && weight > 0.0) {
+            return Err(MatcherError::InvalidPattern(format!(
+                "weight {} of {} is not finite and positive",
+                weight, element
+            )));
+        }
+        self.elements.push((element, weight));
+        self.reset();
+        Ok(())
+    }
+
+    /// Get the elements and their weights.
+    pub fn patterns(&self) -> &[(PatternElement<T, Context>, f64)] {
+        &self.elements
+    }
+
+    /// Get the score at which a match is reported.
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    /// Set the score at which a match is reported.
+    pub fn set_threshold(&mut self, threshold: f64) {
+        self.threshold = threshold;
+    }
+
+    /// Get the amount subtracted for an item that matches no element.
+    pub fn noise_penalty(&self) -> f64 {
+        self.noise_penalty
+    }
+
+    /// Set the amount subtracted for an item that matches no element.
+    ///
+    /// The default is 1.0. A penalty of zero lets any number of stray items
+    /// occur between the elements of a match, up to the window size.
+    pub fn set_noise_penalty(&mut self, penalty: f64) {
+        self.noise_penalty = penalty;
+    }
+
+    /// Get the most items a match may span.
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    /// Get the score of the best attempt in progress, zero if there is none.
+    pub fn score(&self) -> f64 {
+        self.attempts
+            .iter()
+            .flatten()
+            .map(|attempt| attempt.score)
+            .fold(0.0, f64::max)
+    }
+
+    /// Discard the attempts in progress.
+    pub fn reset(&mut self) {
+        self.attempts = vec![None; self.elements.len() + 1];
+    }
+
+    /// Process a single item, returning the match it completes, if any.
+    ///
+    /// Matches do not overlap: every attempt is discarded once one is
+    /// reported.
+    pub fn process_item(&mut self, item: T) -> Result<Option<ScoredMatch>, MatcherError> {
+        if self.elements.is_empty() {
+            return Err(MatcherError::NoPatterns);
+        }
+        let index = self.index;
+        self.index += 1;
+
+        let count = self.elements.len();
+        let mut next: Vec<Option<Attempt>> = vec![None; count + 1];
+
+        // The item is noise to every attempt that does not use it
+        for (done, attempt) in self.attempts.iter().enumerate() {
+            if let Some(attempt) = attempt {
+                if done < count {
+                    keep_best(
+                        &mut next[done],
+                        Attempt {
+                            score: attempt.score - self.noise_penalty,
+                            ..attempt.clone()
+                        },
+                    );
+                }
+            }
+        }
+
+        // Or the next element of an attempt, or of a new one starting here,
+        // matches it, possibly after skipping elements
+        let mut skipping: Option<Attempt> = None;
+        for done in 0..count {
+            let fresh = (done == 0).then(|| Attempt {
+                score: 0.0,
+                start: index,
+                matched: Vec::new(),
+            });
+            for candidate in [self.attempts[done].clone(), fresh].into_iter().flatten() {
+                keep_best(&mut skipping, candidate);
+            }
+            let Some(attempt) = skipping.take() else {
+                continue;
+            };
+            let (element, weight) = &self.elements[done];
+            if element.matches(&item)? {
+                let mut matched = attempt.matched.clone();
+                matched.push(done);
+                keep_best(
+                    &mut next[done + 1],
+                    Attempt {
+                        score: attempt.score + weight,
+                        start: attempt.start,
+                        matched,
+                    },
+                );
+            }
+            skipping = Some(Attempt {
+                score: attempt.score - weight,
+                ..attempt
+            });
+        }
+
+        for attempt in next.iter_mut() {
+            let expired = attempt
+                .as_ref()
+                .is_some_and(|a| a.score <= 0.0 || index - a.start >= self.window_size);
+            if expired {
+                *attempt = None;
+            }
+        }
+
+        let best = next
+            .iter()
+            .flatten()
+            .filter(|attempt| attempt.score >= self.threshold)
+            .max_by(|a, b| a.score.total_cmp(&b.score));
+        if let Some(best) = best {
+            let found = ScoredMatch {
+                span: best.start..index + 1,
+                score: best.score,
+                matched: best.matched.clone(),
+            };
+            self.reset();
+            return Ok(Some(found));
+        }
+        // An attempt through every element can no longer gain
+        next[count] = None;
+        self.attempts = next;
+        Ok(None)
+    }
+
+    /// Process a batch of items, returning the matches they complete.
+    pub fn process_items(
+        &mut self,
+        items: impl IntoIterator<Item = T>,
+    ) -> Result<Vec<ScoredMatch>, MatcherError> {
+        let mut found = Vec::new();
+        for item in items {
+            found.extend(self.process_item(item)?);
+        }
+        Ok(found)
+    }
+}
+
+/// Keep the higher scoring of two attempts, the one already kept on a tie.
+fn keep_best(slot: &mut Option<Attempt>, candidate: Attempt) {
+    if slot
+        .as_ref()
+        .map_or(true, |attempt| candidate.score > attempt.score)
+    {
+        *slot = Some(candidate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn login_matcher() -> ScoredMatcher<&'static str, ()> {
+        let mut matcher = ScoredMatcher::new(8, 6.0);
+        matcher
+            .add_pattern(PatternElement::exact("connect"), 2.0)
+            .unwrap();
+        matcher
+            .add_pattern(PatternElement::exact("auth"), 3.0)
+            .unwrap();
+        matcher
+            .add_pattern(PatternElement::exact("ok"), 2.0)
+            .unwrap();
+        matcher
+    }
+
+    #[test]
+    fn test_scored_matching() {
+        // Every element present, with noise between them
+        let mut matcher = login_matcher();
+        let found = matcher
+            .process_items(["idle", "connect", "ping", "auth", "ok"])
+            .unwrap();
+        assert_eq!(
+            found,
+            vec![ScoredMatch {
+                span: 1..5,
+                score: 6.0,
+                matched: vec![0, 1, 2],
+            }]
+        );
+
+        // The first element is missing, which the rest outweighs
+        let mut matcher = login_matcher();
+        matcher.set_threshold(3.0);
+        let found = matcher.process_items(["auth", "ok"]).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].span, 0..2);
+        assert_eq!(found[0].score, 3.0);
+        assert_eq!(found[0].matched, vec![1, 2]);
+
+        // Too much noise keeps the score below the threshold
+        let mut matcher = login_matcher();
+        matcher.set_noise_penalty(0.5);
+        assert!(matcher
+            .process_items(["connect", "x", "y", "auth", "z", "ok"])
+            .unwrap()
+            .is_empty());
+        assert_eq!(matcher.score(), 3.0);
+        matcher.reset();
+        assert_eq!(matcher.score(), 0.0);
+        matcher.set_noise_penalty(0.0);
+        assert_eq!(
+            matcher
+                .process_items(["connect", "x", "y", "auth", "z", "ok"])
+                .unwrap()[0]
+                .span,
+            6..12
+        );
+    }
+
+    #[test]
+    fn test_scored_matcher_rejects_bad_weights() {
+        let mut matcher = ScoredMatcher::<i32, ()>::new(4, 1.0);
+        assert_eq!(matcher.process_item(1), Err(MatcherError::NoPatterns));
+        assert!(matches!(
+            matcher.add_pattern(PatternElement::exact(1), 0.0),
+            Err(MatcherError::InvalidPattern(_))
+        ));
+        assert!(matcher
+            .add_pattern(PatternElement::exact(1), f64::NAN)
+            .is_err());
+        assert!(matcher.patterns().is_empty());
+
+        // The rejected item is not counted, so spans start at the first item
+        matcher.add_pattern(PatternElement::exact(1), 1.0).unwrap();
+        assert_eq!(matcher.process_item(1).unwrap().unwrap().span, 0..1);
+    }
+}
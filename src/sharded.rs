@@ -0,0 +1,356 @@
+//! Multi-threaded matching of a partitioned stream.
+//!
+//! A [`ShardedMatcher`] spawns one worker thread per shard and routes each
+//! item to a shard by hashing its key, so all items of a key are matched in
+//! order by the same worker. Each shard keeps a separate matcher for every key
+//! routed to it, and the matches of all shards are funnelled into a single
+//! output channel.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::{KeyedMatcher, Matcher, MatcherError, StreamMatcher};
+
+/// Number of items that may wait in the queue of each shard before sending
+/// blocks.
+const SHARD_QUEUE_CAPACITY: usize = 1024;
+
+/// Result delivered on the output channel of a `ShardedMatcher`.
+pub type ShardResult<T> = Result<ShardMatch<T>, MatcherError>;
+
+/// A match found by one of the shards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardMatch<T> {
+    /// The shard whose matcher found the match.
+    pub shard: usize,
+    /// The value produced by the match.
+    pub value: T,
+    /// Zero-based index of the completing item among the items of its shard.
+    pub index: usize,
+}
+
+/// Errors from sending items to a `ShardedMatcher`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShardError {
+    /// The worker thread of the shard has stopped, usually because a pattern
+    /// closure panicked.
+    WorkerStopped { shard: usize },
+}
+
+impl fmt::Display for ShardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShardError::WorkerStopped { shard } => {
+                write!(f, "Worker of shard {} has stopped", shard)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShardError {}
+
+/// Hash-partitions a stream over worker threads, each running its own matcher.
+///
+/// Each shard's matcher is built on its worker thread, so the matcher itself
+/// never has to be moved between threads. Dropping the
+/// sharded matcher stops the workers after they drain their queues.
+pub struct ShardedMatcher<T, K> {
+    key_fn: Box<dyn Fn(&T) -> K>,
+    senders: Vec<SyncSender<(T, Duration)>>,
+    workers: Vec<JoinHandle<()>>,
+    results: Receiver<ShardResult<T>>,
+    epoch: Instant,
+    total_sent: usize,
+}
+
+impl<T, K> ShardedMatcher<T, K>
+where
+    T: Clone + PartialEq + fmt::Debug + Send + 'static,
+    K: Eq + Hash + Clone + 'static,
+{
+    /// Spawn `shards` workers, routing items by `key_fn`, each keeping a
+    /// matcher built by `factory` for every key routed to it.
+    ///
+    /// Keys that hash to the same shard are matched independently, as by a
+    /// [`KeyedMatcher`]. At least one shard is always spawned.
+    pub fn new<F, Context, S>(shards: usize, key_fn: F, factory: S) -> Self
+    where
+        F: Fn(&T) -> K + Send + Sync + 'static,
+        Context: Clone + fmt::Debug + 'static,
+        S: Fn() -> Matcher<T, Context> + Send + Sync + 'static,
+    {
+        let key_fn = Arc::new(key_fn);
+        let factory = Arc::new(factory);
+        let route = Arc::clone(&key_fn);
+        Self::with_shard_matchers(
+            shards,
+            move |item: &T| route(item),
+            move |_| {
+                let key_fn = Arc::clone(&key_fn);
+                let factory = Arc::clone(&factory);
+                KeyedMatcher::new(move |item: &T| key_fn(item), move || factory())
+            },
+        )
+    }
+}
+
+impl<T, K> ShardedMatcher<T, K>
+where
+    T: Send + 'static,
+    K: Hash,
+{
+    /// Spawn `shards` workers, routing items by `key_fn` and building the
+    /// matcher of each shard with `factory`, which receives the shard number.
+    ///
+    /// The matcher of a shard sees the items of every key routed to it
+    /// interleaved, so a match may span items of several keys unless the
+    /// matcher tells keys apart itself. At least one shard is always spawned.
+    pub fn with_shard_matchers<F, M, S>(shards: usize, key_fn: F, factory: S) -> Self
+    where
+        F: Fn(&T) -> K + 'static,
+        S: Fn(usize) -> M + Send + Sync + 'static,
+        M: StreamMatcher<T> + 'static,
+    {
+        let factory = Arc::new(factory);
+        let (output, results) = mpsc::channel();
+        let mut senders = Vec::new();
+        let mut workers = Vec::new();
+
+        for shard in 0..shards.max(1) {
+            let (sender, queue) = mpsc::sync_channel(SHARD_QUEUE_CAPACITY);
+            let factory = Arc::clone(&factory);
+            let output = output.clone();
+            workers.push(thread::spawn(move || {
+                run_shard(shard, factory(shard), queue, output)
+            }));
+            senders.push(sender);
+        }
+
+        Self {
+            key_fn: Box::new(key_fn),
+            senders,
+            workers,
+            results,
+            epoch: Instant::now(),
+            total_sent: 0,
+        }
+    }
+
+    /// Get the number of shards.
+    pub fn shard_count(&self) -> usize {
+        self.senders.len()
+    }
+
+    /// Get the shard that items with the given key are routed to.
+    pub fn shard_for(&self, key: &K) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.senders.len() as u64) as usize
+    }
+
+    /// Send an item to its shard.
+    ///
+    /// Blocks while the queue of the shard is full.
+    pub fn send(&mut self, item: T) -> Result<(), ShardError> {
+        let timestamp = self.epoch.elapsed();
+        self.send_at(item, timestamp)
+    }
+
+    /// Send an item observed at the given timestamp to its shard.
+    pub fn send_at(&mut self, item: T, timestamp: Duration) -> Result<(), ShardError> {
+        let shard = self.shard_for(&(self.key_fn)(&item));
+        self.senders[shard]
+            .send((item, timestamp))
+            .map_err(|_| ShardError::WorkerStopped { shard })?;
+        self.total_sent += 1;
+        Ok(())
+    }
+
+    /// Get the total number of items sent to the shards.
+    pub fn total_sent(&self) -> usize {
+        self.total_sent
+    }
+
+    /// Get the output channel carrying the matches and errors of all shards.
+    pub fn results(&self) -> &Receiver<ShardResult<T>> {
+        &self.results
+    }
+
+    /// Stop accepting items, wait for the workers to drain their queues, and
+    /// return every result not yet received.
+    pub fn finish(mut self) -> Vec<ShardResult<T>> {
+        self.stop();
+        self.results.try_iter().collect()
+    }
+}
+
+impl<T, K> ShardedMatcher<T, K> {
+    /// Close the shard queues and join the workers.
+    fn stop(&mut self) {
+        self.senders.clear();
+        for worker in self.workers.drain(..) {
+            // A panicked worker has already reported itself by closing its queue
+            let _ = worker.join();
+        }
+    }
+}
+
+impl<T, K> Drop for ShardedMatcher<T, K> {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl<T, K> fmt::Debug for ShardedMatcher<T, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardedMatcher")
+            .field("shard_count", &self.senders.len())
+            .field("total_sent", &self.total_sent)
+            .finish()
+    }
+}
+
+/// Run the matcher of one shard until its queue is closed.
+fn run_shard<T, M>(
+    shard: usize,
+    mut matcher: M,
+    queue: Receiver<(T, Duration)>,
+    output: Sender<ShardResult<T>>,
+) where
+    M: StreamMatcher<T>,
+{
+    for (item, timestamp) in queue {
+        let results: Vec<ShardResult<T>> = match matcher.process_captures_at(item, timestamp) {
+            Ok(captures) => captures
+                .into_iter()
+                .map(|capture| {
+                    Ok(ShardMatch {
+                        shard,
+                        value: capture.item,
+                        index: capture.index,
+                    })
+                })
+                .collect(),
+            Err(err) => vec![Err(err)],
+        };
+        for result in results {
+            if output.send(result).is_err() {
+                // Nobody is listening for results any more
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Matcher, PatternElement};
+
+    fn failed_logins() -> Matcher<(u32, &'static str), ()> {
+        let mut matcher = Matcher::new(10);
+        matcher.add_pattern(PatternElement::predicate(|event: &(u32, &str)| {
+            event.1 == "fail"
+        }));
+        matcher.add_pattern(PatternElement::predicate(|event: &(u32, &str)| {
+            event.1 == "fail"
+        }));
+        matcher
+    }
+
+    #[test]
+    fn test_matches_are_funnelled_from_all_shards() {
+        let mut matcher =
+            ShardedMatcher::new(4, |event: &(u32, &'static str)| event.0, failed_logins);
+        assert_eq!(matcher.shard_count(), 4);
+        // One user per shard, so each matcher only sees a single user's events
+        let users: Vec<u32> = (0..4)
+            .map(|shard| (0..).find(|user| matcher.shard_for(user) == shard).unwrap())
+            .collect();
+
+        for user in &users {
+            matcher.send((*user, "fail")).unwrap();
+            matcher.send((*user, "success")).unwrap();
+        }
+        for user in &users[..2] {
+            matcher.send((*user, "fail")).unwrap();
+            matcher.send((*user, "fail")).unwrap();
+        }
+        assert_eq!(matcher.total_sent(), 12);
+
+        let mut found: Vec<ShardMatch<(u32, &str)>> = matcher
+            .finish()
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        found.sort_by_key(|found| found.shard);
+        assert_eq!(found.len(), 2);
+        for (shard, (found, user)) in found.iter().zip(&users).enumerate() {
+            assert_eq!(found.value, (*user, "fail"));
+            assert_eq!(found.shard, shard);
+            assert_eq!(found.index, 3);
+        }
+    }
+
+    #[test]
+    fn test_keys_of_a_shard_are_matched_independently() {
+        let mut matcher =
+            ShardedMatcher::new(2, |event: &(u32, &'static str)| event.0, failed_logins);
+        // Three users on the same shard, with their events interleaved
+        let shard = matcher.shard_for(&0);
+        let users: Vec<u32> = (0..)
+            .filter(|user| matcher.shard_for(user) == shard)
+            .take(3)
+            .collect();
+
+        for user in &users {
+            matcher.send((*user, "fail")).unwrap();
+        }
+        matcher.send((users[0], "success")).unwrap();
+        matcher.send((users[1], "fail")).unwrap();
+        matcher.send((users[0], "fail")).unwrap();
+
+        let found: Vec<ShardMatch<(u32, &str)>> = matcher
+            .finish()
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            found,
+            vec![ShardMatch {
+                shard,
+                value: (users[1], "fail"),
+                index: 4,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_shard_matchers_see_every_key_of_their_shard() {
+        let mut matcher = ShardedMatcher::with_shard_matchers(
+            1,
+            |event: &(u32, &'static str)| event.0,
+            |_| failed_logins(),
+        );
+        matcher.send((1, "fail")).unwrap();
+        matcher.send((2, "fail")).unwrap();
+
+        let found = matcher.finish();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].as_ref().unwrap().value, (2, "fail"));
+    }
+
+    #[test]
+    fn test_errors_are_reported_on_the_output_channel() {
+        let mut matcher =
+            ShardedMatcher::new(2, |item: &u32| *item, || Matcher::<u32, ()>::new(10));
+        matcher.send(7).unwrap();
+        let results = matcher.finish();
+        assert!(matches!(results[..], [Err(MatcherError::NoPatterns)]));
+    }
+}
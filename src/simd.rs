@@ -0,0 +1,154 @@
+//! SIMD batch matching for numeric telemetry.
+//!
+//! Offering values to a range element one at a time costs two comparisons and
+//! a branch per value. [`PatternElement::range_mask`] tests a whole slice of
+//! `i32` or `f32` values, eight lanes at a time, and returns a bitmask of the
+//! values the element accepts, so dense numeric streams can be screened
+//! before any of them reaches a matcher.
+
+use std::fmt;
+use std::ops::Bound;
+
+use wide::{f32x8, i32x8, CmpGe, CmpGt, CmpLe, CmpLt};
+
+use crate::PatternElement;
+
+/// Number of values tested at once.
+const LANES: usize = 8;
+
+/// Item types whose range elements can be tested a slice at a time.
+pub trait SimdRange: Copy + Default + PartialOrd {
+    /// Test a lane of values against the bounds `start` and `end`, setting
+    /// bit `i` of the result, counting from the least significant, if
+    /// `values[i]` is in range.
+    fn range_lanes(values: [Self; LANES], start: Bound<Self>, end: Bound<Self>) -> u32;
+}
+
+impl SimdRange for i32 {
+    fn range_lanes(values: [i32; LANES], start: Bound<i32>, end: Bound<i32>) -> u32 {
+        let values = i32x8::new(values);
+        let below = match start {
+            Bound::Included(start) => values.cmp_lt(i32x8::splat(start)),
+            Bound::Excluded(start) => !values.cmp_gt(i32x8::splat(start)),
+            Bound::Unbounded => i32x8::ZERO,
+        };
+        let above = match end {
+            Bound::Included(end) => values.cmp_gt(i32x8::splat(end)),
+            Bound::Excluded(end) => !values.cmp_lt(i32x8::splat(end)),
+            Bound::Unbounded => i32x8::ZERO,
+        };
+        !(below | above).move_mask() as u32
+    }
+}
+
+impl SimdRange for f32 {
+    fn range_lanes(values: [f32; LANES], start: Bound<f32>, end: Bound<f32>) -> u32 {
+        let values = f32x8::new(values);
+        // NaN fails every comparison, so it is only in range when unbounded
+        let above = match start {
+            Bound::Included(start) => values.cmp_ge(f32x8::splat(start)).move_mask(),
+            Bound::Excluded(start) => values.cmp_gt(f32x8::splat(start)).move_mask(),
+            Bound::Unbounded => -1,
+        };
+        let below = match end {
+            Bound::Included(end) => values.cmp_le(f32x8::splat(end)).move_mask(),
+            Bound::Excluded(end) => values.cmp_lt(f32x8::splat(end)).move_mask(),
+            Bound::Unbounded => -1,
+        };
+        (above & below) as u32
+    }
+}
+
+impl<T, Context> PatternElement<T, Context>
+where
+    T: SimdRange + fmt::Debug,
+    Context: Clone + fmt::Debug,
+{
+    /// Test a slice of values against a range element, returning a bitmask
+    /// with bit `i % 64` of word `i / 64` set if the element accepts
+    /// `values[i]`.
+    ///
+    /// Values are compared with the ordering of the item type, as by
+    /// `PatternElement::range` and `outside`, rather than the element's own
+    /// comparison, so the mask may differ from the element's matches for
+    /// elements built with `range_by` or `float_range`. Returns `None` if the
+    /// element is not a range.
+    pub fn range_mask(&self, values: &[T]) -> Option<Vec<u64>> {
+        let PatternElement::Range {
+            start,
+            end,
+            negated,
+            ..
+        } = self
+        else {
+            return None;
+        };
+
+        let mut mask = vec![0u64; (values.len() + 63) / 64];
+        for (chunk, lane) in values.chunks(LANES).enumerate() {
+            let mut padded = [T::default(); LANES];
+            padded[..lane.len()].copy_from_slice(lane);
+            let mut bits = T::range_lanes(padded, *start, *end);
+            if *negated {
+                bits = !bits;
+            }
+            // Padding lanes are not values
+            bits &= (1 << lane.len()) - 1;
+            let start = chunk * LANES;
+            mask[start / 64] |= u64::from(bits) << (start % 64);
+        }
+        Some(mask)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Expand a bitmask into the matches of `count` values.
+    fn bits(mask: &[u64], count: usize) -> Vec<bool> {
+        (0..count)
+            .map(|i| mask[i / 64] >> (i % 64) & 1 == 1)
+            .collect()
+    }
+
+    #[test]
+    fn test_range_mask_agrees_with_matches() {
+        let values: Vec<i32> = (0..150).map(|i| (i * 37) % 101 - 20).collect();
+        for element in [
+            PatternElement::<i32, ()>::range(-5, 40),
+            PatternElement::outside(-5, 40),
+            PatternElement::from_range(-5..40),
+            PatternElement::from_range(..40),
+            PatternElement::from_range((Bound::Excluded(-5), Bound::Unbounded)),
+        ] {
+            let mask = element.range_mask(&values).unwrap();
+            assert_eq!(mask.len(), 3);
+            let expected: Vec<bool> = values
+                .iter()
+                .map(|value| element.matches(value).unwrap())
+                .collect();
+            assert_eq!(bits(&mask, values.len()), expected);
+        }
+
+        let values = [0.5f32, f32::NAN, 2.0, -1.0, 1.0, 3.5, 1.5, 0.0, 2.5];
+        for element in [
+            PatternElement::<f32, ()>::range(0.0, 2.0),
+            PatternElement::outside(0.0, 2.0),
+            PatternElement::from_range(0.0..2.0),
+            PatternElement::from_range(1.0..),
+            PatternElement::from_range(..),
+        ] {
+            let mask = element.range_mask(&values).unwrap();
+            let expected: Vec<bool> = values
+                .iter()
+                .map(|value| element.matches(value).unwrap())
+                .collect();
+            assert_eq!(bits(&mask, values.len()), expected);
+        }
+
+        assert!(PatternElement::<i32, ()>::exact(1)
+            .range_mask(&[1])
+            .is_none());
+    }
+}
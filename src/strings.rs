@@ -0,0 +1,147 @@
+//! String equality with configurable normalization.
+//!
+//! Log tokens often differ only in case or surrounding whitespace. Rather than
+//! writing a predicate closure for every such comparison, [`StringEquals`]
+//! normalizes both the expected value and each item before comparing them.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::{ElementSettings, Matchable, MatcherError, PatternElement};
+
+/// How strings are normalized before they are compared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Normalization {
+    /// Compare strings case-insensitively, using Unicode lowercase mapping.
+    pub ignore_case: bool,
+    /// Ignore leading and trailing whitespace.
+    pub trim: bool,
+}
+
+impl Normalization {
+    /// Normalization that only ignores case.
+    pub fn ignore_case() -> Self {
+        Self {
+            ignore_case: true,
+            trim: false,
+        }
+    }
+
+    /// Normalization that only trims whitespace.
+    pub fn trim() -> Self {
+        Self {
+            ignore_case: false,
+            trim: true,
+        }
+    }
+
+    /// Normalize a string.
+    pub fn apply<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        let value = if self.trim { value.trim() } else { value };
+        if self.ignore_case {
+            Cow::Owned(value.to_lowercase())
+        } else {
+            Cow::Borrowed(value)
+        }
+    }
+}
+
+/// Matches string items equal to an expected value after normalization.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringEquals {
+    expected: String,
+    normalization: Normalization,
+}
+
+impl StringEquals {
+    /// Create a matcher for strings equal to `expected` after normalization.
+    pub fn new(expected: &str, normalization: Normalization) -> Self {
+        Self {
+            expected: normalization.apply(expected).into_owned(),
+            normalization,
+        }
+    }
+
+    /// Check if a string matches.
+    pub fn is_match(&self, value: &str) -> bool {
+        self.normalization.apply(value) == self.expected
+    }
+}
+
+impl<T> Matchable<T> for StringEquals
+where
+    T: AsRef<str>,
+{
+    fn matches(&self, item: &T) -> Result<bool, MatcherError> {
+        Ok(self.is_match(item.as_ref()))
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "StringEquals({:?}, {:?})",
+            self.expected, self.normalization
+        )
+    }
+}
+
+impl<T, Context> PatternElement<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug + AsRef<str>,
+    Context: Clone + fmt::Debug,
+{
+    /// Create a new pattern element matching strings equal to `value` after
+    /// normalization.
+    pub fn exact_str(value: &str, normalization: Normalization) -> Self {
+        Self::custom(StringEquals::new(value, normalization))
+    }
+
+    /// Create a new normalized string pattern element with settings.
+    pub fn exact_str_with_settings(
+        value: &str,
+        normalization: Normalization,
+        settings: ElementSettings<Context>,
+    ) -> Self {
+        Self::custom_with_settings(StringEquals::new(value, normalization), settings)
+    }
+
+    /// Create a new pattern element matching strings equal to `value`,
+    /// ignoring case.
+    pub fn exact_ignore_case(value: &str) -> Self {
+        Self::exact_str(value, Normalization::ignore_case())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Matcher;
+
+    #[test]
+    fn test_normalization() {
+        let both = Normalization {
+            ignore_case: true,
+            trim: true,
+        };
+        assert_eq!(both.apply("  ERROR "), "error");
+        assert_eq!(Normalization::trim().apply(" Error\n"), "Error");
+        assert_eq!(Normalization::default().apply(" Error"), " Error");
+
+        let equals = StringEquals::new("Straße", Normalization::ignore_case());
+        assert!(equals.is_match("STRAßE"));
+        assert!(!equals.is_match("strasse"));
+    }
+
+    #[test]
+    fn test_exact_str_elements() {
+        let mut matcher = Matcher::<&str, ()>::new(10);
+        matcher.add_pattern(PatternElement::exact_ignore_case("error"));
+        matcher.add_pattern(PatternElement::exact_str("retry", Normalization::trim()));
+
+        let tokens = ["Error", "retry", "ERROR", " retry\t", "error", "Retry"];
+        let results: Vec<&str> = tokens
+            .iter()
+            .filter_map(|token| matcher.process_item(*token).unwrap())
+            .collect();
+        assert_eq!(results, vec!["retry", " retry\t"]);
+    }
+}
@@ -0,0 +1,113 @@
+//! Running a matcher as a tokio task.
+//!
+//! [`Matcher::spawn`] moves a matcher onto the tokio runtime, where it reads
+//! items from a channel and sends a report of each match to another, so it
+//! slots into an async pipeline without a hand-written task loop.
+
+use std::fmt;
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::{Capture, FinishReport, MatchReport, Matcher, MatcherError};
+
+/// Capacity of the channel a spawned matcher sends its matches to.
+const MATCH_CHANNEL_CAPACITY: usize = 1024;
+
+/// Handle of a spawned matcher task, resolving to its finish report.
+pub type MatcherTask<T> = JoinHandle<Result<FinishReport<T>, MatcherError>>;
+
+impl<T, Context> Matcher<T, Context>
+where
+    T: Clone + PartialEq + fmt::Debug + Send + 'static,
+    Context: Clone + fmt::Debug + Send + 'static,
+{
+    /// Process the items received on `input` in a tokio task, sending a
+    /// report of every match to the returned receiver.
+    ///
+    /// Items are timestamped with the time elapsed since the matcher was
+    /// created. When `input` closes, the matcher finishes the stream, sends
+    /// the match it completes, if any, and the task returns the finish report.
+    /// The task stops at the first item the matcher rejects, returning the
+    /// error, or once the receiver is dropped.
+    ///
+    /// Must be called from within a tokio runtime. The `tokio` feature turns
+    /// on the `sync` feature, which makes the matcher `Send`.
+    pub fn spawn(
+        mut self,
+        mut input: mpsc::Receiver<T>,
+    ) -> (MatcherTask<T>, mpsc::Receiver<MatchReport<T>>) {
+        let (sender, output) = mpsc::channel(MATCH_CHANNEL_CAPACITY);
+        let handle = tokio::spawn(async move {
+            while let Some(item) = input.recv().await {
+                let timestamp = self.epoch.elapsed();
+                for report in self.process_item_reports_at(item, timestamp)? {
+                    if sender.send(report).await.is_err() {
+                        return Ok(self.finish());
+                    }
+                }
+            }
+
+            let held = self.pending.clone();
+            self.attempts = Some(Vec::new());
+            let report = self.finish();
+            let attempts = self.attempts.take().unwrap_or_default();
+            if let (Some(item), Some(held), Some(captures)) =
+                (report.completed.clone(), held, attempts.into_iter().next())
+            {
+                let completed = Capture { item, ..held };
+                // The receiver may already be gone, which leaves nobody to tell
+                let _ = sender.send(self.report(completed, captures)).await;
+            }
+            Ok(report)
+        });
+        (handle, output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ElementSettings, MatchPolicy, PatternElement, PatternSettings};
+
+    #[tokio::test]
+    async fn test_spawn_reports_matches() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        matcher.set_pattern_settings(
+            PatternSettings::default().match_policy(MatchPolicy::LongestMatch),
+        );
+        matcher.add_pattern(PatternElement::exact(1));
+        let optional = ElementSettings {
+            optional: true,
+            ..Default::default()
+        };
+        matcher.add_pattern(PatternElement::exact_with_settings(2, optional));
+
+        let (items, input) = mpsc::channel(8);
+        let (handle, mut output) = matcher.spawn(input);
+        for item in [1, 2, 3, 1] {
+            items.send(item).await.unwrap();
+        }
+        drop(items);
+
+        let mut found = Vec::new();
+        while let Some(report) = output.recv().await {
+            found.push((report.value, report.span));
+        }
+        // The trailing match completes when the input closes
+        assert_eq!(found, vec![(2, 0..2), (1, 3..4)]);
+        assert_eq!(handle.await.unwrap().unwrap().completed, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_spawn_stops_on_error() {
+        let matcher = Matcher::<i32, ()>::new(10);
+        let (items, input) = mpsc::channel(1);
+        let (handle, _output) = matcher.spawn(input);
+        items.send(1).await.unwrap();
+        assert!(matches!(
+            handle.await.unwrap(),
+            Err(MatcherError::NoPatterns)
+        ));
+    }
+}
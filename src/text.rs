@@ -0,0 +1,113 @@
+//! Matching over Unicode text segments.
+//!
+//! Splitting text with `str::chars` breaks emoji sequences and combining
+//! characters into several items, so a pattern element for "é" written with a
+//! combining accent never matches. These adapters segment text into extended
+//! grapheme clusters or words with `unicode-segmentation` and feed the
+//! segments to a `Matcher<&str, _>`.
+
+use std::fmt;
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{Matcher, MatcherError};
+
+/// How text is split into items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segmentation {
+    /// Extended grapheme clusters, i.e. user-perceived characters.
+    Graphemes,
+    /// Words, skipping whitespace and punctuation between them.
+    Words,
+}
+
+/// A match found in a piece of text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextMatch<'a> {
+    /// The value produced by the match.
+    pub value: &'a str,
+    /// Stream index of the segment that completed the match.
+    pub index: usize,
+    /// Byte range in the text of the segment that completed the match.
+    pub offset: Range<usize>,
+}
+
+impl<'a, Context> Matcher<&'a str, Context>
+where
+    Context: Clone + fmt::Debug,
+{
+    /// Segment text and process each segment, returning the matches found.
+    pub fn process_text(
+        &mut self,
+        text: &'a str,
+        segmentation: Segmentation,
+    ) -> Result<Vec<TextMatch<'a>>, MatcherError> {
+        let segments: Vec<(usize, &'a str)> = match segmentation {
+            Segmentation::Graphemes => text.grapheme_indices(true).collect(),
+            Segmentation::Words => text.unicode_word_indices().collect(),
+        };
+
+        let first_index = self.offset();
+        let mut found = Vec::new();
+        for &(_, segment) in &segments {
+            let timestamp = self.epoch.elapsed();
+            for capture in self.process_captures(segment, timestamp)? {
+                let (start, completing) = segments[capture.index - first_index];
+                found.push(TextMatch {
+                    value: capture.item,
+                    index: capture.index,
+                    offset: start..start + completing.len(),
+                });
+            }
+        }
+        Ok(found)
+    }
+
+    /// Process the extended grapheme clusters of a piece of text.
+    pub fn process_graphemes(&mut self, text: &'a str) -> Result<Vec<TextMatch<'a>>, MatcherError> {
+        self.process_text(text, Segmentation::Graphemes)
+    }
+
+    /// Process the words of a piece of text.
+    pub fn process_words(&mut self, text: &'a str) -> Result<Vec<TextMatch<'a>>, MatcherError> {
+        self.process_text(text, Segmentation::Words)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PatternElement;
+
+    #[test]
+    fn test_graphemes_keep_combining_characters() {
+        // "e" followed by a combining acute accent is one grapheme
+        let text = "cafe\u{301} ☕";
+        let mut matcher = Matcher::<&str, ()>::new(10);
+        matcher.add_pattern(PatternElement::exact("e\u{301}"));
+        matcher.add_pattern(PatternElement::exact(" "));
+
+        let found = matcher.process_graphemes(text).unwrap();
+        assert_eq!(
+            found,
+            vec![TextMatch {
+                value: " ",
+                index: 4,
+                offset: 6..7,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_words() {
+        let mut matcher = Matcher::<&str, ()>::new(10);
+        matcher.add_pattern(PatternElement::exact("disk"));
+        matcher.add_pattern(PatternElement::exact("full"));
+
+        let found = matcher.process_words("Warning: disk, full!").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].index, 2);
+        assert_eq!(found[0].offset, 15..19);
+    }
+}
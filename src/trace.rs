@@ -0,0 +1,240 @@
+//! Recording the decisions a matcher makes.
+//!
+//! With tracing turned on, a matcher logs every evaluation of a pattern
+//! element, every extractor run, and every abandoned partial match into a
+//! bounded buffer, so a rule that does not behave as expected can be stepped
+//! through after the fact instead of printing from inside its closures.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+
+use crate::ExtractorId;
+
+/// What an extractor decided, as recorded in a trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TraceAction {
+    /// The extractor let matching continue.
+    Continue,
+    /// The extractor ended the match with extracted data.
+    Extract,
+    /// The extractor restarted matching.
+    Restart,
+    /// The extractor failed.
+    Failed,
+}
+
+/// A decision recorded while tracing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TraceEvent {
+    /// An element was offered an item.
+    Evaluated {
+        /// Stream index of the item.
+        index: usize,
+        /// Index of the pattern element.
+        element: usize,
+        /// Number of items the element had already consumed.
+        repeat: usize,
+        /// Whether the element accepted the item.
+        matched: bool,
+    },
+    /// An extractor ran on the item an element consumed.
+    Extracted {
+        /// Stream index of the item.
+        index: usize,
+        /// Index of the pattern element.
+        element: usize,
+        /// ID of the extractor.
+        extractor_id: ExtractorId,
+        /// What the extractor decided.
+        action: TraceAction,
+    },
+    /// A partial match was abandoned.
+    Reset {
+        /// Stream index of the latest item processed.
+        index: usize,
+        /// Index of the pattern element the match was waiting at.
+        element: usize,
+        /// Number of items the match had captured.
+        captured: usize,
+    },
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceEvent::Evaluated {
+                index,
+                element,
+                repeat,
+                matched,
+            } => write!(
+                f,
+                "item {}: element {} (repeat {}) {}",
+                index,
+                element,
+                repeat,
+                if *matched { "matched" } else { "rejected" }
+            ),
+            TraceEvent::Extracted {
+                index,
+                element,
+                extractor_id,
+                action,
+            } => write!(
+                f,
+                "item {}: element {} extractor {} returned {:?}",
+                index, element, extractor_id, action
+            ),
+            TraceEvent::Reset {
+                index,
+                element,
+                captured,
+            } => write!(
+                f,
+                "item {}: abandoned match at element {} after {} items",
+                index, element, captured
+            ),
+        }
+    }
+}
+
+/// Collects the trace of a matcher, keeping the latest events.
+///
+/// Events are recorded through a shared reference because elements are
+/// evaluated while the matcher is borrowed.
+#[derive(Debug)]
+pub(crate) struct Tracer {
+    events: Mutex<VecDeque<TraceEvent>>,
+    capacity: usize,
+}
+
+impl Tracer {
+    /// Create a tracer keeping at most `capacity` events.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            events: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// Get the maximum number of events kept.
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Record an event, dropping the oldest if the buffer is full.
+    pub(crate) fn record(&self, event: TraceEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut events = self.lock();
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// Take a snapshot of the events, oldest first.
+    pub(crate) fn events(&self) -> Vec<TraceEvent> {
+        self.lock().iter().copied().collect()
+    }
+
+    /// Discard the recorded events.
+    pub(crate) fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<TraceEvent>> {
+        self.events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Clone for Tracer {
+    fn clone(&self) -> Self {
+        Self {
+            events: Mutex::new(self.lock().clone()),
+            capacity: self.capacity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ElementSettings, ExtractorAction, Matcher, PatternElement};
+
+    #[test]
+    fn test_trace_records_decisions() {
+        let mut matcher = Matcher::<i32, ()>::new(10);
+        let settings = ElementSettings {
+            extractor_id: Some(1),
+            ..Default::default()
+        };
+        matcher.register_extractor(1, |state| {
+            Ok(if state.current_item < 0 {
+                ExtractorAction::Restart
+            } else {
+                ExtractorAction::Continue
+            })
+        });
+        matcher.add_pattern(PatternElement::exact(1));
+        matcher.add_pattern(PatternElement::predicate_with_settings(
+            |item: &i32| *item != 1,
+            settings,
+        ));
+        matcher.add_pattern(PatternElement::exact(3));
+
+        assert!(!matcher.is_tracing());
+        matcher.set_tracing(Some(100));
+        matcher.process_items(vec![1, -2, 1, 2, 5]).unwrap();
+
+        let events = matcher.trace();
+        assert_eq!(
+            events[..3],
+            [
+                TraceEvent::Evaluated {
+                    index: 0,
+                    element: 0,
+                    repeat: 0,
+                    matched: true
+                },
+                TraceEvent::Evaluated {
+                    index: 1,
+                    element: 1,
+                    repeat: 0,
+                    matched: true
+                },
+                TraceEvent::Extracted {
+                    index: 1,
+                    element: 1,
+                    extractor_id: 1,
+                    action: TraceAction::Restart
+                },
+            ]
+        );
+        assert!(events.contains(&TraceEvent::Reset {
+            index: 4,
+            element: 2,
+            captured: 2
+        }));
+        assert_eq!(
+            events[0].to_string(),
+            "item 0: element 0 (repeat 0) matched"
+        );
+
+        // The buffer keeps only the latest events
+        matcher.set_tracing(Some(2));
+        matcher.process_items(vec![1, 2, 3]).unwrap();
+        assert_eq!(matcher.trace().len(), 2);
+        matcher.clear_trace();
+        assert!(matcher.trace().is_empty());
+
+        matcher.set_tracing(None);
+        assert!(!matcher.is_tracing());
+    }
+}
@@ -8,6 +8,7 @@ use scrolling_window_pattern_matcher::{
 };
 use std::collections::HashMap;
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 struct AdvancedContext {
     name: String,
@@ -60,8 +61,10 @@ fn test_advanced_extractors_with_state() {
         ))
     });
 
-    let mut settings = ElementSettings::default();
-    settings.extractor_id = Some(1);
+    let settings = ElementSettings {
+        extractor_id: Some(1),
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(5, settings));
 
     // Should extract 5 * 0 = 0 (position starts at 0)
@@ -83,8 +86,10 @@ fn test_extractor_restart_complex() {
         }
     });
 
-    let mut settings = ElementSettings::default();
-    settings.extractor_id = Some(1);
+    let settings = ElementSettings {
+        extractor_id: Some(1),
+        ..Default::default()
+    };
 
     matcher.add_pattern(PatternElement::exact(10));
     matcher.add_pattern(PatternElement::exact_with_settings(99, settings)); // Will restart
@@ -107,13 +112,17 @@ fn test_multiple_optional_elements() {
     matcher.add_pattern(PatternElement::exact(1));
 
     // First optional element
-    let mut settings1 = ElementSettings::default();
-    settings1.optional = true;
+    let settings1 = ElementSettings {
+        optional: true,
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(2, settings1));
 
     // Second optional element
-    let mut settings2 = ElementSettings::default();
-    settings2.optional = true;
+    let settings2 = ElementSettings {
+        optional: true,
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(3, settings2));
 
     matcher.add_pattern(PatternElement::exact(4));
@@ -160,15 +169,17 @@ fn test_extractor_error_handling() {
         }
     });
 
-    let mut settings = ElementSettings::default();
-    settings.extractor_id = Some(1);
+    let settings = ElementSettings {
+        extractor_id: Some(1),
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(42, settings));
 
     // Should return an error when processing 42
     let result = matcher.process_item(42);
     assert!(result.is_err());
 
-    if let Err(MatcherError::ExtractorFailed(err)) = result {
+    if let Err(MatcherError::ExtractorFailed { error: err, .. }) = result {
         assert!(err.to_string().contains("Cannot process 42"));
     } else {
         panic!("Expected ExtractorFailed error");
@@ -255,12 +266,16 @@ fn test_edge_case_empty_optional_only_pattern() {
     let mut matcher = Matcher::<i32, AdvancedContext>::new(10);
 
     // Pattern with only optional elements
-    let mut settings1 = ElementSettings::default();
-    settings1.optional = true;
+    let settings1 = ElementSettings {
+        optional: true,
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(1, settings1));
 
-    let mut settings2 = ElementSettings::default();
-    settings2.optional = true;
+    let settings2 = ElementSettings {
+        optional: true,
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(2, settings2));
 
     // When nothing matches optional elements, pattern should not complete
@@ -268,8 +283,10 @@ fn test_edge_case_empty_optional_only_pattern() {
     assert_eq!(matcher.process_item(6).unwrap(), None);
 
     // When some match, pattern should complete
-    assert_eq!(matcher.process_item(1).unwrap(), None); // Matches optional 1, position advances to 1
-    assert_eq!(matcher.process_item(3).unwrap(), None); // Doesn't match optional 2, but pattern completes -> None (no actual matches)
+    assert_eq!(matcher.process_item(1).unwrap(), Some(1)); // Matches optional 1, and the rest may be skipped
+    // FirstMatch already reported the match ending at 1, so 3 starts a new
+    // attempt, and an attempt that matches no element completes nothing
+    assert_eq!(matcher.process_item(3).unwrap(), None);
 }
 
 #[test]
@@ -316,3 +333,25 @@ fn test_context_preservation() {
     // the matcher still works correctly after context operations)
     assert_eq!(matcher.process_item(42).unwrap(), Some(42));
 }
+
+#[test]
+fn test_subscriber_queue_drops_oldest_events() {
+    use scrolling_window_pattern_matcher::OverflowPolicy;
+
+    let mut matcher = Matcher::<i32, ()>::new(10);
+    matcher.add_pattern(PatternElement::predicate(|x| *x > 100));
+    let events = matcher.subscribe(2, OverflowPolicy::DropOldest);
+
+    for value in [150, 5, 200, 300] {
+        matcher.process_item(value).unwrap();
+    }
+
+    // The slow consumer only sees the two most recent matches
+    let values: Vec<i32> = events
+        .drain()
+        .into_iter()
+        .map(|event| event.value)
+        .collect();
+    assert_eq!(values, vec![200, 300]);
+    assert_eq!(events.dropped_count(), 1);
+}
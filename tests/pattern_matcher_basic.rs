@@ -5,6 +5,7 @@
 
 use scrolling_window_pattern_matcher::{ElementSettings, ExtractorAction, Matcher, PatternElement};
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 struct TestContext {
     name: String,
@@ -86,8 +87,10 @@ fn test_optional_elements() {
 
     matcher.add_pattern(PatternElement::exact(1));
 
-    let mut settings = ElementSettings::default();
-    settings.optional = true;
+    let settings = ElementSettings {
+        optional: true,
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(2, settings));
 
     matcher.add_pattern(PatternElement::exact(3));
@@ -113,8 +116,10 @@ fn test_extractors() {
         Ok(ExtractorAction::Extract(state.current_item * 2))
     });
 
-    let mut settings = ElementSettings::default();
-    settings.extractor_id = Some(1);
+    let settings = ElementSettings {
+        extractor_id: Some(1),
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(10, settings));
 
     // Should extract doubled value
@@ -136,8 +141,10 @@ fn test_context_usage() {
         Ok(ExtractorAction::Extract(state.current_item + 100))
     });
 
-    let mut settings = ElementSettings::default();
-    settings.extractor_id = Some(1);
+    let settings = ElementSettings {
+        extractor_id: Some(1),
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(5, settings));
 
     assert_eq!(matcher.process_item(5).unwrap(), Some(105));
@@ -214,8 +221,10 @@ fn test_extractor_restart_action() {
         }
     });
 
-    let mut settings = ElementSettings::default();
-    settings.extractor_id = Some(1);
+    let settings = ElementSettings {
+        extractor_id: Some(1),
+        ..Default::default()
+    };
     matcher.add_pattern(PatternElement::exact_with_settings(5, settings));
 
     // Should restart and return None